@@ -0,0 +1,2 @@
+ALTER TABLE public.users DROP COLUMN IF EXISTS pending_email;
+ALTER TABLE public.users DROP COLUMN IF EXISTS email_verified_at;
@@ -0,0 +1,2 @@
+ALTER TABLE public.users ADD COLUMN email_verified_at TIMESTAMPTZ;
+ALTER TABLE public.users ADD COLUMN pending_email TEXT;
@@ -0,0 +1,2 @@
+DROP TRIGGER IF EXISTS notifications_publish_trigger ON public.notifications;
+DROP FUNCTION IF EXISTS public.publish_notification();
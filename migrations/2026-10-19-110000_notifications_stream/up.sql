@@ -0,0 +1,17 @@
+-- Publish every new notification on the `notifications` channel so that
+-- connected SSE clients can be pushed updates instead of polling.
+CREATE OR REPLACE FUNCTION public.publish_notification()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify(
+        'notifications',
+        json_build_object('id', NEW.id, 'github_id', NEW.github_id)::text
+    );
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER notifications_publish_trigger
+    AFTER INSERT ON public.notifications
+    FOR EACH ROW
+    EXECUTE FUNCTION public.publish_notification();
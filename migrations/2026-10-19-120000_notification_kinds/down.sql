@@ -0,0 +1,28 @@
+DROP TRIGGER IF EXISTS task_event_notifications_trigger ON public.tasks;
+DROP FUNCTION IF EXISTS public.handle_task_event_notifications();
+DROP FUNCTION IF EXISTS public.emit_notification(INT[], INT, TEXT, JSONB);
+
+CREATE OR REPLACE FUNCTION public.handle_task_notifications()
+RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO public.notifications (github_id, task_id)
+    SELECT DISTINCT us.github_id, NEW.id
+    FROM public.user_subscriptions us
+    LEFT JOIN public.repositories r ON r.id = NEW.repository_id
+    JOIN public.projects p ON p.id = COALESCE(NEW.project_id, r.project_id)
+    WHERE
+        (us.purpose IS NOT NULL AND us.purpose = ANY(p.purposes))
+        OR (us.stack_level IS NOT NULL AND us.stack_level = ANY(p.stack_levels))
+        OR (us.technology IS NOT NULL AND us.technology = ANY(p.technologies))
+    ON CONFLICT DO NOTHING;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP FUNCTION IF EXISTS public.notification_enabled(BIGINT, TEXT);
+DROP TABLE IF EXISTS public.notification_preferences;
+
+DELETE FROM public.notifications WHERE kind <> 'task_matched_subscription';
+DROP INDEX IF EXISTS public.notifications_subscription_match_idx;
+ALTER TABLE public.notifications ADD CONSTRAINT notifications_github_id_task_id_key UNIQUE (github_id, task_id);
+ALTER TABLE public.notifications DROP COLUMN payload, DROP COLUMN kind;
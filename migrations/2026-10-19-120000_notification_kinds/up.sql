@@ -0,0 +1,122 @@
+ALTER TABLE public.notifications
+    ADD COLUMN kind TEXT NOT NULL DEFAULT 'task_matched_subscription',
+    ADD COLUMN payload JSONB NOT NULL DEFAULT '{}'::jsonb;
+
+-- Only subscription matches are deduplicated per task, other kinds can
+-- legitimately happen several times for the same task.
+ALTER TABLE public.notifications DROP CONSTRAINT notifications_github_id_task_id_key;
+CREATE UNIQUE INDEX notifications_subscription_match_idx
+    ON public.notifications (github_id, task_id)
+    WHERE kind = 'task_matched_subscription';
+
+-- Per-kind opt-outs, a missing row means the kind is enabled.
+CREATE TABLE public.notification_preferences (
+    github_id BIGINT NOT NULL REFERENCES public.users(github_id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT (now() AT TIME ZONE 'utc') NOT NULL,
+    PRIMARY KEY (github_id, kind)
+);
+
+CREATE OR REPLACE FUNCTION public.notification_enabled(p_github_id BIGINT, p_kind TEXT)
+RETURNS BOOLEAN AS $$
+    SELECT NOT EXISTS (
+        SELECT 1 FROM public.notification_preferences np
+        WHERE np.github_id = p_github_id AND np.kind = p_kind AND NOT np.enabled
+    );
+$$ LANGUAGE sql STABLE;
+
+-- Notifies the given users (by users.id) unless they opted out of the kind.
+CREATE OR REPLACE FUNCTION public.emit_notification(
+    p_user_ids INT[],
+    p_task_id INT,
+    p_kind TEXT,
+    p_payload JSONB
+)
+RETURNS VOID AS $$
+BEGIN
+    INSERT INTO public.notifications (github_id, task_id, kind, payload)
+    SELECT DISTINCT u.github_id, p_task_id, p_kind, p_payload
+    FROM public.users u
+    WHERE u.id = ANY(p_user_ids)
+        AND u.github_id IS NOT NULL
+        AND public.notification_enabled(u.github_id, p_kind);
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION public.handle_task_notifications()
+RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO public.notifications (github_id, task_id, kind)
+    SELECT DISTINCT us.github_id, NEW.id, 'task_matched_subscription'
+    FROM public.user_subscriptions us
+    LEFT JOIN public.repositories r ON r.id = NEW.repository_id
+    JOIN public.projects p ON p.id = COALESCE(NEW.project_id, r.project_id)
+    WHERE
+        ((us.purpose IS NOT NULL AND us.purpose = ANY(p.purposes))
+        OR (us.stack_level IS NOT NULL AND us.stack_level = ANY(p.stack_levels))
+        OR (us.technology IS NOT NULL AND us.technology = ANY(p.technologies)))
+        AND public.notification_enabled(us.github_id, 'task_matched_subscription')
+    ON CONFLICT DO NOTHING;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Assignment, status, approval, team and vote milestone notifications.
+CREATE OR REPLACE FUNCTION public.handle_task_event_notifications()
+RETURNS TRIGGER AS $$
+DECLARE
+    owners INT[] := ARRAY[NEW.created_by_user_id, NEW.assignee_user_id];
+BEGIN
+    IF NEW.assignee_user_id IS NOT NULL
+        AND (TG_OP = 'INSERT' OR NEW.assignee_user_id IS DISTINCT FROM OLD.assignee_user_id) THEN
+        PERFORM public.emit_notification(
+            ARRAY[NEW.assignee_user_id], NEW.id, 'task_assigned',
+            jsonb_build_object('title', NEW.title)
+        );
+    END IF;
+
+    IF NEW.assignee_team_id IS NOT NULL
+        AND (TG_OP = 'INSERT' OR NEW.assignee_team_id IS DISTINCT FROM OLD.assignee_team_id) THEN
+        PERFORM public.emit_notification(
+            ARRAY(SELECT tm.user_id FROM public.team_memberships tm WHERE tm.team_id = NEW.assignee_team_id),
+            NEW.id, 'team_task_assigned',
+            jsonb_build_object('title', NEW.title, 'team_id', NEW.assignee_team_id)
+        );
+    END IF;
+
+    IF TG_OP = 'INSERT' THEN
+        RETURN NEW;
+    END IF;
+
+    IF NEW.status IS DISTINCT FROM OLD.status THEN
+        PERFORM public.emit_notification(
+            owners, NEW.id, 'task_status_changed',
+            jsonb_build_object('title', NEW.title, 'old_status', OLD.status, 'new_status', NEW.status)
+        );
+    END IF;
+
+    IF NEW.approved_at IS NOT NULL AND OLD.approved_at IS NULL
+        OR COALESCE(cardinality(NEW.approved_by), 0) > COALESCE(cardinality(OLD.approved_by), 0) THEN
+        PERFORM public.emit_notification(
+            ARRAY[NEW.created_by_user_id], NEW.id, 'task_approved',
+            jsonb_build_object('title', NEW.title, 'approved_by', NEW.approved_by)
+        );
+    END IF;
+
+    IF COALESCE(NEW.upvotes, 0) > COALESCE(OLD.upvotes, 0)
+        AND NEW.upvotes = ANY(ARRAY[5, 10, 25, 50, 100, 250, 500, 1000]) THEN
+        PERFORM public.emit_notification(
+            owners, NEW.id, 'vote_milestone',
+            jsonb_build_object('title', NEW.title, 'upvotes', NEW.upvotes)
+        );
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER task_event_notifications_trigger
+    AFTER INSERT OR UPDATE ON public.tasks
+    FOR EACH ROW
+    EXECUTE FUNCTION public.handle_task_event_notifications();
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS public.notifications_github_id_unread_idx;
+ALTER TABLE public.notifications DROP COLUMN archived_at, DROP COLUMN read_at;
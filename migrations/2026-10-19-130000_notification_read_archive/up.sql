@@ -0,0 +1,13 @@
+ALTER TABLE public.notifications
+    ADD COLUMN read_at TIMESTAMPTZ,
+    ADD COLUMN archived_at TIMESTAMPTZ;
+
+-- Rows "deleted" through the API used to be flagged as seen and hidden from
+-- the list, keep them hidden by archiving them.
+UPDATE public.notifications
+SET read_at = created_at, archived_at = created_at
+WHERE seen;
+
+CREATE INDEX notifications_github_id_unread_idx
+    ON public.notifications (github_id)
+    WHERE seen = FALSE AND archived_at IS NULL;
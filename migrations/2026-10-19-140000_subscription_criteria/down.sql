@@ -0,0 +1,43 @@
+DROP TRIGGER IF EXISTS issue_notifications_trigger ON public.issues;
+DROP FUNCTION IF EXISTS public.handle_issue_notifications();
+
+CREATE OR REPLACE FUNCTION public.handle_task_notifications()
+RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO public.notifications (github_id, task_id, kind)
+    SELECT DISTINCT us.github_id, NEW.id, 'task_matched_subscription'
+    FROM public.user_subscriptions us
+    LEFT JOIN public.repositories r ON r.id = NEW.repository_id
+    JOIN public.projects p ON p.id = COALESCE(NEW.project_id, r.project_id)
+    WHERE
+        ((us.purpose IS NOT NULL AND us.purpose = ANY(p.purposes))
+        OR (us.stack_level IS NOT NULL AND us.stack_level = ANY(p.stack_levels))
+        OR (us.technology IS NOT NULL AND us.technology = ANY(p.technologies)))
+        AND public.notification_enabled(us.github_id, 'task_matched_subscription')
+    ON CONFLICT DO NOTHING;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP FUNCTION IF EXISTS public.subscription_matches(public.user_subscriptions, INT, INT, TEXT[], TEXT, INT);
+
+DROP INDEX IF EXISTS public.notifications_issue_subscription_match_idx;
+DELETE FROM public.notifications WHERE task_id IS NULL;
+ALTER TABLE public.notifications
+    DROP CONSTRAINT notifications_has_subject,
+    DROP COLUMN issue_id,
+    ALTER COLUMN task_id SET NOT NULL;
+
+DELETE FROM public.user_subscriptions
+WHERE purpose IS NULL AND stack_level IS NULL AND technology IS NULL;
+ALTER TABLE public.user_subscriptions
+    DROP CONSTRAINT user_subscriptions_has_criteria,
+    DROP COLUMN min_bounty,
+    DROP COLUMN task_types,
+    DROP COLUMN languages,
+    DROP COLUMN labels,
+    DROP COLUMN repository_ids,
+    DROP COLUMN project_ids,
+    ADD CONSTRAINT user_subscriptions_github_id_purpose_key UNIQUE (github_id, purpose),
+    ADD CONSTRAINT user_subscriptions_github_id_stack_level_key UNIQUE (github_id, stack_level),
+    ADD CONSTRAINT user_subscriptions_github_id_technology_key UNIQUE (github_id, technology);
@@ -0,0 +1,91 @@
+-- Subscriptions can now combine several criteria, all of which must match.
+-- Array criteria match when any of their values does.
+ALTER TABLE public.user_subscriptions
+    DROP CONSTRAINT user_subscriptions_github_id_purpose_key,
+    DROP CONSTRAINT user_subscriptions_github_id_stack_level_key,
+    DROP CONSTRAINT user_subscriptions_github_id_technology_key,
+    ADD COLUMN project_ids INT[],
+    ADD COLUMN repository_ids INT[],
+    ADD COLUMN labels TEXT[],
+    ADD COLUMN languages TEXT[],
+    ADD COLUMN task_types TEXT[],
+    ADD COLUMN min_bounty INT,
+    ADD CONSTRAINT user_subscriptions_has_criteria CHECK (
+        purpose IS NOT NULL
+        OR stack_level IS NOT NULL
+        OR technology IS NOT NULL
+        OR project_ids IS NOT NULL
+        OR repository_ids IS NOT NULL
+        OR labels IS NOT NULL
+        OR languages IS NOT NULL
+        OR task_types IS NOT NULL
+        OR min_bounty IS NOT NULL
+    );
+
+-- Notifications may now point at an imported issue instead of a task.
+ALTER TABLE public.notifications
+    ALTER COLUMN task_id DROP NOT NULL,
+    ADD COLUMN issue_id INT REFERENCES public.issues(id) ON DELETE CASCADE,
+    ADD CONSTRAINT notifications_has_subject CHECK (task_id IS NOT NULL OR issue_id IS NOT NULL);
+
+CREATE UNIQUE INDEX notifications_issue_subscription_match_idx
+    ON public.notifications (github_id, issue_id)
+    WHERE kind = 'issue_matched_subscription';
+
+-- Issues have neither a type nor a bounty, so subscriptions filtering on
+-- those never match them (p_type and p_bounty are NULL).
+CREATE OR REPLACE FUNCTION public.subscription_matches(
+    us public.user_subscriptions,
+    p_repository_id INT,
+    p_project_id INT,
+    p_labels TEXT[],
+    p_type TEXT,
+    p_bounty INT
+)
+RETURNS BOOLEAN AS $$
+    SELECT
+        (us.purpose IS NULL OR COALESCE(us.purpose = ANY(p.purposes), FALSE))
+        AND (us.stack_level IS NULL OR COALESCE(us.stack_level = ANY(p.stack_levels), FALSE))
+        AND (us.technology IS NULL OR COALESCE(us.technology = ANY(p.technologies), FALSE))
+        AND (us.project_ids IS NULL OR COALESCE(p.id = ANY(us.project_ids), FALSE))
+        AND (us.repository_ids IS NULL OR COALESCE(p_repository_id = ANY(us.repository_ids), FALSE))
+        AND (us.labels IS NULL OR COALESCE(p_labels && us.labels, FALSE))
+        AND (us.languages IS NULL OR COALESCE(r.language_slug = ANY(us.languages), FALSE))
+        AND (us.task_types IS NULL OR COALESCE(p_type = ANY(us.task_types), FALSE))
+        AND (us.min_bounty IS NULL OR COALESCE(p_bounty >= us.min_bounty, FALSE))
+    FROM (SELECT 1) AS dummy
+    LEFT JOIN public.repositories r ON r.id = p_repository_id
+    LEFT JOIN public.projects p ON p.id = COALESCE(p_project_id, r.project_id);
+$$ LANGUAGE sql STABLE;
+
+CREATE OR REPLACE FUNCTION public.handle_task_notifications()
+RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO public.notifications (github_id, task_id, kind)
+    SELECT DISTINCT us.github_id, NEW.id, 'task_matched_subscription'
+    FROM public.user_subscriptions us
+    WHERE public.subscription_matches(us, NEW.repository_id, NEW.project_id, NEW.labels, NEW.type, NEW.bounty)
+        AND public.notification_enabled(us.github_id, 'task_matched_subscription')
+    ON CONFLICT DO NOTHING;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION public.handle_issue_notifications()
+RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO public.notifications (github_id, issue_id, kind, payload)
+    SELECT DISTINCT us.github_id, NEW.id, 'issue_matched_subscription',
+        jsonb_build_object('title', NEW.title, 'number', NEW.number)
+    FROM public.user_subscriptions us
+    WHERE public.subscription_matches(us, NEW.repository_id, NULL, NEW.labels, NULL, NULL)
+        AND public.notification_enabled(us.github_id, 'issue_matched_subscription')
+    ON CONFLICT DO NOTHING;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER issue_notifications_trigger
+    AFTER INSERT ON public.issues
+    FOR EACH ROW
+    EXECUTE FUNCTION public.handle_issue_notifications();
@@ -0,0 +1,16 @@
+CREATE TABLE public.notification_schedule (
+    id SERIAL PRIMARY KEY,
+    next_run TIMESTAMPTZ NOT NULL,
+    last_run TIMESTAMPTZ,
+    created_at TIMESTAMPTZ DEFAULT (now() AT TIME ZONE 'utc') NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT (now() AT TIME ZONE 'utc') NOT NULL
+);
+
+INSERT INTO public.notification_schedule (next_run)
+SELECT COALESCE(
+    (SELECT next_run_at FROM public.jobs WHERE name = 'email_digest'),
+    (now() AT TIME ZONE 'utc')
+);
+
+DROP TABLE IF EXISTS public.job_runs;
+DROP TABLE IF EXISTS public.jobs;
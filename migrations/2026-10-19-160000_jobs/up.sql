@@ -0,0 +1,39 @@
+-- Background jobs registered by the API. Rows are claimed with
+-- FOR UPDATE SKIP LOCKED and leased through `locked_until`, so a due job
+-- runs on a single replica.
+CREATE TABLE public.jobs (
+    name TEXT PRIMARY KEY,
+    schedule TEXT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    next_run_at TIMESTAMPTZ NOT NULL,
+    last_run_at TIMESTAMPTZ,
+    last_success_at TIMESTAMPTZ,
+    consecutive_failures INT NOT NULL DEFAULT 0,
+    locked_until TIMESTAMPTZ,
+    locked_by TEXT,
+    created_at TIMESTAMPTZ DEFAULT (now() AT TIME ZONE 'utc') NOT NULL,
+    updated_at TIMESTAMPTZ
+);
+
+CREATE TABLE public.job_runs (
+    id SERIAL PRIMARY KEY,
+    job_name TEXT NOT NULL REFERENCES public.jobs(name) ON DELETE CASCADE,
+    runner TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'running' CHECK (status IN ('running', 'succeeded', 'failed')),
+    error TEXT,
+    started_at TIMESTAMPTZ DEFAULT (now() AT TIME ZONE 'utc') NOT NULL,
+    finished_at TIMESTAMPTZ
+);
+
+CREATE INDEX job_runs_job_name_idx ON public.job_runs (job_name, started_at DESC);
+
+-- Carry the pending digest over from the old single-instance scheduler.
+INSERT INTO public.jobs (name, schedule, next_run_at, last_run_at)
+SELECT 'email_digest', '@every 30d', ns.next_run,
+    (SELECT max(last_run) FROM public.notification_schedule)
+FROM public.notification_schedule ns
+WHERE ns.last_run IS NULL
+ORDER BY ns.id DESC
+LIMIT 1;
+
+DROP TABLE public.notification_schedule;
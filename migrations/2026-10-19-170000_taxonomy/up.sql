@@ -0,0 +1,25 @@
+-- Controlled vocabulary for the project tags. Project payloads are
+-- validated against it and renames are carried into the projects and the
+-- user subscriptions using the old value.
+CREATE TABLE public.taxonomy (
+    id SERIAL PRIMARY KEY,
+    kind TEXT NOT NULL CHECK (kind IN ('type', 'purpose', 'stack_level', 'technology')),
+    value TEXT NOT NULL CHECK (btrim(value) <> ''),
+    created_at TIMESTAMPTZ DEFAULT (now() AT TIME ZONE 'utc') NOT NULL,
+    updated_at TIMESTAMPTZ,
+    UNIQUE (kind, value)
+);
+
+-- Start from the values already in use.
+INSERT INTO public.taxonomy (kind, value)
+SELECT DISTINCT kind, value FROM (
+    SELECT 'type' AS kind, unnest(types) AS value FROM public.projects
+    UNION ALL SELECT 'purpose', unnest(purposes) FROM public.projects
+    UNION ALL SELECT 'stack_level', unnest(stack_levels) FROM public.projects
+    UNION ALL SELECT 'technology', unnest(technologies) FROM public.projects
+    UNION ALL SELECT 'purpose', purpose FROM public.user_subscriptions
+    UNION ALL SELECT 'stack_level', stack_level FROM public.user_subscriptions
+    UNION ALL SELECT 'technology', technology FROM public.user_subscriptions
+) used
+WHERE value IS NOT NULL AND btrim(value) <> ''
+ON CONFLICT DO NOTHING;
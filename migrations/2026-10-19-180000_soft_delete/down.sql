@@ -0,0 +1,8 @@
+ALTER TABLE public.notifications
+    DROP CONSTRAINT notifications_task_id_fkey,
+    ADD CONSTRAINT notifications_task_id_fkey
+        FOREIGN KEY (task_id) REFERENCES public.tasks (id);
+ALTER TABLE public.tasks DROP COLUMN archived_at, DROP COLUMN deleted_at;
+ALTER TABLE public.issues DROP COLUMN archived_at, DROP COLUMN deleted_at;
+ALTER TABLE public.repositories DROP COLUMN archived_at, DROP COLUMN deleted_at;
+ALTER TABLE public.projects DROP COLUMN archived_at, DROP COLUMN deleted_at;
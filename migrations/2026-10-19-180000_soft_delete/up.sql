@@ -0,0 +1,20 @@
+-- Archived rows are hidden from the listings, deleted rows from every read
+-- until they are restored or purged. Archiving or deleting a row stamps its
+-- children with the same time so restoring it only brings back what went
+-- with it.
+ALTER TABLE public.projects ADD COLUMN archived_at TIMESTAMPTZ, ADD COLUMN deleted_at TIMESTAMPTZ;
+ALTER TABLE public.repositories ADD COLUMN archived_at TIMESTAMPTZ, ADD COLUMN deleted_at TIMESTAMPTZ;
+ALTER TABLE public.issues ADD COLUMN archived_at TIMESTAMPTZ, ADD COLUMN deleted_at TIMESTAMPTZ;
+ALTER TABLE public.tasks ADD COLUMN archived_at TIMESTAMPTZ, ADD COLUMN deleted_at TIMESTAMPTZ;
+
+-- the purge looks for rows deleted before the retention period
+CREATE INDEX projects_deleted_at_idx ON public.projects (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX repositories_deleted_at_idx ON public.repositories (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX issues_deleted_at_idx ON public.issues (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX tasks_deleted_at_idx ON public.tasks (deleted_at) WHERE deleted_at IS NOT NULL;
+
+-- purging a task takes its notifications with it, as it does for issues
+ALTER TABLE public.notifications
+    DROP CONSTRAINT notifications_task_id_fkey,
+    ADD CONSTRAINT notifications_task_id_fkey
+        FOREIGN KEY (task_id) REFERENCES public.tasks (id) ON DELETE CASCADE;
@@ -0,0 +1,5 @@
+DROP TRIGGER IF EXISTS repositories_slug_history_trigger ON public.repositories;
+DROP FUNCTION IF EXISTS public.record_repository_slug();
+DROP TRIGGER IF EXISTS projects_slug_history_trigger ON public.projects;
+DROP FUNCTION IF EXISTS public.record_project_slug();
+DROP TABLE IF EXISTS public.slug_history;
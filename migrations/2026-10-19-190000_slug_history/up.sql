@@ -0,0 +1,47 @@
+-- Former slugs of the projects and repositories, so that links and filters
+-- using an old slug still find the renamed row. A slug stays with the row it
+-- was given to: another row can't take it while it is in the history.
+CREATE TABLE public.slug_history (
+    id SERIAL PRIMARY KEY,
+    project_id INTEGER REFERENCES public.projects (id) ON DELETE CASCADE,
+    repository_id INTEGER REFERENCES public.repositories (id) ON DELETE CASCADE,
+    slug TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (num_nonnulls(project_id, repository_id) = 1)
+);
+
+CREATE UNIQUE INDEX slug_history_project_slug_idx ON public.slug_history (slug) WHERE project_id IS NOT NULL;
+CREATE UNIQUE INDEX slug_history_repository_slug_idx ON public.slug_history (repository_id, slug) WHERE repository_id IS NOT NULL;
+CREATE INDEX slug_history_repository_lookup_idx ON public.slug_history (slug) WHERE repository_id IS NOT NULL;
+
+-- Record the old slug on every rename, whatever does the update. Going back
+-- to a former slug takes it out of the history.
+CREATE OR REPLACE FUNCTION public.record_project_slug()
+RETURNS TRIGGER AS $$
+BEGIN
+    DELETE FROM public.slug_history WHERE project_id = NEW.id AND slug = NEW.slug;
+    INSERT INTO public.slug_history (project_id, slug) VALUES (NEW.id, OLD.slug);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER projects_slug_history_trigger
+    AFTER UPDATE OF slug ON public.projects
+    FOR EACH ROW
+    WHEN (OLD.slug IS DISTINCT FROM NEW.slug)
+    EXECUTE FUNCTION public.record_project_slug();
+
+CREATE OR REPLACE FUNCTION public.record_repository_slug()
+RETURNS TRIGGER AS $$
+BEGIN
+    DELETE FROM public.slug_history WHERE repository_id = NEW.id AND slug = NEW.slug;
+    INSERT INTO public.slug_history (repository_id, slug) VALUES (NEW.id, OLD.slug);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER repositories_slug_history_trigger
+    AFTER UPDATE OF slug ON public.repositories
+    FOR EACH ROW
+    WHEN (OLD.slug IS DISTINCT FROM NEW.slug)
+    EXECUTE FUNCTION public.record_repository_slug();
@@ -0,0 +1,55 @@
+-- The user changing a task is set in `kudos.actor_id` for the transaction,
+-- they don't need to be told about their own status change.
+CREATE OR REPLACE FUNCTION public.handle_task_event_notifications()
+RETURNS TRIGGER AS $$
+DECLARE
+    owners INT[] := ARRAY[NEW.created_by_user_id, NEW.assignee_user_id];
+    actor INT := NULLIF(current_setting('kudos.actor_id', true), '')::INT;
+BEGIN
+    IF NEW.assignee_user_id IS NOT NULL
+        AND (TG_OP = 'INSERT' OR NEW.assignee_user_id IS DISTINCT FROM OLD.assignee_user_id) THEN
+        PERFORM public.emit_notification(
+            ARRAY[NEW.assignee_user_id], NEW.id, 'task_assigned',
+            jsonb_build_object('title', NEW.title)
+        );
+    END IF;
+
+    IF NEW.assignee_team_id IS NOT NULL
+        AND (TG_OP = 'INSERT' OR NEW.assignee_team_id IS DISTINCT FROM OLD.assignee_team_id) THEN
+        PERFORM public.emit_notification(
+            ARRAY(SELECT tm.user_id FROM public.team_memberships tm WHERE tm.team_id = NEW.assignee_team_id),
+            NEW.id, 'team_task_assigned',
+            jsonb_build_object('title', NEW.title, 'team_id', NEW.assignee_team_id)
+        );
+    END IF;
+
+    IF TG_OP = 'INSERT' THEN
+        RETURN NEW;
+    END IF;
+
+    IF NEW.status IS DISTINCT FROM OLD.status THEN
+        PERFORM public.emit_notification(
+            array_remove(owners, actor), NEW.id, 'task_status_changed',
+            jsonb_build_object('title', NEW.title, 'old_status', OLD.status, 'new_status', NEW.status)
+        );
+    END IF;
+
+    IF NEW.approved_at IS NOT NULL AND OLD.approved_at IS NULL
+        OR COALESCE(cardinality(NEW.approved_by), 0) > COALESCE(cardinality(OLD.approved_by), 0) THEN
+        PERFORM public.emit_notification(
+            ARRAY[NEW.created_by_user_id], NEW.id, 'task_approved',
+            jsonb_build_object('title', NEW.title, 'approved_by', NEW.approved_by)
+        );
+    END IF;
+
+    IF COALESCE(NEW.upvotes, 0) > COALESCE(OLD.upvotes, 0)
+        AND NEW.upvotes = ANY(ARRAY[5, 10, 25, 50, 100, 250, 500, 1000]) THEN
+        PERFORM public.emit_notification(
+            owners, NEW.id, 'vote_milestone',
+            jsonb_build_object('title', NEW.title, 'upvotes', NEW.upvotes)
+        );
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
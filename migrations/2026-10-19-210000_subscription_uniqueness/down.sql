@@ -0,0 +1 @@
+DROP INDEX IF EXISTS public.user_subscriptions_criteria_idx;
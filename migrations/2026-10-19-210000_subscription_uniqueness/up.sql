@@ -0,0 +1,20 @@
+-- A user can't hold the same subscription twice. Duplicates created since
+-- the single criterion constraints were dropped are merged into the oldest.
+DELETE FROM public.user_subscriptions a
+USING public.user_subscriptions b
+WHERE a.id > b.id
+    AND a.github_id = b.github_id
+    AND a.purpose IS NOT DISTINCT FROM b.purpose
+    AND a.stack_level IS NOT DISTINCT FROM b.stack_level
+    AND a.technology IS NOT DISTINCT FROM b.technology
+    AND a.project_ids IS NOT DISTINCT FROM b.project_ids
+    AND a.repository_ids IS NOT DISTINCT FROM b.repository_ids
+    AND a.labels IS NOT DISTINCT FROM b.labels
+    AND a.languages IS NOT DISTINCT FROM b.languages
+    AND a.task_types IS NOT DISTINCT FROM b.task_types
+    AND a.min_bounty IS NOT DISTINCT FROM b.min_bounty;
+
+CREATE UNIQUE INDEX user_subscriptions_criteria_idx ON public.user_subscriptions (
+    github_id, purpose, stack_level, technology, project_ids, repository_ids,
+    labels, languages, task_types, min_bounty
+) NULLS NOT DISTINCT;
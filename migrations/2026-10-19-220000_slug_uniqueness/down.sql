@@ -0,0 +1,7 @@
+DROP TRIGGER IF EXISTS repositories_slug_rename_check_trigger ON public.repositories;
+DROP TRIGGER IF EXISTS repositories_slug_check_trigger ON public.repositories;
+DROP FUNCTION IF EXISTS public.check_repository_slug();
+DROP TRIGGER IF EXISTS projects_slug_rename_check_trigger ON public.projects;
+DROP TRIGGER IF EXISTS projects_slug_check_trigger ON public.projects;
+DROP FUNCTION IF EXISTS public.check_project_slug();
+DROP FUNCTION IF EXISTS public.lock_slug(TEXT, TEXT[]);
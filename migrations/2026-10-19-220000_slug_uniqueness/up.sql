@@ -0,0 +1,68 @@
+-- A slug can't be given to a row while another row holds it, either as its
+-- current slug or in its history. The check runs under a transaction lock on
+-- the slug, which renames take as well for the slug they give up, so two
+-- writers can't both see a slug as free.
+CREATE OR REPLACE FUNCTION public.lock_slug(kind TEXT, slugs TEXT[])
+RETURNS VOID AS $$
+BEGIN
+    -- in order, so that two renames swapping slugs can't deadlock
+    PERFORM pg_advisory_xact_lock(hashtext(kind), hashtext(slug))
+    FROM (SELECT DISTINCT unnest(slugs) AS slug ORDER BY 1) AS slugs;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION public.check_project_slug()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'UPDATE' THEN
+        PERFORM public.lock_slug('projects', ARRAY[OLD.slug, NEW.slug]);
+    ELSE
+        PERFORM public.lock_slug('projects', ARRAY[NEW.slug]);
+    END IF;
+    IF EXISTS (SELECT 1 FROM public.projects WHERE slug = NEW.slug AND id <> NEW.id)
+        OR EXISTS (SELECT 1 FROM public.slug_history WHERE slug = NEW.slug AND project_id <> NEW.id) THEN
+        RAISE EXCEPTION 'project slug "%" is taken', NEW.slug
+            USING ERRCODE = 'unique_violation';
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER projects_slug_check_trigger
+    BEFORE INSERT ON public.projects
+    FOR EACH ROW
+    EXECUTE FUNCTION public.check_project_slug();
+
+CREATE TRIGGER projects_slug_rename_check_trigger
+    BEFORE UPDATE OF slug ON public.projects
+    FOR EACH ROW
+    WHEN (OLD.slug IS DISTINCT FROM NEW.slug)
+    EXECUTE FUNCTION public.check_project_slug();
+
+CREATE OR REPLACE FUNCTION public.check_repository_slug()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'UPDATE' THEN
+        PERFORM public.lock_slug('repositories', ARRAY[OLD.slug, NEW.slug]);
+    ELSE
+        PERFORM public.lock_slug('repositories', ARRAY[NEW.slug]);
+    END IF;
+    IF EXISTS (SELECT 1 FROM public.repositories WHERE slug = NEW.slug AND id <> NEW.id)
+        OR EXISTS (SELECT 1 FROM public.slug_history WHERE slug = NEW.slug AND repository_id <> NEW.id) THEN
+        RAISE EXCEPTION 'repository slug "%" is taken', NEW.slug
+            USING ERRCODE = 'unique_violation';
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER repositories_slug_check_trigger
+    BEFORE INSERT ON public.repositories
+    FOR EACH ROW
+    EXECUTE FUNCTION public.check_repository_slug();
+
+CREATE TRIGGER repositories_slug_rename_check_trigger
+    BEFORE UPDATE OF slug ON public.repositories
+    FOR EACH ROW
+    WHEN (OLD.slug IS DISTINCT FROM NEW.slug)
+    EXECUTE FUNCTION public.check_repository_slug();
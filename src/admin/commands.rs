@@ -0,0 +1,148 @@
+use std::path::Path;
+
+use crate::api::{
+    projects::{db::DBProject, models::Project},
+    repositories::db::DBRepository,
+    taxonomy::{db::DBTaxonomy, utils::unknown_tags},
+    roles::{
+        db::DBRole,
+        models::{NewUserProjectRole, UserProjectRole},
+    },
+    users::db::DBUser,
+};
+
+use super::{
+    errors::AdminError,
+    models::{Manifest, ManifestSummary},
+};
+
+/// A renamed project is still found by its former slugs.
+fn project_by_slug(db_access: &impl DBProject, slug: &str) -> Result<Option<Project>, AdminError> {
+    match DBProject::by_slug(db_access, slug)? {
+        Some(project) => Ok(Some(project)),
+        None => Ok(DBProject::by_former_slug(db_access, slug)?),
+    }
+}
+
+/// Resolves the user, role and optional project slug of a role assignment.
+fn resolve_assignment(
+    db_access: &(impl DBRole + DBUser + DBProject),
+    username: &str,
+    role: &str,
+    project_slug: Option<&str>,
+) -> Result<NewUserProjectRole, AdminError> {
+    let user = db_access
+        .by_username(username)?
+        .ok_or_else(|| AdminError::UserNotFound(username.to_owned()))?;
+    let role = DBRole::by_name(db_access, role)?.ok_or_else(|| AdminError::RoleNotFound(role.to_owned()))?;
+    let project_id = match project_slug {
+        Some(slug) => Some(
+            project_by_slug(db_access, slug)?
+                .ok_or_else(|| AdminError::ProjectNotFound(slug.to_owned()))?
+                .id,
+        ),
+        None => None,
+    };
+    Ok(NewUserProjectRole {
+        user_id: user.id,
+        project_id,
+        role_id: role.id,
+    })
+}
+
+/// Gives `role` to the user, on `project_slug` or globally. Returns `None`
+/// when the user already had it.
+pub fn grant_role(
+    db_access: &(impl DBRole + DBUser + DBProject),
+    username: &str,
+    role: &str,
+    project_slug: Option<&str>,
+) -> Result<Option<UserProjectRole>, AdminError> {
+    let assignment = resolve_assignment(db_access, username, role, project_slug)?;
+    if db_access
+        .user_project_role(assignment.user_id, assignment.project_id, assignment.role_id)?
+        .is_some()
+    {
+        return Ok(None);
+    }
+    Ok(Some(db_access.create_role_to_user_and_project(&assignment)?))
+}
+
+/// Takes `role` away from the user, on `project_slug` or globally. Returns
+/// `false` when the user didn't have it.
+pub fn revoke_role(
+    db_access: &(impl DBRole + DBUser + DBProject),
+    username: &str,
+    role: &str,
+    project_slug: Option<&str>,
+) -> Result<bool, AdminError> {
+    let assignment = resolve_assignment(db_access, username, role, project_slug)?;
+    match db_access.user_project_role(assignment.user_id, assignment.project_id, assignment.role_id)? {
+        Some(existing) => {
+            db_access.delete_role_to_user_and_project(existing.id)?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+pub fn read_manifest(path: &Path) -> Result<Manifest, AdminError> {
+    let contents = std::fs::read_to_string(path).map_err(|source| AdminError::ReadManifest {
+        path: path.display().to_string(),
+        source,
+    })?;
+    serde_yaml::from_str(&contents).map_err(|source| AdminError::InvalidManifest {
+        path: path.display().to_string(),
+        source,
+    })
+}
+
+/// Creates the projects and repositories of `manifest` that don't exist yet,
+/// matching them by slug, so applying a manifest twice is harmless. Project
+/// tags must be terms of the taxonomy. Nothing is written on a dry run.
+pub fn apply_manifest(
+    db_access: &(impl DBProject + DBRepository + DBTaxonomy),
+    manifest: &Manifest,
+    dry_run: bool,
+) -> Result<ManifestSummary, AdminError> {
+    // checked upfront so an invalid manifest changes nothing
+    for project in &manifest.projects {
+        if let Some((kind, unknown)) = unknown_tags(db_access, project.tags())? {
+            return Err(AdminError::UnknownTerms(project.slug.clone(), kind.project_column(), unknown.join(", ")));
+        }
+    }
+
+    let mut summary = ManifestSummary::default();
+    for project in &manifest.projects {
+        let project_id = match project_by_slug(db_access, &project.slug)? {
+            Some(existing) => {
+                summary.existing_projects.push(project.slug.clone());
+                Some(existing.id)
+            }
+            None => {
+                summary.created_projects.push(project.slug.clone());
+                if dry_run {
+                    None
+                } else {
+                    Some(DBProject::create(db_access, &project.to_new_project())?.id)
+                }
+            }
+        };
+
+        for repository in &project.repositories {
+            let existing = match DBRepository::by_slug(db_access, &repository.slug)? {
+                Some(existing) => Some(existing),
+                None => DBRepository::by_former_slug(db_access, &repository.slug)?,
+            };
+            if existing.is_some() {
+                summary.existing_repositories.push(repository.slug.clone());
+                continue;
+            }
+            summary.created_repositories.push(repository.slug.clone());
+            if let Some(project_id) = project_id.filter(|_| !dry_run) {
+                DBRepository::create(db_access, &repository.to_new_repository(project_id))?;
+            }
+        }
+    }
+    Ok(summary)
+}
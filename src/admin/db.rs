@@ -0,0 +1,30 @@
+use diesel::{sql_query, RunQueryDsl};
+
+use super::models::DBStats;
+use crate::db::{errors::DBError, pool::DBAccess};
+
+pub trait DBAdmin: Send + Sync + Clone + 'static {
+    fn stats(&self) -> Result<DBStats, DBError>;
+}
+
+impl DBAdmin for DBAccess {
+    fn stats(&self) -> Result<DBStats, DBError> {
+        self.with_conn(|conn| {
+            let stats = sql_query(
+                "SELECT
+                    (SELECT COUNT(*) FROM users) AS users,
+                    (SELECT COUNT(*) FROM projects) AS projects,
+                    (SELECT COUNT(*) FROM repositories) AS repositories,
+                    (SELECT COUNT(*) FROM tasks) AS tasks,
+                    (SELECT COUNT(*) FROM tasks_votes) AS task_votes,
+                    (SELECT COUNT(*) FROM notifications WHERE NOT seen) AS unseen_notifications,
+                    (SELECT COUNT(*) FROM webhook_deliveries WHERE status = 'pending') AS pending_webhook_deliveries,
+                    (SELECT COUNT(*) FROM jobs WHERE consecutive_failures > 0) AS failing_jobs,
+                    (SELECT MAX(version) FROM __diesel_schema_migrations) AS last_applied_migration,
+                    pg_size_pretty(pg_database_size(current_database())) AS database_size",
+            )
+            .get_result::<DBStats>(conn)?;
+            Ok(stats)
+        })
+    }
+}
@@ -0,0 +1,23 @@
+use thiserror::Error;
+
+use crate::db::errors::DBError;
+
+#[derive(Error, Debug)]
+pub enum AdminError {
+    #[error(transparent)]
+    DB(#[from] DBError),
+    #[error("user '{0}' not found, users are created on their first login")]
+    UserNotFound(String),
+    #[error("role '{0}' not found")]
+    RoleNotFound(String),
+    #[error("project '{0}' not found")]
+    ProjectNotFound(String),
+    #[error("project '{0}' has unknown {1}: {2}")]
+    UnknownTerms(String, &'static str, String),
+    #[error("error reading manifest '{path}': {source}")]
+    ReadManifest { path: String, source: std::io::Error },
+    #[error("invalid manifest '{path}': {source}")]
+    InvalidManifest { path: String, source: serde_yaml::Error },
+    #[error("error sending digest: {0}")]
+    Digest(String),
+}
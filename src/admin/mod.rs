@@ -0,0 +1,4 @@
+pub mod commands;
+pub mod db;
+pub mod errors;
+pub mod models;
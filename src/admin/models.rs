@@ -0,0 +1,136 @@
+use std::fmt;
+
+use diesel::{
+    sql_types::{BigInt, Nullable, Text},
+    QueryableByName,
+};
+use serde::Deserialize;
+
+use crate::api::{projects::models::NewProject, repositories::models::NewRepository, taxonomy::models::TaxonomyKind};
+
+/// Projects and their repositories to create, read from YAML.
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(default)]
+    pub projects: Vec<ManifestProject>,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestProject {
+    pub name: String,
+    pub slug: String,
+    pub avatar: Option<String>,
+    pub types: Option<Vec<String>>,
+    pub purposes: Option<Vec<String>>,
+    pub stack_levels: Option<Vec<String>>,
+    pub technologies: Option<Vec<String>>,
+    pub rewards: Option<bool>,
+    #[serde(default)]
+    pub repositories: Vec<ManifestRepository>,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestRepository {
+    pub name: String,
+    pub slug: String,
+    pub url: String,
+    pub language_slug: Option<String>,
+}
+
+fn tags(values: &Option<Vec<String>>) -> Option<Vec<Option<String>>> {
+    values.as_ref().map(|values| values.iter().cloned().map(Some).collect())
+}
+
+impl ManifestProject {
+    /// The tags taking their values from the taxonomy.
+    pub fn tags(&self) -> [(TaxonomyKind, Vec<String>); 4] {
+        let values = |values: &Option<Vec<String>>| values.clone().unwrap_or_default();
+        [
+            (TaxonomyKind::Type, values(&self.types)),
+            (TaxonomyKind::Purpose, values(&self.purposes)),
+            (TaxonomyKind::StackLevel, values(&self.stack_levels)),
+            (TaxonomyKind::Technology, values(&self.technologies)),
+        ]
+    }
+
+    pub fn to_new_project(&self) -> NewProject {
+        NewProject {
+            name: self.name.clone(),
+            slug: self.slug.clone(),
+            types: tags(&self.types),
+            purposes: tags(&self.purposes),
+            stack_levels: tags(&self.stack_levels),
+            technologies: tags(&self.technologies),
+            avatar: self.avatar.clone(),
+            rewards: self.rewards,
+        }
+    }
+}
+
+impl ManifestRepository {
+    pub fn to_new_repository(&self, project_id: i32) -> NewRepository {
+        NewRepository {
+            slug: self.slug.clone(),
+            name: self.name.clone(),
+            url: self.url.clone(),
+            language_slug: self.language_slug.clone(),
+            project_id,
+        }
+    }
+}
+
+/// What applying a manifest did, or would do on a dry run.
+#[derive(Debug, Default, PartialEq)]
+pub struct ManifestSummary {
+    pub created_projects: Vec<String>,
+    pub existing_projects: Vec<String>,
+    pub created_repositories: Vec<String>,
+    pub existing_repositories: Vec<String>,
+}
+
+/// Row counts and size of the database.
+#[derive(QueryableByName, Debug)]
+pub struct DBStats {
+    #[diesel(sql_type = BigInt)]
+    pub users: i64,
+    #[diesel(sql_type = BigInt)]
+    pub projects: i64,
+    #[diesel(sql_type = BigInt)]
+    pub repositories: i64,
+    #[diesel(sql_type = BigInt)]
+    pub tasks: i64,
+    #[diesel(sql_type = BigInt)]
+    pub task_votes: i64,
+    #[diesel(sql_type = BigInt)]
+    pub unseen_notifications: i64,
+    #[diesel(sql_type = BigInt)]
+    pub pending_webhook_deliveries: i64,
+    #[diesel(sql_type = BigInt)]
+    pub failing_jobs: i64,
+    #[diesel(sql_type = Nullable<Text>)]
+    pub last_applied_migration: Option<String>,
+    #[diesel(sql_type = Text)]
+    pub database_size: String,
+}
+
+impl fmt::Display for DBStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "users                       {}", self.users)?;
+        writeln!(f, "projects                    {}", self.projects)?;
+        writeln!(f, "repositories                {}", self.repositories)?;
+        writeln!(f, "tasks                       {}", self.tasks)?;
+        writeln!(f, "task votes                  {}", self.task_votes)?;
+        writeln!(f, "unseen notifications        {}", self.unseen_notifications)?;
+        writeln!(f, "pending webhook deliveries  {}", self.pending_webhook_deliveries)?;
+        writeln!(f, "failing jobs                {}", self.failing_jobs)?;
+        writeln!(
+            f,
+            "last migration              {}",
+            self.last_applied_migration.as_deref().unwrap_or("none")
+        )?;
+        write!(f, "database size               {}", self.database_size)
+    }
+}
@@ -0,0 +1,270 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::Utc;
+use diesel::dsl::now;
+use diesel::prelude::*;
+
+use super::models::{stored_tags, Catalog, CatalogDiff, CatalogProject, CatalogRepository, CATALOG_VERSION};
+use crate::api::{
+    archival::{
+        db::mark,
+        models::{ArchivalKind, Stamp},
+    },
+    projects::models::{NewProject, Project},
+    repositories::models::{NewRepository, Repository},
+};
+use crate::db::{errors::DBError, pool::DBAccess, types::DBConn};
+use crate::schema::projects::dsl as projects_dsl;
+use crate::schema::repositories::dsl as repositories_dsl;
+use crate::schema::slug_history::dsl as slug_history_dsl;
+use crate::schema::tasks::dsl as tasks_dsl;
+
+pub trait DBCatalog: Send + Sync + Clone + 'static {
+    fn export_catalog(&self) -> Result<Catalog, DBError>;
+    /// Makes the stored projects and repositories match `catalog` in a
+    /// single transaction, or only reports the changes on a dry run.
+    fn import_catalog(&self, catalog: &Catalog, dry_run: bool) -> Result<CatalogDiff, DBError>;
+    /// Slugs of `catalog`, as `project/repository` for repositories, that
+    /// are taken by deleted rows and can't be created until restored.
+    /// Repository slugs are taken whatever the project of the deleted row.
+    fn deleted_slugs(&self, catalog: &Catalog) -> Result<Vec<String>, DBError>;
+    /// Slugs of `catalog` that renamed rows used to have, with their current
+    /// slug, in the same form as [`DBCatalog::deleted_slugs`].
+    fn former_slugs(&self, catalog: &Catalog) -> Result<Vec<(String, String)>, DBError>;
+}
+
+/// The catalog covers archived projects and repositories, not deleted ones.
+fn load(conn: &mut DBConn) -> Result<(Vec<Project>, Vec<Repository>), DBError> {
+    let projects = projects_dsl::projects
+        .filter(projects_dsl::deleted_at.is_null())
+        .order(projects_dsl::slug.asc())
+        .load::<Project>(conn)?;
+    let repositories = repositories_dsl::repositories
+        .filter(repositories_dsl::deleted_at.is_null())
+        .order((repositories_dsl::slug.asc(), repositories_dsl::id.asc()))
+        .load::<Repository>(conn)?;
+    Ok((projects, repositories))
+}
+
+fn to_catalog(projects: &[Project], repositories: &[Repository]) -> Catalog {
+    let projects = projects
+        .iter()
+        .map(|project| {
+            let repositories = repositories
+                .iter()
+                .filter(|repository| repository.project_id == project.id)
+                .map(CatalogRepository::from)
+                .collect();
+            CatalogProject::from_rows(project, repositories)
+        })
+        .collect();
+    Catalog {
+        version: CATALOG_VERSION,
+        projects,
+    }
+}
+
+fn apply(
+    conn: &mut DBConn,
+    catalog: &Catalog,
+    projects: &[Project],
+    repositories: &[Repository],
+    current: &Catalog,
+) -> Result<(), DBError> {
+    let current_projects: HashMap<&str, &CatalogProject> =
+        current.projects.iter().map(|project| (project.slug.as_str(), project)).collect();
+    let mut project_ids: HashMap<&str, i32> =
+        projects.iter().map(|project| (project.slug.as_str(), project.id)).collect();
+
+    // projects first, so that repositories can move to the new ones
+    for project in &catalog.projects {
+        match project_ids.get(project.slug.as_str()) {
+            Some(&id) => {
+                if !current_projects[project.slug.as_str()].changed_fields(project).is_empty() {
+                    diesel::update(projects_dsl::projects.find(id))
+                        .set((
+                            projects_dsl::name.eq(&project.name),
+                            projects_dsl::avatar.eq(&project.avatar),
+                            projects_dsl::types.eq(stored_tags(&project.types)),
+                            projects_dsl::purposes.eq(stored_tags(&project.purposes)),
+                            projects_dsl::stack_levels.eq(stored_tags(&project.stack_levels)),
+                            projects_dsl::technologies.eq(stored_tags(&project.technologies)),
+                            projects_dsl::rewards.eq(project.rewards),
+                            projects_dsl::updated_at.eq(now),
+                        ))
+                        .execute(conn)?;
+                }
+            }
+            None => {
+                let id = diesel::insert_into(projects_dsl::projects)
+                    .values(&NewProject {
+                        name: project.name.clone(),
+                        slug: project.slug.clone(),
+                        types: stored_tags(&project.types),
+                        purposes: stored_tags(&project.purposes),
+                        stack_levels: stored_tags(&project.stack_levels),
+                        technologies: stored_tags(&project.technologies),
+                        avatar: project.avatar.clone(),
+                        rewards: Some(project.rewards),
+                    })
+                    .returning(projects_dsl::id)
+                    .get_result::<i32>(conn)?;
+                project_ids.insert(project.slug.as_str(), id);
+            }
+        }
+    }
+
+    // repository slugs are unique across projects
+    let mut stored_repositories: HashMap<&str, &Repository> = HashMap::new();
+    for repository in repositories {
+        stored_repositories.entry(repository.slug.as_str()).or_insert(repository);
+    }
+    for project in &catalog.projects {
+        let project_id = project_ids[project.slug.as_str()];
+        for repository in &project.repositories {
+            match stored_repositories.get(repository.slug.as_str()) {
+                Some(stored) => {
+                    let moved = stored.project_id != project_id;
+                    if moved || !CatalogRepository::from(*stored).changed_fields(repository).is_empty() {
+                        diesel::update(repositories_dsl::repositories.find(stored.id))
+                            .set((
+                                repositories_dsl::name.eq(&repository.name),
+                                repositories_dsl::url.eq(&repository.url),
+                                repositories_dsl::language_slug.eq(&repository.language_slug),
+                                repositories_dsl::project_id.eq(project_id),
+                                repositories_dsl::updated_at.eq(now),
+                            ))
+                            .execute(conn)?;
+                    }
+                    if moved {
+                        // the tasks of the repository follow it, and don't go
+                        // with its former project if that one is deleted
+                        diesel::update(
+                            tasks_dsl::tasks
+                                .filter(tasks_dsl::repository_id.eq(stored.id))
+                                .filter(tasks_dsl::project_id.eq(stored.project_id)),
+                        )
+                        .set(tasks_dsl::project_id.eq(project_id))
+                        .execute(conn)?;
+                    }
+                }
+                None => {
+                    diesel::insert_into(repositories_dsl::repositories)
+                        .values(&NewRepository {
+                            slug: repository.slug.clone(),
+                            name: repository.name.clone(),
+                            url: repository.url.clone(),
+                            language_slug: repository.language_slug.clone(),
+                            project_id,
+                        })
+                        .execute(conn)?;
+                }
+            }
+        }
+    }
+
+    // removed rows are deleted softly, once the repositories moved out of
+    // their project; the other repositories go with their project
+    let deleted_at = Utc::now();
+    let wanted: HashSet<&str> = catalog
+        .projects
+        .iter()
+        .flat_map(|project| project.repositories.iter().map(|repository| repository.slug.as_str()))
+        .collect();
+    for repository in repositories {
+        if !wanted.contains(repository.slug.as_str()) {
+            mark(conn, ArchivalKind::Repositories, repository.id, Stamp::Deleted, deleted_at)?;
+        }
+    }
+    for project in projects {
+        if !catalog.projects.iter().any(|p| p.slug == project.slug) {
+            mark(conn, ArchivalKind::Projects, project.id, Stamp::Deleted, deleted_at)?;
+        }
+    }
+    Ok(())
+}
+
+impl DBCatalog for DBAccess {
+    fn export_catalog(&self) -> Result<Catalog, DBError> {
+        self.with_conn(|conn| {
+            let (projects, repositories) = load(conn)?;
+            Ok(to_catalog(&projects, &repositories))
+        })
+    }
+
+    fn import_catalog(&self, catalog: &Catalog, dry_run: bool) -> Result<CatalogDiff, DBError> {
+        self.with_conn(|conn| {
+            conn.transaction(|conn| {
+                // concurrent imports would each diff against a stale catalog
+                diesel::sql_query("LOCK TABLE projects, repositories IN SHARE ROW EXCLUSIVE MODE").execute(conn)?;
+                let (projects, repositories) = load(conn)?;
+                let current = to_catalog(&projects, &repositories);
+                let mut diff = current.diff(catalog);
+                diff.dry_run = dry_run;
+                if !dry_run {
+                    apply(conn, catalog, &projects, &repositories, &current)?;
+                }
+                Ok(diff)
+            })
+        })
+    }
+
+    fn deleted_slugs(&self, catalog: &Catalog) -> Result<Vec<String>, DBError> {
+        self.with_conn(|conn| {
+            let projects = projects_dsl::projects
+                .filter(projects_dsl::deleted_at.is_not_null())
+                .select(projects_dsl::slug)
+                .load::<String>(conn)?;
+            let repositories = repositories_dsl::repositories
+                .filter(repositories_dsl::deleted_at.is_not_null())
+                .select(repositories_dsl::slug)
+                .load::<String>(conn)?;
+
+            let mut slugs = Vec::new();
+            for project in &catalog.projects {
+                if projects.contains(&project.slug) {
+                    slugs.push(project.slug.clone());
+                    continue;
+                }
+                for repository in &project.repositories {
+                    if repositories.contains(&repository.slug) {
+                        slugs.push(format!("{}/{}", project.slug, repository.slug));
+                    }
+                }
+            }
+            Ok(slugs)
+        })
+    }
+
+    fn former_slugs(&self, catalog: &Catalog) -> Result<Vec<(String, String)>, DBError> {
+        self.with_conn(|conn| {
+            let projects = slug_history_dsl::slug_history
+                .inner_join(projects_dsl::projects)
+                .select((slug_history_dsl::slug, projects_dsl::slug))
+                .load::<(String, String)>(conn)?;
+            let repositories = slug_history_dsl::slug_history
+                .inner_join(repositories_dsl::repositories.inner_join(projects_dsl::projects))
+                .select((projects_dsl::slug, slug_history_dsl::slug, repositories_dsl::slug))
+                .load::<(String, String, String)>(conn)?;
+
+            let mut slugs = Vec::new();
+            for project in &catalog.projects {
+                if let Some((_, current)) = projects.iter().find(|(former, _)| *former == project.slug) {
+                    slugs.push((project.slug.clone(), current.clone()));
+                    continue;
+                }
+                for repository in &project.repositories {
+                    if let Some((current_project, _, current)) =
+                        repositories.iter().find(|(_, former, _)| *former == repository.slug)
+                    {
+                        slugs.push((
+                            format!("{}/{}", project.slug, repository.slug),
+                            format!("{current_project}/{current}"),
+                        ));
+                    }
+                }
+            }
+            Ok(slugs)
+        })
+    }
+}
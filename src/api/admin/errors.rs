@@ -0,0 +1,49 @@
+use std::fmt;
+
+use serde_derive::Deserialize;
+use thiserror::Error;
+use warp::{
+    http::StatusCode,
+    reject::Reject,
+    reply::{Reply, Response},
+};
+
+use crate::errors::ErrorResponse;
+
+#[derive(Clone, Error, Debug, Deserialize, PartialEq)]
+pub enum CatalogError {
+    InvalidDocument(String),
+    UnsupportedVersion(u32),
+    DuplicateSlug(String),
+    Deleted(String),
+    Renamed(String, String),
+    UnknownTerms(String, String),
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatalogError::InvalidDocument(error) => write!(f, "Invalid catalog: {error}"),
+            CatalogError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported catalog version {version}, expected {}", super::models::CATALOG_VERSION)
+            }
+            CatalogError::DuplicateSlug(slug) => write!(f, "Duplicate slug '{slug}' in the catalog"),
+            CatalogError::Deleted(slug) => write!(f, "'{slug}' is deleted, restore it before importing it"),
+            CatalogError::Renamed(former, current) => write!(f, "'{former}' was renamed to '{current}'"),
+            CatalogError::UnknownTerms(slug, detail) => write!(f, "'{slug}' has unknown {detail}"),
+        }
+    }
+}
+
+impl Reject for CatalogError {}
+
+impl Reply for CatalogError {
+    fn into_response(self) -> Response {
+        let code = StatusCode::UNPROCESSABLE_ENTITY;
+        let message = self.to_string();
+
+        let json = warp::reply::json(&ErrorResponse::new(message));
+
+        warp::reply::with_status(json, code).into_response()
+    }
+}
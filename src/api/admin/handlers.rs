@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use bytes::Bytes;
+use log::{info, warn};
+use warp::{
+    http::header::CONTENT_TYPE,
+    reject::{self, Rejection},
+    reply::{json, Reply},
+};
+
+use super::{
+    db::DBCatalog,
+    errors::CatalogError,
+    models::{Catalog, CatalogFormat, ExportParams, ImportParams},
+};
+use crate::{
+    api::{
+        roles::{db::DBRole, models::KudosRole, utils::user_has_at_least_one_role},
+        taxonomy::{db::DBTaxonomy, utils::unknown_tags},
+    },
+    config::Config,
+    middlewares::github::model::GitHubUser,
+};
+
+pub const YAML_CONTENT_TYPE: &str = "application/yaml";
+
+pub async fn config_handler(
+    user: GitHubUser,
+    db_access: impl DBRole,
+    config: Arc<Config>,
+) -> Result<impl Reply, Rejection> {
+    let roles = DBRole::user_roles(&db_access, &user.username)?;
+    user_has_at_least_one_role(roles, vec![KudosRole::Admin])?;
+
+    Ok(json(&config.redacted()))
+}
+
+pub async fn export_handler(
+    user: GitHubUser,
+    params: ExportParams,
+    db_access: impl DBRole + DBCatalog,
+) -> Result<impl Reply, Rejection> {
+    let roles = DBRole::user_roles(&db_access, &user.username)?;
+    user_has_at_least_one_role(roles, vec![KudosRole::Admin])?;
+
+    let catalog = db_access.export_catalog()?;
+    info!("exporting the catalog ({} projects) for '{}'", catalog.projects.len(), user.username);
+    let reply = match params.format {
+        CatalogFormat::Json => json(&catalog).into_response(),
+        CatalogFormat::Yaml => {
+            let yaml = serde_yaml::to_string(&catalog)
+                .map_err(|e| reject::custom(CatalogError::InvalidDocument(e.to_string())))?;
+            warp::reply::with_header(yaml, CONTENT_TYPE, YAML_CONTENT_TYPE).into_response()
+        }
+    };
+    Ok(reply)
+}
+
+/// Parses a catalog sent as YAML when the content type says so, as JSON otherwise.
+pub fn parse_catalog(content_type: Option<&str>, body: &[u8]) -> Result<Catalog, CatalogError> {
+    let is_yaml = content_type.is_some_and(|content_type| content_type.contains("yaml"));
+    let catalog: Catalog = if is_yaml {
+        serde_yaml::from_slice(body).map_err(|e| CatalogError::InvalidDocument(e.to_string()))?
+    } else {
+        let des = &mut serde_json::Deserializer::from_slice(body);
+        serde_path_to_error::deserialize(des).map_err(|e| CatalogError::InvalidDocument(e.to_string()))?
+    };
+    catalog.validate()?;
+    Ok(catalog)
+}
+
+pub async fn import_handler(
+    user: GitHubUser,
+    params: ImportParams,
+    content_type: Option<String>,
+    body: Bytes,
+    db_access: impl DBRole + DBCatalog + DBTaxonomy,
+) -> Result<impl Reply, Rejection> {
+    let roles = DBRole::user_roles(&db_access, &user.username)?;
+    user_has_at_least_one_role(roles, vec![KudosRole::Admin])?;
+
+    let catalog = parse_catalog(content_type.as_deref(), &body).map_err(|e| {
+        warn!("invalid catalog: {e}");
+        reject::custom(e)
+    })?;
+    if let Some(slug) = db_access.deleted_slugs(&catalog)?.into_iter().next() {
+        return Err(reject::custom(CatalogError::Deleted(slug)));
+    }
+    if let Some((former, current)) = db_access.former_slugs(&catalog)?.into_iter().next() {
+        return Err(reject::custom(CatalogError::Renamed(former, current)));
+    }
+    for project in &catalog.projects {
+        if let Some((kind, unknown)) = unknown_tags(&db_access, project.tags())? {
+            let detail = format!("{}: {}", kind.project_column(), unknown.join(", "));
+            return Err(reject::custom(CatalogError::UnknownTerms(project.slug.clone(), detail)));
+        }
+    }
+    let diff = db_access.import_catalog(&catalog, params.dry_run)?;
+    info!(
+        "catalog import by '{}'{}: projects +{} ~{} -{}, repositories +{} ~{} -{}",
+        user.username,
+        if params.dry_run { " (dry run)" } else { "" },
+        diff.projects.created.len(),
+        diff.projects.updated.len(),
+        diff.projects.deleted.len(),
+        diff.repositories.created.len(),
+        diff.repositories.updated.len(),
+        diff.repositories.deleted.len(),
+    );
+    Ok(json(&diff))
+}
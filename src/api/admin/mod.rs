@@ -0,0 +1,5 @@
+pub mod db;
+pub mod errors;
+pub mod handlers;
+pub mod models;
+pub mod routes;
@@ -0,0 +1,277 @@
+use std::collections::{HashMap, HashSet};
+
+use serde_derive::{Deserialize, Serialize};
+
+use super::errors::CatalogError;
+use crate::api::{projects::models::Project, repositories::models::Repository, taxonomy::models::TaxonomyKind};
+
+/// Version of the catalog document written by export and accepted by import.
+pub const CATALOG_VERSION: u32 = 1;
+
+/// The ecosystem catalog: every project and its repositories, identified by
+/// slug so a document can move between environments.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Catalog {
+    pub version: u32,
+    #[serde(default)]
+    pub projects: Vec<CatalogProject>,
+}
+
+impl Catalog {
+    /// Checks the version, and that slugs are present and unique across the
+    /// catalog, for repositories as well as projects.
+    pub fn validate(&self) -> Result<(), CatalogError> {
+        if self.version != CATALOG_VERSION {
+            return Err(CatalogError::UnsupportedVersion(self.version));
+        }
+        let mut project_slugs = HashSet::new();
+        let mut repository_slugs = HashSet::new();
+        for project in &self.projects {
+            if project.slug.trim().is_empty() || project.name.trim().is_empty() {
+                return Err(CatalogError::InvalidDocument("projects need a name and a slug".to_owned()));
+            }
+            if !project_slugs.insert(project.slug.as_str()) {
+                return Err(CatalogError::DuplicateSlug(project.slug.clone()));
+            }
+            for repository in &project.repositories {
+                if repository.slug.trim().is_empty() || repository.url.trim().is_empty() {
+                    return Err(CatalogError::InvalidDocument(format!(
+                        "repositories of '{}' need a slug and a url",
+                        project.slug
+                    )));
+                }
+                if !repository_slugs.insert(repository.slug.as_str()) {
+                    return Err(CatalogError::DuplicateSlug(format!("{}/{}", project.slug, repository.slug)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The repositories by slug, with the slug of their project.
+    fn repositories(&self) -> HashMap<&str, (&str, &CatalogRepository)> {
+        self.projects
+            .iter()
+            .flat_map(|project| {
+                project
+                    .repositories
+                    .iter()
+                    .map(move |repository| (repository.slug.as_str(), (project.slug.as_str(), repository)))
+            })
+            .collect()
+    }
+
+    /// What importing `desired` over this catalog creates, updates and deletes.
+    /// Repositories are matched by slug across projects, one found under
+    /// another project is moved and reported with a changed `project`.
+    pub fn diff(&self, desired: &Catalog) -> CatalogDiff {
+        let mut diff = CatalogDiff::default();
+        let current: HashMap<&str, &CatalogProject> =
+            self.projects.iter().map(|project| (project.slug.as_str(), project)).collect();
+        let wanted: HashSet<&str> = desired.projects.iter().map(|project| project.slug.as_str()).collect();
+        let current_repositories = self.repositories();
+        let wanted_repositories = desired.repositories();
+
+        for project in &desired.projects {
+            let existing = current.get(project.slug.as_str());
+            match existing {
+                None => diff.projects.created.push(project.slug.clone()),
+                Some(existing) => {
+                    let fields = existing.changed_fields(project);
+                    if !fields.is_empty() {
+                        diff.projects.updated.push(CatalogUpdate {
+                            slug: project.slug.clone(),
+                            fields,
+                        });
+                    }
+                }
+            }
+
+            for repository in &project.repositories {
+                let slug = format!("{}/{}", project.slug, repository.slug);
+                match current_repositories.get(repository.slug.as_str()) {
+                    None => diff.repositories.created.push(slug),
+                    Some((project_slug, existing)) => {
+                        let mut fields = existing.changed_fields(repository);
+                        if *project_slug != project.slug {
+                            fields.insert(0, "project".to_owned());
+                        }
+                        if !fields.is_empty() {
+                            diff.repositories.updated.push(CatalogUpdate { slug, fields });
+                        }
+                    }
+                }
+            }
+        }
+
+        for project in &self.projects {
+            if !wanted.contains(project.slug.as_str()) {
+                diff.projects.deleted.push(project.slug.clone());
+            }
+            for repository in &project.repositories {
+                if !wanted_repositories.contains_key(repository.slug.as_str()) {
+                    diff.repositories.deleted.push(format!("{}/{}", project.slug, repository.slug));
+                }
+            }
+        }
+        diff
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CatalogProject {
+    pub name: String,
+    pub slug: String,
+    #[serde(default)]
+    pub avatar: Option<String>,
+    #[serde(default)]
+    pub types: Vec<String>,
+    #[serde(default)]
+    pub purposes: Vec<String>,
+    #[serde(default)]
+    pub stack_levels: Vec<String>,
+    #[serde(default)]
+    pub technologies: Vec<String>,
+    #[serde(default)]
+    pub rewards: bool,
+    #[serde(default)]
+    pub repositories: Vec<CatalogRepository>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CatalogRepository {
+    pub name: String,
+    pub slug: String,
+    pub url: String,
+    #[serde(default)]
+    pub language_slug: Option<String>,
+}
+
+fn tags(values: &Option<Vec<Option<String>>>) -> Vec<String> {
+    values.iter().flatten().flatten().cloned().collect()
+}
+
+/// Tags are stored as nullable arrays, an empty list is stored as NULL.
+pub fn stored_tags(values: &[String]) -> Option<Vec<Option<String>>> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().cloned().map(Some).collect())
+    }
+}
+
+impl CatalogProject {
+    /// The tags taking their values from the taxonomy.
+    pub fn tags(&self) -> [(TaxonomyKind, Vec<String>); 4] {
+        [
+            (TaxonomyKind::Type, self.types.clone()),
+            (TaxonomyKind::Purpose, self.purposes.clone()),
+            (TaxonomyKind::StackLevel, self.stack_levels.clone()),
+            (TaxonomyKind::Technology, self.technologies.clone()),
+        ]
+    }
+
+    pub fn from_rows(project: &Project, repositories: Vec<CatalogRepository>) -> Self {
+        Self {
+            name: project.name.clone(),
+            slug: project.slug.clone(),
+            avatar: project.avatar.clone(),
+            types: tags(&project.types),
+            purposes: tags(&project.purposes),
+            stack_levels: tags(&project.stack_levels),
+            technologies: tags(&project.technologies),
+            rewards: project.rewards,
+            repositories,
+        }
+    }
+
+    /// Names of the project fields that differ from `other`, repositories aside.
+    pub fn changed_fields(&self, other: &CatalogProject) -> Vec<String> {
+        let mut fields = Vec::new();
+        let mut compare = |name: &str, changed: bool| {
+            if changed {
+                fields.push(name.to_owned());
+            }
+        };
+        compare("name", self.name != other.name);
+        compare("avatar", self.avatar != other.avatar);
+        compare("types", self.types != other.types);
+        compare("purposes", self.purposes != other.purposes);
+        compare("stack_levels", self.stack_levels != other.stack_levels);
+        compare("technologies", self.technologies != other.technologies);
+        compare("rewards", self.rewards != other.rewards);
+        fields
+    }
+}
+
+impl From<&Repository> for CatalogRepository {
+    fn from(repository: &Repository) -> Self {
+        Self {
+            name: repository.name.clone(),
+            slug: repository.slug.clone(),
+            url: repository.url.clone(),
+            language_slug: repository.language_slug.clone(),
+        }
+    }
+}
+
+impl CatalogRepository {
+    /// Names of the repository fields that differ from `other`.
+    pub fn changed_fields(&self, other: &CatalogRepository) -> Vec<String> {
+        let mut fields = Vec::new();
+        let mut compare = |name: &str, changed: bool| {
+            if changed {
+                fields.push(name.to_owned());
+            }
+        };
+        compare("name", self.name != other.name);
+        compare("url", self.url != other.url);
+        compare("language_slug", self.language_slug != other.language_slug);
+        fields
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CatalogFormat {
+    #[default]
+    Json,
+    Yaml,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ExportParams {
+    #[serde(default)]
+    pub format: CatalogFormat,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ImportParams {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CatalogUpdate {
+    pub slug: String,
+    pub fields: Vec<String>,
+}
+
+/// Slugs created, updated or deleted by an import. Repositories are named
+/// `<project slug>/<repository slug>`.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct CatalogChanges {
+    pub created: Vec<String>,
+    pub updated: Vec<CatalogUpdate>,
+    pub deleted: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct CatalogDiff {
+    pub dry_run: bool,
+    pub projects: CatalogChanges,
+    pub repositories: CatalogChanges,
+}
@@ -0,0 +1,58 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use warp::filters::BoxedFilter;
+use warp::{Filter, Reply};
+
+use crate::api::{roles::db::DBRole, taxonomy::db::DBTaxonomy};
+use crate::config::Config;
+use crate::middlewares::github::auth::with_github_auth;
+
+use super::db::DBCatalog;
+use super::handlers;
+use super::models::{ExportParams, ImportParams};
+
+/// Largest catalog document accepted by the import.
+const MAX_CATALOG_BYTES: u64 = 8 * 1024 * 1024;
+
+fn with_db(
+    db_pool: impl DBRole + DBCatalog + DBTaxonomy,
+) -> impl Filter<Extract = (impl DBRole + DBCatalog + DBTaxonomy,), Error = Infallible> + Clone {
+    warp::any().map(move || db_pool.clone())
+}
+
+fn with_config(config: Arc<Config>) -> impl Filter<Extract = (Arc<Config>,), Error = Infallible> + Clone {
+    warp::any().map(move || config.clone())
+}
+
+pub fn routes(db_access: impl DBRole + DBCatalog + DBTaxonomy, config: Arc<Config>) -> BoxedFilter<(impl Reply,)> {
+    let admin_config = warp::path!("admin" / "config");
+    let admin_export = warp::path!("admin" / "export");
+    let admin_import = warp::path!("admin" / "import");
+
+    let get_config = admin_config
+        .and(warp::get())
+        .and(with_github_auth())
+        .and(with_db(db_access.clone()))
+        .and(with_config(config))
+        .and_then(handlers::config_handler);
+
+    let export = admin_export
+        .and(warp::get())
+        .and(with_github_auth())
+        .and(warp::query::<ExportParams>())
+        .and(with_db(db_access.clone()))
+        .and_then(handlers::export_handler);
+
+    let import = admin_import
+        .and(warp::post())
+        .and(with_github_auth())
+        .and(warp::query::<ImportParams>())
+        .and(warp::header::optional::<String>("content-type"))
+        .and(warp::body::content_length_limit(MAX_CATALOG_BYTES))
+        .and(warp::body::bytes())
+        .and(with_db(db_access))
+        .and_then(handlers::import_handler);
+
+    get_config.or(export).or(import).boxed()
+}
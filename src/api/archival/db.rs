@@ -0,0 +1,161 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel::sql_query;
+use diesel::sql_types::{BigInt, Integer, Timestamptz};
+
+use super::models::{ArchivalKind, DeletedItem, ParentStamped, PurgeCounts, Stamp, StampedAt, Stamps};
+use crate::types::PaginationParams;
+
+use crate::db::{
+    errors::DBError,
+    pool::DBAccess,
+    types::DBConn,
+};
+
+/// Stamps row `id` of `kind` and the rows that go with it, leaving the rows
+/// already stamped as they are. Returns whether the row itself was stamped.
+pub fn mark(conn: &mut DBConn, kind: ArchivalKind, id: i32, stamp: Stamp, at: DateTime<Utc>) -> Result<bool, DBError> {
+    conn.transaction(|conn| {
+        let mut stamped = false;
+        for (index, (table, condition)) in kind.cascade().iter().enumerate() {
+            let column = stamp.column();
+            let count = sql_query(format!(
+                "UPDATE {table} SET {column} = $2 WHERE ({condition}) AND {column} IS NULL"
+            ))
+            .bind::<Integer, _>(id)
+            .bind::<Timestamptz, _>(at)
+            .execute(conn)?;
+            if index == 0 {
+                if count == 0 {
+                    return Ok(false);
+                }
+                stamped = true;
+            }
+        }
+        Ok(stamped)
+    })
+}
+
+/// Clears the stamp of row `id` of `kind` and of the rows stamped along with
+/// it, leaving the rows stamped on their own as they are.
+pub fn clear(conn: &mut DBConn, kind: ArchivalKind, id: i32, stamp: Stamp) -> Result<(), DBError> {
+    conn.transaction(|conn| {
+        let column = stamp.column();
+        let at = sql_query(format!("SELECT {column} AS at FROM {kind} WHERE id = $1 FOR UPDATE"))
+            .bind::<Integer, _>(id)
+            .get_result::<StampedAt>(conn)
+            .optional()?
+            .and_then(|stamped| stamped.at);
+        let Some(at) = at else {
+            return Ok(());
+        };
+        for (table, condition) in kind.cascade() {
+            sql_query(format!("UPDATE {table} SET {column} = NULL WHERE ({condition}) AND {column} = $2"))
+                .bind::<Integer, _>(id)
+                .bind::<Timestamptz, _>(at)
+                .execute(conn)?;
+        }
+        Ok(())
+    })
+}
+
+pub trait DBArchival: Send + Sync + Clone + 'static {
+    /// When row `id` of `kind` was archived and deleted, `None` if it doesn't exist.
+    fn stamps(&self, kind: ArchivalKind, id: i32) -> Result<Option<Stamps>, DBError>;
+    fn archive(&self, kind: ArchivalKind, id: i32) -> Result<bool, DBError>;
+    fn soft_delete(&self, kind: ArchivalKind, id: i32) -> Result<bool, DBError>;
+    /// Brings back the row and the rows deleted along with it. Archived rows
+    /// stay archived.
+    fn restore(&self, kind: ArchivalKind, id: i32) -> Result<(), DBError>;
+    /// Unarchives the row and the rows archived along with it.
+    fn unarchive(&self, kind: ArchivalKind, id: i32) -> Result<(), DBError>;
+    /// Whether a parent of row `id` carries `stamp`.
+    fn parent_stamped(&self, kind: ArchivalKind, id: i32, stamp: Stamp) -> Result<bool, DBError>;
+    /// Deleted rows of `kind`, most recently deleted first.
+    fn deleted(&self, kind: ArchivalKind, pagination: PaginationParams) -> Result<(Vec<DeletedItem>, i64), DBError>;
+    /// Hard deletes the rows deleted before `before`.
+    fn purge_deleted(&self, before: DateTime<Utc>) -> Result<PurgeCounts, DBError>;
+}
+
+#[derive(QueryableByName)]
+struct Count {
+    #[diesel(sql_type = BigInt)]
+    count: i64,
+}
+
+impl DBArchival for DBAccess {
+    fn stamps(&self, kind: ArchivalKind, id: i32) -> Result<Option<Stamps>, DBError> {
+        self.with_conn(|conn| {
+            let stamps = sql_query(format!("SELECT archived_at, deleted_at FROM {kind} WHERE id = $1"))
+                .bind::<Integer, _>(id)
+                .get_result::<Stamps>(conn)
+                .optional()?;
+            Ok(stamps)
+        })
+    }
+
+    fn archive(&self, kind: ArchivalKind, id: i32) -> Result<bool, DBError> {
+        self.with_conn(|conn| mark(conn, kind, id, Stamp::Archived, Utc::now()))
+    }
+
+    fn soft_delete(&self, kind: ArchivalKind, id: i32) -> Result<bool, DBError> {
+        self.with_conn(|conn| mark(conn, kind, id, Stamp::Deleted, Utc::now()))
+    }
+
+    fn restore(&self, kind: ArchivalKind, id: i32) -> Result<(), DBError> {
+        self.with_conn(|conn| clear(conn, kind, id, Stamp::Deleted))
+    }
+
+    fn unarchive(&self, kind: ArchivalKind, id: i32) -> Result<(), DBError> {
+        self.with_conn(|conn| clear(conn, kind, id, Stamp::Archived))
+    }
+
+    fn parent_stamped(&self, kind: ArchivalKind, id: i32, stamp: Stamp) -> Result<bool, DBError> {
+        let Some(query) = kind.parent_stamped(stamp) else {
+            return Ok(false);
+        };
+        self.with_conn(|conn| {
+            let result = sql_query(query)
+                .bind::<Integer, _>(id)
+                .get_result::<ParentStamped>(conn)?;
+            Ok(result.stamped)
+        })
+    }
+
+    fn deleted(&self, kind: ArchivalKind, pagination: PaginationParams) -> Result<(Vec<DeletedItem>, i64), DBError> {
+        self.with_conn(|conn| {
+            let total = sql_query(format!("SELECT count(*) AS count FROM {kind} WHERE deleted_at IS NOT NULL"))
+                .get_result::<Count>(conn)?
+                .count;
+            let items = sql_query(format!(
+                "SELECT id, {label} AS label, deleted_at FROM {kind} WHERE deleted_at IS NOT NULL
+                ORDER BY deleted_at DESC, id DESC OFFSET $1 LIMIT $2",
+                label = kind.label_column()
+            ))
+            .bind::<BigInt, _>(pagination.offset)
+            .bind::<BigInt, _>(pagination.limit)
+            .load::<DeletedItem>(conn)?;
+            Ok((items, total))
+        })
+    }
+
+    fn purge_deleted(&self, before: DateTime<Utc>) -> Result<PurgeCounts, DBError> {
+        self.with_conn(|conn| {
+            conn.transaction(|conn| {
+                // children first, so the counts don't hide the rows removed by
+                // the ON DELETE CASCADE of their parent
+                let mut purge = |kind: ArchivalKind| {
+                    sql_query(format!("DELETE FROM {kind} WHERE deleted_at < $1"))
+                        .bind::<Timestamptz, _>(before)
+                        .execute(conn)
+                };
+                Ok(PurgeCounts {
+                    tasks: purge(ArchivalKind::Tasks)?,
+                    issues: purge(ArchivalKind::Issues)?,
+                    repositories: purge(ArchivalKind::Repositories)?,
+                    projects: purge(ArchivalKind::Projects)?,
+                })
+            })
+        })
+    }
+}
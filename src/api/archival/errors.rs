@@ -0,0 +1,50 @@
+use std::fmt;
+
+use serde_derive::Deserialize;
+use thiserror::Error;
+use warp::{
+    http::StatusCode,
+    reject::Reject,
+    reply::{Reply, Response},
+};
+
+use crate::errors::ErrorResponse;
+
+#[derive(Clone, Error, Debug, Deserialize, PartialEq)]
+pub enum ArchivalError {
+    NotFound(String, i32),
+    ParentDeleted(String, i32),
+    ParentArchived(String, i32),
+}
+
+impl fmt::Display for ArchivalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchivalError::NotFound(kind, id) => write!(f, "{kind} #{id} not found"),
+            ArchivalError::ParentDeleted(kind, id) => {
+                write!(f, "{kind} #{id} belongs to a deleted parent, restore it first")
+            }
+            ArchivalError::ParentArchived(kind, id) => {
+                write!(f, "{kind} #{id} belongs to an archived parent, unarchive it first")
+            }
+        }
+    }
+}
+
+impl Reject for ArchivalError {}
+
+impl Reply for ArchivalError {
+    fn into_response(self) -> Response {
+        let code = match self {
+            ArchivalError::NotFound(..) => StatusCode::NOT_FOUND,
+            ArchivalError::ParentDeleted(..) | ArchivalError::ParentArchived(..) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+        };
+        let message = self.to_string();
+
+        let json = warp::reply::json(&ErrorResponse::new(message));
+
+        warp::reply::with_status(json, code).into_response()
+    }
+}
@@ -0,0 +1,96 @@
+use log::info;
+use warp::{
+    http::StatusCode,
+    reject,
+    reject::Rejection,
+    reply::{json, Reply},
+};
+
+use crate::{
+    api::roles::{db::DBRole, models::KudosRole, utils::user_has_at_least_one_role},
+    middlewares::github::model::GitHubUser,
+    types::{PaginatedResponse, PaginationParams},
+};
+
+use super::{
+    db::DBArchival,
+    errors::ArchivalError,
+    models::{ArchivalKind, Stamp},
+};
+
+fn authorize(user: &GitHubUser, db_access: &impl DBRole) -> Result<(), Rejection> {
+    let roles = DBRole::user_roles(db_access, &user.username)?;
+    user_has_at_least_one_role(roles, vec![KudosRole::Admin])
+}
+
+pub async fn archive_handler(
+    kind: ArchivalKind,
+    id: i32,
+    user: GitHubUser,
+    db_access: impl DBArchival + DBRole,
+) -> Result<impl Reply, Rejection> {
+    authorize(&user, &db_access)?;
+    match db_access.stamps(kind, id)? {
+        Some(stamps) if stamps.deleted_at.is_none() => {
+            if db_access.archive(kind, id)? {
+                info!("{kind} #{id} archived by {}", user.username);
+            }
+            Ok(StatusCode::NO_CONTENT)
+        }
+        _ => Err(reject::custom(ArchivalError::NotFound(kind.to_string(), id))),
+    }
+}
+
+pub async fn restore_handler(
+    kind: ArchivalKind,
+    id: i32,
+    user: GitHubUser,
+    db_access: impl DBArchival + DBRole,
+) -> Result<impl Reply, Rejection> {
+    authorize(&user, &db_access)?;
+    if db_access.stamps(kind, id)?.is_none() {
+        return Err(reject::custom(ArchivalError::NotFound(kind.to_string(), id)));
+    }
+    if db_access.parent_stamped(kind, id, Stamp::Deleted)? {
+        return Err(reject::custom(ArchivalError::ParentDeleted(kind.to_string(), id)));
+    }
+    db_access.restore(kind, id)?;
+    info!("{kind} #{id} restored by {}", user.username);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn unarchive_handler(
+    kind: ArchivalKind,
+    id: i32,
+    user: GitHubUser,
+    db_access: impl DBArchival + DBRole,
+) -> Result<impl Reply, Rejection> {
+    authorize(&user, &db_access)?;
+    match db_access.stamps(kind, id)? {
+        Some(stamps) if stamps.deleted_at.is_none() => {
+            if db_access.parent_stamped(kind, id, Stamp::Archived)? {
+                return Err(reject::custom(ArchivalError::ParentArchived(kind.to_string(), id)));
+            }
+            db_access.unarchive(kind, id)?;
+            info!("{kind} #{id} unarchived by {}", user.username);
+            Ok(StatusCode::NO_CONTENT)
+        }
+        _ => Err(reject::custom(ArchivalError::NotFound(kind.to_string(), id))),
+    }
+}
+
+pub async fn deleted_handler(
+    kind: ArchivalKind,
+    user: GitHubUser,
+    pagination: PaginationParams,
+    db_access: impl DBArchival + DBRole,
+) -> Result<impl Reply, Rejection> {
+    authorize(&user, &db_access)?;
+    let (items, total_count) = db_access.deleted(kind, pagination.clone())?;
+    Ok(json(&PaginatedResponse {
+        total_count: Some(total_count),
+        has_next_page: pagination.offset + pagination.limit < total_count,
+        has_previous_page: pagination.offset > 0,
+        data: items,
+    }))
+}
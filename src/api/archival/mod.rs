@@ -0,0 +1,6 @@
+pub mod db;
+pub mod errors;
+pub mod handlers;
+pub mod models;
+pub mod retention;
+pub mod routes;
@@ -0,0 +1,161 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use diesel::sql_types::{Integer, Nullable, Text, Timestamptz};
+use diesel::QueryableByName;
+use serde::Serialize;
+
+/// The tables that are archived and deleted softly, named as in the paths.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchivalKind {
+    Projects,
+    Repositories,
+    Issues,
+    Tasks,
+}
+
+impl ArchivalKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArchivalKind::Projects => "projects",
+            ArchivalKind::Repositories => "repositories",
+            ArchivalKind::Issues => "issues",
+            ArchivalKind::Tasks => "tasks",
+        }
+    }
+
+    /// The rows that go with row `$1`: each table with its condition, the
+    /// row itself first.
+    pub fn cascade(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            ArchivalKind::Projects => &[
+                ("projects", "id = $1"),
+                ("repositories", "project_id = $1"),
+                ("issues", "repository_id IN (SELECT id FROM repositories WHERE project_id = $1)"),
+                ("tasks", "project_id = $1 OR repository_id IN (SELECT id FROM repositories WHERE project_id = $1)"),
+            ],
+            ArchivalKind::Repositories => &[
+                ("repositories", "id = $1"),
+                ("issues", "repository_id = $1"),
+                ("tasks", "repository_id = $1"),
+            ],
+            ArchivalKind::Issues => &[("issues", "id = $1")],
+            ArchivalKind::Tasks => &[("tasks", "id = $1")],
+        }
+    }
+
+    /// Whether a parent of row `$1` carries `stamp`, which must be cleared
+    /// there first.
+    pub fn parent_stamped(&self, stamp: Stamp) -> Option<String> {
+        let column = stamp.column();
+        match self {
+            ArchivalKind::Projects => None,
+            ArchivalKind::Repositories => Some(format!(
+                "SELECT EXISTS (SELECT 1 FROM repositories r JOIN projects p ON p.id = r.project_id
+                WHERE r.id = $1 AND p.{column} IS NOT NULL) AS stamped"
+            )),
+            ArchivalKind::Issues => Some(format!(
+                "SELECT EXISTS (SELECT 1 FROM issues i JOIN repositories r ON r.id = i.repository_id
+                WHERE i.id = $1 AND r.{column} IS NOT NULL) AS stamped"
+            )),
+            ArchivalKind::Tasks => Some(format!(
+                "SELECT EXISTS (SELECT 1 FROM tasks t
+                LEFT JOIN projects p ON p.id = t.project_id
+                LEFT JOIN repositories r ON r.id = t.repository_id
+                WHERE t.id = $1 AND (p.{column} IS NOT NULL OR r.{column} IS NOT NULL)) AS stamped"
+            )),
+        }
+    }
+
+    /// The column describing a row in the listing of deleted rows.
+    pub fn label_column(&self) -> &'static str {
+        match self {
+            ArchivalKind::Projects | ArchivalKind::Repositories => "slug",
+            ArchivalKind::Issues | ArchivalKind::Tasks => "title",
+        }
+    }
+}
+
+impl fmt::Display for ArchivalKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ArchivalKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "projects" => Ok(ArchivalKind::Projects),
+            "repositories" => Ok(ArchivalKind::Repositories),
+            "issues" => Ok(ArchivalKind::Issues),
+            "tasks" => Ok(ArchivalKind::Tasks),
+            _ => Err(format!("unknown kind '{s}'")),
+        }
+    }
+}
+
+/// The column stamped by archiving or deleting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stamp {
+    Archived,
+    Deleted,
+}
+
+impl Stamp {
+    pub fn column(&self) -> &'static str {
+        match self {
+            Stamp::Archived => "archived_at",
+            Stamp::Deleted => "deleted_at",
+        }
+    }
+}
+
+#[derive(QueryableByName, Serialize, Debug, Clone, PartialEq)]
+pub struct Stamps {
+    #[diesel(sql_type = Nullable<Timestamptz>)]
+    pub archived_at: Option<DateTime<Utc>>,
+    #[diesel(sql_type = Nullable<Timestamptz>)]
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(QueryableByName, Serialize, Debug, Clone, PartialEq)]
+pub struct DeletedItem {
+    #[diesel(sql_type = Integer)]
+    pub id: i32,
+    /// Slug of projects and repositories, title of issues and tasks.
+    #[diesel(sql_type = Text)]
+    pub label: String,
+    #[diesel(sql_type = Timestamptz)]
+    pub deleted_at: DateTime<Utc>,
+}
+
+#[derive(QueryableByName, Debug)]
+pub struct StampedAt {
+    #[diesel(sql_type = Nullable<Timestamptz>)]
+    pub at: Option<DateTime<Utc>>,
+}
+
+#[derive(QueryableByName, Debug)]
+pub struct ParentStamped {
+    #[diesel(sql_type = diesel::sql_types::Bool)]
+    pub stamped: bool,
+}
+
+/// Rows hard deleted by a purge, per table.
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct PurgeCounts {
+    pub projects: usize,
+    pub repositories: usize,
+    pub issues: usize,
+    pub tasks: usize,
+}
+
+impl PurgeCounts {
+    pub fn total(&self) -> usize {
+        self.projects + self.repositories + self.issues + self.tasks
+    }
+}
@@ -0,0 +1,16 @@
+use chrono::Utc;
+use log::info;
+
+use super::db::DBArchival;
+
+/// Hard deletes the rows deleted more than `retention_days` ago.
+pub async fn purge_deleted(db_access: impl DBArchival, retention_days: i64) -> Result<(), String> {
+    let deleted_before = Utc::now() - chrono::Duration::days(retention_days);
+    let counts = db_access
+        .purge_deleted(deleted_before)
+        .map_err(|e| format!("error purging deleted rows: {e}"))?;
+    if counts.total() > 0 {
+        info!("purged rows deleted before {deleted_before}: {counts:?}");
+    }
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use std::convert::Infallible;
+
+use warp::filters::BoxedFilter;
+use warp::{Filter, Reply};
+
+use crate::api::roles::db::DBRole;
+use crate::middlewares::github::auth::with_github_auth;
+use crate::types::PaginationParams;
+
+use super::db::DBArchival;
+use super::handlers;
+use super::models::ArchivalKind;
+
+fn with_db(
+    db_pool: impl DBArchival + DBRole,
+) -> impl Filter<Extract = (impl DBArchival + DBRole,), Error = Infallible> + Clone {
+    warp::any().map(move || db_pool.clone())
+}
+
+/// `/{kind}/{id}/archive`, `/{kind}/{id}/unarchive`, `/{kind}/{id}/restore`
+/// and `/{kind}/deleted`, next to the routes of each kind.
+pub fn routes(db_access: impl DBArchival + DBRole) -> BoxedFilter<(impl Reply,)> {
+    let archive = warp::path!(ArchivalKind / i32 / "archive")
+        .and(warp::post())
+        .and(with_github_auth())
+        .and(with_db(db_access.clone()))
+        .and_then(handlers::archive_handler);
+
+    let unarchive = warp::path!(ArchivalKind / i32 / "unarchive")
+        .and(warp::post())
+        .and(with_github_auth())
+        .and(with_db(db_access.clone()))
+        .and_then(handlers::unarchive_handler);
+
+    let restore = warp::path!(ArchivalKind / i32 / "restore")
+        .and(warp::post())
+        .and(with_github_auth())
+        .and(with_db(db_access.clone()))
+        .and_then(handlers::restore_handler);
+
+    let deleted = warp::path!(ArchivalKind / "deleted")
+        .and(warp::get())
+        .and(with_github_auth())
+        .and(warp::query::<PaginationParams>())
+        .and(with_db(db_access))
+        .and_then(handlers::deleted_handler);
+
+    archive.or(unarchive).or(restore).or(deleted).boxed()
+}
@@ -0,0 +1,393 @@
+use chrono::{DateTime, Utc};
+use diesel::{
+    pg::Pg,
+    query_builder::QueryFragment,
+    prelude::*,
+    dsl::not,
+    expression::{is_aggregate::No, ValidGrouping},
+    sql_types::{Array, Bool, Integer, Nullable, Text},
+};
+use serde_derive::Deserialize;
+
+use crate::api::issues::models::QueryParams as IssueParams;
+use crate::api::projects::models::QueryParams as ProjectParams;
+use crate::api::repositories::models::LanguageQueryParams as LanguageParams;
+use crate::schema::{issues, projects, repositories, slug_history, tasks};
+use crate::utils;
+
+/// A boxed `WHERE` condition on the table `QS`. Conditions on related tables
+/// are expressed as subqueries; queries joining `QS` with other tables apply
+/// it through the ids it selects.
+pub type Predicate<QS> = Box<dyn BoxableExpression<QS, Pg, SqlType = Nullable<Bool>>>;
+
+/// Adds `next` to the conditions in `all`.
+fn and<QS: 'static>(all: Option<Predicate<QS>>, next: Predicate<QS>) -> Option<Predicate<QS>> {
+    Some(match all {
+        Some(all) => Box::new(all.and(next)),
+        None => next,
+    })
+}
+
+fn values(param: &Option<String>) -> Option<Vec<String>> {
+    param.as_deref().map(utils::parse_comma_values)
+}
+
+/// Conditions on the project of an issue, task or repository.
+#[derive(Debug, Clone, Default)]
+pub struct ProjectFilter {
+    pub slugs: Option<Vec<String>>,
+    pub purposes: Option<Vec<String>>,
+    pub stack_levels: Option<Vec<String>>,
+    pub technologies: Option<Vec<String>>,
+    pub types: Option<Vec<String>>,
+    pub rewards: Option<bool>,
+}
+
+impl ProjectFilter {
+    pub fn new(
+        slugs: &Option<String>,
+        purposes: &Option<String>,
+        stack_levels: &Option<String>,
+        technologies: &Option<String>,
+        types: &Option<String>,
+        rewards: Option<bool>,
+    ) -> Self {
+        Self {
+            slugs: values(slugs),
+            purposes: values(purposes),
+            stack_levels: values(stack_levels),
+            technologies: values(technologies),
+            types: values(types),
+            rewards,
+        }
+    }
+
+    pub fn predicate(&self) -> Option<Predicate<projects::table>> {
+        let mut all = None;
+        if let Some(slugs) = self.slugs.clone() {
+            // the former slugs of renamed projects still match them
+            let renamed = slug_history::table
+                .filter(slug_history::slug.eq_any(slugs.clone()))
+                .select(slug_history::project_id);
+            all = and(
+                all,
+                Box::new(projects::slug.eq_any(slugs).or(projects::id.nullable().eq_any(renamed)).nullable()),
+            );
+        }
+        if let Some(purposes) = self.purposes.clone() {
+            all = and(all, Box::new(projects::purposes.overlaps_with(purposes)));
+        }
+        if let Some(stack_levels) = self.stack_levels.clone() {
+            all = and(all, Box::new(projects::stack_levels.overlaps_with(stack_levels)));
+        }
+        if let Some(technologies) = self.technologies.clone() {
+            all = and(all, Box::new(projects::technologies.overlaps_with(technologies)));
+        }
+        if let Some(types) = self.types.clone() {
+            all = and(all, Box::new(projects::types.overlaps_with(types)));
+        }
+        if let Some(rewards) = self.rewards {
+            all = and(all, Box::new(projects::rewards.eq(rewards).nullable()));
+        }
+        all
+    }
+
+    /// Ids of the matching projects, `None` when every project matches.
+    pub fn ids(&self) -> Option<projects::BoxedQuery<'static, Pg, Integer>> {
+        let predicate = self.predicate()?;
+        Some(projects::table.filter(predicate).select(projects::id).into_boxed())
+    }
+}
+
+/// The items whose projects are listed or counted: GitHub issues or tasks.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemSource {
+    #[default]
+    Issues,
+    Tasks,
+}
+
+/// How the `labels` of a filter are matched against the labels of an item.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LabelMatch {
+    /// The item has at least one of the labels.
+    #[default]
+    Any,
+    /// The item has every label.
+    All,
+}
+
+type Labels = Nullable<Array<Nullable<Text>>>;
+
+/// A column of `T` with SQL type `ST`, usable in a boxed predicate on `T`.
+trait ItemColumn<T, ST>:
+    Column<Table = T, SqlType = ST>
+    + SelectableExpression<T>
+    + QueryFragment<Pg>
+    + ValidGrouping<(), IsAggregate = No>
+    + Send
+    + 'static
+{
+}
+
+impl<T, ST, C> ItemColumn<T, ST> for C where
+    C: Column<Table = T, SqlType = ST>
+        + SelectableExpression<T>
+        + QueryFragment<Pg>
+        + ValidGrouping<(), IsAggregate = No>
+        + Send
+        + 'static
+{
+}
+
+/// Label, certification and state conditions shared by issues and tasks.
+#[derive(Debug, Clone, Default)]
+pub struct ItemFilter {
+    pub labels: Option<Vec<String>>,
+    pub label_match: LabelMatch,
+    /// Items with any of these labels are left out, whatever the other conditions.
+    pub excluded_labels: Option<Vec<String>>,
+    pub certified: Option<bool>,
+    /// Match items with the labels or the certification instead of both.
+    pub certified_or_labels: bool,
+    pub open: Option<bool>,
+}
+
+impl ItemFilter {
+    pub fn new(
+        labels: &Option<String>,
+        excluded_labels: &Option<String>,
+        label_match: Option<LabelMatch>,
+        certified: Option<bool>,
+        certified_or_labels: Option<bool>,
+        open: Option<bool>,
+    ) -> Self {
+        Self {
+            labels: values(labels),
+            label_match: label_match.unwrap_or_default(),
+            excluded_labels: values(excluded_labels),
+            certified,
+            certified_or_labels: certified_or_labels.unwrap_or(false),
+            open,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_none() && self.excluded_labels.is_none() && self.certified.is_none() && self.open.is_none()
+    }
+
+    /// Builds the conditions of the filter from the columns of one item table.
+    /// The label and certification conditions are OR-ed with
+    /// `certified_or_labels` and AND-ed otherwise.
+    fn predicate<T, L, C, O>(&self, labels: L, certified: C, open: O) -> Option<Predicate<T>>
+    where
+        T: Table + 'static,
+        L: ItemColumn<T, Labels> + Copy,
+        C: ItemColumn<T, Nullable<Bool>>,
+        O: ItemColumn<T, Bool>,
+    {
+        let matching: Option<Predicate<T>> = self.labels.clone().map(|values| match self.label_match {
+            LabelMatch::Any => Box::new(labels.overlaps_with(values)) as Predicate<T>,
+            LabelMatch::All => Box::new(labels.contains(values)),
+        });
+        let certified: Option<Predicate<T>> =
+            self.certified.map(|value| Box::new(certified.eq(value)) as Predicate<T>);
+        let mut all = match (matching, certified) {
+            (Some(matching), Some(certified)) if self.certified_or_labels => Some(Box::new(matching.or(certified)) as _),
+            (Some(matching), Some(certified)) => and(Some(matching), certified),
+            (matching, certified) => matching.or(certified),
+        };
+        if let Some(values) = self.excluded_labels.clone() {
+            // items without labels have none of the excluded ones
+            all = and(all, Box::new(labels.is_null().or(not(labels.overlaps_with(values)))));
+        }
+        if let Some(value) = self.open {
+            all = and(all, Box::new(open.eq(value).nullable()));
+        }
+        all
+    }
+
+    pub fn issue_predicate(&self) -> Option<Predicate<issues::table>> {
+        self.predicate(issues::labels, issues::certified, issues::open)
+    }
+
+    pub fn task_predicate(&self) -> Option<Predicate<tasks::table>> {
+        self.predicate(tasks::labels, tasks::is_certified, tasks::open)
+    }
+
+    /// Condition on projects having at least one matching item of `source`,
+    /// archived and deleted items aside.
+    pub fn project_predicate(&self, source: ItemSource) -> Predicate<projects::table> {
+        match source {
+            ItemSource::Issues => {
+                let mut issues = issues::table
+                    .filter(issues::archived_at.is_null())
+                    .filter(issues::deleted_at.is_null())
+                    .select(issues::repository_id)
+                    .into_boxed();
+                if let Some(predicate) = self.issue_predicate() {
+                    issues = issues.filter(predicate);
+                }
+                let repositories = repositories::table
+                    .filter(repositories::id.eq_any(issues))
+                    .select(repositories::project_id);
+                Box::new(projects::id.eq_any(repositories).nullable())
+            }
+            ItemSource::Tasks => {
+                let mut by_project = tasks::table
+                    .filter(tasks::archived_at.is_null())
+                    .filter(tasks::deleted_at.is_null())
+                    .filter(tasks::project_id.is_not_null())
+                    .select(tasks::project_id.assume_not_null())
+                    .into_boxed();
+                let mut by_repository = tasks::table
+                    .filter(tasks::archived_at.is_null())
+                    .filter(tasks::deleted_at.is_null())
+                    .filter(tasks::repository_id.is_not_null())
+                    .select(tasks::repository_id.assume_not_null())
+                    .into_boxed();
+                if let Some(predicate) = self.task_predicate() {
+                    by_project = by_project.filter(predicate);
+                }
+                if let Some(predicate) = self.task_predicate() {
+                    by_repository = by_repository.filter(predicate);
+                }
+                let repositories = repositories::table
+                    .filter(repositories::id.eq_any(by_repository))
+                    .select(repositories::project_id);
+                Box::new(
+                    projects::id
+                        .eq_any(by_project)
+                        .or(projects::id.eq_any(repositories))
+                        .nullable(),
+                )
+            }
+        }
+    }
+}
+
+/// Every condition of the issue list.
+#[derive(Debug, Clone, Default)]
+pub struct IssueFilter {
+    pub item: ItemFilter,
+    pub project: ProjectFilter,
+    pub language_slugs: Option<Vec<String>>,
+    pub repository_id: Option<i32>,
+    pub assignee_id: Option<i32>,
+    pub has_assignee: Option<bool>,
+    pub closed_at_min: Option<DateTime<Utc>>,
+    pub closed_at_max: Option<DateTime<Utc>>,
+}
+
+impl IssueFilter {
+    pub fn predicate(&self) -> Option<Predicate<issues::table>> {
+        let mut all = self.item.issue_predicate();
+        if let Some(projects) = self.project.ids() {
+            let repositories = repositories::table
+                .filter(repositories::project_id.eq_any(projects))
+                .select(repositories::id)
+                .into_boxed();
+            all = and(all, Box::new(issues::repository_id.eq_any(repositories).nullable()));
+        }
+        if let Some(language_slugs) = self.language_slugs.clone() {
+            let repositories = repositories::table
+                .filter(repositories::language_slug.eq_any(language_slugs))
+                .select(repositories::id);
+            all = and(all, Box::new(issues::repository_id.eq_any(repositories).nullable()));
+        }
+        if let Some(repository_id) = self.repository_id {
+            all = and(all, Box::new(issues::repository_id.eq(repository_id).nullable()));
+        }
+        if let Some(assignee_id) = self.assignee_id {
+            all = and(all, Box::new(issues::assignee_id.eq(assignee_id)));
+        }
+        match self.has_assignee {
+            Some(true) => all = and(all, Box::new(issues::assignee_id.is_not_null().nullable())),
+            Some(false) => all = and(all, Box::new(issues::assignee_id.is_null().nullable())),
+            None => {}
+        }
+        if let Some(closed_at_min) = self.closed_at_min {
+            all = and(all, Box::new(issues::issue_closed_at.ge(closed_at_min)));
+        }
+        if let Some(closed_at_max) = self.closed_at_max {
+            all = and(all, Box::new(issues::issue_closed_at.le(closed_at_max)));
+        }
+        all
+    }
+
+    /// Ids of the matching issues, `None` when every issue matches.
+    pub fn ids(&self) -> Option<issues::BoxedQuery<'static, Pg, Integer>> {
+        let predicate = self.predicate()?;
+        Some(issues::table.filter(predicate).select(issues::id).into_boxed())
+    }
+}
+
+impl From<&IssueParams> for IssueFilter {
+    fn from(params: &IssueParams) -> Self {
+        Self {
+            item: ItemFilter::new(
+                &params.labels,
+                &params.excluded_labels,
+                params.label_match,
+                params.certified,
+                params.certified_or_labels,
+                params.open,
+            ),
+            project: ProjectFilter::new(
+                &params.slugs,
+                &params.purposes,
+                &params.stack_levels,
+                &params.technologies,
+                &params.types,
+                params.rewards,
+            ),
+            language_slugs: values(&params.language_slugs),
+            repository_id: params.repository_id,
+            assignee_id: params.assignee_id,
+            has_assignee: params.has_assignee,
+            closed_at_min: params.issue_closed_at_min,
+            closed_at_max: params.issue_closed_at_max,
+        }
+    }
+}
+
+impl From<&ProjectParams> for ProjectFilter {
+    fn from(params: &ProjectParams) -> Self {
+        Self::new(
+            &params.slugs,
+            &params.purposes,
+            &params.stack_levels,
+            &params.technologies,
+            &params.types,
+            params.rewards,
+        )
+    }
+}
+
+impl From<&ProjectParams> for ItemFilter {
+    fn from(params: &ProjectParams) -> Self {
+        Self::new(
+            &params.labels,
+            &params.excluded_labels,
+            params.label_match,
+            params.certified,
+            params.certified_or_labels,
+            params.open,
+        )
+    }
+}
+
+impl From<&LanguageParams> for ItemFilter {
+    fn from(params: &LanguageParams) -> Self {
+        Self::new(
+            &params.labels,
+            &params.excluded_labels,
+            params.label_match,
+            params.certified,
+            params.certified_or_labels,
+            params.open,
+        )
+    }
+}
@@ -1,22 +1,47 @@
 use diesel::sql_query;
 use diesel::RunQueryDsl;
+use diesel_migrations::MigrationHarness;
 
+use super::models::PoolState;
 use crate::db::{
     errors::DBError,
-    pool::{DBAccess, DBAccessor},
+    pool::DBAccess,
+    MIGRATIONS,
 };
 
 pub trait DBHealth: Send + Sync + Clone + 'static {
     fn health(&self) -> Result<(), DBError>;
+    /// Names of the embedded migrations not applied to the database yet.
+    fn pending_migrations(&self) -> Result<Vec<String>, DBError>;
+    fn pool_state(&self) -> PoolState;
 }
 
 impl DBHealth for DBAccess {
     fn health(&self) -> Result<(), DBError> {
-        let conn = &mut self.get_db_conn();
-        sql_query("SELECT 1")
-            .execute(conn)
-            .map_err(DBError::DBQuery)?;
+        self.with_conn(|conn| {
+            sql_query("SELECT 1")
+                .execute(conn)
+                .map_err(DBError::from)?;
 
-        Ok(())
+            Ok(())
+        })
+    }
+
+    fn pending_migrations(&self) -> Result<Vec<String>, DBError> {
+        self.with_conn(|conn| {
+            let pending = conn
+                .pending_migrations(MIGRATIONS)
+                .map_err(|e| DBError::DBMigration(e.to_string()))?;
+            Ok(pending.iter().map(|migration| migration.name().to_string()).collect())
+        })
+    }
+
+    fn pool_state(&self) -> PoolState {
+        let state = self.db_pool.state();
+        PoolState {
+            connections: state.connections,
+            idle_connections: state.idle_connections,
+            max_connections: self.db_pool.max_size(),
+        }
     }
 }
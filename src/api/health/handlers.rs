@@ -1,8 +1,92 @@
-use warp::{http::StatusCode, Rejection, Reply};
+use std::{collections::BTreeMap, time::Instant};
+
+use chrono::Utc;
+use warp::{http::StatusCode, reply::json, Rejection, Reply};
 
 use super::db::DBHealth;
+use super::models::{CheckResult, CheckStatus, HealthReport};
+use crate::api::jobs::runner::{Heartbeat, POLL_INTERVAL_SECONDS};
+
+/// A job runner that has not polled for this long is considered stuck.
+const HEARTBEAT_MAX_AGE_SECONDS: i64 = 4 * POLL_INTERVAL_SECONDS as i64;
 
 pub async fn health_handler(db_access: impl DBHealth) -> Result<impl Reply, Rejection> {
     db_access.health().map_err(warp::reject::custom)?;
     Ok(StatusCode::OK)
 }
+
+pub async fn live_handler() -> Result<impl Reply, Rejection> {
+    Ok(json(&HealthReport {
+        status: CheckStatus::Ok,
+        checks: BTreeMap::new(),
+    }))
+}
+
+pub async fn ready_handler(db_access: impl DBHealth, heartbeat: Heartbeat) -> Result<impl Reply, Rejection> {
+    let mut checks = BTreeMap::new();
+
+    checks.insert(
+        "pool".to_owned(),
+        check(|| {
+            let state = db_access.pool_state();
+            let detail = format!(
+                "{} of {} connections in use",
+                state.connections - state.idle_connections,
+                state.max_connections
+            );
+            if state.is_saturated() {
+                Err(detail)
+            } else {
+                Ok(Some(detail))
+            }
+        }),
+    );
+    checks.insert(
+        "database".to_owned(),
+        check(|| db_access.health().map(|_| None).map_err(|e| e.to_string())),
+    );
+    checks.insert(
+        "migrations".to_owned(),
+        check(|| match db_access.pending_migrations() {
+            Ok(pending) if pending.is_empty() => Ok(None),
+            Ok(pending) => Err(format!("pending: {}", pending.join(", "))),
+            Err(e) => Err(e.to_string()),
+        }),
+    );
+    checks.insert(
+        "jobs".to_owned(),
+        check(|| match heartbeat.last() {
+            _ if heartbeat.is_idle() => Ok(Some("no jobs registered".to_owned())),
+            Some(last) if (Utc::now() - last).num_seconds() <= HEARTBEAT_MAX_AGE_SECONDS => {
+                Ok(Some(format!("last poll at {last}")))
+            }
+            Some(last) => Err(format!("no poll since {last}")),
+            None => Err("job runner has not polled yet".to_owned()),
+        }),
+    );
+
+    let ready = checks.values().all(|result| result.status == CheckStatus::Ok);
+    let (status, code) = match ready {
+        true => (CheckStatus::Ok, StatusCode::OK),
+        false => (CheckStatus::Fail, StatusCode::SERVICE_UNAVAILABLE),
+    };
+    Ok(warp::reply::with_status(json(&HealthReport { status, checks }), code))
+}
+
+fn check(run: impl FnOnce() -> Result<Option<String>, String>) -> CheckResult {
+    let started = Instant::now();
+    let result = run();
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+    match result {
+        Ok(detail) => CheckResult {
+            status: CheckStatus::Ok,
+            latency_ms,
+            detail,
+        },
+        Err(detail) => CheckResult {
+            status: CheckStatus::Fail,
+            latency_ms,
+            detail: Some(detail),
+        },
+    }
+}
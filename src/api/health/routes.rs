@@ -1,12 +1,35 @@
+use std::convert::Infallible;
+
 use warp::filters::BoxedFilter;
 use warp::{Filter, Reply};
 
 use super::db::DBHealth;
 use super::handlers;
+use crate::api::jobs::runner::Heartbeat;
+
+fn with_db(db_access: impl DBHealth) -> impl Filter<Extract = (impl DBHealth,), Error = Infallible> + Clone {
+    warp::any().map(move || db_access.clone())
+}
+
+fn with_heartbeat(heartbeat: Heartbeat) -> impl Filter<Extract = (Heartbeat,), Error = Infallible> + Clone {
+    warp::any().map(move || heartbeat.clone())
+}
+
+pub fn routes(db_access: impl DBHealth, heartbeat: Heartbeat) -> BoxedFilter<(impl Reply,)> {
+    let health = warp::path!("health")
+        .and(warp::get())
+        .and(with_db(db_access.clone()))
+        .and_then(handlers::health_handler);
+
+    let live = warp::path!("health" / "live")
+        .and(warp::get())
+        .and_then(handlers::live_handler);
+
+    let ready = warp::path!("health" / "ready")
+        .and(warp::get())
+        .and(with_db(db_access))
+        .and(with_heartbeat(heartbeat))
+        .and_then(handlers::ready_handler);
 
-pub fn routes(db_access: impl DBHealth) -> BoxedFilter<(impl Reply,)> {
-    warp::path!("health")
-        .and(warp::any().map(move || db_access.clone()))
-        .and_then(handlers::health_handler)
-        .boxed()
+    health.or(live).or(ready).boxed()
 }
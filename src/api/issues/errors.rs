@@ -13,6 +13,7 @@ use crate::errors::ErrorResponse;
 #[derive(Clone, Error, Debug, Deserialize, PartialEq)]
 pub enum IssueError {
     AlreadyExists(i32),
+    Deleted(i32),
     NotFound(i32),
     RepositoryNotFound(i32),
     InvalidPayload(String),
@@ -24,6 +25,7 @@ impl fmt::Display for IssueError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             IssueError::AlreadyExists(id) => write!(f, "Issue #{id} already exists"),
+            IssueError::Deleted(id) => write!(f, "Issue #{id} is deleted, restore it instead"),
             IssueError::NotFound(id) => write!(f, "Issue #{id} not found"),
             IssueError::InvalidPayload(error) => write!(f, "Invalid payload: {error}"),
             IssueError::RepositoryNotFound(id) => write!(f, "Repository #{id} not found"),
@@ -39,6 +41,7 @@ impl Reply for IssueError {
     fn into_response(self) -> Response {
         let code = match self {
             IssueError::AlreadyExists(_) => StatusCode::BAD_REQUEST,
+            IssueError::Deleted(_) => StatusCode::UNPROCESSABLE_ENTITY,
             IssueError::NotFound(_) => StatusCode::NOT_FOUND,
             IssueError::InvalidPayload(_) => StatusCode::UNPROCESSABLE_ENTITY,
             IssueError::CannotCreate(_) => StatusCode::INTERNAL_SERVER_ERROR,
@@ -47,7 +50,7 @@ impl Reply for IssueError {
         };
         let message = self.to_string();
 
-        let json = warp::reply::json(&ErrorResponse { message });
+        let json = warp::reply::json(&ErrorResponse::new(message));
 
         warp::reply::with_status(json, code).into_response()
     }
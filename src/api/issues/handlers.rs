@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use bytes::Buf;
+use serde_json::json;
 use log::{error, info, warn};
 use warp::{
     http::StatusCode,
@@ -10,7 +11,7 @@ use warp::{
 };
 
 use crate::{
-    api::{issues::models::LeaderboardEntry, repositories::db::DBRepository, roles::{db::DBRole, models::KudosRole, utils::user_has_at_least_one_role}, users::db::DBUser}, middlewares::github::model::GitHubUser, types::{PaginatedResponse, PaginationParams}
+    api::{issues::models::LeaderboardEntry, repositories::db::DBRepository, roles::{db::DBRole, models::KudosRole, utils::user_has_at_least_one_role}, users::db::DBUser, webhooks::{db::DBWebhook, models::WebhookEvent, utils::dispatch_event}}, middlewares::github::model::GitHubUser, types::{PaginatedResponse, PaginationParams}
 };
 
 use super::{
@@ -59,6 +60,8 @@ pub async fn leaderboard(
             stack_levels: params.stack_levels,
             technologies: params.technologies,
             labels: params.labels,
+            excluded_labels: None,
+            label_match: None,
             language_slugs: params.language_slug,
             repository_id: params.repository_id,
             assignee_id: None,
@@ -69,6 +72,8 @@ pub async fn leaderboard(
             rewards: params.rewards,
             certified_or_labels: Some(false),
             types: params.types,
+            // archiving a project keeps its contributions on the leaderboard
+            include_archived: Some(true),
         },
         PaginationParams {
             limit: i64::MAX,
@@ -102,7 +107,7 @@ pub async fn leaderboard(
         }
     })
     .collect();
-    leaderboard.sort_by(|a, b| b.score.cmp(&a.score));
+    leaderboard.sort_by_key(|entry| std::cmp::Reverse(entry.score));
 
     Ok(json(&leaderboard))
 }
@@ -134,6 +139,10 @@ pub async fn create_handler(
     match DBRepository::by_id(&db_access, issue.repository_id) {
         Ok(repo) => match repo {
             Some(_) => match db_access.by_number(issue.repository_id, issue.number)? {
+                Some(r) if r.deleted_at.is_some() => {
+                    warn!("issue number '{}' is deleted", issue.number);
+                    Err(warp::reject::custom(IssueError::Deleted(r.id)))
+                }
                 Some(r) => {
                     warn!("issue number '{}' exists", issue.number);
                     Err(warp::reject::custom(IssueError::AlreadyExists(r.number)))
@@ -239,7 +248,7 @@ pub async fn update_asignee_handler(
     id: i32,
     user: GitHubUser,
     buf: impl Buf,
-    db_access: impl DBIssue + DBUser + DBRole,
+    db_access: impl DBIssue + DBUser + DBRole + DBWebhook,
 ) -> Result<impl Reply, Rejection> {
     let user_roles = DBRole::user_roles(&db_access, &user.username)?;
     user_has_at_least_one_role(
@@ -264,6 +273,13 @@ pub async fn update_asignee_handler(
             match DBIssue::update(&db_access, id, &update_issue) {
                 Ok(issue) => {
                     info!("issue '{}' assignee '{}' updated", issue.id, u.id);
+                    dispatch_event(
+                        &db_access,
+                        WebhookEvent::IssueAssigned,
+                        None,
+                        Some(issue.repository_id),
+                        &json!({ "issue": issue, "assignee": { "id": u.id, "username": u.username } }),
+                    );
                     Ok(with_status(json(&issue), StatusCode::OK))
                 }
                 Err(error) => {
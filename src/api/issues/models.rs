@@ -1,4 +1,5 @@
 use crate::{api::repositories::models::RepositoryResponse, schema::issues};
+use crate::api::filters::LabelMatch;
 use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 
@@ -24,6 +25,10 @@ pub struct Issue {
     pub updated_at: Option<DateTime<Utc>>,
     pub description: Option<String>,
     pub estimation: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Insertable, Serialize, Deserialize, Debug)]
@@ -75,6 +80,11 @@ pub struct QueryParams {
     pub stack_levels: Option<String>,
     pub technologies: Option<String>,
     pub labels: Option<String>,
+    /// Leave out items with any of these labels, as `labels!=a,b`.
+    #[serde(rename = "labels!")]
+    pub excluded_labels: Option<String>,
+    /// Match `any` (default) or `all` of the labels.
+    pub label_match: Option<LabelMatch>,
     pub language_slugs: Option<String>,
     pub repository_id: Option<i32>,
     pub assignee_id: Option<i32>,
@@ -85,6 +95,8 @@ pub struct QueryParams {
     pub rewards: Option<bool>,
     pub certified_or_labels: Option<bool>,
     pub types: Option<String>,
+    /// Also list the archived ones, deleted ones are never listed.
+    pub include_archived: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
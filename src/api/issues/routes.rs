@@ -6,6 +6,7 @@ use warp::{Filter, Reply};
 use crate::api::repositories::db::DBRepository;
 use crate::api::roles::db::DBRole;
 use crate::api::users::db::DBUser;
+use crate::api::webhooks::db::DBWebhook;
 use crate::middlewares::github::auth::with_github_auth;
 use crate::types::PaginationParams;
 
@@ -14,12 +15,12 @@ use super::handlers;
 use super::models::{LeaderboardQueryParams, QueryParams};
 
 fn with_db(
-    db_pool: impl DBIssue + DBRepository + DBUser + DBRole,
-) -> impl Filter<Extract = (impl DBIssue + DBRepository + DBUser + DBRole,), Error = Infallible> + Clone {
+    db_pool: impl DBIssue + DBRepository + DBUser + DBRole + DBWebhook,
+) -> impl Filter<Extract = (impl DBIssue + DBRepository + DBUser + DBRole + DBWebhook,), Error = Infallible> + Clone {
     warp::any().map(move || db_pool.clone())
 }
 
-pub fn routes(db_access: impl DBIssue + DBRepository + DBUser+ DBRole) -> BoxedFilter<(impl Reply,)> {
+pub fn routes(db_access: impl DBIssue + DBRepository + DBUser + DBRole + DBWebhook) -> BoxedFilter<(impl Reply,)> {
     let issue = warp::path!("issues");
     let issue_id = warp::path!("issues" / i32);
     let issue_id_assignee = warp::path!("issues" / i32 / "assignee");
@@ -0,0 +1,185 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+
+use super::models::{
+    Job, JobOutcome, JobRun, JobRunsParams, NewJob, NewJobRun, RUN_FAILED, RUN_SUCCEEDED,
+};
+use crate::schema::{job_runs::dsl as runs_dsl, jobs::dsl as jobs_dsl};
+use crate::types::PaginationParams;
+
+use crate::db::{
+    errors::DBError,
+    pool::DBAccess,
+};
+
+pub trait DBJob: Send + Sync + Clone + 'static {
+    fn jobs(&self) -> Result<Vec<Job>, DBError>;
+    fn job_runs(
+        &self,
+        params: JobRunsParams,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<JobRun>, i64), DBError>;
+    /// Inserts the job if it is not known yet. When the stored schedule
+    /// differs, it is replaced and the job rescheduled to `next_run_at`.
+    fn register_job(&self, job: &NewJob) -> Result<Job, DBError>;
+    /// Leases one due job to `runner` until `lease_until` and records the
+    /// start of its run. Jobs leased by another runner are skipped.
+    fn claim_due_job(
+        &self,
+        names: &[String],
+        runner: &str,
+        lease_until: DateTime<Utc>,
+    ) -> Result<Option<(Job, JobRun)>, DBError>;
+    fn finish_run(&self, job: &Job, run_id: i32, outcome: &JobOutcome) -> Result<(), DBError>;
+}
+
+impl DBJob for DBAccess {
+    fn jobs(&self) -> Result<Vec<Job>, DBError> {
+        self.with_conn(|conn| {
+            let result = jobs_dsl::jobs
+                .order(jobs_dsl::name.asc())
+                .load::<Job>(conn)?;
+            Ok(result)
+        })
+    }
+
+    fn job_runs(
+        &self,
+        params: JobRunsParams,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<JobRun>, i64), DBError> {
+        self.with_conn(|conn| {
+            let build_query = || {
+                let mut query = runs_dsl::job_runs.into_boxed();
+                if let Some(job_name) = &params.job_name {
+                    query = query.filter(runs_dsl::job_name.eq(job_name.clone()));
+                }
+                if let Some(status) = &params.status {
+                    query = query.filter(runs_dsl::status.eq(status.clone()));
+                }
+                query
+            };
+
+            let total_count = build_query().count().get_result::<i64>(conn)?;
+            let result = build_query()
+                .order(runs_dsl::started_at.desc())
+                .offset(pagination.offset)
+                .limit(pagination.limit)
+                .load::<JobRun>(conn)?;
+
+            Ok((result, total_count))
+        })
+    }
+
+    fn register_job(&self, job: &NewJob) -> Result<Job, DBError> {
+        self.with_conn(|conn| {
+            let result = conn.transaction(|conn| {
+                diesel::insert_into(jobs_dsl::jobs)
+                    .values(job)
+                    .on_conflict_do_nothing()
+                    .execute(conn)?;
+
+                diesel::update(
+                    jobs_dsl::jobs
+                        .find(&job.name)
+                        .filter(jobs_dsl::schedule.ne(&job.schedule)),
+                )
+                .set((
+                    jobs_dsl::schedule.eq(&job.schedule),
+                    jobs_dsl::next_run_at.eq(job.next_run_at),
+                    jobs_dsl::updated_at.eq(Some(Utc::now())),
+                ))
+                .execute(conn)?;
+
+                jobs_dsl::jobs.find(&job.name).first::<Job>(conn)
+            })?;
+            Ok(result)
+        })
+    }
+
+    fn claim_due_job(
+        &self,
+        names: &[String],
+        runner: &str,
+        lease_until: DateTime<Utc>,
+    ) -> Result<Option<(Job, JobRun)>, DBError> {
+        self.with_conn(|conn| {
+            let now = Utc::now();
+            let result = conn.transaction(|conn| {
+                let name = jobs_dsl::jobs
+                    .filter(jobs_dsl::name.eq_any(names))
+                    .filter(jobs_dsl::enabled.eq(true))
+                    .filter(jobs_dsl::next_run_at.le(now))
+                    .filter(
+                        jobs_dsl::locked_until
+                            .is_null()
+                            .or(jobs_dsl::locked_until.lt(now)),
+                    )
+                    .order(jobs_dsl::next_run_at.asc())
+                    .select(jobs_dsl::name)
+                    .for_update()
+                    .skip_locked()
+                    .first::<String>(conn)
+                    .optional()?;
+
+                let Some(name) = name else {
+                    return Ok(None);
+                };
+
+                let job = diesel::update(jobs_dsl::jobs.find(&name))
+                    .set((
+                        jobs_dsl::locked_until.eq(Some(lease_until)),
+                        jobs_dsl::locked_by.eq(Some(runner)),
+                        jobs_dsl::last_run_at.eq(Some(now)),
+                    ))
+                    .get_result::<Job>(conn)?;
+                let run = diesel::insert_into(runs_dsl::job_runs)
+                    .values(NewJobRun {
+                        job_name: name,
+                        runner: runner.to_owned(),
+                    })
+                    .get_result::<JobRun>(conn)?;
+                Ok::<_, diesel::result::Error>(Some((job, run)))
+            })?;
+            Ok(result)
+        })
+    }
+
+    fn finish_run(&self, job: &Job, run_id: i32, outcome: &JobOutcome) -> Result<(), DBError> {
+        self.with_conn(|conn| {
+            let now = Utc::now();
+            conn.transaction(|conn| {
+                let (status, consecutive_failures, last_success_at) = match outcome.error {
+                    None => (RUN_SUCCEEDED, 0, Some(now)),
+                    Some(_) => (RUN_FAILED, job.consecutive_failures + 1, job.last_success_at),
+                };
+
+                diesel::update(runs_dsl::job_runs.find(run_id))
+                    .set((
+                        runs_dsl::status.eq(status),
+                        runs_dsl::error.eq(outcome.error.as_deref()),
+                        runs_dsl::finished_at.eq(Some(now)),
+                    ))
+                    .execute(conn)?;
+
+                // only release the lease if it is still ours
+                diesel::update(
+                    jobs_dsl::jobs
+                        .find(&job.name)
+                        .filter(jobs_dsl::locked_by.eq(&job.locked_by)),
+                )
+                .set((
+                    jobs_dsl::next_run_at.eq(outcome.next_run_at),
+                    jobs_dsl::consecutive_failures.eq(consecutive_failures),
+                    jobs_dsl::last_success_at.eq(last_success_at),
+                    jobs_dsl::locked_until.eq(None::<DateTime<Utc>>),
+                    jobs_dsl::locked_by.eq(None::<String>),
+                    jobs_dsl::updated_at.eq(Some(now)),
+                ))
+                .execute(conn)?;
+                Ok::<_, diesel::result::Error>(())
+            })?;
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,46 @@
+use warp::{
+    reject::Rejection,
+    reply::{json, Reply},
+};
+
+use crate::{
+    api::roles::{db::DBRole, models::KudosRole, utils::user_has_at_least_one_role},
+    middlewares::github::model::GitHubUser,
+    types::{PaginatedResponse, PaginationParams},
+};
+
+use super::{db::DBJob, models::JobRunsParams};
+
+pub async fn all_handler(
+    user: GitHubUser,
+    db_access: impl DBJob + DBRole,
+) -> Result<impl Reply, Rejection> {
+    let roles = DBRole::user_roles(&db_access, &user.username)?;
+    user_has_at_least_one_role(roles, vec![KudosRole::Admin])?;
+
+    let jobs = db_access.jobs()?;
+    Ok(json(&jobs))
+}
+
+pub async fn runs_handler(
+    user: GitHubUser,
+    db_access: impl DBJob + DBRole,
+    params: JobRunsParams,
+    pagination: PaginationParams,
+) -> Result<impl Reply, Rejection> {
+    let roles = DBRole::user_roles(&db_access, &user.username)?;
+    user_has_at_least_one_role(roles, vec![KudosRole::Admin])?;
+
+    let (runs, total_count) = db_access.job_runs(params, pagination.clone())?;
+    let has_next_page = pagination.offset + pagination.limit < total_count;
+    let has_previous_page = pagination.offset > 0;
+
+    let response = PaginatedResponse {
+        total_count: Some(total_count),
+        has_next_page,
+        has_previous_page,
+        data: runs,
+    };
+
+    Ok(json(&response))
+}
@@ -0,0 +1,69 @@
+use crate::schema::{job_runs, jobs};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Queryable, Identifiable, Selectable, Debug, PartialEq, Serialize, Clone)]
+#[diesel(table_name = jobs)]
+#[diesel(primary_key(name))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Job {
+    pub name: String,
+    pub schedule: String,
+    pub enabled: bool,
+    pub next_run_at: DateTime<Utc>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_success_at: Option<DateTime<Utc>>,
+    pub consecutive_failures: i32,
+    pub locked_until: Option<DateTime<Utc>>,
+    pub locked_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = jobs)]
+pub struct NewJob {
+    pub name: String,
+    pub schedule: String,
+    pub next_run_at: DateTime<Utc>,
+}
+
+#[derive(Queryable, Identifiable, Selectable, Debug, PartialEq, Serialize, Clone)]
+#[diesel(table_name = job_runs)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct JobRun {
+    pub id: i32,
+    pub job_name: String,
+    pub runner: String,
+    pub status: String,
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = job_runs)]
+pub struct NewJobRun {
+    pub job_name: String,
+    pub runner: String,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct JobRunsParams {
+    pub job_name: Option<String>,
+    pub status: Option<String>,
+}
+
+/// Result of running a job to completion (or timing out).
+#[derive(Debug, PartialEq)]
+pub struct JobOutcome {
+    pub error: Option<String>,
+    /// When the job should run next, either its next scheduled time or an
+    /// earlier retry after a failure.
+    pub next_run_at: DateTime<Utc>,
+}
+
+pub const RUN_SUCCEEDED: &str = "succeeded";
+pub const RUN_FAILED: &str = "failed";
@@ -0,0 +1,41 @@
+use std::convert::Infallible;
+
+use warp::filters::BoxedFilter;
+use warp::{Filter, Reply};
+
+use crate::api::roles::db::DBRole;
+use crate::middlewares::github::auth::with_github_auth;
+use crate::types::PaginationParams;
+
+use super::db::DBJob;
+use super::handlers;
+use super::models::JobRunsParams;
+
+fn with_db(
+    db_pool: impl DBJob + DBRole,
+) -> impl Filter<Extract = (impl DBJob + DBRole,), Error = Infallible> + Clone {
+    warp::any().map(move || db_pool.clone())
+}
+
+pub fn routes(db_access: impl DBJob + DBRole) -> BoxedFilter<(impl Reply,)> {
+    let jobs = warp::path!("admin" / "jobs");
+    let job_runs = warp::path!("admin" / "jobs" / "runs");
+
+    let get_jobs = jobs
+        .and(warp::get())
+        .and(with_github_auth())
+        .and(with_db(db_access.clone()))
+        .and_then(handlers::all_handler);
+
+    let get_job_runs = job_runs
+        .and(warp::get())
+        .and(with_github_auth())
+        .and(with_db(db_access.clone()))
+        .and(warp::query::<JobRunsParams>())
+        .and(warp::query::<PaginationParams>())
+        .and_then(handlers::runs_handler);
+
+    let route = get_jobs.or(get_job_runs);
+
+    route.boxed()
+}
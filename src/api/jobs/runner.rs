@@ -0,0 +1,244 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use log::{error, info, warn};
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+
+use super::{
+    db::DBJob,
+    models::{Job, JobOutcome, JobRun, NewJob, RUN_FAILED, RUN_SUCCEEDED},
+    schedule::Schedule,
+};
+use crate::metrics::metrics;
+
+pub const POLL_INTERVAL_SECONDS: u64 = 15;
+/// Extra time on top of the job timeout before another runner may take over.
+const LEASE_GRACE_SECONDS: i64 = 60;
+const BACKOFF_BASE_SECONDS: i64 = 60;
+const BACKOFF_MAX_SECONDS: i64 = 60 * 60;
+
+type JobFuture = Pin<Box<dyn Future<Output = Result<(), String>> + Send>>;
+type JobFn = Arc<dyn Fn(CancellationToken) -> JobFuture + Send + Sync>;
+
+/// Stored in place of a poll time by runners without jobs.
+const IDLE: i64 = -1;
+
+/// Time of the last successful poll of a runner, shared with the readiness
+/// check.
+#[derive(Debug, Clone, Default)]
+pub struct Heartbeat(Arc<AtomicI64>);
+
+impl Heartbeat {
+    pub fn beat(&self) {
+        self.0.store(Utc::now().timestamp(), Ordering::Relaxed);
+    }
+
+    /// Marks a runner with no jobs to run, which never polls.
+    pub fn idle(&self) {
+        self.0.store(IDLE, Ordering::Relaxed);
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.0.load(Ordering::Relaxed) == IDLE
+    }
+
+    pub fn last(&self) -> Option<DateTime<Utc>> {
+        match self.0.load(Ordering::Relaxed) {
+            0 | IDLE => None,
+            timestamp => DateTime::from_timestamp(timestamp, 0),
+        }
+    }
+}
+
+struct RegisteredJob {
+    name: String,
+    expression: String,
+    schedule: Schedule,
+    timeout: Duration,
+    run: JobFn,
+}
+
+/// Runs registered jobs on their schedule. Jobs are persisted in the `jobs`
+/// table and leased before running, so several API replicas can share the
+/// same runner without running a job twice.
+pub struct JobRunner<D: DBJob> {
+    db_access: D,
+    runner_id: String,
+    heartbeat: Heartbeat,
+    jobs: Vec<RegisteredJob>,
+    running: TaskTracker,
+}
+
+/// Delay before retrying a job that failed `consecutive_failures` times in a row.
+pub fn failure_backoff(consecutive_failures: i32) -> chrono::Duration {
+    let exponent = consecutive_failures.saturating_sub(1).clamp(0, 20) as u32;
+    let seconds = BACKOFF_BASE_SECONDS.saturating_mul(2_i64.pow(exponent));
+    chrono::Duration::seconds(seconds.min(BACKOFF_MAX_SECONDS))
+}
+
+/// Next run after a finished attempt. Failures are retried with backoff, but
+/// never later than the next scheduled run.
+pub fn next_run_at(
+    schedule: &Schedule,
+    finished_at: DateTime<Utc>,
+    consecutive_failures: i32,
+) -> DateTime<Utc> {
+    let scheduled = schedule
+        .next_after(finished_at)
+        .unwrap_or(finished_at + chrono::Duration::seconds(BACKOFF_MAX_SECONDS));
+    if consecutive_failures == 0 {
+        return scheduled;
+    }
+    scheduled.min(finished_at + failure_backoff(consecutive_failures))
+}
+
+impl<D: DBJob> JobRunner<D> {
+    pub fn new(db_access: D) -> Self {
+        let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "kudos-api".to_owned());
+        Self {
+            db_access,
+            runner_id: format!("{host}-{}", std::process::id()),
+            heartbeat: Heartbeat::default(),
+            jobs: Vec::new(),
+            running: TaskTracker::new(),
+        }
+    }
+
+    pub fn heartbeat(&self) -> Heartbeat {
+        self.heartbeat.clone()
+    }
+
+    /// Registers `run` under `name`. Jobs with an invalid schedule are logged
+    /// and skipped. `run` is given the shutdown token, and should stop at the
+    /// end of its current unit of work once it is cancelled.
+    pub fn register<F, Fut>(mut self, name: &str, expression: &str, timeout: Duration, run: F) -> Self
+    where
+        F: Fn(CancellationToken) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        match expression.parse::<Schedule>() {
+            Ok(schedule) => self.jobs.push(RegisteredJob {
+                name: name.to_owned(),
+                expression: expression.to_owned(),
+                schedule,
+                timeout,
+                run: Arc::new(move |shutdown| Box::pin(run(shutdown)) as JobFuture),
+            }),
+            Err(e) => error!("not registering job '{name}': {e}"),
+        }
+        self
+    }
+
+    /// Registers the jobs and runs them when due until `shutdown` is
+    /// cancelled, then waits for the runs in progress to finish.
+    pub async fn start(self, shutdown: CancellationToken) {
+        let now = Utc::now();
+        for job in &self.jobs {
+            let new_job = NewJob {
+                name: job.name.clone(),
+                schedule: job.expression.clone(),
+                next_run_at: job.schedule.next_after(now).unwrap_or(now),
+            };
+            match self.db_access.register_job(&new_job) {
+                Ok(stored) => info!(
+                    "job '{}' scheduled '{}', next run at {}",
+                    stored.name, stored.schedule, stored.next_run_at
+                ),
+                Err(e) => error!("error registering job '{}': {e}", job.name),
+            }
+        }
+
+        // a claimed job is leased for the longest timeout, as the job is
+        // only known once claimed
+        let Some(lease) = self.jobs.iter().map(|job| job.timeout).max() else {
+            info!("no jobs registered, job runner not started");
+            self.heartbeat.idle();
+            return;
+        };
+        let lease = chrono::Duration::from_std(lease).unwrap_or(chrono::Duration::zero())
+            + chrono::Duration::seconds(LEASE_GRACE_SECONDS);
+
+        let runner = Arc::new(self);
+        let names: Vec<String> = runner.jobs.iter().map(|job| job.name.clone()).collect();
+        let mut interval = tokio::time::interval(Duration::from_secs(POLL_INTERVAL_SECONDS));
+        while !shutdown.is_cancelled() {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = interval.tick() => {}
+            }
+            while !shutdown.is_cancelled() {
+                let lease_until = Utc::now() + lease;
+                match runner.db_access.claim_due_job(&names, &runner.runner_id, lease_until) {
+                    Ok(Some((job, run))) => {
+                        runner.running.spawn(runner.clone().run(job, run, shutdown.clone()));
+                    }
+                    Ok(None) => {
+                        runner.heartbeat.beat();
+                        break;
+                    }
+                    Err(e) => {
+                        error!("error claiming due jobs: {e}");
+                        break;
+                    }
+                }
+            }
+        }
+
+        runner.running.close();
+        if !runner.running.is_empty() {
+            info!("waiting for {} running jobs to finish", runner.running.len());
+        }
+        runner.running.wait().await;
+        info!("job runner stopped");
+    }
+
+    async fn run(self: Arc<Self>, job: Job, run: JobRun, shutdown: CancellationToken) {
+        let Some(registered) = self.jobs.iter().find(|registered| registered.name == job.name) else {
+            return;
+        };
+
+        info!("running job '{}' (run {})", job.name, run.id);
+        let started = Instant::now();
+        let error = match tokio::time::timeout(registered.timeout, (registered.run)(shutdown)).await {
+            Ok(Ok(())) => None,
+            Ok(Err(e)) => Some(e),
+            Err(_) => Some(format!("timed out after {}s", registered.timeout.as_secs())),
+        };
+        let status = if error.is_none() { RUN_SUCCEEDED } else { RUN_FAILED };
+        metrics()
+            .job_run_duration
+            .with_label_values(&[&job.name, status])
+            .observe(started.elapsed().as_secs_f64());
+
+        let finished_at = Utc::now();
+        let consecutive_failures = match &error {
+            None => {
+                info!("job '{}' succeeded", job.name);
+                0
+            }
+            Some(e) => {
+                warn!(
+                    "job '{}' failed ({} in a row): {e}",
+                    job.name,
+                    job.consecutive_failures + 1
+                );
+                job.consecutive_failures + 1
+            }
+        };
+        let outcome = JobOutcome {
+            error,
+            next_run_at: next_run_at(&registered.schedule, finished_at, consecutive_failures),
+        };
+        if let Err(e) = self.db_access.finish_run(&job, run.id, &outcome) {
+            error!("error recording run {} of job '{}': {e}", run.id, job.name);
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, Duration, DurationRound, Timelike, Utc};
+
+/// Upper bound on the minutes scanned when looking for the next cron match,
+/// five years so that leap day schedules (`0 0 29 2 *`) still resolve.
+const MAX_CRON_LOOKAHEAD_MINUTES: i64 = 5 * 366 * 24 * 60;
+const MAX_INTERVAL_SECONDS: i64 = 366 * 24 * 60 * 60;
+
+/// When a job should run, either a fixed interval (`@every 30d`) or a
+/// five-field cron expression evaluated in UTC.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Schedule {
+    Every(Duration),
+    Cron(CronSchedule),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    days_of_month_restricted: bool,
+    days_of_week_restricted: bool,
+}
+
+impl Schedule {
+    /// First time strictly after `after` at which the schedule fires.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Schedule::Every(interval) => Some(after + *interval),
+            Schedule::Cron(cron) => cron.next_after(after),
+        }
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let schedule = match s {
+            "@hourly" => Schedule::Cron(CronSchedule::parse("0 * * * *")?),
+            "@daily" => Schedule::Cron(CronSchedule::parse("0 0 * * *")?),
+            "@weekly" => Schedule::Cron(CronSchedule::parse("0 0 * * 0")?),
+            "@monthly" => Schedule::Cron(CronSchedule::parse("0 0 1 * *")?),
+            _ => match s.strip_prefix("@every") {
+                Some(interval) => Schedule::Every(parse_interval(interval.trim())?),
+                None => Schedule::Cron(CronSchedule::parse(s)?),
+            },
+        };
+        if schedule.next_after(Utc::now()).is_none() {
+            return Err(format!("schedule '{s}' never fires"));
+        }
+        Ok(schedule)
+    }
+}
+
+fn parse_interval(interval: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid interval '{interval}', expected e.g. '30s', '15m', '6h' or '7d'");
+    if interval.len() < 2 {
+        return Err(invalid());
+    }
+    let (amount, unit) = interval.split_at(interval.len() - 1);
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    if amount <= 0 {
+        return Err(invalid());
+    }
+    let unit_seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    match amount.checked_mul(unit_seconds) {
+        Some(seconds) if seconds <= MAX_INTERVAL_SECONDS => Ok(Duration::seconds(seconds)),
+        _ => Err(format!("interval '{interval}' must not exceed a year")),
+    }
+}
+
+/// Parses one cron field (`*`, `5`, `1-5`, `*/15`, `10-40/10`, or a comma
+/// separated list of those) into a bitmask of the allowed values.
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let invalid = || format!("invalid cron field '{field}'");
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(invalid());
+        }
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (
+                    start.parse().map_err(|_| invalid())?,
+                    end.parse().map_err(|_| invalid())?,
+                ),
+                None => {
+                    let value = range.parse().map_err(|_| invalid())?;
+                    // `5/10` means every 10 starting at 5
+                    (value, if part.contains('/') { max } else { value })
+                }
+            },
+        };
+        if start < min || end > max || start > end {
+            return Err(format!("cron field '{field}' must be within {min}-{max}"));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minutes, hours, days_of_month, months, days_of_week] = fields[..] else {
+            return Err(format!(
+                "invalid schedule '{expression}', expected '@every <interval>' or five cron fields"
+            ));
+        };
+
+        let mut days_of_week_mask = parse_field(days_of_week, 0, 7)?;
+        // both 0 and 7 are Sunday
+        if days_of_week_mask & (1 << 7) != 0 {
+            days_of_week_mask = (days_of_week_mask | 1) & !(1 << 7);
+        }
+
+        Ok(Self {
+            minutes: parse_field(minutes, 0, 59)?,
+            hours: parse_field(hours, 0, 23)?,
+            days_of_month: parse_field(days_of_month, 1, 31)?,
+            months: parse_field(months, 1, 12)?,
+            days_of_week: days_of_week_mask,
+            days_of_month_restricted: days_of_month != "*",
+            days_of_week_restricted: days_of_week != "*",
+        })
+    }
+
+    /// Like cron, a day matches if either the day of month or the day of week
+    /// matches when both are restricted.
+    fn matches_day(&self, time: DateTime<Utc>) -> bool {
+        let day_of_month = self.days_of_month & (1 << time.day()) != 0;
+        let day_of_week = self.days_of_week & (1 << time.weekday().num_days_from_sunday()) != 0;
+        match (self.days_of_month_restricted, self.days_of_week_restricted) {
+            (true, true) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        }
+    }
+
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let limit = after + Duration::minutes(MAX_CRON_LOOKAHEAD_MINUTES);
+        let mut time = after.duration_trunc(Duration::minutes(1)).ok()? + Duration::minutes(1);
+
+        while time <= limit {
+            if self.months & (1 << time.month()) == 0 {
+                let (year, month) = match time.month() {
+                    12 => (time.year() + 1, 1),
+                    month => (time.year(), month + 1),
+                };
+                time = time
+                    .with_day(1)?
+                    .with_hour(0)?
+                    .with_minute(0)?
+                    .with_month(month)?
+                    .with_year(year)?;
+                continue;
+            }
+            if !self.matches_day(time) {
+                time = time.with_hour(0)?.with_minute(0)? + Duration::days(1);
+                continue;
+            }
+            if self.hours & (1 << time.hour()) == 0 {
+                time = time.with_minute(0)? + Duration::hours(1);
+                continue;
+            }
+            if self.minutes & (1 << time.minute()) == 0 {
+                time += Duration::minutes(1);
+                continue;
+            }
+            return Some(time);
+        }
+        None
+    }
+}
@@ -0,0 +1,41 @@
+use std::sync::Arc;
+
+use warp::{
+    http::{header::CONTENT_TYPE, HeaderMap, HeaderValue},
+    reject::{self, Rejection},
+    reply::{with_header, Reply},
+};
+
+use crate::{
+    config::MetricsConfig,
+    db::pool::DBAccess,
+    metrics::metrics,
+    middlewares::{
+        errors::AuthenticationError,
+        utils::{token_from_header, BEARER},
+    },
+};
+
+pub async fn metrics_handler(
+    headers: HeaderMap<HeaderValue>,
+    db_access: DBAccess,
+    config: Arc<MetricsConfig>,
+) -> Result<impl Reply, Rejection> {
+    if !config.enabled {
+        return Err(reject::not_found());
+    }
+    if !config.bearer_token.is_empty() {
+        let token = token_from_header(&headers, BEARER).map_err(reject::custom)?;
+        if token != config.bearer_token {
+            return Err(reject::custom(AuthenticationError::WrongCredentials));
+        }
+    }
+
+    let metrics = metrics();
+    metrics.set_pool_state(db_access.db_pool.state(), db_access.db_pool.max_size());
+    Ok(with_header(
+        metrics.encode(),
+        CONTENT_TYPE,
+        "text/plain; version=0.0.4; charset=utf-8",
+    ))
+}
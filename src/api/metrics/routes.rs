@@ -0,0 +1,30 @@
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use warp::filters::BoxedFilter;
+use warp::{Filter, Reply};
+
+use crate::config::MetricsConfig;
+use crate::db::pool::DBAccess;
+
+use super::handlers;
+
+fn with_db(db_access: DBAccess) -> impl Filter<Extract = (DBAccess,), Error = Infallible> + Clone {
+    warp::any().map(move || db_access.clone())
+}
+
+fn with_config(
+    config: Arc<MetricsConfig>,
+) -> impl Filter<Extract = (Arc<MetricsConfig>,), Error = Infallible> + Clone {
+    warp::any().map(move || config.clone())
+}
+
+pub fn routes(db_access: DBAccess, config: MetricsConfig) -> BoxedFilter<(impl Reply,)> {
+    warp::path!("metrics")
+        .and(warp::get())
+        .and(warp::header::headers_cloned())
+        .and(with_db(db_access))
+        .and(with_config(Arc::new(config)))
+        .and_then(handlers::metrics_handler)
+        .boxed()
+}
@@ -1,10 +1,17 @@
+pub mod admin;
+pub mod archival;
+pub mod filters;
 pub mod health;
 pub mod issues;
+pub mod jobs;
+pub mod metrics;
 pub mod projects;
 pub mod repositories;
 pub mod roles;
 pub mod subscriptions;
 pub mod tasks;
+pub mod taxonomy;
 pub mod teams;
 pub mod users;
-pub mod notifications;
\ No newline at end of file
+pub mod notifications;
+pub mod webhooks;
@@ -1,69 +1,306 @@
+use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 
-use super::models::{Notification, DeleteNotification, NotificationResponse};
-use crate::schema::{notifications::dsl as notifications_dsl, tasks::dsl as tasks_dsl};
-use crate::api::tasks::models::{Task};
+use super::models::{
+    DeleteNotification, NewNotificationPreference, Notification, NotificationKind,
+    NotificationPreference, NotificationResponse, QueryParams,
+};
+use crate::schema::{
+    issues::dsl as issues_dsl, notification_preferences::dsl as preferences_dsl,
+    notifications::dsl as notifications_dsl, tasks::dsl as tasks_dsl,
+};
+use crate::api::{issues::models::Issue, tasks::models::Task};
+use crate::types::PaginationParams;
+use crate::utils;
 
 use crate::db::{
     errors::DBError,
-    pool::{DBAccess, DBAccessor},
+    pool::DBAccess,
 };
 
 pub trait DBNotification: Send + Sync + Clone + 'static {
-    fn by_github_id(&self, github_id: i64, seen: bool) -> Result<Vec<NotificationResponse>, DBError>;
-    fn delete(&self, notification: &DeleteNotification) -> Result<(), DBError>;
+    fn all(
+        &self,
+        github_id: i64,
+        params: QueryParams,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<NotificationResponse>, i64), DBError>;
+    fn unseen_after(&self, github_id: i64, after_id: i32) -> Result<Vec<NotificationResponse>, DBError>;
+    /// Id of the user's most recent notification, `None` if they have none.
+    fn latest_id(&self, github_id: i64) -> Result<Option<i32>, DBError>;
+    fn unread_count(&self, github_id: i64) -> Result<i64, DBError>;
+    fn mark_read(&self, notification: &DeleteNotification) -> Result<bool, DBError>;
+    fn mark_all_read(&self, github_id: i64) -> Result<usize, DBError>;
+    fn archive(&self, notification: &DeleteNotification) -> Result<bool, DBError>;
+    fn delete(&self, notification: &DeleteNotification) -> Result<bool, DBError>;
     fn delete_all(&self, github_id: i64) -> Result<(), DBError>;
+    fn purge_seen(&self, read_before: DateTime<Utc>) -> Result<usize, DBError>;
+    fn preferences(&self, github_id: i64) -> Result<Vec<NotificationPreference>, DBError>;
+    fn set_preferences(
+        &self,
+        github_id: i64,
+        preferences: &[NotificationPreference],
+    ) -> Result<Vec<NotificationPreference>, DBError>;
 }
 
 impl DBNotification for DBAccess {
-    fn by_github_id(&self, github_id: i64, seen: bool) -> Result<Vec<NotificationResponse>, DBError> {
-        let conn = &mut self.get_db_conn();
-
-        let result = notifications_dsl::notifications
-            .inner_join(tasks_dsl::tasks)
-            .filter(notifications_dsl::github_id.eq(github_id))
-            .filter(notifications_dsl::seen.eq(seen))
-            .select((
-                notifications_dsl::notifications::all_columns(),
-                tasks_dsl::tasks::all_columns(),
-            ))
-            .load::<(Notification, Task)>(conn)
-            .map_err(DBError::from)?
-            .into_iter()
-            .map(|(notification, task)| NotificationResponse {
-                id: notification.id,
-                task_id: notification.task_id,
-                task: task.into(),
-                created_at: notification.created_at,
-            })
-            .collect();
+    fn all(
+        &self,
+        github_id: i64,
+        params: QueryParams,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<NotificationResponse>, i64), DBError> {
+        self.with_conn(|conn| {
+            let build_query = || {
+                let mut query = notifications_dsl::notifications
+                    .left_join(tasks_dsl::tasks)
+                    .left_join(issues_dsl::issues)
+                    .filter(notifications_dsl::github_id.eq(github_id))
+                    .into_boxed();
+
+                if params.archived.unwrap_or(false) {
+                    query = query.filter(notifications_dsl::archived_at.is_not_null());
+                } else {
+                    query = query.filter(notifications_dsl::archived_at.is_null());
+                }
+                if let Some(seen) = params.seen {
+                    query = query.filter(notifications_dsl::seen.eq(seen));
+                }
+                if let Some(kind) = params.kind.as_ref() {
+                    query = query.filter(notifications_dsl::kind.eq_any(utils::parse_comma_values(kind)));
+                }
+                if let Some(since) = params.since {
+                    query = query.filter(notifications_dsl::created_at.ge(since));
+                }
+                if let Some(until) = params.until {
+                    query = query.filter(notifications_dsl::created_at.lt(until));
+                }
 
-        Ok(result)
+                query
+            };
+
+            let total_count = build_query().count().get_result::<i64>(conn)?;
+
+            let result = build_query()
+                .select((
+                    notifications_dsl::notifications::all_columns(),
+                    tasks_dsl::tasks::all_columns().nullable(),
+                    issues_dsl::issues::all_columns().nullable(),
+                ))
+                .order(notifications_dsl::created_at.desc())
+                .offset(pagination.offset)
+                .limit(pagination.limit)
+                .load::<(Notification, Option<Task>, Option<Issue>)>(conn)?
+                .into_iter()
+                .map(NotificationResponse::from)
+                .collect();
+
+            Ok((result, total_count))
+        })
     }
 
-    fn delete(&self, notification: &DeleteNotification) -> Result<(), DBError> {
-        let conn = &mut self.get_db_conn();
-        let github_id = notification.github_id.ok_or_else(|| {
-            DBError::DBQuery(diesel::result::Error::NotFound)
-        })?;
-        
-        diesel::update(
-            notifications_dsl::notifications
+    fn unseen_after(&self, github_id: i64, after_id: i32) -> Result<Vec<NotificationResponse>, DBError> {
+        self.with_conn(|conn| {
+            let result = notifications_dsl::notifications
+                .left_join(tasks_dsl::tasks)
+                .left_join(issues_dsl::issues)
+                .filter(notifications_dsl::github_id.eq(github_id))
+                .filter(notifications_dsl::seen.eq(false))
+                .filter(notifications_dsl::archived_at.is_null())
+                .filter(notifications_dsl::id.gt(after_id))
+                .order(notifications_dsl::id.asc())
+                .select((
+                    notifications_dsl::notifications::all_columns(),
+                    tasks_dsl::tasks::all_columns().nullable(),
+                    issues_dsl::issues::all_columns().nullable(),
+                ))
+                .load::<(Notification, Option<Task>, Option<Issue>)>(conn)
+                .map_err(DBError::from)?
+                .into_iter()
+                .map(NotificationResponse::from)
+                .collect();
+
+            Ok(result)
+        })
+    }
+
+    fn latest_id(&self, github_id: i64) -> Result<Option<i32>, DBError> {
+        self.with_conn(|conn| {
+            let result = notifications_dsl::notifications
+                .filter(notifications_dsl::github_id.eq(github_id))
+                .select(diesel::dsl::max(notifications_dsl::id))
+                .first::<Option<i32>>(conn)?;
+
+            Ok(result)
+        })
+    }
+
+    fn unread_count(&self, github_id: i64) -> Result<i64, DBError> {
+        self.with_conn(|conn| {
+            let count = notifications_dsl::notifications
                 .filter(notifications_dsl::github_id.eq(github_id))
-                .filter(notifications_dsl::id.eq(notification.id))
-        )
-        .set(notifications_dsl::seen.eq(true))
-        .execute(conn)
-        .map_err(DBError::from)?;
+                .filter(notifications_dsl::seen.eq(false))
+                .filter(notifications_dsl::archived_at.is_null())
+                .count()
+                .get_result::<i64>(conn)?;
+            Ok(count)
+        })
+    }
+
+    fn mark_read(&self, notification: &DeleteNotification) -> Result<bool, DBError> {
+        self.with_conn(|conn| {
+            let github_id = notification.github_id.ok_or_else(|| {
+                DBError::DBQuery(diesel::result::Error::NotFound)
+            })?;
+
+            // Reading an already read notification keeps its first read_at,
+            // otherwise the retention clock would restart.
+            let owned = notifications_dsl::notifications
+                .filter(notifications_dsl::github_id.eq(github_id))
+                .filter(notifications_dsl::id.eq(notification.id));
+            diesel::update(owned.filter(notifications_dsl::read_at.is_null()))
+                .set((
+                    notifications_dsl::seen.eq(true),
+                    notifications_dsl::read_at.eq(Some(Utc::now())),
+                ))
+                .execute(conn)?;
+            let found = owned.count().get_result::<i64>(conn)?;
 
-        Ok(())
+            Ok(found > 0)
+        })
+    }
+
+    fn mark_all_read(&self, github_id: i64) -> Result<usize, DBError> {
+        self.with_conn(|conn| {
+            let updated = diesel::update(
+                notifications_dsl::notifications
+                    .filter(notifications_dsl::github_id.eq(github_id))
+                    .filter(notifications_dsl::seen.eq(false))
+                    .filter(notifications_dsl::archived_at.is_null())
+            )
+            .set((
+                notifications_dsl::seen.eq(true),
+                notifications_dsl::read_at.eq(Some(Utc::now())),
+            ))
+            .execute(conn)?;
+            Ok(updated)
+        })
+    }
+
+    fn archive(&self, notification: &DeleteNotification) -> Result<bool, DBError> {
+        self.with_conn(|conn| {
+            let github_id = notification.github_id.ok_or_else(|| {
+                DBError::DBQuery(diesel::result::Error::NotFound)
+            })?;
+
+            // Archiving also reads the notification so it drops out of the
+            // unread count and becomes eligible for retention.
+            let updated = conn.transaction(|conn| {
+                let owned = notifications_dsl::notifications
+                    .filter(notifications_dsl::github_id.eq(github_id))
+                    .filter(notifications_dsl::id.eq(notification.id));
+
+                diesel::update(owned.filter(notifications_dsl::read_at.is_null()))
+                    .set((
+                        notifications_dsl::seen.eq(true),
+                        notifications_dsl::read_at.eq(Some(Utc::now())),
+                    ))
+                    .execute(conn)?;
+                diesel::update(owned)
+                    .set(notifications_dsl::archived_at.eq(Some(Utc::now())))
+                    .execute(conn)
+            })?;
+
+            Ok(updated > 0)
+        })
+    }
+
+    fn delete(&self, notification: &DeleteNotification) -> Result<bool, DBError> {
+        self.with_conn(|conn| {
+            let github_id = notification.github_id.ok_or_else(|| {
+                DBError::DBQuery(diesel::result::Error::NotFound)
+            })?;
+
+            let deleted = diesel::delete(
+                notifications_dsl::notifications
+                    .filter(notifications_dsl::github_id.eq(github_id))
+                    .filter(notifications_dsl::id.eq(notification.id))
+            )
+            .execute(conn)?;
+
+            Ok(deleted > 0)
+        })
     }
 
     fn delete_all(&self, github_id: i64) -> Result<(), DBError> {
-        let conn = &mut self.get_db_conn();
-        diesel::delete(notifications_dsl::notifications.filter(notifications_dsl::github_id.eq(github_id)))
-            .execute(conn)
-            .map_err(DBError::from)?;
-        Ok(())
+        self.with_conn(|conn| {
+            diesel::delete(notifications_dsl::notifications.filter(notifications_dsl::github_id.eq(github_id)))
+                .execute(conn)
+                .map_err(DBError::from)?;
+            Ok(())
+        })
+    }
+
+    fn purge_seen(&self, read_before: DateTime<Utc>) -> Result<usize, DBError> {
+        self.with_conn(|conn| {
+            let deleted = diesel::delete(
+                notifications_dsl::notifications
+                    .filter(notifications_dsl::seen.eq(true))
+                    .filter(notifications_dsl::read_at.lt(read_before))
+            )
+            .execute(conn)?;
+            Ok(deleted)
+        })
     }
-}
\ No newline at end of file
+
+    fn preferences(&self, github_id: i64) -> Result<Vec<NotificationPreference>, DBError> {
+        self.with_conn(|conn| {
+            let stored = preferences_dsl::notification_preferences
+                .filter(preferences_dsl::github_id.eq(github_id))
+                .select((preferences_dsl::kind, preferences_dsl::enabled))
+                .load::<(String, bool)>(conn)?;
+
+            // Kinds without a stored preference are enabled.
+            let result = NotificationKind::ALL
+                .into_iter()
+                .map(|kind| NotificationPreference {
+                    kind,
+                    enabled: !stored
+                        .iter()
+                        .any(|(stored_kind, enabled)| stored_kind == kind.as_str() && !enabled),
+                })
+                .collect();
+            Ok(result)
+        })
+    }
+
+    fn set_preferences(
+        &self,
+        github_id: i64,
+        preferences: &[NotificationPreference],
+    ) -> Result<Vec<NotificationPreference>, DBError> {
+        let rows: Vec<NewNotificationPreference> = preferences
+            .iter()
+            .map(|preference| NewNotificationPreference {
+                github_id,
+                kind: preference.kind.as_str().to_owned(),
+                enabled: preference.enabled,
+            })
+            .collect();
+
+        self.with_conn(|conn| {
+            diesel::insert_into(preferences_dsl::notification_preferences)
+                .values(&rows)
+                .on_conflict((preferences_dsl::github_id, preferences_dsl::kind))
+                .do_update()
+                .set((
+                    preferences_dsl::enabled.eq(diesel::upsert::excluded(preferences_dsl::enabled)),
+                    preferences_dsl::updated_at.eq(diesel::dsl::now),
+                ))
+                .execute(conn)?;
+            Ok(())
+        })?;
+
+        self.preferences(github_id)
+    }
+}
@@ -47,7 +47,7 @@ impl Reply for NotificationError {
         };
         let message = self.to_string();
 
-        let json = warp::reply::json(&ErrorResponse { message });
+        let json = warp::reply::json(&ErrorResponse::new(message));
 
         warp::reply::with_status(json, code).into_response()
     }
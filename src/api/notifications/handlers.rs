@@ -1,26 +1,100 @@
+use std::time::Duration;
+
+use bytes::Buf;
+use futures_util::{stream, StreamExt};
+use log::{error, info, warn};
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
 use warp::{
     http::StatusCode,
-    reject::Rejection,
+    reject::{self, Rejection},
     reply::{json, Reply},
+    sse::Event,
 };
 
 use crate::{
-    middlewares::github::model::GitHubUser
+    middlewares::github::model::GitHubUser,
+    types::{PaginatedResponse, PaginationParams},
 };
 
 use super::{
     db::DBNotification,
-    models::{DeleteNotification},
+    errors::NotificationError,
+    models::{
+        DeleteNotification, NotificationPreference, NotificationResponse, QueryParams,
+        UnreadCountResponse,
+    },
+    stream::NotificationBroadcaster,
 };
 
+const STREAM_HEARTBEAT_SECONDS: u64 = 15;
+
+
+pub async fn all_handler(
+    user: GitHubUser,
+    db_access: impl DBNotification,
+    params: QueryParams,
+    pagination: PaginationParams,
+) -> Result<impl Reply, Rejection> {
+    let (notifications, total_count) = db_access.all(user.id, params, pagination.clone())?;
+    let has_next_page = pagination.offset + pagination.limit < total_count;
+    let has_previous_page = pagination.offset > 0;
+
+    let response = PaginatedResponse {
+        total_count: Some(total_count),
+        has_next_page,
+        has_previous_page,
+        data: notifications,
+    };
+
+    Ok(json(&response))
+}
+
+pub async fn unread_count_handler(
+    user: GitHubUser,
+    db_access: impl DBNotification,
+) -> Result<impl Reply, Rejection> {
+    let count = db_access.unread_count(user.id)?;
+    Ok(json(&UnreadCountResponse { count }))
+}
+
+pub async fn read_handler(
+    id: i32,
+    user: GitHubUser,
+    db_access: impl DBNotification,
+) -> Result<impl Reply, Rejection> {
+    let notification = DeleteNotification {
+        id,
+        github_id: Some(user.id),
+    };
+    if !db_access.mark_read(&notification)? {
+        return Err(warp::reject::custom(NotificationError::NotFound(id)));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
 
-pub async fn by_github_id(
-     user: GitHubUser,
-     db_access: impl DBNotification) -> Result<impl Reply, Rejection> {
-    let notifications = db_access.by_github_id(user.id, false)?;
-    Ok(json(&notifications))
+pub async fn read_all_handler(
+    user: GitHubUser,
+    db_access: impl DBNotification,
+) -> Result<impl Reply, Rejection> {
+    let count = db_access.mark_all_read(user.id)?;
+    info!("marked {count} notifications as read for github user '{}'", user.id);
+    Ok(StatusCode::NO_CONTENT)
 }
 
+pub async fn archive_handler(
+    id: i32,
+    user: GitHubUser,
+    db_access: impl DBNotification,
+) -> Result<impl Reply, Rejection> {
+    let notification = DeleteNotification {
+        id,
+        github_id: Some(user.id),
+    };
+    if !db_access.archive(&notification)? {
+        return Err(warp::reject::custom(NotificationError::NotFound(id)));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
 
 pub async fn delete_handler(
     id: i32,
@@ -31,9 +105,10 @@ pub async fn delete_handler(
         id,
         github_id: Some(user.id),
     };
-    db_access.delete(&notification)?;
+    if !db_access.delete(&notification)? {
+        return Err(warp::reject::custom(NotificationError::NotFound(id)));
+    }
     Ok(StatusCode::NO_CONTENT)
-
 }
 
 pub async fn delete_all_handler(
@@ -43,3 +118,95 @@ pub async fn delete_all_handler(
     db_access.delete_all(user.id)?;
     Ok(StatusCode::NO_CONTENT)
 }
+
+pub async fn preferences_handler(
+    user: GitHubUser,
+    db_access: impl DBNotification,
+) -> Result<impl Reply, Rejection> {
+    let preferences = db_access.preferences(user.id)?;
+    Ok(json(&preferences))
+}
+
+pub async fn update_preferences_handler(
+    user: GitHubUser,
+    buf: impl Buf,
+    db_access: impl DBNotification,
+) -> Result<impl Reply, Rejection> {
+    let des = &mut serde_json::Deserializer::from_reader(buf.reader());
+    let preferences: Vec<NotificationPreference> = serde_path_to_error::deserialize(des)
+        .map_err(|e| {
+            let e = e.to_string();
+            warn!("invalid notification preferences '{e}'");
+            reject::custom(NotificationError::InvalidPayload(e))
+        })?;
+
+    info!("updating notification preferences for github user '{}'", user.id);
+    let preferences = db_access.set_preferences(user.id, &preferences)?;
+    Ok(json(&preferences))
+}
+
+/// Streams the user's notifications as Server-Sent Events. A new stream
+/// starts after the user's latest notification; when the client reconnects
+/// with `Last-Event-ID`, unseen notifications created after that id are
+/// replayed before live ones.
+pub async fn stream_handler(
+    user: GitHubUser,
+    last_event_id: Option<String>,
+    db_access: impl DBNotification,
+    broadcaster: NotificationBroadcaster,
+) -> Result<impl Reply, Rejection> {
+    let github_id = user.id;
+    let last_event_id = last_event_id.and_then(|id| id.trim().parse::<i32>().ok());
+    let start = match last_event_id {
+        Some(id) => id,
+        None => db_access.latest_id(github_id)?.unwrap_or(0),
+    };
+    // Subscribe before the replay so nothing inserted in between is lost.
+    let events = BroadcastStream::new(broadcaster.subscribe());
+
+    let missed = match last_event_id {
+        Some(id) => db_access.unseen_after(github_id, id)?,
+        None => vec![],
+    };
+    let last_sent = missed.last().map(|n| n.id).unwrap_or(start);
+    info!("opening notification stream for github user '{github_id}' from id '{last_sent}'");
+
+    let live = stream::unfold((events, last_sent), move |(mut events, mut last_sent)| {
+        let db_access = db_access.clone();
+        async move {
+            loop {
+                match events.next().await? {
+                    Ok(event) if event.github_id != github_id || event.id <= last_sent => continue,
+                    // After a lag we can't tell whose events were dropped, so
+                    // catch up from the database either way.
+                    Ok(_) | Err(BroadcastStreamRecvError::Lagged(_)) => {}
+                }
+                match db_access.unseen_after(github_id, last_sent) {
+                    Ok(batch) => {
+                        if let Some(notification) = batch.last() {
+                            last_sent = notification.id;
+                        }
+                        return Some((batch, (events, last_sent)));
+                    }
+                    Err(e) => error!("error loading notifications for github user '{github_id}': {e}"),
+                }
+            }
+        }
+    });
+
+    let notifications = stream::iter(missed)
+        .chain(live.flat_map(stream::iter))
+        .map(|notification: NotificationResponse| {
+            Event::default()
+                .id(notification.id.to_string())
+                .event("notification")
+                .json_data(&notification)
+        });
+
+    let keep_alive = warp::sse::keep_alive()
+        .interval(Duration::from_secs(STREAM_HEARTBEAT_SECONDS))
+        .text("ping");
+
+    // Open streams would otherwise keep the server from draining on shutdown.
+    Ok(warp::sse::reply(keep_alive.stream(notifications.take_until(broadcaster.closed()))))
+}
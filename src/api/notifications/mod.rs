@@ -3,3 +3,5 @@ pub mod errors;
 pub mod handlers;
 pub mod models;
 pub mod routes;
+pub mod stream;
+pub mod retention;
@@ -1,9 +1,12 @@
-use crate::schema::notifications;
+use crate::schema::{notification_preferences, notifications};
 use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 
 use serde::{Deserialize, Serialize};
-use crate::api::tasks::models::TaskResponse;
+use crate::api::{
+    issues::models::Issue,
+    tasks::models::{Task, TaskResponse},
+};
 
 #[derive(
     AsChangeset,
@@ -21,9 +24,14 @@ use crate::api::tasks::models::TaskResponse;
 pub struct Notification {
     pub id: i32,
     pub github_id: i64,
-    pub task_id: i32,
+    pub task_id: Option<i32>,
     pub seen: Option<bool>,
     pub created_at: DateTime<Utc>,
+    pub kind: String,
+    pub payload: serde_json::Value,
+    pub read_at: Option<DateTime<Utc>>,
+    pub archived_at: Option<DateTime<Utc>>,
+    pub issue_id: Option<i32>,
 }
 
 #[derive(Insertable, Serialize, Deserialize, Debug)]
@@ -36,7 +44,106 @@ pub struct DeleteNotification {
 #[derive(Serialize, Debug)]
 pub struct NotificationResponse {
     pub id: i32,
-    pub task_id: i32,
-    pub task: TaskResponse,
+    pub task_id: Option<i32>,
+    pub issue_id: Option<i32>,
+    pub kind: String,
+    pub payload: serde_json::Value,
+    pub task: Option<TaskResponse>,
+    pub issue: Option<Issue>,
+    pub seen: bool,
+    pub read_at: Option<DateTime<Utc>>,
+    pub archived_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
-}
\ No newline at end of file
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct QueryParams {
+    pub seen: Option<bool>,
+    pub archived: Option<bool>,
+    /// Comma separated list of notification kinds.
+    pub kind: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct UnreadCountResponse {
+    pub count: i64,
+}
+
+impl From<(Notification, Option<Task>, Option<Issue>)> for NotificationResponse {
+    fn from((notification, task, issue): (Notification, Option<Task>, Option<Issue>)) -> Self {
+        NotificationResponse {
+            id: notification.id,
+            task_id: notification.task_id,
+            issue_id: notification.issue_id,
+            kind: notification.kind,
+            payload: notification.payload,
+            task: task.map(TaskResponse::from),
+            issue,
+            seen: notification.seen.unwrap_or(false),
+            read_at: notification.read_at,
+            archived_at: notification.archived_at,
+            created_at: notification.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    TaskMatchedSubscription,
+    IssueMatchedSubscription,
+    TaskAssigned,
+    TaskStatusChanged,
+    TaskApproved,
+    TeamTaskAssigned,
+    VoteMilestone,
+}
+
+impl NotificationKind {
+    pub const ALL: [NotificationKind; 7] = [
+        NotificationKind::TaskMatchedSubscription,
+        NotificationKind::IssueMatchedSubscription,
+        NotificationKind::TaskAssigned,
+        NotificationKind::TaskStatusChanged,
+        NotificationKind::TaskApproved,
+        NotificationKind::TeamTaskAssigned,
+        NotificationKind::VoteMilestone,
+    ];
+
+    /// Value stored in `notifications.kind` and `notification_preferences.kind`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationKind::TaskMatchedSubscription => "task_matched_subscription",
+            NotificationKind::IssueMatchedSubscription => "issue_matched_subscription",
+            NotificationKind::TaskAssigned => "task_assigned",
+            NotificationKind::TaskStatusChanged => "task_status_changed",
+            NotificationKind::TaskApproved => "task_approved",
+            NotificationKind::TeamTaskAssigned => "team_task_assigned",
+            NotificationKind::VoteMilestone => "vote_milestone",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NotificationPreference {
+    pub kind: NotificationKind,
+    pub enabled: bool,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = notification_preferences)]
+pub struct NewNotificationPreference {
+    pub github_id: i64,
+    pub kind: String,
+    pub enabled: bool,
+}
+
+/// Payload published by the `publish_notification` trigger on the
+/// `notifications` channel.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct NotificationEvent {
+    pub id: i32,
+    pub github_id: i64,
+}
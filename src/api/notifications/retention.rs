@@ -0,0 +1,19 @@
+use chrono::Utc;
+use log::info;
+
+use super::db::DBNotification;
+
+/// Deletes notifications that were read more than `retention_days` ago.
+pub async fn purge_read_notifications(
+    db_access: impl DBNotification,
+    retention_days: i64,
+) -> Result<(), String> {
+    let read_before = Utc::now() - chrono::Duration::days(retention_days);
+    let count = db_access
+        .purge_seen(read_before)
+        .map_err(|e| format!("error purging read notifications: {e}"))?;
+    if count > 0 {
+        info!("purged {count} notifications read before {read_before}");
+    }
+    Ok(())
+}
@@ -6,8 +6,12 @@ use warp::{Filter, Reply};
 use crate::api::roles::db::DBRole;
 use crate::middlewares::github::auth::with_github_auth;
 
+use crate::types::PaginationParams;
+
 use super::db::DBNotification;
 use super::handlers;
+use super::models::QueryParams;
+use super::stream::NotificationBroadcaster;
 
 fn with_db(
     db_pool: impl DBNotification + DBRole,
@@ -15,15 +19,77 @@ fn with_db(
     warp::any().map(move || db_pool.clone())
 }
 
-pub fn routes(db_access: impl DBNotification + DBRole) -> BoxedFilter<(impl Reply,)> {
+fn with_broadcaster(
+    broadcaster: NotificationBroadcaster,
+) -> impl Filter<Extract = (NotificationBroadcaster,), Error = Infallible> + Clone {
+    warp::any().map(move || broadcaster.clone())
+}
+
+pub fn routes(
+    db_access: impl DBNotification + DBRole,
+    broadcaster: NotificationBroadcaster,
+) -> BoxedFilter<(impl Reply,)> {
     let notifications = warp::path!("notifications");
     let notification_id = warp::path!("notifications" / i32);
+    let notifications_stream = warp::path!("notifications" / "stream");
+    let notification_preferences = warp::path!("notifications" / "preferences");
+    let notification_read = warp::path!("notifications" / i32 / "read");
+    let notification_archive = warp::path!("notifications" / i32 / "archive");
+    let notifications_read_all = warp::path!("notifications" / "read-all");
+    let notifications_unread_count = warp::path!("notifications" / "unread-count");
 
     let get_notifications = notifications
         .and(warp::get())
         .and(with_github_auth())
         .and(with_db(db_access.clone()))
-        .and_then(handlers::by_github_id);
+        .and(warp::query::<QueryParams>())
+        .and(warp::query::<PaginationParams>())
+        .and_then(handlers::all_handler);
+
+    let get_unread_count = notifications_unread_count
+        .and(warp::get())
+        .and(with_github_auth())
+        .and(with_db(db_access.clone()))
+        .and_then(handlers::unread_count_handler);
+
+    let read_notification = notification_read
+        .and(warp::post())
+        .and(with_github_auth())
+        .and(with_db(db_access.clone()))
+        .and_then(handlers::read_handler);
+
+    let read_all_notifications = notifications_read_all
+        .and(warp::post())
+        .and(with_github_auth())
+        .and(with_db(db_access.clone()))
+        .and_then(handlers::read_all_handler);
+
+    let archive_notification = notification_archive
+        .and(warp::post())
+        .and(with_github_auth())
+        .and(with_db(db_access.clone()))
+        .and_then(handlers::archive_handler);
+
+    let stream_notifications = notifications_stream
+        .and(warp::get())
+        .and(with_github_auth())
+        .and(warp::header::optional::<String>("last-event-id"))
+        .and(with_db(db_access.clone()))
+        .and(with_broadcaster(broadcaster))
+        .and_then(handlers::stream_handler);
+
+    let get_preferences = notification_preferences
+        .and(warp::get())
+        .and(with_github_auth())
+        .and(with_db(db_access.clone()))
+        .and_then(handlers::preferences_handler);
+
+    let update_preferences = notification_preferences
+        .and(warp::put())
+        .and(with_github_auth())
+        .and(warp::body::aggregate())
+        .and(with_db(db_access.clone()))
+        .and_then(handlers::update_preferences_handler);
 
     let delete_notification = notification_id
         .and(with_github_auth())
@@ -38,6 +104,13 @@ pub fn routes(db_access: impl DBNotification + DBRole) -> BoxedFilter<(impl Repl
         .and_then(handlers::delete_all_handler);
 
     let route = get_notifications
+        .or(get_unread_count)
+        .or(read_notification)
+        .or(read_all_notifications)
+        .or(archive_notification)
+        .or(stream_notifications)
+        .or(get_preferences)
+        .or(update_preferences)
         .or(delete_notification)
         .or(delete_all_notifications);
 
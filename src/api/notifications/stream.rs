@@ -0,0 +1,193 @@
+use std::{error::Error, fs, time::Duration};
+
+use futures_util::{stream, StreamExt};
+use log::{error, info, warn};
+use native_tls::{Certificate, TlsConnector};
+use postgres_native_tls::MakeTlsConnector;
+use tokio::sync::broadcast;
+use tokio_postgres::AsyncMessage;
+use tokio_util::sync::{CancellationToken, WaitForCancellationFutureOwned};
+use url::Url;
+
+use super::models::NotificationEvent;
+
+/// Postgres channel the `publish_notification` trigger notifies on.
+pub const NOTIFICATIONS_CHANNEL: &str = "notifications";
+
+const BROADCAST_CAPACITY: usize = 1024;
+const RECONNECT_DELAY_SECONDS: u64 = 5;
+
+/// Fans out notification events received from Postgres to every open stream.
+#[derive(Clone)]
+pub struct NotificationBroadcaster {
+    sender: broadcast::Sender<NotificationEvent>,
+    closed: CancellationToken,
+}
+
+impl NotificationBroadcaster {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(BROADCAST_CAPACITY);
+        Self {
+            sender,
+            closed: CancellationToken::new(),
+        }
+    }
+
+    /// Ends the listener and every open stream, so they don't hold up a
+    /// shutdown.
+    pub fn close(&self) {
+        self.closed.cancel();
+    }
+
+    /// Completes once the broadcaster is closed.
+    pub fn closed(&self) -> WaitForCancellationFutureOwned {
+        self.closed.clone().cancelled_owned()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<NotificationEvent> {
+        self.sender.subscribe()
+    }
+
+    pub fn publish(&self, event: NotificationEvent) {
+        // An error only means nobody is connected right now.
+        let _ = self.sender.send(event);
+    }
+}
+
+impl Default for NotificationBroadcaster {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How the certificate of the server is checked, after libpq's `sslmode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CertificateCheck {
+    /// `disable`, `allow`, `prefer` and `require` encrypt when asked to but
+    /// accept any certificate.
+    None,
+    /// `verify-ca` checks the certificate against the trusted roots.
+    Authority,
+    /// `verify-full` also checks that it was issued for the host.
+    Full,
+}
+
+/// TLS settings of the database URL, which the pool hands to libpq as they
+/// are, translated for the listener's `tokio-postgres` connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListenerTls {
+    /// The database URL without the options `tokio-postgres` doesn't know.
+    pub url: String,
+    pub check: CertificateCheck,
+    pub root_certificate: Option<String>,
+}
+
+impl ListenerTls {
+    pub fn from_database_url(database_url: &str) -> Self {
+        let Ok(mut url) = Url::parse(database_url) else {
+            // key=value connection strings are passed on as they are
+            return Self {
+                url: database_url.to_owned(),
+                check: CertificateCheck::None,
+                root_certificate: None,
+            };
+        };
+        let params: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+        let mut check = CertificateCheck::None;
+        let mut root_certificate = None;
+        url.set_query(None);
+        for (key, value) in params {
+            match (key.as_str(), value.as_str()) {
+                // tokio-postgres only knows disable, prefer and require, the
+                // certificate itself is checked by the connector
+                ("sslmode", "verify-ca" | "verify-full") => {
+                    check = if value == "verify-ca" { CertificateCheck::Authority } else { CertificateCheck::Full };
+                    url.query_pairs_mut().append_pair("sslmode", "require");
+                }
+                ("sslmode", "allow") => {
+                    url.query_pairs_mut().append_pair("sslmode", "prefer");
+                }
+                ("sslrootcert", _) => root_certificate = Some(value),
+                ("sslcert" | "sslkey" | "sslcrl", _) => warn!("'{key}' is not supported by the notification listener"),
+                _ => {
+                    url.query_pairs_mut().append_pair(&key, &value);
+                }
+            }
+        }
+        Self {
+            url: url.to_string(),
+            check,
+            root_certificate,
+        }
+    }
+
+    fn connector(&self) -> Result<MakeTlsConnector, Box<dyn Error + Send + Sync>> {
+        let mut builder = TlsConnector::builder();
+        match self.check {
+            CertificateCheck::None => {
+                builder.danger_accept_invalid_certs(true);
+            }
+            CertificateCheck::Authority => {
+                builder.danger_accept_invalid_hostnames(true);
+            }
+            CertificateCheck::Full => {}
+        }
+        if let Some(path) = &self.root_certificate {
+            builder.add_root_certificate(Certificate::from_pem(&fs::read(path)?)?);
+        }
+        Ok(MakeTlsConnector::new(builder.build()?))
+    }
+}
+
+/// Keeps a dedicated connection LISTENing on [`NOTIFICATIONS_CHANNEL`],
+/// reconnecting whenever it drops, until the broadcaster is closed.
+pub async fn start_notification_listener(database_url: String, broadcaster: NotificationBroadcaster) {
+    let closed = broadcaster.closed();
+    tokio::pin!(closed);
+    loop {
+        tokio::select! {
+            _ = &mut closed => break,
+            result = listen(&database_url, &broadcaster) => match result {
+                Ok(()) => warn!("notification listener connection closed"),
+                Err(e) => error!("notification listener failed: {}", e),
+            },
+        }
+        tokio::select! {
+            _ = &mut closed => break,
+            _ = tokio::time::sleep(Duration::from_secs(RECONNECT_DELAY_SECONDS)) => {}
+        }
+    }
+    info!("notification listener stopped");
+}
+
+async fn listen(database_url: &str, broadcaster: &NotificationBroadcaster) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let tls = ListenerTls::from_database_url(database_url);
+    let (client, mut connection) = tokio_postgres::connect(&tls.url, tls.connector()?).await?;
+
+    // The connection has to be polled for LISTEN to complete, so forward its
+    // messages from a separate task while the client issues the command.
+    let broadcaster = broadcaster.clone();
+    let forward = tokio::spawn(async move {
+        let mut messages = stream::poll_fn(move |cx| connection.poll_message(cx));
+        while let Some(message) = messages.next().await {
+            if let AsyncMessage::Notification(notification) = message? {
+                match serde_json::from_str::<NotificationEvent>(notification.payload()) {
+                    Ok(event) => broadcaster.publish(event),
+                    Err(e) => warn!("invalid notification payload '{}': {}", notification.payload(), e),
+                }
+            }
+        }
+        Ok::<_, tokio_postgres::Error>(())
+    });
+
+    client.batch_execute(&format!("LISTEN {}", NOTIFICATIONS_CHANNEL)).await?;
+    info!("listening for notifications on channel '{}'", NOTIFICATIONS_CHANNEL);
+
+    match forward.await {
+        Ok(result) => Ok(result?),
+        Err(e) => {
+            error!("notification listener task aborted: {}", e);
+            Ok(())
+        }
+    }
+}
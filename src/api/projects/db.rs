@@ -1,19 +1,34 @@
-use std::collections::HashSet;
+use std::collections::BTreeSet;
 
+use chrono::Utc;
 use diesel::dsl::now;
 use diesel::prelude::*;
+use diesel::sql_query;
+use diesel::sql_types::{Integer, Nullable, Text, Timestamptz};
 
-use super::models::{NewProject, Project, ProjectOptions, QueryParams, UpdateProject};
+use super::models::{
+    FacetRow, Maintainer, NewProject, OpenCounts, Project, ProjectDetail, ProjectIncludes, ProjectOptions,
+    ProjectStats, QueryParams, StatsRange, StatsTotals,
+    UpdateProject, WeeklyStats, GOOD_FIRST_ISSUE_LABEL,
+};
+use crate::api::archival::{db::mark, models::{ArchivalKind, Stamp}};
+use crate::api::filters::{ItemFilter, ProjectFilter};
+use crate::api::repositories::models::Repository;
+use crate::api::roles::models::MAINTAINER_ROLE;
 use crate::schema::issues::dsl as issues_dsl;
 use crate::schema::projects::dsl as projects_dsl;
 use crate::schema::repositories::dsl as repositories_dsl;
+use crate::schema::roles::dsl as roles_dsl;
+use crate::schema::slug_history::dsl as slug_history_dsl;
+use crate::schema::tasks::dsl as tasks_dsl;
+use crate::schema::users::dsl as users_dsl;
+use crate::schema::users_projects_roles::dsl as users_projects_roles_dsl;
 
 use crate::db::{
     errors::DBError,
-    pool::{DBAccess, DBAccessor},
+    pool::DBAccess,
 };
 use crate::types::PaginationParams;
-use crate::utils;
 pub trait DBProject: Send + Sync + Clone + 'static {
     fn all(
         &self,
@@ -21,8 +36,15 @@ pub trait DBProject: Send + Sync + Clone + 'static {
         pagination: PaginationParams,
     ) -> Result<(Vec<Project>, i64), DBError>;
     fn options(&self, params: QueryParams) -> Result<ProjectOptions, DBError>;
+    fn stats(&self, id: i32, range: &StatsRange) -> Result<ProjectStats, DBError>;
     fn by_id(&self, id: i32) -> Result<Option<Project>, DBError>;
     fn by_slug(&self, slug: &str) -> Result<Option<Project>, DBError>;
+    /// Live project that used to have `slug`.
+    fn by_former_slug(&self, slug: &str) -> Result<Option<Project>, DBError>;
+    /// Project that has or used to have `slug`, deleted ones included.
+    fn slug_owner(&self, slug: &str) -> Result<Option<i32>, DBError>;
+    /// Embeds the parts of `includes` in `project`.
+    fn detail(&self, project: Project, includes: ProjectIncludes) -> Result<ProjectDetail, DBError>;
     fn create(&self, form: &NewProject) -> Result<Project, DBError>;
     fn update(&self, id: i32, form: &UpdateProject) -> Result<Project, DBError>;
     fn delete(&self, id: i32) -> Result<(), DBError>;
@@ -30,372 +52,305 @@ pub trait DBProject: Send + Sync + Clone + 'static {
 
 impl DBProject for DBAccess {
     fn options(&self, params: QueryParams) -> Result<ProjectOptions, DBError> {
-        let conn = &mut self.get_db_conn();
-        println!("{:?}", params);
-        let project_ids: Option<Vec<i32>> = {
-            let base = issues_dsl::issues
-                .inner_join(
-                    repositories_dsl::repositories
-                        .on(issues_dsl::repository_id.eq(repositories_dsl::id)),
-                )
-                .inner_join(
-                    projects_dsl::projects.on(repositories_dsl::project_id.eq(projects_dsl::id)),
-                )
-                .select(projects_dsl::id);
-
-                match (
-                    params.certified_or_labels,
-                    params.labels.as_ref(),
-                    params.certified.as_ref(),
-                    params.open,
-                ) {
-                    // Case 1: certified_or_labels is true, both labels and certified are provided
-                    (Some(true), Some(labels), Some(certified), Some(open)) => base
-                        .filter(
-                            issues_dsl::labels
-                                .overlaps_with(utils::parse_comma_values(labels))
-                                .or(issues_dsl::certified.eq(certified))
-                                .and(issues_dsl::open.eq(open)),
-                        )
-                        .distinct()
-                        .load::<i32>(conn)
-                        .optional()?,
-                    
-                    // Case 2: certified_or_labels is true, only labels are provided
-                    (Some(true), Some(labels), None, Some(open)) => base
-                        .filter(
-                            issues_dsl::labels
-                                .overlaps_with(utils::parse_comma_values(labels))
-                                .and(issues_dsl::open.eq(open)),
-                        )
-                        .distinct()
-                        .load::<i32>(conn)
-                        .optional()?,
-                    
-                    // Case 3: certified_or_labels is true, only certified is provided
-                    (Some(true), None, Some(certified), Some(open)) => base
-                        .filter(
-                            issues_dsl::certified
-                                .eq(certified)
-                                .and(issues_dsl::open.eq(open)),
-                        )
-                        .distinct()
-                        .load::<i32>(conn)
-                        .optional()?,
-                    
-                    // Case 4: certified_or_labels is true, neither labels nor certified are provided, but open is specified
-                    (Some(true), None, None, Some(open)) => base
-                        .filter(issues_dsl::open.eq(open))
-                        .distinct()
-                        .load::<i32>(conn)
-                        .optional()?,
-                    
-                    // Case 5: certified_or_labels is false or None, both labels and certified are provided
-                    (_, Some(labels), Some(certified), Some(open)) => base
-                        .filter(
-                            issues_dsl::labels
-                                .overlaps_with(utils::parse_comma_values(labels))
-                                .and(issues_dsl::certified.eq(certified))
-                                .and(issues_dsl::open.eq(open)),
-                        )
-                        .distinct()
-                        .load::<i32>(conn)
-                        .optional()?,
-                    
-                    // Case 6: certified_or_labels is false or None, only labels are provided
-                    (_, Some(labels), None, Some(open)) => base
-                        .filter(
-                            issues_dsl::labels
-                                .overlaps_with(utils::parse_comma_values(labels))
-                                .and(issues_dsl::open.eq(open)),
-                        )
-                        .distinct()
-                        .load::<i32>(conn)
-                        .optional()?,
-                    
-                    // Case 7: certified_or_labels is false or None, only certified is provided
-                    (_, None, Some(certified), Some(open)) => base
-                        .filter(
-                            issues_dsl::certified
-                                .eq(certified)
-                                .and(issues_dsl::open.eq(open)),
-                        )
-                        .distinct()
-                        .load::<i32>(conn)
-                        .optional()?,
-                    
-                    // Case 8: certified_or_labels is false or None, and open is specified, no labels or certified
-                    (_, None, None, Some(open)) => issues_dsl::issues
-                        .inner_join(
-                            repositories_dsl::repositories
-                                .on(issues_dsl::repository_id.eq(repositories_dsl::id)),
-                        )
-                        .inner_join(
-                            projects_dsl::projects
-                                .on(repositories_dsl::project_id.eq(projects_dsl::id)),
-                        )
-                        .select(projects_dsl::id)
-                        .filter(issues_dsl::open.eq(open))
-                        .distinct()
-                        .load::<i32>(conn)
-                        .optional()?,
-                    
-                    // Case 9: certified_or_labels is true, only labels are provided and open is not specified
-                    (Some(true), Some(labels), None, None) => base
-                        .filter(
-                            issues_dsl::labels
-                                .overlaps_with(utils::parse_comma_values(labels)),
-                        )
-                        .distinct()
-                        .load::<i32>(conn)
-                        .optional()?,
-                    
-                    // Case 10: certified_or_labels is true, only certified is provided and open is not specified
-                    (Some(true), None, Some(certified), None) => base
-                        .filter(
-                            issues_dsl::certified.eq(certified),
-                        )
-                        .distinct()
-                        .load::<i32>(conn)
-                        .optional()?,
-                    
-                    // Case 11: certified_or_labels is false or None, both labels and certified are provided but open is not specified
-                    (_, Some(labels), Some(certified), None) => base
-                        .filter(
-                            issues_dsl::labels
-                                .overlaps_with(utils::parse_comma_values(labels))
-                                .and(issues_dsl::certified.eq(certified)),
-                        )
-                        .distinct()
-                        .load::<i32>(conn)
-                        .optional()?,
-                    
-                    // Case 12: certified_or_labels is false or None, only labels are provided and open is not specified
-                    (_, Some(labels), None, None) => base
-                        .filter(
-                            issues_dsl::labels
-                                .overlaps_with(utils::parse_comma_values(labels)),
-                        )
-                        .distinct()
-                        .load::<i32>(conn)
-                        .optional()?,
-                    
-                    // Case 13: certified_or_labels is false or None, only certified is provided and open is not specified
-                    (_, None, Some(certified), None) => base
-                        .filter(
-                            issues_dsl::certified.eq(certified),
-                        )
-                        .distinct()
-                        .load::<i32>(conn)
-                        .optional()?,
-                    
-                    // Case 14: certified_or_labels is false or None, no labels or certified, and open is not specified
-                    (_, None, None, None) => base
-                        .distinct()
-                        .load::<i32>(conn)
-                        .optional()?,
-                }
-        };
-
-        let build_query = || {
-            let mut query = projects_dsl::projects.into_boxed();
-            if let Some(slugs) = params.slugs.as_ref() {
-                query = query.filter(projects_dsl::slug.eq_any(utils::parse_comma_values(slugs)));
-            }
-            if let Some(purposes) = params.purposes.as_ref() {
-                query = query.filter(
-                    projects_dsl::purposes.overlaps_with(utils::parse_comma_values(purposes)),
-                );
-            }
-            if let Some(technologies) = params.technologies.as_ref() {
-                query = query.filter(
-                    projects_dsl::technologies
-                        .overlaps_with(utils::parse_comma_values(technologies)),
-                );
-            }
-            if let Some(stack_levels) = params.stack_levels.as_ref() {
-                query = query.filter(
-                    projects_dsl::stack_levels
-                        .overlaps_with(utils::parse_comma_values(stack_levels)),
-                );
-            }
-            if let Some(rewards) = params.rewards.as_ref() {
-                query = query.filter(projects_dsl::rewards.eq(rewards));
-            }
-
-            if let Some(project_ids) = project_ids.as_ref() {
-                query = query.filter(projects_dsl::id.eq_any(project_ids));
-            }
-
-            query
-        };
-
-        let result = build_query().load::<Project>(conn)?;
-
-        let mut unique_types = HashSet::new();
-        let mut unique_purposes = HashSet::new();
-        let mut unique_technologies = HashSet::new();
-        let mut unique_stack_levels = HashSet::new();
-
-        for project in &result {
-            if let Some(types) = &project.types {
-                for type_option in types {
-                    unique_types.insert(type_option.clone());
-                }
-            }
-
-            if let Some(purposes) = &project.purposes {
-                for purpose_option in purposes {
-                    unique_purposes.insert(purpose_option.clone());
-                }
-            }
-            if let Some(stack_levels) = &project.stack_levels {
-                for stack_level in stack_levels {
-                    unique_stack_levels.insert(stack_level.clone());
-                }
-            }
-            if let Some(technologies) = &project.technologies {
-                for technology in technologies {
-                    unique_technologies.insert(technology.clone());
-                }
+        let projects = ProjectFilter::from(&params);
+        let items = ItemFilter::from(&params);
+        let source = params.source.unwrap_or_default();
+        self.with_conn(|conn| {
+            // only projects with at least one matching issue or task have options
+            let mut project_ids = projects_dsl::projects
+                .filter(projects_dsl::archived_at.is_null())
+                .filter(projects_dsl::deleted_at.is_null())
+                .filter(items.project_predicate(source))
+                .select(projects_dsl::id)
+                .into_boxed();
+            if let Some(predicate) = projects.predicate() {
+                project_ids = project_ids.filter(predicate);
             }
-        }
-
-        let project_options = ProjectOptions {
-            types: Some(unique_types.into_iter().collect()),
-            purposes: Some(unique_purposes.into_iter().collect()),
-            technologies: Some(unique_technologies.into_iter().collect()),
-            stack_levels: Some(unique_stack_levels.into_iter().collect()),
-        };
+            let rows = projects_dsl::projects
+                .left_join(
+                    repositories_dsl::repositories.on(repositories_dsl::project_id
+                        .eq(projects_dsl::id)
+                        .and(repositories_dsl::archived_at.is_null())
+                        .and(repositories_dsl::deleted_at.is_null())),
+                )
+                .filter(projects_dsl::id.eq_any(project_ids))
+                .select((
+                    projects_dsl::id,
+                    projects_dsl::types,
+                    projects_dsl::purposes,
+                    projects_dsl::technologies,
+                    projects_dsl::stack_levels,
+                    repositories_dsl::language_slug.nullable(),
+                ))
+                .load::<FacetRow>(conn)?;
+
+            Ok(ProjectOptions::from_rows(&rows))
+        })
+    }
 
-        Ok(project_options)
+    fn stats(&self, id: i32, range: &StatsRange) -> Result<ProjectStats, DBError> {
+        self.with_conn(|conn| {
+            let totals = sql_query(
+                "WITH project_issues AS (
+                    SELECT i.* FROM issues i
+                    JOIN repositories r ON r.id = i.repository_id
+                    WHERE r.project_id = $1 AND i.deleted_at IS NULL
+                        AND ($2 IS NULL OR i.issue_created_at >= $2)
+                        AND i.issue_created_at < $3
+                ), project_tasks AS (
+                    SELECT t.* FROM tasks t
+                    LEFT JOIN repositories r ON r.id = t.repository_id
+                    WHERE (t.project_id = $1 OR r.project_id = $1) AND t.deleted_at IS NULL
+                        AND ($2 IS NULL OR t.created_at >= $2)
+                        AND t.created_at < $3
+                )
+                SELECT
+                    (SELECT COUNT(*) FROM project_issues WHERE open) AS open_issues,
+                    (SELECT COUNT(*) FROM project_issues WHERE NOT open) AS closed_issues,
+                    (SELECT COUNT(*) FROM project_tasks WHERE open) AS open_tasks,
+                    (SELECT COUNT(*) FROM project_tasks WHERE NOT open) AS closed_tasks,
+                    (SELECT percentile_cont(0.5) WITHIN GROUP (
+                        ORDER BY EXTRACT(EPOCH FROM issue_closed_at - issue_created_at)::float8 / 3600)
+                        FROM project_issues WHERE issue_closed_at IS NOT NULL) AS median_hours_to_close,
+                    (SELECT COUNT(DISTINCT user_id) FROM (
+                        SELECT assignee_id AS user_id FROM project_issues
+                        UNION SELECT assignee_user_id FROM project_tasks) assignees) AS contributors,
+                    (SELECT COUNT(*) FROM project_issues
+                        WHERE open AND assignee_id IS NULL AND $4 = ANY(labels)) AS unassigned_good_first_issues,
+                    (SELECT COALESCE(SUM(bounty), 0)::bigint FROM project_tasks
+                        WHERE open AND status <> 'completed') AS outstanding_bounty",
+            )
+            .bind::<Integer, _>(id)
+            .bind::<Nullable<Timestamptz>, _>(range.from)
+            .bind::<Timestamptz, _>(range.to)
+            .bind::<Text, _>(GOOD_FIRST_ISSUE_LABEL)
+            .get_result::<StatsTotals>(conn)?;
+
+            let weekly = sql_query(
+                "WITH weeks AS (
+                    SELECT generate_series(date_trunc('week', $2), $3, interval '1 week') AS week
+                ), project_issues AS (
+                    SELECT i.issue_created_at, i.issue_closed_at FROM issues i
+                    JOIN repositories r ON r.id = i.repository_id
+                    WHERE r.project_id = $1 AND i.deleted_at IS NULL
+                ), project_tasks AS (
+                    SELECT t.created_at FROM tasks t
+                    LEFT JOIN repositories r ON r.id = t.repository_id
+                    WHERE (t.project_id = $1 OR r.project_id = $1) AND t.deleted_at IS NULL
+                )
+                SELECT
+                    week,
+                    (SELECT COUNT(*) FROM project_issues
+                        WHERE issue_created_at >= week AND issue_created_at < week + interval '1 week') AS issues_opened,
+                    (SELECT COUNT(*) FROM project_issues
+                        WHERE issue_closed_at >= week AND issue_closed_at < week + interval '1 week') AS issues_closed,
+                    (SELECT COUNT(*) FROM project_tasks
+                        WHERE created_at >= week AND created_at < week + interval '1 week') AS tasks_created
+                FROM weeks
+                ORDER BY week",
+            )
+            .bind::<Integer, _>(id)
+            .bind::<Timestamptz, _>(range.series_from)
+            .bind::<Timestamptz, _>(range.to)
+            .load::<WeeklyStats>(conn)?;
+
+            Ok(ProjectStats::new(id, *range, totals, weekly))
+        })
     }
+
     fn all(
         &self,
         params: QueryParams,
         pagination: PaginationParams,
     ) -> Result<(Vec<Project>, i64), DBError> {
-        let conn = &mut self.get_db_conn();
-
-        // filter by labels
-        let project_ids: Option<Vec<i32>> = if let Some(certified) = params.certified.as_ref() {
-            issues_dsl::issues
-                .inner_join(
-                    repositories_dsl::repositories
-                        .on(issues_dsl::repository_id.eq(repositories_dsl::id)),
-                )
-                .inner_join(
-                    projects_dsl::projects.on(repositories_dsl::project_id.eq(projects_dsl::id)),
-                )
-                .select(projects_dsl::id)
-                .filter(issues_dsl::certified.eq(certified))
-                .distinct()
-                .load::<i32>(conn)
-                .optional()?
-        } else {
-            None
-        };
-
-        let build_query = || {
-            let mut query = projects_dsl::projects.into_boxed();
-            if let Some(slugs) = params.slugs.as_ref() {
-                query = query.filter(projects_dsl::slug.eq_any(utils::parse_comma_values(slugs)));
-            }
-            if let Some(purposes) = params.purposes.as_ref() {
-                query = query.filter(
-                    projects_dsl::purposes.overlaps_with(utils::parse_comma_values(purposes)),
-                );
-            }
-            if let Some(technologies) = params.technologies.as_ref() {
-                query = query.filter(
-                    projects_dsl::technologies
-                        .overlaps_with(utils::parse_comma_values(technologies)),
-                );
-            }
-            if let Some(stack_levels) = params.stack_levels.as_ref() {
-                query = query.filter(
-                    projects_dsl::stack_levels
-                        .overlaps_with(utils::parse_comma_values(stack_levels)),
-                );
-            }
-            if let Some(rewards) = params.rewards.as_ref() {
-                query = query.filter(projects_dsl::rewards.eq(rewards));
-            }
-
-            if let Some(project_ids) = project_ids.as_ref() {
-                query = query.filter(projects_dsl::id.eq_any(project_ids));
-            }
-            if let Some(types) = params.types.as_ref() {
-                query = query.filter(
-                    projects_dsl::types
-                        .overlaps_with(utils::parse_comma_values(types)),
-                );
-            }
-            query
-        };
+        let projects = ProjectFilter::from(&params);
+        let items = ItemFilter::from(&params);
+        let source = params.source.unwrap_or_default();
+        let include_archived = params.include_archived.unwrap_or(false);
+        self.with_conn(|conn| {
+            let build_query = || {
+                let mut query = projects_dsl::projects
+                    .filter(projects_dsl::deleted_at.is_null())
+                    .into_boxed();
+                if !include_archived {
+                    query = query.filter(projects_dsl::archived_at.is_null());
+                }
+                if let Some(predicate) = projects.predicate() {
+                    query = query.filter(predicate);
+                }
+                if !items.is_empty() {
+                    query = query.filter(items.project_predicate(source));
+                }
+                query
+            };
 
-        let total_count = build_query().count().get_result::<i64>(conn)?;
+            let total_count = build_query().count().get_result::<i64>(conn)?;
 
-        let result = build_query()
-            .offset(pagination.offset)
-            .limit(pagination.limit)
-            .load::<Project>(conn)?;
+            let result = build_query()
+                .offset(pagination.offset)
+                .limit(pagination.limit)
+                .load::<Project>(conn)?;
 
-        Ok((result, total_count))
+            Ok((result, total_count))
+        })
     }
 
     fn by_id(&self, id: i32) -> Result<Option<Project>, DBError> {
-        let conn = &mut self.get_db_conn();
-
-        let result = projects_dsl::projects
-            .find(id)
-            .first::<Project>(conn)
-            .optional()
-            .map_err(DBError::from)?;
-
-        Ok(result)
+        self.with_conn(|conn| {
+            let result = projects_dsl::projects
+                .filter(projects_dsl::id.eq(id))
+                .filter(projects_dsl::deleted_at.is_null())
+                .first::<Project>(conn)
+                .optional()
+                .map_err(DBError::from)?;
+
+            Ok(result)
+        })
     }
 
     fn by_slug(&self, slug: &str) -> Result<Option<Project>, DBError> {
-        let conn = &mut self.get_db_conn();
-
-        let result = projects_dsl::projects
-            .filter(projects_dsl::slug.eq(slug))
-            .first::<Project>(conn)
-            .optional()
-            .map_err(DBError::from)?;
+        self.with_conn(|conn| {
+            let result = projects_dsl::projects
+                .filter(projects_dsl::slug.eq(slug))
+                .filter(projects_dsl::deleted_at.is_null())
+                .first::<Project>(conn)
+                .optional()
+                .map_err(DBError::from)?;
+
+            Ok(result)
+        })
+    }
 
-        Ok(result)
+    fn by_former_slug(&self, slug: &str) -> Result<Option<Project>, DBError> {
+        self.with_conn(|conn| {
+            let former = slug_history_dsl::slug_history
+                .filter(slug_history_dsl::slug.eq(slug))
+                .select(slug_history_dsl::project_id);
+            let result = projects_dsl::projects
+                .filter(projects_dsl::id.nullable().eq_any(former))
+                .filter(projects_dsl::deleted_at.is_null())
+                .first::<Project>(conn)
+                .optional()?;
+
+            Ok(result)
+        })
     }
 
-    fn create(&self, form: &NewProject) -> Result<Project, DBError> {
-        let conn = &mut self.get_db_conn();
+    fn slug_owner(&self, slug: &str) -> Result<Option<i32>, DBError> {
+        self.with_conn(|conn| {
+            let current = projects_dsl::projects
+                .filter(projects_dsl::slug.eq(slug))
+                .select(projects_dsl::id)
+                .first::<i32>(conn)
+                .optional()?;
+            if current.is_some() {
+                return Ok(current);
+            }
+            let former = slug_history_dsl::slug_history
+                .filter(slug_history_dsl::slug.eq(slug))
+                .filter(slug_history_dsl::project_id.is_not_null())
+                .select(slug_history_dsl::project_id.assume_not_null())
+                .first::<i32>(conn)
+                .optional()?;
+            Ok(former)
+        })
+    }
 
-        let project = diesel::insert_into(projects_dsl::projects)
-            .values(form)
-            .get_result(conn)
-            .map_err(DBError::from)?;
+    fn detail(&self, project: Project, includes: ProjectIncludes) -> Result<ProjectDetail, DBError> {
+        self.with_conn(|conn| {
+            let repositories = if includes.repositories || includes.languages {
+                repositories_dsl::repositories
+                    .filter(repositories_dsl::project_id.eq(project.id))
+                    .filter(repositories_dsl::deleted_at.is_null())
+                    .order(repositories_dsl::slug.asc())
+                    .load::<Repository>(conn)?
+            } else {
+                Vec::new()
+            };
+            let languages = includes.languages.then(|| {
+                let languages: BTreeSet<String> =
+                    repositories.iter().filter_map(|r| r.language_slug.clone()).collect();
+                languages.into_iter().collect()
+            });
+
+            let maintainers = if includes.maintainers {
+                Some(
+                    users_projects_roles_dsl::users_projects_roles
+                        .inner_join(users_dsl::users)
+                        .inner_join(roles_dsl::roles)
+                        .filter(users_projects_roles_dsl::project_id.eq(project.id))
+                        .filter(roles_dsl::name.eq(MAINTAINER_ROLE))
+                        .select((users_dsl::id, users_dsl::username, users_dsl::avatar))
+                        .distinct()
+                        .order(users_dsl::username.asc())
+                        .load::<Maintainer>(conn)?,
+                )
+            } else {
+                None
+            };
+
+            let counts = if includes.counts {
+                let project_repositories = repositories_dsl::repositories
+                    .filter(repositories_dsl::project_id.eq(project.id))
+                    .select(repositories_dsl::id);
+                let open_issues = issues_dsl::issues
+                    .filter(issues_dsl::open.eq(true))
+                    .filter(issues_dsl::deleted_at.is_null())
+                    .filter(issues_dsl::repository_id.eq_any(project_repositories))
+                    .count()
+                    .get_result::<i64>(conn)?;
+                let open_tasks = tasks_dsl::tasks
+                    .filter(tasks_dsl::open.eq(true))
+                    .filter(tasks_dsl::deleted_at.is_null())
+                    .filter(
+                        tasks_dsl::project_id
+                            .eq(project.id)
+                            .or(tasks_dsl::repository_id.eq_any(project_repositories.nullable())),
+                    )
+                    .count()
+                    .get_result::<i64>(conn)?;
+                Some(OpenCounts { open_issues, open_tasks })
+            } else {
+                None
+            };
+
+            Ok(ProjectDetail {
+                project,
+                repositories: includes.repositories.then_some(repositories),
+                languages,
+                maintainers,
+                counts,
+            })
+        })
+    }
 
-        Ok(project)
+    fn create(&self, form: &NewProject) -> Result<Project, DBError> {
+        self.with_conn(|conn| {
+            let project = diesel::insert_into(projects_dsl::projects)
+                .values(form)
+                .get_result(conn)
+                .map_err(DBError::from)?;
+
+            Ok(project)
+        })
     }
 
     fn update(&self, id: i32, form: &UpdateProject) -> Result<Project, DBError> {
-        let conn = &mut self.get_db_conn();
-
-        let project = diesel::update(projects_dsl::projects.filter(projects_dsl::id.eq(id)))
-            .set((form, projects_dsl::updated_at.eq(now)))
-            .get_result::<Project>(conn)
-            .map_err(DBError::from)?;
-
-        Ok(project)
+        self.with_conn(|conn| {
+            let project = diesel::update(projects_dsl::projects.filter(projects_dsl::id.eq(id)))
+                .set((form, projects_dsl::updated_at.eq(now)))
+                .get_result::<Project>(conn)
+                .map_err(DBError::from)?;
+
+            Ok(project)
+        })
     }
 
     fn delete(&self, id: i32) -> Result<(), DBError> {
-        let conn = &mut self.get_db_conn();
-        diesel::delete(projects_dsl::projects.filter(projects_dsl::id.eq(id)))
-            .execute(conn)
-            .map_err(DBError::from)?;
-
-        Ok(())
+        // soft delete, the rows are purged after the retention period
+        self.with_conn(|conn| {
+            mark(conn, ArchivalKind::Projects, id, Stamp::Deleted, Utc::now())?;
+            Ok(())
+        })
     }
 }
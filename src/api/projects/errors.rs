@@ -17,6 +17,8 @@ pub enum ProjectError {
     NotFoundBySlug(String),
     InvalidPayload(String),
     CannotCreate(String),
+    InvalidDateRange(String),
+    InvalidInclude(String),
 }
 
 impl fmt::Display for ProjectError {
@@ -27,6 +29,8 @@ impl fmt::Display for ProjectError {
             ProjectError::NotFoundBySlug(slug) => write!(f, "Project {slug} not found"),
             ProjectError::InvalidPayload(error) => write!(f, "Invalid payload: {error}"),
             ProjectError::CannotCreate(error) => write!(f, "Cannot create the project: {error}"),
+            ProjectError::InvalidDateRange(error) => write!(f, "Invalid date range: {error}"),
+            ProjectError::InvalidInclude(error) => write!(f, "Invalid include: {error}"),
         }
     }
 }
@@ -41,10 +45,12 @@ impl Reply for ProjectError {
             ProjectError::NotFoundBySlug(_) => StatusCode::NOT_FOUND,
             ProjectError::InvalidPayload(_) => StatusCode::UNPROCESSABLE_ENTITY,
             ProjectError::CannotCreate(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ProjectError::InvalidDateRange(_) => StatusCode::BAD_REQUEST,
+            ProjectError::InvalidInclude(_) => StatusCode::BAD_REQUEST,
         };
         let message = self.to_string();
 
-        let json = warp::reply::json(&ErrorResponse { message });
+        let json = warp::reply::json(&ErrorResponse::new(message));
 
         warp::reply::with_status(json, code).into_response()
     }
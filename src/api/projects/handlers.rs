@@ -1,19 +1,36 @@
-use crate::{api::roles::{db::DBRole, models::KudosRole, utils::user_has_at_least_one_role}, middlewares::github::model::GitHubUser, types::{PaginatedResponse, PaginationParams}};
+use crate::{api::{roles::{db::DBRole, models::KudosRole, utils::user_has_at_least_one_role}, taxonomy::{db::DBTaxonomy, models::TaxonomyKind, utils::unknown_tags}}, middlewares::github::model::GitHubUser, types::{PaginatedResponse, PaginationParams}};
 
 use super::{
     db::DBProject,
     errors::ProjectError,
-    models::{NewProject, QueryParams, UpdateProject},
+    models::{DetailParams, NewProject, ProjectIncludes, QueryParams, StatsParams, UpdateProject},
 };
+use chrono::Utc;
 use bytes::Buf;
 use log::{error, info, warn};
 use warp::{
-    http::StatusCode,
+    http::{header::LOCATION, StatusCode},
     reject,
     reject::Rejection,
-    reply::{json, with_status, Reply},
+    reply::{json, with_header, with_status, Reply},
 };
 
+/// Project tags must be terms of the taxonomy.
+fn validate_tags(
+    db_access: &impl DBTaxonomy,
+    tags: [(TaxonomyKind, &Option<Vec<Option<String>>>); 4],
+) -> Result<(), Rejection> {
+    let tags = tags.map(|(kind, values)| (kind, values.iter().flatten().flatten().cloned().collect()));
+    match unknown_tags(db_access, tags)? {
+        Some((kind, unknown)) => Err(reject::custom(ProjectError::InvalidPayload(format!(
+            "unknown {}: {}",
+            kind.project_column(),
+            unknown.join(", ")
+        )))),
+        None => Ok(()),
+    }
+}
+
 pub async fn all_handler(
     db_access: impl DBProject,
     params: QueryParams,
@@ -41,10 +58,49 @@ pub async fn options(
     Ok(json(&options))
 }
 
+pub async fn stats_handler(
+    id: i32,
+    params: StatsParams,
+    db_access: impl DBProject,
+) -> Result<impl Reply, Rejection> {
+    let range = params
+        .range(Utc::now())
+        .map_err(|e| reject::custom(ProjectError::InvalidDateRange(e)))?;
+    if db_access.by_id(id)?.is_none() {
+        return Err(reject::custom(ProjectError::NotFound(id)));
+    }
+    Ok(json(&db_access.stats(id, &range)?))
+}
+
+/// A former slug of a renamed project redirects to its current slug.
+pub async fn by_slug_handler(
+    slug: String,
+    params: DetailParams,
+    db_access: impl DBProject,
+) -> Result<impl Reply, Rejection> {
+    let includes = ProjectIncludes::parse(params.include.as_deref())
+        .map_err(|e| reject::custom(ProjectError::InvalidInclude(e)))?;
+    if let Some(project) = db_access.by_slug(&slug)? {
+        return Ok(json(&db_access.detail(project, includes)?).into_response());
+    }
+    match db_access.by_former_slug(&slug)? {
+        None => Err(reject::custom(ProjectError::NotFoundBySlug(slug))),
+        Some(project) => {
+            let query = params.include.map(|include| format!("?include={include}")).unwrap_or_default();
+            Ok(with_header(
+                StatusCode::MOVED_PERMANENTLY,
+                LOCATION,
+                format!("/projects/slug/{}{query}", project.slug),
+            )
+            .into_response())
+        }
+    }
+}
+
 pub async fn create_handler(
     user: GitHubUser,
     buf: impl Buf,
-    db_access: impl DBProject + DBRole,
+    db_access: impl DBProject + DBRole + DBTaxonomy,
 ) -> Result<impl Reply, Rejection> {
     let user_roles = DBRole::user_roles(&db_access, &user.username)?;
     user_has_at_least_one_role(
@@ -59,20 +115,33 @@ pub async fn create_handler(
         warn!("invalid project '{e}'",);
         reject::custom(ProjectError::InvalidPayload(e))
     })?;
-    match db_access.by_slug(&project.slug)? {
-        Some(p) => Err(warp::reject::custom(ProjectError::AlreadyExists(p.id))),
-        None => match DBProject::create(&db_access, &project) {
-            Ok(project) => {
-                info!("project slug '{}' created", project.slug);
-                Ok(with_status(json(&project), StatusCode::CREATED))
-            }
-            Err(error) => {
-                error!("error creating the project '{:?}': {}", project, error);
-                Err(warp::reject::custom(ProjectError::CannotCreate(
-                    "error creating the project".to_string(),
-                )))
-            }
+    validate_tags(
+        &db_access,
+        [
+            (TaxonomyKind::Type, &project.types),
+            (TaxonomyKind::Purpose, &project.purposes),
+            (TaxonomyKind::StackLevel, &project.stack_levels),
+            (TaxonomyKind::Technology, &project.technologies),
+        ],
+    )?;
+    match DBProject::create(&db_access, &project) {
+        Ok(project) => {
+            info!("project slug '{}' created", project.slug);
+            Ok(with_status(json(&project), StatusCode::CREATED))
+        }
+        // the database refuses slugs held by another project, former ones included
+        Err(error) if error.is_unique_violation() => match db_access.slug_owner(&project.slug)? {
+            Some(owner) => Err(warp::reject::custom(ProjectError::AlreadyExists(owner))),
+            None => Err(warp::reject::custom(ProjectError::CannotCreate(
+                "error creating the project".to_string(),
+            ))),
         },
+        Err(error) => {
+            error!("error creating the project '{:?}': {}", project, error);
+            Err(warp::reject::custom(ProjectError::CannotCreate(
+                "error creating the project".to_string(),
+            )))
+        }
     }
 }
 
@@ -80,7 +149,7 @@ pub async fn update_handler(
     id: i32,
     user: GitHubUser,
     form: UpdateProject,
-    db_access: impl DBProject + DBRole,
+    db_access: impl DBProject + DBRole + DBTaxonomy,
 ) -> Result<impl Reply, Rejection> {
     let user_roles = DBRole::user_roles(&db_access, &user.username)?;
     user_has_at_least_one_role(
@@ -89,11 +158,28 @@ pub async fn update_handler(
             KudosRole::Admin,
         ],
     )?;
+    validate_tags(
+        &db_access,
+        [
+            (TaxonomyKind::Type, &form.types),
+            (TaxonomyKind::Purpose, &form.purposes),
+            (TaxonomyKind::StackLevel, &form.stack_levels),
+            (TaxonomyKind::Technology, &form.technologies),
+        ],
+    )?;
     match DBProject::by_id(&db_access,id)? {
-        Some(p) => Ok(with_status(
-            json(&DBProject::update(&db_access, p.id, &form)?),
-            StatusCode::OK,
-        )),
+        Some(p) => match DBProject::update(&db_access, p.id, &form) {
+            Ok(project) => Ok(with_status(json(&project), StatusCode::OK)),
+            // former slugs stay with their project so that old links keep working
+            Err(error) if error.is_unique_violation() => {
+                let slug = form.slug.as_deref().unwrap_or_default();
+                match DBProject::slug_owner(&db_access, slug)? {
+                    Some(owner) => Err(warp::reject::custom(ProjectError::AlreadyExists(owner))),
+                    None => Err(warp::reject::custom(error)),
+                }
+            }
+            Err(error) => Err(warp::reject::custom(error)),
+        },
         None => Err(warp::reject::custom(ProjectError::NotFound(id))),
     }
 }
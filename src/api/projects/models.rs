@@ -1,6 +1,12 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::api::filters::{ItemSource, LabelMatch};
+use crate::api::repositories::models::Repository;
 use crate::schema::projects;
-use chrono::{DateTime, Utc};
+use crate::utils;
+use chrono::{DateTime, Duration, Utc};
 use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Double, Nullable, Timestamptz};
 
 use serde_derive::{Deserialize, Serialize};
 
@@ -21,16 +27,76 @@ pub struct Project {
     pub created_at: DateTime<Utc>,
     pub updated_at: Option<DateTime<Utc>>,
     pub rewards: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
-#[derive(
-    Debug, PartialEq, Serialize, Deserialize,
-)]
+/// A facet value and the number of matching projects that have it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FacetCount {
+    pub value: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct ProjectOptions {
+    pub types: Vec<FacetCount>,
+    pub purposes: Vec<FacetCount>,
+    pub technologies: Vec<FacetCount>,
+    pub stack_levels: Vec<FacetCount>,
+    pub languages: Vec<FacetCount>,
+}
+
+/// One project joined with one of its repositories, as loaded for the options.
+#[derive(Queryable, Debug, Clone)]
+pub struct FacetRow {
+    pub project_id: i32,
     pub types: Option<Vec<Option<String>>>,
     pub purposes: Option<Vec<Option<String>>>,
     pub technologies: Option<Vec<Option<String>>>,
     pub stack_levels: Option<Vec<Option<String>>>,
+    pub language_slug: Option<String>,
+}
+
+fn facet_counts<'a>(values: impl Iterator<Item = (i32, &'a str)>) -> Vec<FacetCount> {
+    let mut projects: HashMap<&str, HashSet<i32>> = HashMap::new();
+    for (project_id, value) in values {
+        projects.entry(value).or_default().insert(project_id);
+    }
+    let mut facets: Vec<FacetCount> = projects
+        .into_iter()
+        .map(|(value, ids)| FacetCount {
+            value: value.to_owned(),
+            count: ids.len() as i64,
+        })
+        .collect();
+    facets.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    facets
+}
+
+fn tags(values: &Option<Vec<Option<String>>>) -> impl Iterator<Item = &str> {
+    values.iter().flatten().flatten().map(String::as_str)
+}
+
+impl ProjectOptions {
+    /// Counts distinct projects per facet value, most common values first.
+    pub fn from_rows(rows: &[FacetRow]) -> Self {
+        Self {
+            types: facet_counts(rows.iter().flat_map(|row| tags(&row.types).map(|v| (row.project_id, v)))),
+            purposes: facet_counts(rows.iter().flat_map(|row| tags(&row.purposes).map(|v| (row.project_id, v)))),
+            technologies: facet_counts(
+                rows.iter().flat_map(|row| tags(&row.technologies).map(|v| (row.project_id, v))),
+            ),
+            stack_levels: facet_counts(
+                rows.iter().flat_map(|row| tags(&row.stack_levels).map(|v| (row.project_id, v))),
+            ),
+            languages: facet_counts(
+                rows.iter().filter_map(|row| Some((row.project_id, row.language_slug.as_deref()?))),
+            ),
+        }
+    }
 }
 #[derive(Deserialize, Debug)]
 pub struct QueryParams {
@@ -42,9 +108,17 @@ pub struct QueryParams {
     pub certified: Option<bool>,
     pub open: Option<bool>,    
     pub labels: Option<String>,
+    /// Leave out items with any of these labels, as `labels!=a,b`.
+    #[serde(rename = "labels!")]
+    pub excluded_labels: Option<String>,
+    /// Match `any` (default) or `all` of the labels.
+    pub label_match: Option<LabelMatch>,
     pub certified_or_labels: Option<bool>,
     pub types: Option<String>,
-
+    /// Count the projects of matching issues (default) or tasks.
+    pub source: Option<ItemSource>,
+    /// Also list the archived ones, deleted ones are never listed.
+    pub include_archived: Option<bool>,
 }
 
 #[derive(Insertable, Serialize, Deserialize, Debug)]
@@ -52,6 +126,7 @@ pub struct QueryParams {
 pub struct NewProject {
     pub name: String,
     pub slug: String,
+    pub types: Option<Vec<Option<String>>>,
     pub purposes: Option<Vec<Option<String>>>,
     pub stack_levels: Option<Vec<Option<String>>>,
     pub technologies: Option<Vec<Option<String>>>,
@@ -64,6 +139,7 @@ pub struct NewProject {
 pub struct UpdateProject {
     pub name: Option<String>,
     pub slug: Option<String>,
+    pub types: Option<Vec<Option<String>>>,
     pub purposes: Option<Vec<Option<String>>>,
     pub stack_levels: Option<Vec<Option<String>>>,
     pub technologies: Option<Vec<Option<String>>>,
@@ -76,6 +152,7 @@ pub struct ProjectResponse {
     pub id: i32,
     pub name: String,
     pub slug: String,
+    pub types: Option<Vec<Option<String>>>,
     pub purposes: Option<Vec<Option<String>>>,
     pub stack_levels: Option<Vec<Option<String>>>,
     pub technologies: Option<Vec<Option<String>>>,
@@ -84,3 +161,201 @@ pub struct ProjectResponse {
     pub updated_at: Option<DateTime<Utc>>,
     pub rewards: bool,
 }
+
+/// Label GitHub gives to issues suited to newcomers.
+pub const GOOD_FIRST_ISSUE_LABEL: &str = "good first issue";
+
+/// Weeks covered by the time series when no start date is given.
+pub const DEFAULT_STATS_WEEKS: i64 = 12;
+
+/// Longest time series served, about ten years of weeks.
+pub const MAX_STATS_WEEKS: i64 = 520;
+
+#[derive(Deserialize, Debug, Default)]
+pub struct StatsParams {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// The period of the project statistics. Counts only consider items created
+/// in `[from, to)` when `from` was given, the weekly series always covers it.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct StatsRange {
+    pub from: Option<DateTime<Utc>>,
+    pub to: DateTime<Utc>,
+    #[serde(skip)]
+    pub series_from: DateTime<Utc>,
+}
+
+impl StatsParams {
+    pub fn range(&self, now: DateTime<Utc>) -> Result<StatsRange, String> {
+        let to = self.to.unwrap_or(now);
+        let series_from = self.from.unwrap_or(to - Duration::weeks(DEFAULT_STATS_WEEKS));
+        if series_from >= to {
+            return Err("'from' must be before 'to'".to_owned());
+        }
+        if to - series_from > Duration::weeks(MAX_STATS_WEEKS) {
+            return Err(format!("the range cannot exceed {MAX_STATS_WEEKS} weeks"));
+        }
+        Ok(StatsRange {
+            from: self.from,
+            to,
+            series_from,
+        })
+    }
+}
+
+#[derive(QueryableByName, Debug)]
+pub struct StatsTotals {
+    #[diesel(sql_type = BigInt)]
+    pub open_issues: i64,
+    #[diesel(sql_type = BigInt)]
+    pub closed_issues: i64,
+    #[diesel(sql_type = BigInt)]
+    pub open_tasks: i64,
+    #[diesel(sql_type = BigInt)]
+    pub closed_tasks: i64,
+    #[diesel(sql_type = Nullable<Double>)]
+    pub median_hours_to_close: Option<f64>,
+    #[diesel(sql_type = BigInt)]
+    pub contributors: i64,
+    #[diesel(sql_type = BigInt)]
+    pub unassigned_good_first_issues: i64,
+    #[diesel(sql_type = BigInt)]
+    pub outstanding_bounty: i64,
+}
+
+#[derive(QueryableByName, Serialize, Debug, Clone, PartialEq)]
+pub struct WeeklyStats {
+    /// Start of the week, a Monday.
+    #[diesel(sql_type = Timestamptz)]
+    pub week: DateTime<Utc>,
+    #[diesel(sql_type = BigInt)]
+    pub issues_opened: i64,
+    #[diesel(sql_type = BigInt)]
+    pub issues_closed: i64,
+    #[diesel(sql_type = BigInt)]
+    pub tasks_created: i64,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct OpenClosed {
+    pub open: i64,
+    pub closed: i64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ProjectStats {
+    pub project_id: i32,
+    pub range: StatsRange,
+    pub issues: OpenClosed,
+    pub tasks: OpenClosed,
+    /// Median hours between the creation and the closing of closed issues.
+    pub median_hours_to_close: Option<f64>,
+    /// Distinct users assigned to an issue or a task.
+    pub contributors: i64,
+    pub unassigned_good_first_issues: i64,
+    /// Bounties of the open tasks that are not completed.
+    pub outstanding_bounty: i64,
+    pub weekly: Vec<WeeklyStats>,
+}
+
+impl ProjectStats {
+    pub fn new(project_id: i32, range: StatsRange, totals: StatsTotals, weekly: Vec<WeeklyStats>) -> Self {
+        Self {
+            project_id,
+            range,
+            issues: OpenClosed {
+                open: totals.open_issues,
+                closed: totals.closed_issues,
+            },
+            tasks: OpenClosed {
+                open: totals.open_tasks,
+                closed: totals.closed_tasks,
+            },
+            median_hours_to_close: totals.median_hours_to_close,
+            contributors: totals.contributors,
+            unassigned_good_first_issues: totals.unassigned_good_first_issues,
+            outstanding_bounty: totals.outstanding_bounty,
+            weekly,
+        }
+    }
+}
+
+/// Parts embedded in the project detail, all of them unless `include=` lists some.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProjectIncludes {
+    pub repositories: bool,
+    pub languages: bool,
+    pub maintainers: bool,
+    pub counts: bool,
+}
+
+impl Default for ProjectIncludes {
+    fn default() -> Self {
+        Self {
+            repositories: true,
+            languages: true,
+            maintainers: true,
+            counts: true,
+        }
+    }
+}
+
+impl ProjectIncludes {
+    pub fn parse(include: Option<&str>) -> Result<Self, String> {
+        let Some(include) = include else {
+            return Ok(Self::default());
+        };
+        let mut includes = Self {
+            repositories: false,
+            languages: false,
+            maintainers: false,
+            counts: false,
+        };
+        for part in utils::parse_comma_values(include) {
+            match part.trim() {
+                "" => {}
+                "repositories" => includes.repositories = true,
+                "languages" => includes.languages = true,
+                "maintainers" => includes.maintainers = true,
+                "counts" => includes.counts = true,
+                other => return Err(format!("unknown include '{other}'")),
+            }
+        }
+        Ok(includes)
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct DetailParams {
+    pub include: Option<String>,
+}
+
+#[derive(Queryable, Serialize, Debug, Clone, PartialEq)]
+pub struct Maintainer {
+    pub id: i32,
+    pub username: String,
+    pub avatar: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct OpenCounts {
+    pub open_issues: i64,
+    pub open_tasks: i64,
+}
+
+/// A project with the parts requested by `include=`; the others are omitted.
+#[derive(Serialize, Debug)]
+pub struct ProjectDetail {
+    #[serde(flatten)]
+    pub project: Project,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repositories: Option<Vec<Repository>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub languages: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maintainers: Option<Vec<Maintainer>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub counts: Option<OpenCounts>,
+}
@@ -3,23 +3,26 @@ use warp::filters::BoxedFilter;
 use warp::{Filter, Reply};
 
 use crate::api::roles::db::DBRole;
+use crate::api::taxonomy::db::DBTaxonomy;
 use crate::middlewares::github::auth::with_github_auth;
 use crate::types::PaginationParams;
 
 use super::db::DBProject;
 use super::handlers;
-use super::models::QueryParams;
+use super::models::{DetailParams, QueryParams, StatsParams};
 
 fn with_db(
-    db_pool: impl DBProject + DBRole,
-) -> impl Filter<Extract = (impl DBProject + DBRole,), Error = Infallible> + Clone {
+    db_pool: impl DBProject + DBRole + DBTaxonomy,
+) -> impl Filter<Extract = (impl DBProject + DBRole + DBTaxonomy,), Error = Infallible> + Clone {
     warp::any().map(move || db_pool.clone())
 }
 
-pub fn routes(db_access: impl DBProject + DBRole) -> BoxedFilter<(impl Reply,)> {
+pub fn routes(db_access: impl DBProject + DBRole + DBTaxonomy) -> BoxedFilter<(impl Reply,)> {
     let project = warp::path!("projects");
     let project_options = warp::path!("projects" / "options");
     let project_id = warp::path!("projects" / i32);
+    let project_stats = warp::path!("projects" / i32 / "stats");
+    let project_slug = warp::path!("projects" / "slug" / String);
 
     let all_route = project
         .and(warp::get())
@@ -34,6 +37,18 @@ pub fn routes(db_access: impl DBProject + DBRole) -> BoxedFilter<(impl Reply,)>
         .and(warp::query::<QueryParams>())
         .and_then(handlers::options);
 
+    let stats_route = project_stats
+        .and(warp::get())
+        .and(warp::query::<StatsParams>())
+        .and(with_db(db_access.clone()))
+        .and_then(handlers::stats_handler);
+
+    let slug_route = project_slug
+        .and(warp::get())
+        .and(warp::query::<DetailParams>())
+        .and(with_db(db_access.clone()))
+        .and_then(handlers::by_slug_handler);
+
     let get_route = project_id
         .and(warp::get())
         .and(with_db(db_access.clone()))
@@ -65,5 +80,7 @@ pub fn routes(db_access: impl DBProject + DBRole) -> BoxedFilter<(impl Reply,)>
         .or(update_route)
         .or(delete_route)
         .or(options)
+        .or(stats_route)
+        .or(slug_route)
         .boxed()
 }
@@ -1,26 +1,53 @@
 use std::collections::HashSet;
 
-use diesel::dsl::sql;
-use diesel::sql_types::Text;
+use chrono::Utc;
 use diesel::{dsl::now, prelude::*};
 
 use super::models::{
     LanguageQueryParams, NewRepository, QueryParams, Repository, RepositoryWithProject,
     UpdateRepository,
 };
+use crate::api::archival::{db::mark, models::{ArchivalKind, Stamp}};
+use crate::api::filters::{ItemFilter, ItemSource, ProjectFilter};
 use crate::api::projects::models::{Project, ProjectResponse};
 use crate::schema::issues::dsl as issues_dsl;
 use crate::schema::projects::dsl as projects_dsl;
 use crate::schema::repositories::dsl as repositories_dsl;
+use crate::schema::slug_history::dsl as slug_history_dsl;
 use crate::utils;
 use crate::{
     db::{
         errors::DBError,
-        pool::{DBAccess, DBAccessor},
+        pool::DBAccess,
     },
     types::PaginationParams,
 };
 
+fn with_project((repo, project): (Repository, Project)) -> RepositoryWithProject {
+    RepositoryWithProject {
+        id: repo.id,
+        slug: repo.slug,
+        name: repo.name,
+        url: repo.url,
+        language_slug: repo.language_slug,
+        project: ProjectResponse {
+            id: project.id,
+            name: project.name,
+            slug: project.slug,
+            types: project.types,
+            purposes: project.purposes,
+            stack_levels: project.stack_levels,
+            technologies: project.technologies,
+            avatar: project.avatar,
+            created_at: project.created_at,
+            updated_at: project.updated_at,
+            rewards: project.rewards,
+        },
+        created_at: repo.created_at,
+        updated_at: repo.updated_at,
+    }
+}
+
 pub trait DBRepository: Send + Sync + Clone + 'static {
     fn by_id(&self, id: i32) -> Result<Option<Repository>, DBError>;
     fn all(
@@ -32,6 +59,10 @@ pub trait DBRepository: Send + Sync + Clone + 'static {
     fn update(&self, id: i32, repo: &UpdateRepository) -> Result<Repository, DBError>;
     fn delete(&self, id: i32) -> Result<(), DBError>;
     fn by_slug(&self, slug: &str) -> Result<Option<RepositoryWithProject>, DBError>;
+    /// Live repository that used to have `slug`.
+    fn by_former_slug(&self, slug: &str) -> Result<Option<RepositoryWithProject>, DBError>;
+    /// Repository that has or used to have `slug`, deleted ones included.
+    fn slug_owner(&self, slug: &str) -> Result<Option<i32>, DBError>;
     fn aggregate_languages(&self, params: LanguageQueryParams) -> Result<Vec<String>, DBError>;
 }
 
@@ -41,269 +72,229 @@ impl DBRepository for DBAccess {
         params: QueryParams,
         pagination: PaginationParams,
     ) -> Result<Vec<RepositoryWithProject>, DBError> {
-        let conn = &mut self.get_db_conn();
-        let mut query = repositories_dsl::repositories
-            .inner_join(
-                projects_dsl::projects.on(repositories_dsl::project_id.eq(projects_dsl::id)),
-            )
-            .into_boxed();
+        self.with_conn(|conn| {
+            let mut query = repositories_dsl::repositories
+                .inner_join(
+                    projects_dsl::projects.on(repositories_dsl::project_id.eq(projects_dsl::id)),
+                )
+                .filter(repositories_dsl::deleted_at.is_null())
+                .into_boxed();
 
-        if let Some(languages) = params.languages {
-            query = query.filter(
-                repositories_dsl::language_slug.eq_any(utils::parse_comma_values(&languages)),
-            );
-        }
-        if let Some(slugs) = params.slugs {
-            query = query.filter(repositories_dsl::slug.eq_any(utils::parse_comma_values(&slugs)));
-        }
-        if let Some(names) = params.names {
-            query = query.filter(repositories_dsl::name.eq_any(utils::parse_comma_values(&names)));
-        }
+            if !params.include_archived.unwrap_or(false) {
+                query = query.filter(repositories_dsl::archived_at.is_null());
+            }
 
-        if let Some(project_id) = params.project_ids {
-            let ids: Vec<i32> = utils::parse_ids(&project_id);
-            if !ids.is_empty() {
-                query = query.filter(repositories_dsl::project_id.eq_any(ids));
+            if let Some(languages) = params.languages {
+                query = query.filter(
+                    repositories_dsl::language_slug.eq_any(utils::parse_comma_values(&languages)),
+                );
+            }
+            if let Some(slugs) = params.slugs {
+                query = query.filter(repositories_dsl::slug.eq_any(utils::parse_comma_values(&slugs)));
+            }
+            if let Some(names) = params.names {
+                query = query.filter(repositories_dsl::name.eq_any(utils::parse_comma_values(&names)));
             }
-        }
 
-        query = query
-            .offset(pagination.offset)
-            .limit(pagination.limit)
-            .select((
-                repositories_dsl::repositories::all_columns(),
-                projects_dsl::projects::all_columns(),
-            ));
+            if let Some(project_id) = params.project_ids {
+                let ids: Vec<i32> = utils::parse_ids(&project_id);
+                if !ids.is_empty() {
+                    query = query.filter(repositories_dsl::project_id.eq_any(ids));
+                }
+            }
 
-        let result = query.load::<(Repository, Project)>(conn)?;
-        let repos = result
-            .into_iter()
-            .map(|(repo, project)| RepositoryWithProject {
-                id: repo.id,
-                slug: repo.slug,
-                name: repo.name,
-                url: repo.url,
-                language_slug: repo.language_slug,
-                project: ProjectResponse {
-                    id: project.id,
-                    name: project.name,
-                    slug: project.slug,
-                    purposes: project.purposes,
-                    stack_levels: project.stack_levels,
-                    technologies: project.technologies,
-                    avatar: project.avatar,
-                    created_at: project.created_at,
-                    updated_at: project.updated_at,
-                    rewards: project.rewards,
-                },
-                created_at: repo.created_at,
-                updated_at: repo.updated_at,
-            })
-            .collect();
-        Ok(repos)
+            query = query
+                .offset(pagination.offset)
+                .limit(pagination.limit)
+                .select((
+                    repositories_dsl::repositories::all_columns(),
+                    projects_dsl::projects::all_columns(),
+                ));
+
+            let result = query.load::<(Repository, Project)>(conn)?;
+            let repos = result
+                .into_iter()
+                .map(|(repo, project)| RepositoryWithProject {
+                    id: repo.id,
+                    slug: repo.slug,
+                    name: repo.name,
+                    url: repo.url,
+                    language_slug: repo.language_slug,
+                    project: ProjectResponse {
+                        id: project.id,
+                        name: project.name,
+                        slug: project.slug,
+                        types: project.types,
+                        purposes: project.purposes,
+                        stack_levels: project.stack_levels,
+                        technologies: project.technologies,
+                        avatar: project.avatar,
+                        created_at: project.created_at,
+                        updated_at: project.updated_at,
+                        rewards: project.rewards,
+                    },
+                    created_at: repo.created_at,
+                    updated_at: repo.updated_at,
+                })
+                .collect();
+            Ok(repos)
+        })
     }
 
     fn by_id(&self, id: i32) -> Result<Option<Repository>, DBError> {
-        let conn = &mut self.get_db_conn();
-
-        let result = repositories_dsl::repositories
-            .find(id)
-            .first::<Repository>(conn)
-            .optional()
-            .map_err(DBError::from)?;
+        self.with_conn(|conn| {
+            let result = repositories_dsl::repositories
+                .filter(repositories_dsl::id.eq(id))
+                .filter(repositories_dsl::deleted_at.is_null())
+                .first::<Repository>(conn)
+                .optional()
+                .map_err(DBError::from)?;
 
-        Ok(result)
+            Ok(result)
+        })
     }
 
     fn create(&self, repository: &NewRepository) -> Result<Repository, DBError> {
-        let conn = &mut self.get_db_conn();
-
-        let repository = diesel::insert_into(repositories_dsl::repositories)
-            .values(repository)
-            .get_result(conn)
-            .map_err(DBError::from)?;
+        self.with_conn(|conn| {
+            let repository = diesel::insert_into(repositories_dsl::repositories)
+                .values(repository)
+                .get_result(conn)
+                .map_err(DBError::from)?;
 
-        Ok(repository)
+            Ok(repository)
+        })
     }
 
     fn update(&self, id: i32, repository: &UpdateRepository) -> Result<Repository, DBError> {
-        let conn = &mut self.get_db_conn();
-
-        let project =
-            diesel::update(repositories_dsl::repositories.filter(repositories_dsl::id.eq(id)))
-                .set((repository, repositories_dsl::updated_at.eq(now)))
-                .get_result::<Repository>(conn)
-                .map_err(DBError::from)?;
-
-        Ok(project)
+        self.with_conn(|conn| {
+            let project =
+                diesel::update(repositories_dsl::repositories.filter(repositories_dsl::id.eq(id)))
+                    .set((repository, repositories_dsl::updated_at.eq(now)))
+                    .get_result::<Repository>(conn)
+                    .map_err(DBError::from)?;
+
+            Ok(project)
+        })
     }
 
     fn delete(&self, id: i32) -> Result<(), DBError> {
-        let conn = &mut self.get_db_conn();
-        diesel::delete(repositories_dsl::repositories.filter(repositories_dsl::id.eq(id)))
-            .execute(conn)
-            .map_err(DBError::from)?;
-
-        Ok(())
+        // soft delete, the rows are purged after the retention period
+        self.with_conn(|conn| {
+            mark(conn, ArchivalKind::Repositories, id, Stamp::Deleted, Utc::now())?;
+            Ok(())
+        })
     }
 
     fn by_slug(&self, slug: &str) -> Result<Option<RepositoryWithProject>, DBError> {
-        let conn = &mut self.get_db_conn();
-
-        let result = repositories_dsl::repositories
-            .inner_join(
-                projects_dsl::projects.on(repositories_dsl::project_id.eq(projects_dsl::id)),
-            )
-            .filter(repositories_dsl::slug.eq(slug))
-            .select((
-                repositories_dsl::repositories::all_columns(),
-                projects_dsl::projects::all_columns(),
-            ))
-            .first::<(Repository, Project)>(conn)
-            .optional()
-            .map_err(DBError::from)?;
+        self.with_conn(|conn| {
+            let result = repositories_dsl::repositories
+                .inner_join(
+                    projects_dsl::projects.on(repositories_dsl::project_id.eq(projects_dsl::id)),
+                )
+                .filter(repositories_dsl::slug.eq(slug))
+                .filter(repositories_dsl::deleted_at.is_null())
+                .select((
+                    repositories_dsl::repositories::all_columns(),
+                    projects_dsl::projects::all_columns(),
+                ))
+                .first::<(Repository, Project)>(conn)
+                .optional()
+                .map_err(DBError::from)?;
 
-        let repo = result
-            .map(|(repo, project)| RepositoryWithProject {
-                id: repo.id,
-                slug: repo.slug,
-                name: repo.name,
-                url: repo.url,
-                language_slug: repo.language_slug,
-                project: ProjectResponse {
-                    id: project.id,
-                    name: project.name,
-                    slug: project.slug,
-                    purposes: project.purposes,
-                    stack_levels: project.stack_levels,
-                    technologies: project.technologies,
-                    avatar: project.avatar,
-                    created_at: project.created_at,
-                    updated_at: project.updated_at,
-                    rewards: project.rewards,
-                },
-                created_at: repo.created_at,
-                updated_at: repo.updated_at,
-            });
-        Ok(repo)
+            Ok(result.map(with_project))
+        })
     }
 
-    fn aggregate_languages(&self, params: LanguageQueryParams) -> Result<Vec<String>, DBError> {
-        let conn = &mut self.get_db_conn();
-
-        let mut query = repositories_dsl::repositories
-            .inner_join(issues_dsl::issues.on(issues_dsl::repository_id.eq(repositories_dsl::id)))
-            .inner_join(
-                projects_dsl::projects.on(repositories_dsl::project_id.eq(projects_dsl::id)),
-            )
-            .select(sql::<Text>("DISTINCT language_slug"))
-            .filter(repositories_dsl::language_slug.is_not_null())
-            .into_boxed();
-
-        if let Some(slugs) = params.slugs.as_ref() {
-            query = query.filter(projects_dsl::slug.eq_any(utils::parse_comma_values(slugs)));
-        }
-
-        if let Some(true) = params.certified_or_labels {
-            if let (Some(labels), Some(certified)) =
-                (params.labels.as_ref(), params.certified.as_ref())
-            {
-                query = query.filter(
-                    issues_dsl::labels
-                        .overlaps_with(utils::parse_comma_values(labels))
-                        .or(issues_dsl::certified.eq(certified)),
-                );
-            } else if let Some(labels) = params.labels.as_ref() {
-                query = query
-                    .filter(issues_dsl::labels.overlaps_with(utils::parse_comma_values(labels)));
-            } else if let Some(certified) = params.certified.as_ref() {
-                query = query.filter(issues_dsl::certified.eq(certified));
-            }
-        } else {
-            if let Some(labels) = params.labels.as_ref() {
-                query = query
-                    .filter(issues_dsl::labels.overlaps_with(utils::parse_comma_values(labels)));
-            }
-            if let Some(certified) = params.certified.as_ref() {
-                query = query.filter(issues_dsl::certified.eq(certified));
-            }
-        }
-
-        if let Some(open) = params.open.as_ref() {
-            query = query.filter(issues_dsl::open.eq(open));
-        }
-
-        let languages: HashSet<String> = query.load::<String>(conn)?.into_iter().collect();
-
-        let mut technologies: HashSet<String> = HashSet::new();
-
-        // Query for technologies if with_technologies is true
-        if params.with_technologies.unwrap_or(false) {
-            let mut tech_query = projects_dsl::projects
-                .inner_join(
-                    repositories_dsl::repositories
-                        .on(repositories_dsl::project_id.eq(projects_dsl::id)),
-                )
+    fn by_former_slug(&self, slug: &str) -> Result<Option<RepositoryWithProject>, DBError> {
+        self.with_conn(|conn| {
+            let former = slug_history_dsl::slug_history
+                .filter(slug_history_dsl::slug.eq(slug))
+                .select(slug_history_dsl::repository_id);
+            let result = repositories_dsl::repositories
                 .inner_join(
-                    issues_dsl::issues.on(issues_dsl::repository_id.eq(repositories_dsl::id)),
+                    projects_dsl::projects.on(repositories_dsl::project_id.eq(projects_dsl::id)),
                 )
-                .select(projects_dsl::technologies)
-                .filter(projects_dsl::technologies.is_not_null())
-                .into_boxed();
+                .filter(repositories_dsl::id.nullable().eq_any(former))
+                .filter(repositories_dsl::deleted_at.is_null())
+                .select((
+                    repositories_dsl::repositories::all_columns(),
+                    projects_dsl::projects::all_columns(),
+                ))
+                .first::<(Repository, Project)>(conn)
+                .optional()?;
+
+            Ok(result.map(with_project))
+        })
+    }
 
-            if let Some(slugs) = params.slugs.as_ref() {
-                tech_query =
-                    tech_query.filter(projects_dsl::slug.eq_any(utils::parse_comma_values(slugs)));
+    fn slug_owner(&self, slug: &str) -> Result<Option<i32>, DBError> {
+        self.with_conn(|conn| {
+            let current = repositories_dsl::repositories
+                .filter(repositories_dsl::slug.eq(slug))
+                .select(repositories_dsl::id)
+                .first::<i32>(conn)
+                .optional()?;
+            if current.is_some() {
+                return Ok(current);
             }
+            let former = slug_history_dsl::slug_history
+                .filter(slug_history_dsl::slug.eq(slug))
+                .filter(slug_history_dsl::repository_id.is_not_null())
+                .select(slug_history_dsl::repository_id.assume_not_null())
+                .first::<i32>(conn)
+                .optional()?;
+            Ok(former)
+        })
+    }
 
-            if let Some(true) = params.certified_or_labels {
-                if let (Some(labels), Some(certified)) =
-                    (params.labels.as_ref(), params.certified.as_ref())
-                {
-                    tech_query = tech_query.filter(
-                        issues_dsl::labels
-                            .overlaps_with(utils::parse_comma_values(labels))
-                            .or(issues_dsl::certified.eq(certified)),
-                    );
-                } else if let Some(labels) = params.labels.as_ref() {
-                    tech_query = tech_query.filter(
-                        issues_dsl::labels.overlaps_with(utils::parse_comma_values(labels)),
-                    );
-                } else if let Some(certified) = params.certified.as_ref() {
-                    tech_query = tech_query.filter(issues_dsl::certified.eq(certified));
-                }
-            } else {
-                if let Some(labels) = params.labels.as_ref() {
-                    tech_query = tech_query.filter(
-                        issues_dsl::labels.overlaps_with(utils::parse_comma_values(labels)),
-                    );
-                }
-                if let Some(certified) = params.certified.as_ref() {
-                    tech_query = tech_query.filter(issues_dsl::certified.eq(certified));
-                }
+    fn aggregate_languages(&self, params: LanguageQueryParams) -> Result<Vec<String>, DBError> {
+        let items = ItemFilter::from(&params);
+        let projects = ProjectFilter {
+            slugs: params.slugs.as_deref().map(utils::parse_comma_values),
+            ..Default::default()
+        };
+        self.with_conn(|conn| {
+            let mut issues = issues_dsl::issues
+                .filter(issues_dsl::archived_at.is_null())
+                .filter(issues_dsl::deleted_at.is_null())
+                .select(issues_dsl::repository_id)
+                .into_boxed();
+            if let Some(predicate) = items.issue_predicate() {
+                issues = issues.filter(predicate);
             }
-
-            if let Some(open) = params.open.as_ref() {
-                tech_query = tech_query.filter(issues_dsl::open.eq(open));
+            let mut query = repositories_dsl::repositories
+                .filter(repositories_dsl::language_slug.is_not_null())
+                .filter(repositories_dsl::archived_at.is_null())
+                .filter(repositories_dsl::deleted_at.is_null())
+                .filter(repositories_dsl::id.eq_any(issues))
+                .select(repositories_dsl::language_slug.assume_not_null())
+                .distinct()
+                .into_boxed();
+            if let Some(project_ids) = projects.ids() {
+                query = query.filter(repositories_dsl::project_id.eq_any(project_ids));
             }
+            let mut unique_items: HashSet<String> = query.load::<String>(conn)?.into_iter().collect();
+
+            if params.with_technologies.unwrap_or(false) {
+                let mut tech_query = projects_dsl::projects
+                    .filter(projects_dsl::technologies.is_not_null())
+                    .filter(projects_dsl::archived_at.is_null())
+                    .filter(projects_dsl::deleted_at.is_null())
+                    .filter(items.project_predicate(ItemSource::Issues))
+                    .select(projects_dsl::technologies)
+                    .into_boxed();
+                if let Some(predicate) = projects.predicate() {
+                    tech_query = tech_query.filter(predicate);
+                }
+                let tech_results: Vec<Option<Vec<Option<String>>>> = tech_query.load(conn)?;
 
-            let tech_results: Vec<Option<Vec<Option<String>>>> = tech_query.load(conn)?;
-
-            // Collect technologies while flattening the nested structure
-            for tech_list in tech_results {
-                if let Some(list) = tech_list {
-                    for tech in list {
-                        if let Some(tech_name) = tech {
-                            technologies.insert(tech_name);
-                        }
-                    }
+                // Collect technologies while flattening the nested structure
+                for list in tech_results.into_iter().flatten() {
+                    unique_items.extend(list.into_iter().flatten());
                 }
             }
-        }
-
-        let mut unique_items: HashSet<String> = languages;
-        unique_items.extend(technologies);
 
-        Ok(unique_items.into_iter().collect())
+            Ok(unique_items.into_iter().collect())
+        })
     }
 }
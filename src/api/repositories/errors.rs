@@ -59,7 +59,7 @@ impl Reply for RepositoryError {
         };
         let message = self.to_string();
 
-        let json = warp::reply::json(&ErrorResponse { message });
+        let json = warp::reply::json(&ErrorResponse::new(message));
 
         warp::reply::with_status(json, code).into_response()
     }
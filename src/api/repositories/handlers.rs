@@ -1,10 +1,10 @@
 use bytes::Buf;
 use log::{error, info, warn};
 use warp::{
-    http::StatusCode,
+    http::{header::LOCATION, StatusCode},
     reject,
     reject::Rejection,
-    reply::{json, with_status, Reply},
+    reply::{json, with_header, with_status, Reply},
 };
 
 use crate::{
@@ -24,6 +24,22 @@ pub async fn by_id(id: i32, db_access: impl DBRepository) -> Result<impl Reply,
     }
 }
 
+/// A former slug of a renamed repository redirects to its current slug.
+pub async fn by_slug_handler(slug: String, db_access: impl DBRepository) -> Result<impl Reply, Rejection> {
+    if let Some(repository) = db_access.by_slug(&slug)? {
+        return Ok(json(&repository).into_response());
+    }
+    match db_access.by_former_slug(&slug)? {
+        None => Err(warp::reject::custom(RepositoryError::NotFoundByName(slug))),
+        Some(repository) => Ok(with_header(
+            StatusCode::MOVED_PERMANENTLY,
+            LOCATION,
+            format!("/repositories/slug/{}", repository.slug),
+        )
+        .into_response()),
+    }
+}
+
 pub async fn all_handler(
     db_access: impl DBRepository,
     params: QueryParams,
@@ -62,33 +78,39 @@ pub async fn create_handler(
             KudosRole::Admin,
         ],
     )?;
-    match DBRepository::by_slug(&db_access, &repository.slug)? {
-        Some(r) => Err(warp::reject::custom(RepositoryError::AlreadyExists(r.id))),
-        None => match DBProject::by_id(&db_access, repository.project_id) {
-            Ok(project) => match project {
-                Some(_) => match DBRepository::create(&db_access, &repository) {
-                    Ok(_) => {
-                        info!("repository slug '{}' created", repository.slug);
-                        Ok(with_status(json(&repository), StatusCode::CREATED))
-                    }
-                    Err(err) => {
-                        error!("error creating the repository '{:?}': {}", repository, err);
-                        Err(warp::reject::custom(RepositoryError::CannotCreate(
+    match DBProject::by_id(&db_access, repository.project_id) {
+        Ok(project) => match project {
+            Some(_) => match DBRepository::create(&db_access, &repository) {
+                Ok(_) => {
+                    info!("repository slug '{}' created", repository.slug);
+                    Ok(with_status(json(&repository), StatusCode::CREATED))
+                }
+                // the database refuses slugs held by another repository, former ones included
+                Err(err) if err.is_unique_violation() => {
+                    match DBRepository::slug_owner(&db_access, &repository.slug)? {
+                        Some(owner) => Err(warp::reject::custom(RepositoryError::AlreadyExists(owner))),
+                        None => Err(warp::reject::custom(RepositoryError::CannotCreate(
                             "error creating the repository".to_owned(),
-                        )))
+                        ))),
                     }
-                },
-                None => {
-                    warn!("project id '{}' does not exist", repository.project_id);
-                    Err(warp::reject::custom(RepositoryError::ProjectNotFound(
-                        repository.project_id,
+                }
+                Err(err) => {
+                    error!("error creating the repository '{:?}': {}", repository, err);
+                    Err(warp::reject::custom(RepositoryError::CannotCreate(
+                        "error creating the repository".to_owned(),
                     )))
                 }
             },
-            Err(_) => Err(warp::reject::custom(RepositoryError::CannotCreate(
-                "cannot check if the repository exists".to_owned(),
-            ))),
+            None => {
+                warn!("project id '{}' does not exist", repository.project_id);
+                Err(warp::reject::custom(RepositoryError::ProjectNotFound(
+                    repository.project_id,
+                )))
+            }
         },
+        Err(_) => Err(warp::reject::custom(RepositoryError::CannotCreate(
+            "cannot check if the repository exists".to_owned(),
+        ))),
     }
 }
 pub async fn update_handler(
@@ -105,10 +127,18 @@ pub async fn update_handler(
         ],
     )?;
     match DBRepository::by_id(&db_access,id)? {
-        Some(p) => Ok(with_status(
-            json(&DBRepository::update(&db_access, p.id, &repo)?),
-            StatusCode::OK,
-        )),
+        Some(p) => match DBRepository::update(&db_access, p.id, &repo) {
+            Ok(repository) => Ok(with_status(json(&repository), StatusCode::OK)),
+            // former slugs stay with their repository so that old links keep working
+            Err(error) if error.is_unique_violation() => {
+                let slug = repo.slug.as_deref().unwrap_or_default();
+                match DBRepository::slug_owner(&db_access, slug)? {
+                    Some(owner) => Err(warp::reject::custom(RepositoryError::AlreadyExists(owner))),
+                    None => Err(warp::reject::custom(error)),
+                }
+            }
+            Err(error) => Err(warp::reject::custom(error)),
+        },
         None => Err(warp::reject::custom(RepositoryError::NotFound(id))),
     }
 }
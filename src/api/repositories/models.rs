@@ -1,4 +1,5 @@
 use crate::{api::projects::models::ProjectResponse, schema::repositories};
+use crate::api::filters::LabelMatch;
 use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 
@@ -18,6 +19,10 @@ pub struct Repository {
     pub project_id: i32,
     pub created_at: DateTime<Utc>,
     pub updated_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -26,6 +31,8 @@ pub struct QueryParams {
     pub names: Option<String>,
     pub languages: Option<String>,
     pub project_ids: Option<String>,
+    /// Also list the archived ones, deleted ones are never listed.
+    pub include_archived: Option<bool>,
 }
 
 #[derive(Serialize, Debug)]
@@ -44,6 +51,11 @@ pub struct RepositoryWithProject {
 pub struct LanguageQueryParams {
     pub slugs: Option<String>,
     pub labels: Option<String>,
+    /// Leave out items with any of these labels, as `labels!=a,b`.
+    #[serde(rename = "labels!")]
+    pub excluded_labels: Option<String>,
+    /// Match `any` (default) or `all` of the labels.
+    pub label_match: Option<LabelMatch>,
     pub certified: Option<bool>,
     pub open: Option<bool>,
     pub certified_or_labels: Option<bool>,
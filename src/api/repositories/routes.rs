@@ -21,6 +21,7 @@ fn with_db(
 pub fn routes(db_access: impl DBRepository + DBProject + DBRole) -> BoxedFilter<(impl Reply,)> {
     let repository = warp::path!("repositories");
     let repository_id = warp::path!("repositories" / i32);
+    let repository_slug = warp::path!("repositories" / "slug" / String);
 
     let all_route = repository
         .and(warp::get())
@@ -34,6 +35,11 @@ pub fn routes(db_access: impl DBRepository + DBProject + DBRole) -> BoxedFilter<
         .and(with_db(db_access.clone()))
         .and_then(handlers::by_id);
 
+    let slug_route = repository_slug
+        .and(warp::get())
+        .and(with_db(db_access.clone()))
+        .and_then(handlers::by_slug_handler);
+
     let create_route = repository
         .and(with_github_auth())
         .and(warp::post())
@@ -62,6 +68,7 @@ pub fn routes(db_access: impl DBRepository + DBProject + DBRole) -> BoxedFilter<
 
     all_route
         .or(by_id_route)
+        .or(slug_route)
         .or(create_route)
         .or(update_route)
         .or(delete_route)
@@ -62,7 +62,7 @@ impl Reply for RoleError {
         };
         let message = self.to_string();
 
-        let json = warp::reply::json(&ErrorResponse { message });
+        let json = warp::reply::json(&ErrorResponse::new(message));
 
         warp::reply::with_status(json, code).into_response()
     }
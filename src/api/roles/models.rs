@@ -74,6 +74,9 @@ pub struct UserProjectRoleResponse {
     pub role_id: i32,
 }
 
+/// Name of the role that maintains the projects it is granted on.
+pub const MAINTAINER_ROLE: &str = "Maintainer";
+
 // role
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[repr(i32)] // Ensures the enum is represented as an i32 for FFI and database comparisons
@@ -5,69 +5,122 @@ use crate::schema::user_subscriptions::dsl as subscriptions_dsl;
 
 use crate::db::{
     errors::DBError,
-    pool::{DBAccess, DBAccessor},
+    pool::DBAccess,
 };
 
 pub trait DBUserSubscription: Send + Sync + Clone + 'static {
     fn by_github_id(&self, github_id: i64) -> Result<Vec<UserSubscription>, DBError>;
     fn create(&self, subscription: &NewUserSubscription) -> Result<UserSubscription, DBError>;
+    /// The user's subscription with exactly the criteria of `subscription`.
+    fn same_as(&self, subscription: &NewUserSubscription) -> Result<Option<UserSubscription>, DBError>;
     fn delete(&self, subscription: &DeleteUserSubscription) -> Result<(), DBError>;
+    fn delete_by_id(&self, github_id: i64, id: i32) -> Result<bool, DBError>;
 }
 
 impl DBUserSubscription for DBAccess {
     fn by_github_id(&self, github_id: i64) -> Result<Vec<UserSubscription>, DBError> {
-        let conn = &mut self.get_db_conn();
+        self.with_conn(|conn| {
+            let result = subscriptions_dsl::user_subscriptions
+                .filter(subscriptions_dsl::github_id.eq(github_id))
+                .load::<UserSubscription>(conn)
+                .map_err(DBError::from)?;
 
-        let result = subscriptions_dsl::user_subscriptions
-            .filter(subscriptions_dsl::github_id.eq(github_id))
-            .load::<UserSubscription>(conn)
-            .map_err(DBError::from)?;
-
-        Ok(result)
+            Ok(result)
+        })
     }
 
     fn create(&self, subscription: &NewUserSubscription) -> Result<UserSubscription, DBError> {
-        let conn = &mut self.get_db_conn();
+        self.with_conn(|conn| {
+            let subscription = diesel::insert_into(subscriptions_dsl::user_subscriptions)
+                .values(subscription)
+                .get_result(conn)
+                .map_err(DBError::from)?;
+
+            Ok(subscription)
+        })
+    }
 
-        let subscription = diesel::insert_into(subscriptions_dsl::user_subscriptions)
-            .values(subscription)
-            .get_result(conn)
-            .map_err(DBError::from)?;
+    fn same_as(&self, subscription: &NewUserSubscription) -> Result<Option<UserSubscription>, DBError> {
+        self.with_conn(|conn| {
+            let result = subscriptions_dsl::user_subscriptions
+                .filter(subscriptions_dsl::github_id.eq(subscription.github_id.unwrap_or_default()))
+                .filter(subscriptions_dsl::purpose.is_not_distinct_from(subscription.purpose.clone()))
+                .filter(subscriptions_dsl::stack_level.is_not_distinct_from(subscription.stack_level.clone()))
+                .filter(subscriptions_dsl::technology.is_not_distinct_from(subscription.technology.clone()))
+                .filter(subscriptions_dsl::project_ids.is_not_distinct_from(subscription.project_ids.clone()))
+                .filter(subscriptions_dsl::repository_ids.is_not_distinct_from(subscription.repository_ids.clone()))
+                .filter(subscriptions_dsl::labels.is_not_distinct_from(subscription.labels.clone()))
+                .filter(subscriptions_dsl::languages.is_not_distinct_from(subscription.languages.clone()))
+                .filter(subscriptions_dsl::task_types.is_not_distinct_from(subscription.task_types.clone()))
+                .filter(subscriptions_dsl::min_bounty.is_not_distinct_from(subscription.min_bounty))
+                .first::<UserSubscription>(conn)
+                .optional()?;
 
-        Ok(subscription)
+            Ok(result)
+        })
     }
 
     fn delete(&self, subscription: &DeleteUserSubscription) -> Result<(), DBError> {
-        let conn = &mut self.get_db_conn();
-        let github_id = subscription.github_id.ok_or_else(|| {
-            DBError::DBQuery(diesel::result::Error::NotFound)
-        })?;
+        self.with_conn(|conn| {
+            let github_id = subscription.github_id.ok_or_else(|| {
+                DBError::DBQuery(diesel::result::Error::NotFound)
+            })?;
         
-        let query = subscriptions_dsl::user_subscriptions
-            .filter(subscriptions_dsl::github_id.eq(github_id));
+            // only the single criterion subscriptions of the legacy API, not
+            // the ones combining that value with other criteria
+            let query = subscriptions_dsl::user_subscriptions
+                .filter(subscriptions_dsl::github_id.eq(github_id))
+                .filter(subscriptions_dsl::project_ids.is_null())
+                .filter(subscriptions_dsl::repository_ids.is_null())
+                .filter(subscriptions_dsl::labels.is_null())
+                .filter(subscriptions_dsl::languages.is_null())
+                .filter(subscriptions_dsl::task_types.is_null())
+                .filter(subscriptions_dsl::min_bounty.is_null());
 
-        match (&subscription.purpose, &subscription.stack_level, &subscription.technology) {
-            (Some(purpose), None, None) => {
-                diesel::delete(
-                    query.filter(subscriptions_dsl::purpose.eq(purpose)))
-                    .execute(conn)
-                    .map_err(DBError::from)?;
-            }
-            (None, Some(stack_level), None) => {
-                diesel::delete(
-                    query.filter(subscriptions_dsl::stack_level.eq(stack_level)))
-                    .execute(conn)
-                    .map_err(DBError::from)?;
+            match (&subscription.purpose, &subscription.stack_level, &subscription.technology) {
+                (Some(purpose), None, None) => {
+                    diesel::delete(
+                        query
+                            .filter(subscriptions_dsl::purpose.eq(purpose))
+                            .filter(subscriptions_dsl::stack_level.is_null())
+                            .filter(subscriptions_dsl::technology.is_null()))
+                        .execute(conn)
+                        .map_err(DBError::from)?;
+                }
+                (None, Some(stack_level), None) => {
+                    diesel::delete(
+                        query
+                            .filter(subscriptions_dsl::stack_level.eq(stack_level))
+                            .filter(subscriptions_dsl::purpose.is_null())
+                            .filter(subscriptions_dsl::technology.is_null()))
+                        .execute(conn)
+                        .map_err(DBError::from)?;
+                }
+                (None, None, Some(technology)) => {
+                    diesel::delete(
+                        query
+                            .filter(subscriptions_dsl::technology.eq(technology))
+                            .filter(subscriptions_dsl::purpose.is_null())
+                            .filter(subscriptions_dsl::stack_level.is_null()))
+                        .execute(conn)
+                        .map_err(DBError::from)?;
+                }
+                _ => return Err(DBError::DBQuery(diesel::result::Error::NotFound)),
             }
-            (None, None, Some(technology)) => {
-                diesel::delete(
-                    query.filter(subscriptions_dsl::technology.eq(technology)))
-                    .execute(conn)
-                    .map_err(DBError::from)?;
-            }
-            _ => return Err(DBError::DBQuery(diesel::result::Error::NotFound)),
-        }
 
-        Ok(())
+            Ok(())
+        })
+    }
+
+    fn delete_by_id(&self, github_id: i64, id: i32) -> Result<bool, DBError> {
+        self.with_conn(|conn| {
+            let deleted = diesel::delete(
+                subscriptions_dsl::user_subscriptions
+                    .filter(subscriptions_dsl::github_id.eq(github_id))
+                    .filter(subscriptions_dsl::id.eq(id)),
+            )
+            .execute(conn)?;
+            Ok(deleted > 0)
+        })
     }
-}
\ No newline at end of file
+}
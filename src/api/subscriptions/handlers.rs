@@ -7,6 +7,7 @@ use warp::{
 };
 
 use crate::{
+    api::tasks::utils::validate_task_type,
     middlewares::github::model::GitHubUser,
 };
 use log::{error, info, warn};
@@ -57,18 +58,16 @@ fn create_subscription(
             info!("subscription for user '{:?}' created", subscription.github_id);
             Ok(with_status(json(&subscription), StatusCode::CREATED))
         }
+        Err(error) if error.is_unique_violation() => {
+            let existing = db_access.same_as(&subscription)?.map(|existing| existing.id).unwrap_or_default();
+            warn!("subscription '{:?}' already exists as '{existing}'", subscription);
+            Err(warp::reject::custom(UserSubscriptionError::AlreadyExists(existing)))
+        }
         Err(error) => {
             error!("error creating the subscription '{:?}': {}", subscription, error);
-            let error_str = error.to_string();
-            if error_str.contains("user_subscriptions_github_id_purpose_key") || error_str.contains("user_subscriptions_github_id_stack_level_key") ||  error_str.contains("user_subscriptions_github_id_technology_key") {
-                Err(warp::reject::custom(UserSubscriptionError::AlreadyExists(
-                    subscription.github_id.unwrap_or(0) as i32
-                )))
-            } else {
-                Err(warp::reject::custom(UserSubscriptionError::CannotCreate(
-                    "error creating the subscription".to_string(),
-                )))
-            }
+            Err(warp::reject::custom(UserSubscriptionError::CannotCreate(
+                "error creating the subscription".to_string(),
+            )))
         }
     }
 }
@@ -92,25 +91,62 @@ pub async fn delete_handler(
 }
 
 fn validate_new_fields(subscription: &NewUserSubscription) -> Result<(), Rejection> {
-    let fields = [
-        ("purpose", subscription.purpose.is_some()),
-        ("stack level", subscription.stack_level.is_some()),
-        ("technology", subscription.technology.is_some()),
-    ];
-    let provided_fields: Vec<_> = fields.iter()
-        .filter(|(_, is_some)| *is_some)
-        .map(|(name, _)| *name)
-        .collect();
-    
+    let has_criteria = subscription.purpose.is_some()
+        || subscription.stack_level.is_some()
+        || subscription.technology.is_some()
+        || subscription.project_ids.is_some()
+        || subscription.repository_ids.is_some()
+        || subscription.labels.is_some()
+        || subscription.languages.is_some()
+        || subscription.task_types.is_some()
+        || subscription.min_bounty.is_some();
+    if !has_criteria {
+        return Err(warp::reject::custom(UserSubscriptionError::InvalidPayload(
+            "at least one criterion must be provided".to_owned(),
+        )));
+    }
 
-    if provided_fields.len() > 1 {
+    let empty_lists: Vec<_> = [
+        ("project_ids", subscription.project_ids.as_ref().map(Vec::is_empty)),
+        ("repository_ids", subscription.repository_ids.as_ref().map(Vec::is_empty)),
+        ("labels", subscription.labels.as_ref().map(Vec::is_empty)),
+        ("languages", subscription.languages.as_ref().map(Vec::is_empty)),
+        ("task_types", subscription.task_types.as_ref().map(Vec::is_empty)),
+    ]
+    .into_iter()
+    .filter(|(_, is_empty)| *is_empty == Some(true))
+    .map(|(name, _)| name)
+    .collect();
+    if !empty_lists.is_empty() {
         return Err(warp::reject::custom(UserSubscriptionError::InvalidPayload(
-            format!("only one field can be provided at a time, got: {}", provided_fields.join(", "))
+            format!("lists cannot be empty: {}", empty_lists.join(", "))
+        )));
+    }
+
+    if subscription.min_bounty.is_some_and(|bounty| bounty < 0) {
+        return Err(warp::reject::custom(UserSubscriptionError::InvalidPayload(
+            "min_bounty cannot be negative".to_owned(),
         )));
     }
+    for type_ in subscription.task_types.iter().flatten().flatten() {
+        validate_task_type(&type_.to_lowercase())
+            .map_err(|e| warp::reject::custom(UserSubscriptionError::InvalidPayload(e.to_string())))?;
+    }
     Ok(())
 }
 
+pub async fn delete_by_id_handler(
+    id: i32,
+    user: GitHubUser,
+    db_access: impl DBUserSubscription,
+) -> Result<impl Reply, Rejection> {
+    if !db_access.delete_by_id(user.id, id)? {
+        return Err(warp::reject::custom(UserSubscriptionError::NotFound(id)));
+    }
+    info!("subscription '{id}' for user '{}' deleted", user.id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
 fn validate_delete_fields(subscription: &DeleteUserSubscription) -> Result<(), Rejection> {
     let fields = [
         ("purpose", subscription.purpose.is_some()),
@@ -159,6 +195,21 @@ fn normalize_new_subscription(subscription: &mut NewUserSubscription)  {
     if subscription.technology.is_some() {
         subscription.technology = Some(subscription.technology.as_ref().unwrap().to_lowercase());
     }
+    // Labels are matched verbatim, as they come from GitHub.
+    for list in [&mut subscription.languages, &mut subscription.task_types] {
+        for value in list.iter_mut().flatten().flatten() {
+            *value = value.to_lowercase();
+        }
+    }
+    // Lists are sets, sorted so that the same subscription is stored once.
+    for values in [&mut subscription.labels, &mut subscription.languages, &mut subscription.task_types].into_iter().flatten() {
+        values.sort();
+        values.dedup();
+    }
+    for values in [&mut subscription.project_ids, &mut subscription.repository_ids].into_iter().flatten() {
+        values.sort();
+        values.dedup();
+    }
 }
 fn normalize_delete_subscription(subscription: &mut DeleteUserSubscription)  {
     if subscription.purpose.is_some() {
@@ -24,8 +24,16 @@ pub struct UserSubscription {
     pub stack_level: Option<String>,
     pub technology: Option<String>,
     pub created_at: DateTime<Utc>,
+    pub project_ids: Option<Vec<Option<i32>>>,
+    pub repository_ids: Option<Vec<Option<i32>>>,
+    pub labels: Option<Vec<Option<String>>>,
+    pub languages: Option<Vec<Option<String>>>,
+    pub task_types: Option<Vec<Option<String>>>,
+    pub min_bounty: Option<i32>,
 }
 
+/// All provided criteria must match for a subscription to fire; list
+/// criteria match when any of their values does.
 #[derive(Insertable, Serialize, Deserialize, Debug)]
 #[diesel(table_name = user_subscriptions)]
 pub struct NewUserSubscription {
@@ -33,6 +41,12 @@ pub struct NewUserSubscription {
     pub purpose: Option<String>,
     pub stack_level: Option<String>,
     pub technology: Option<String>,
+    pub project_ids: Option<Vec<Option<i32>>>,
+    pub repository_ids: Option<Vec<Option<i32>>>,
+    pub labels: Option<Vec<Option<String>>>,
+    pub languages: Option<Vec<Option<String>>>,
+    pub task_types: Option<Vec<Option<String>>>,
+    pub min_bounty: Option<i32>,
 }
 
 #[derive(Insertable, Serialize, Deserialize, Debug)]
@@ -17,6 +17,7 @@ fn with_db(
 
 pub fn routes(db_access: impl DBUserSubscription + DBRole) -> BoxedFilter<(impl Reply,)> {
     let subscriptions = warp::path!("subscriptions");
+    let subscription_id = warp::path!("subscriptions" / i32);
 
     let get_user_subscriptions = subscriptions
         .and(warp::get())
@@ -38,9 +39,16 @@ pub fn routes(db_access: impl DBUserSubscription + DBRole) -> BoxedFilter<(impl
         .and(with_db(db_access.clone()))
         .and_then(handlers::delete_handler);
 
+    let delete_subscription_by_id = subscription_id
+        .and(warp::delete())
+        .and(with_github_auth())
+        .and(with_db(db_access.clone()))
+        .and_then(handlers::delete_by_id_handler);
+
     let route = get_user_subscriptions
         .or(create_subscription)
-        .or(delete_subscription);
+        .or(delete_subscription)
+        .or(delete_subscription_by_id);
 
     route.boxed()
 }
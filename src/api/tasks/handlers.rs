@@ -1,4 +1,5 @@
 use bytes::Buf;
+use serde_json::json;
 use log::{error, info, warn};
 use warp::{
     http::StatusCode,
@@ -12,6 +13,7 @@ use crate::{
         roles::{db::DBRole, models::KudosRole, utils::user_has_at_least_one_role},
         tasks::{models::NewTask, utils::validate_task_type},
         users::{db::DBUser, errors::UserError},
+        webhooks::{db::DBWebhook, models::WebhookEvent, utils::dispatch_event},
     },
     middlewares::github::model::GitHubUser,
     types::{PaginatedResponse, PaginationParams},
@@ -54,7 +56,7 @@ pub async fn all_handler(
 pub async fn create_handler(
     user: GitHubUser,
     buf: impl Buf,
-    db_access: impl DBTask + DBRole + DBUser,
+    db_access: impl DBTask + DBRole + DBUser + DBWebhook,
 ) -> Result<impl Reply, Rejection> {
     let des = &mut serde_json::Deserializer::from_reader(buf.reader());
     let mut task: NewTask = serde_path_to_error::deserialize(des).map_err(|e| {
@@ -105,6 +107,7 @@ pub async fn create_handler(
     match DBTask::create(&db_access, &task) {
         Ok(task) => {
             info!("task id '{}' created", task.id);
+            dispatch_event(&db_access, WebhookEvent::TaskCreated, task.project_id, task.repository_id, &task);
             Ok(with_status(json(&task), StatusCode::CREATED))
         }
         Err(err) => {
@@ -125,7 +128,7 @@ pub async fn update_handler(
     id: i32,
     user: GitHubUser,
     buf: impl Buf,
-    db_access: impl DBTask + DBRole + DBUser,
+    db_access: impl DBTask + DBRole + DBUser + DBWebhook,
 ) -> Result<impl Reply, Rejection> {
     let des = &mut serde_json::Deserializer::from_reader(buf.reader());
     let task: UpdateTask = serde_path_to_error::deserialize(des).map_err(|e| {
@@ -146,10 +149,20 @@ pub async fn update_handler(
     )?;
 
     task.type_.as_deref().map(validate_task_type).transpose()?;
+    let actor_id = DBUser::by_github_id(&db_access, user.id)?.map(|actor| actor.id);
     match DBTask::by_id(&db_access, id)? {
-        Some(p) => match DBTask::update(&db_access, p.id, &task) {
+        Some(p) => match DBTask::update(&db_access, p.id, &task, actor_id) {
             Ok(task) => {
                 info!("task '{}' updated", task.id);
+                if task.status != p.status {
+                    dispatch_event(
+                        &db_access,
+                        WebhookEvent::TaskStatusChanged,
+                        task.project_id,
+                        task.repository_id,
+                        &json!({ "previous_status": p.status, "task": task }),
+                    );
+                }
                 Ok(with_status(json(&task), StatusCode::OK))
             }
             Err(error) => {
@@ -189,7 +202,7 @@ pub async fn delete_handler(
 pub async fn add_upvote_to_task(
     user: GitHubUser,
     buf: impl Buf,
-    db_access: impl DBTask + DBUser + DBRole,
+    db_access: impl DBTask + DBUser + DBRole + DBWebhook,
 ) -> Result<impl Reply, Rejection> {
     let user_roles = DBRole::user_roles(&db_access, &user.username)?;
     user_has_at_least_one_role(
@@ -211,7 +224,7 @@ pub async fn add_upvote_to_task(
 
     match DBUser::by_id(&db_access, task_vote.user_id)? {
         Some(_) => match DBTask::by_id(&db_access, task_vote.task_id)? {
-            Some(_) => {
+            Some(task) => {
                 match DBTask::add_vote_to_task(
                     &db_access,
                     &TaskVoteDB {
@@ -222,6 +235,7 @@ pub async fn add_upvote_to_task(
                 ) {
                     Ok(task_vote) => {
                         info!("vote '{}' created", task_vote.id);
+                        dispatch_event(&db_access, WebhookEvent::VoteCast, task.project_id, task.repository_id, &task_vote);
                         Ok(with_status(json(&task_vote), StatusCode::CREATED))
                     }
                     Err(error) => {
@@ -246,7 +260,7 @@ pub async fn add_upvote_to_task(
 pub async fn add_downvote_to_task(
     user: GitHubUser,
     buf: impl Buf,
-    db_access: impl DBTask + DBUser + DBRole,
+    db_access: impl DBTask + DBUser + DBRole + DBWebhook,
 ) -> Result<impl Reply, Rejection> {
     let user_roles = DBRole::user_roles(&db_access, &user.username)?;
     user_has_at_least_one_role(
@@ -268,7 +282,7 @@ pub async fn add_downvote_to_task(
 
     match DBUser::by_id(&db_access, task_vote.user_id)? {
         Some(_) => match DBTask::by_id(&db_access, task_vote.task_id)? {
-            Some(_) => {
+            Some(task) => {
                 match DBTask::add_vote_to_task(
                     &db_access,
                     &TaskVoteDB {
@@ -279,6 +293,7 @@ pub async fn add_downvote_to_task(
                 ) {
                     Ok(task_vote) => {
                         info!("vote '{}' created", task_vote.id);
+                        dispatch_event(&db_access, WebhookEvent::VoteCast, task.project_id, task.repository_id, &task_vote);
                         Ok(with_status(json(&task_vote), StatusCode::CREATED))
                     }
                     Err(error) => {
@@ -38,7 +38,11 @@ pub struct Task {
     pub issue_created_at: Option<DateTime<Utc>>,
     pub issue_closed_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
-    pub updated_at: Option<DateTime<Utc>>
+    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Insertable, Serialize, Deserialize, Debug)]
@@ -171,6 +175,8 @@ pub struct QueryParams {
     pub featured_by_user_id: Option<i32>,
     pub issue_created_at: Option<DateTime<Utc>>,
     pub issue_closed_at: Option<DateTime<Utc>>,
+    /// Also list the archived ones, deleted ones are never listed.
+    pub include_archived: Option<bool>,
 }
 
 // tasks
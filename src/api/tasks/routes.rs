@@ -2,6 +2,7 @@ use std::convert::Infallible;
 
 use crate::api::roles::db::DBRole;
 use crate::api::users::db::DBUser;
+use crate::api::webhooks::db::DBWebhook;
 use warp::filters::BoxedFilter;
 use warp::{Filter, Reply};
 use crate::middlewares::github::auth::with_github_auth;
@@ -12,12 +13,12 @@ use super::handlers;
 use super::models::QueryParams;
 
 fn with_db(
-    db_pool: impl DBTask + DBUser + DBRole,
-) -> impl Filter<Extract = (impl DBTask + DBUser + DBRole,), Error = Infallible> + Clone {
+    db_pool: impl DBTask + DBUser + DBRole + DBWebhook,
+) -> impl Filter<Extract = (impl DBTask + DBUser + DBRole + DBWebhook,), Error = Infallible> + Clone {
     warp::any().map(move || db_pool.clone())
 }
 
-pub fn routes(db_access: impl DBTask + DBUser + DBRole) -> BoxedFilter<(impl Reply,)> {
+pub fn routes(db_access: impl DBTask + DBUser + DBRole + DBWebhook) -> BoxedFilter<(impl Reply,)> {
     let task = warp::path!("tasks");
     let task_id = warp::path!("tasks" / i32);
     let task_upvote = warp::path!("tasks" / "upvotes");
@@ -0,0 +1,166 @@
+use diesel::dsl::now;
+use diesel::prelude::*;
+use diesel::sql_query;
+use diesel::sql_types::Text;
+
+use super::models::{InUse, TaxonomyKind, Term, TermDB};
+use crate::schema::taxonomy::dsl as taxonomy_dsl;
+
+use crate::db::{
+    errors::DBError,
+    pool::DBAccess,
+};
+
+/// Columns of `user_subscriptions` a subscription is made of, besides its user.
+const SUBSCRIPTION_CRITERIA: [&str; 9] = [
+    "purpose",
+    "stack_level",
+    "technology",
+    "project_ids",
+    "repository_ids",
+    "labels",
+    "languages",
+    "task_types",
+    "min_bounty",
+];
+
+pub trait DBTaxonomy: Send + Sync + Clone + 'static {
+    /// Lists the terms alphabetically, of one kind when provided.
+    fn terms(&self, kind: Option<TaxonomyKind>) -> Result<Vec<Term>, DBError>;
+    fn term_by_id(&self, id: i32) -> Result<Option<Term>, DBError>;
+    fn term_by_value(&self, kind: TaxonomyKind, value: &str) -> Result<Option<Term>, DBError>;
+    fn create_term(&self, term: &TermDB) -> Result<Term, DBError>;
+    /// Renames the term along with its uses in projects and subscriptions.
+    fn rename_term(&self, term: &Term, kind: TaxonomyKind, value: &str) -> Result<Term, DBError>;
+    fn delete_term(&self, id: i32) -> Result<(), DBError>;
+    /// Whether a project or a subscription uses the term.
+    fn term_in_use(&self, kind: TaxonomyKind, value: &str) -> Result<bool, DBError>;
+    /// The values of `values` that aren't terms of `kind`.
+    fn unknown_terms(&self, kind: TaxonomyKind, values: &[String]) -> Result<Vec<String>, DBError>;
+}
+
+impl DBTaxonomy for DBAccess {
+    fn terms(&self, kind: Option<TaxonomyKind>) -> Result<Vec<Term>, DBError> {
+        self.with_conn(|conn| {
+            let mut query = taxonomy_dsl::taxonomy.into_boxed();
+            if let Some(kind) = kind {
+                query = query.filter(taxonomy_dsl::kind.eq(kind.as_str()));
+            }
+            let terms = query
+                .order((taxonomy_dsl::kind, taxonomy_dsl::value))
+                .load::<Term>(conn)?;
+            Ok(terms)
+        })
+    }
+
+    fn term_by_id(&self, id: i32) -> Result<Option<Term>, DBError> {
+        self.with_conn(|conn| {
+            let term = taxonomy_dsl::taxonomy
+                .find(id)
+                .first::<Term>(conn)
+                .optional()?;
+            Ok(term)
+        })
+    }
+
+    fn term_by_value(&self, kind: TaxonomyKind, value: &str) -> Result<Option<Term>, DBError> {
+        self.with_conn(|conn| {
+            let term = taxonomy_dsl::taxonomy
+                .filter(taxonomy_dsl::kind.eq(kind.as_str()))
+                .filter(taxonomy_dsl::value.eq(value))
+                .first::<Term>(conn)
+                .optional()?;
+            Ok(term)
+        })
+    }
+
+    fn create_term(&self, term: &TermDB) -> Result<Term, DBError> {
+        self.with_conn(|conn| {
+            let term = diesel::insert_into(taxonomy_dsl::taxonomy)
+                .values(term)
+                .get_result::<Term>(conn)?;
+            Ok(term)
+        })
+    }
+
+    fn rename_term(&self, term: &Term, kind: TaxonomyKind, value: &str) -> Result<Term, DBError> {
+        self.with_conn(|conn| {
+            conn.transaction(|conn| {
+                let renamed = diesel::update(taxonomy_dsl::taxonomy.find(term.id))
+                    .set((taxonomy_dsl::value.eq(value), taxonomy_dsl::updated_at.eq(now)))
+                    .get_result::<Term>(conn)?;
+                let column = kind.project_column();
+                // projects tagged with both values keep one, where the first was
+                sql_query(format!(
+                    "UPDATE projects SET {column} = ARRAY(
+                        SELECT tag FROM unnest(array_replace({column}, $1, $2)) WITH ORDINALITY AS tags (tag, position)
+                        GROUP BY tag ORDER BY min(position)
+                    ), updated_at = now()
+                    WHERE $1 = ANY({column})"
+                ))
+                .bind::<Text, _>(&term.value)
+                .bind::<Text, _>(value)
+                .execute(conn)?;
+                if let Some(column) = kind.subscription_column() {
+                    // subscriptions that would become copies of another one go
+                    let others = SUBSCRIPTION_CRITERIA.iter().filter(|other| **other != column);
+                    let same = others
+                        .map(|other| format!("renamed.{other} IS NOT DISTINCT FROM kept.{other}"))
+                        .collect::<Vec<_>>()
+                        .join(" AND ");
+                    sql_query(format!(
+                        "DELETE FROM user_subscriptions renamed WHERE renamed.{column} = $1 AND EXISTS (
+                            SELECT 1 FROM user_subscriptions kept
+                            WHERE kept.github_id = renamed.github_id AND kept.{column} = $2 AND {same}
+                        )"
+                    ))
+                    .bind::<Text, _>(&term.value)
+                    .bind::<Text, _>(value)
+                    .execute(conn)?;
+                    sql_query(format!("UPDATE user_subscriptions SET {column} = $2 WHERE {column} = $1"))
+                        .bind::<Text, _>(&term.value)
+                        .bind::<Text, _>(value)
+                        .execute(conn)?;
+                }
+                Ok(renamed)
+            })
+        })
+    }
+
+    fn delete_term(&self, id: i32) -> Result<(), DBError> {
+        self.with_conn(|conn| {
+            diesel::delete(taxonomy_dsl::taxonomy.find(id)).execute(conn)?;
+            Ok(())
+        })
+    }
+
+    fn term_in_use(&self, kind: TaxonomyKind, value: &str) -> Result<bool, DBError> {
+        self.with_conn(|conn| {
+            let subscriptions = match kind.subscription_column() {
+                Some(column) => format!("OR EXISTS (SELECT 1 FROM user_subscriptions WHERE {column} = $1)"),
+                None => String::new(),
+            };
+            let result = sql_query(format!(
+                "SELECT EXISTS (SELECT 1 FROM projects WHERE $1 = ANY({})) {subscriptions} AS in_use",
+                kind.project_column()
+            ))
+            .bind::<Text, _>(value)
+            .get_result::<InUse>(conn)?;
+            Ok(result.in_use)
+        })
+    }
+
+    fn unknown_terms(&self, kind: TaxonomyKind, values: &[String]) -> Result<Vec<String>, DBError> {
+        if values.is_empty() {
+            return Ok(vec![]);
+        }
+        self.with_conn(|conn| {
+            let known = taxonomy_dsl::taxonomy
+                .filter(taxonomy_dsl::kind.eq(kind.as_str()))
+                .filter(taxonomy_dsl::value.eq_any(values))
+                .select(taxonomy_dsl::value)
+                .load::<String>(conn)?;
+            Ok(values.iter().filter(|value| !known.contains(value)).cloned().collect())
+        })
+    }
+}
@@ -0,0 +1,48 @@
+use std::fmt;
+
+use serde_derive::Deserialize;
+use thiserror::Error;
+use warp::{
+    http::StatusCode,
+    reject::Reject,
+    reply::{Reply, Response},
+};
+
+use crate::errors::ErrorResponse;
+
+#[derive(Clone, Error, Debug, Deserialize, PartialEq)]
+pub enum TaxonomyError {
+    NotFound(i32),
+    AlreadyExists(String),
+    InvalidPayload(String),
+    InUse(String),
+}
+
+impl fmt::Display for TaxonomyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TaxonomyError::NotFound(id) => write!(f, "Term #{id} not found"),
+            TaxonomyError::AlreadyExists(value) => write!(f, "Term {value} already exists"),
+            TaxonomyError::InvalidPayload(error) => write!(f, "Invalid term: {error}"),
+            TaxonomyError::InUse(value) => write!(f, "Term {value} is still in use"),
+        }
+    }
+}
+
+impl Reject for TaxonomyError {}
+
+impl Reply for TaxonomyError {
+    fn into_response(self) -> Response {
+        let code = match self {
+            TaxonomyError::NotFound(_) => StatusCode::NOT_FOUND,
+            TaxonomyError::AlreadyExists(_) => StatusCode::BAD_REQUEST,
+            TaxonomyError::InvalidPayload(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            TaxonomyError::InUse(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        };
+        let message = self.to_string();
+
+        let json = warp::reply::json(&ErrorResponse::new(message));
+
+        warp::reply::with_status(json, code).into_response()
+    }
+}
@@ -0,0 +1,103 @@
+use bytes::Buf;
+use log::{info, warn};
+use warp::{
+    http::StatusCode,
+    reject,
+    reject::Rejection,
+    reply::{json, with_status, Reply},
+};
+
+use crate::{
+    api::roles::{db::DBRole, models::KudosRole, utils::user_has_at_least_one_role},
+    middlewares::github::model::GitHubUser,
+};
+
+use super::{
+    db::DBTaxonomy,
+    errors::TaxonomyError,
+    models::{normalize_term, NewTerm, QueryParams, TaxonomyKind, TermDB, UpdateTerm},
+};
+
+fn payload<T: serde::de::DeserializeOwned>(buf: impl Buf) -> Result<T, Rejection> {
+    let des = &mut serde_json::Deserializer::from_reader(buf.reader());
+    serde_path_to_error::deserialize(des).map_err(|e| {
+        let e = e.to_string();
+        warn!("invalid term '{e}'");
+        reject::custom(TaxonomyError::InvalidPayload(e))
+    })
+}
+
+fn authorize(user: &GitHubUser, db_access: &impl DBRole) -> Result<(), Rejection> {
+    let roles = DBRole::user_roles(db_access, &user.username)?;
+    user_has_at_least_one_role(roles, vec![KudosRole::Admin])
+}
+
+pub async fn all_handler(
+    params: QueryParams,
+    db_access: impl DBTaxonomy + DBRole,
+) -> Result<impl Reply, Rejection> {
+    Ok(json(&db_access.terms(params.kind)?))
+}
+
+pub async fn create_handler(
+    user: GitHubUser,
+    buf: impl Buf,
+    db_access: impl DBTaxonomy + DBRole,
+) -> Result<impl Reply, Rejection> {
+    authorize(&user, &db_access)?;
+    let term: NewTerm = payload(buf)?;
+    let value = normalize_term(&term.value).map_err(|e| reject::custom(TaxonomyError::InvalidPayload(e)))?;
+    if db_access.term_by_value(term.kind, &value)?.is_some() {
+        return Err(reject::custom(TaxonomyError::AlreadyExists(value)));
+    }
+    let created = db_access.create_term(&TermDB {
+        kind: term.kind.as_str().to_owned(),
+        value,
+    })?;
+    info!("{} '{}' added to the taxonomy", created.kind, created.value);
+    Ok(with_status(json(&created), StatusCode::CREATED))
+}
+
+pub async fn update_handler(
+    id: i32,
+    user: GitHubUser,
+    buf: impl Buf,
+    db_access: impl DBTaxonomy + DBRole,
+) -> Result<impl Reply, Rejection> {
+    authorize(&user, &db_access)?;
+    let update: UpdateTerm = payload(buf)?;
+    let value = normalize_term(&update.value).map_err(|e| reject::custom(TaxonomyError::InvalidPayload(e)))?;
+    let term = db_access
+        .term_by_id(id)?
+        .ok_or_else(|| reject::custom(TaxonomyError::NotFound(id)))?;
+    if term.value == value {
+        return Ok(json(&term));
+    }
+    let kind = TaxonomyKind::parse(&term.kind)
+        .ok_or_else(|| reject::custom(TaxonomyError::InvalidPayload(format!("unknown kind {}", term.kind))))?;
+    if db_access.term_by_value(kind, &value)?.is_some() {
+        return Err(reject::custom(TaxonomyError::AlreadyExists(value)));
+    }
+    let renamed = db_access.rename_term(&term, kind, &value)?;
+    info!("{} '{}' renamed to '{}'", term.kind, term.value, renamed.value);
+    Ok(json(&renamed))
+}
+
+pub async fn delete_handler(
+    id: i32,
+    user: GitHubUser,
+    db_access: impl DBTaxonomy + DBRole,
+) -> Result<impl Reply, Rejection> {
+    authorize(&user, &db_access)?;
+    let term = db_access
+        .term_by_id(id)?
+        .ok_or_else(|| reject::custom(TaxonomyError::NotFound(id)))?;
+    if let Some(kind) = TaxonomyKind::parse(&term.kind) {
+        if db_access.term_in_use(kind, &term.value)? {
+            return Err(reject::custom(TaxonomyError::InUse(term.value)));
+        }
+    }
+    db_access.delete_term(id)?;
+    info!("{} '{}' removed from the taxonomy", term.kind, term.value);
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,6 @@
+pub mod db;
+pub mod errors;
+pub mod handlers;
+pub mod models;
+pub mod routes;
+pub mod utils;
@@ -0,0 +1,108 @@
+use crate::schema::taxonomy;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel::sql_types::Bool;
+
+use serde::{Deserialize, Serialize};
+
+/// The project tags that take their values from the taxonomy.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaxonomyKind {
+    Type,
+    Purpose,
+    StackLevel,
+    Technology,
+}
+
+impl TaxonomyKind {
+    pub const ALL: [TaxonomyKind; 4] = [
+        TaxonomyKind::Type,
+        TaxonomyKind::Purpose,
+        TaxonomyKind::StackLevel,
+        TaxonomyKind::Technology,
+    ];
+
+    pub fn parse(kind: &str) -> Option<TaxonomyKind> {
+        TaxonomyKind::ALL.into_iter().find(|k| k.as_str() == kind)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaxonomyKind::Type => "type",
+            TaxonomyKind::Purpose => "purpose",
+            TaxonomyKind::StackLevel => "stack_level",
+            TaxonomyKind::Technology => "technology",
+        }
+    }
+
+    /// The array column of `projects` holding the terms.
+    pub fn project_column(&self) -> &'static str {
+        match self {
+            TaxonomyKind::Type => "types",
+            TaxonomyKind::Purpose => "purposes",
+            TaxonomyKind::StackLevel => "stack_levels",
+            TaxonomyKind::Technology => "technologies",
+        }
+    }
+
+    /// The column of `user_subscriptions` holding a term, subscriptions
+    /// don't filter on types.
+    pub fn subscription_column(&self) -> Option<&'static str> {
+        match self {
+            TaxonomyKind::Type => None,
+            TaxonomyKind::Purpose => Some("purpose"),
+            TaxonomyKind::StackLevel => Some("stack_level"),
+            TaxonomyKind::Technology => Some("technology"),
+        }
+    }
+}
+
+#[derive(Queryable, Identifiable, Selectable, Debug, PartialEq, Serialize, Clone)]
+#[diesel(table_name = taxonomy)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Term {
+    pub id: i32,
+    pub kind: String,
+    pub value: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NewTerm {
+    pub kind: TaxonomyKind,
+    pub value: String,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = taxonomy)]
+pub struct TermDB {
+    pub kind: String,
+    pub value: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UpdateTerm {
+    pub value: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct QueryParams {
+    pub kind: Option<TaxonomyKind>,
+}
+
+#[derive(QueryableByName, Debug)]
+pub struct InUse {
+    #[diesel(sql_type = Bool)]
+    pub in_use: bool,
+}
+
+/// Trims a term, rejecting blank ones.
+pub fn normalize_term(value: &str) -> Result<String, String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("value cannot be empty".to_owned());
+    }
+    Ok(value.to_owned())
+}
@@ -0,0 +1,54 @@
+use std::convert::Infallible;
+
+use warp::filters::BoxedFilter;
+use warp::{Filter, Reply};
+
+use crate::api::roles::db::DBRole;
+use crate::middlewares::github::auth::with_github_auth;
+
+use super::db::DBTaxonomy;
+use super::handlers;
+use super::models::QueryParams;
+
+fn with_db(
+    db_pool: impl DBTaxonomy + DBRole,
+) -> impl Filter<Extract = (impl DBTaxonomy + DBRole,), Error = Infallible> + Clone {
+    warp::any().map(move || db_pool.clone())
+}
+
+pub fn routes(db_access: impl DBTaxonomy + DBRole) -> BoxedFilter<(impl Reply,)> {
+    let taxonomy = warp::path!("taxonomy");
+    let term_id = warp::path!("taxonomy" / i32);
+
+    let all_route = taxonomy
+        .and(warp::get())
+        .and(warp::query::<QueryParams>())
+        .and(with_db(db_access.clone()))
+        .and_then(handlers::all_handler);
+
+    let create_route = taxonomy
+        .and(with_github_auth())
+        .and(warp::post())
+        .and(warp::body::aggregate())
+        .and(with_db(db_access.clone()))
+        .and_then(handlers::create_handler);
+
+    let update_route = term_id
+        .and(with_github_auth())
+        .and(warp::put())
+        .and(warp::body::aggregate())
+        .and(with_db(db_access.clone()))
+        .and_then(handlers::update_handler);
+
+    let delete_route = term_id
+        .and(with_github_auth())
+        .and(warp::delete())
+        .and(with_db(db_access.clone()))
+        .and_then(handlers::delete_handler);
+
+    all_route
+        .or(create_route)
+        .or(update_route)
+        .or(delete_route)
+        .boxed()
+}
@@ -0,0 +1,17 @@
+use super::{db::DBTaxonomy, models::TaxonomyKind};
+use crate::db::errors::DBError;
+
+/// The first kind of `tags` with values that aren't terms of the taxonomy,
+/// along with those values.
+pub fn unknown_tags(
+    db_access: &impl DBTaxonomy,
+    tags: [(TaxonomyKind, Vec<String>); 4],
+) -> Result<Option<(TaxonomyKind, Vec<String>)>, DBError> {
+    for (kind, values) in tags {
+        let unknown = db_access.unknown_terms(kind, &values)?;
+        if !unknown.is_empty() {
+            return Ok(Some((kind, unknown)));
+        }
+    }
+    Ok(None)
+}
@@ -8,7 +8,7 @@ use crate::schema::users::dsl as users_dsl;
 
 use crate::db::{
     errors::DBError,
-    pool::{DBAccess, DBAccessor},
+    pool::DBAccess,
 };
 use crate::types::PaginationParams;
 
@@ -20,119 +20,162 @@ pub trait DBUser: Send + Sync + Clone + 'static {
     fn create(&self, user: &NewUser) -> Result<User, DBError>;
     fn update(&self, id: i32, user: &UpdateUser) -> Result<User, DBError>;
     fn delete(&self, id: i32) -> Result<(), DBError>;
+    fn set_pending_email(&self, id: i32, email: &str) -> Result<User, DBError>;
+    fn confirm_email(&self, id: i32, email: &str) -> Result<Option<User>, DBError>;
 }
 
 impl DBUser for DBAccess {
     fn by_id(&self, id: i32) -> Result<Option<User>, DBError> {
-        let conn = &mut self.get_db_conn();
-
-        let result = users_dsl::users
-            .find(id)
-            .first::<User>(conn)
-            .optional()
-            .map_err(DBError::from)?;
-
-        Ok(result)
+        self.with_conn(|conn| {
+            let result = users_dsl::users
+                .find(id)
+                .first::<User>(conn)
+                .optional()
+                .map_err(DBError::from)?;
+
+            Ok(result)
+        })
     }
     fn by_github_id(&self, id: i64) -> Result<Option<User>, DBError> {
-        let conn = &mut self.get_db_conn();
-
-        let result = users_dsl::users
-            .filter(users_dsl::github_id.eq(id))
-            .first::<User>(conn)
-            .optional()
-            .map_err(DBError::from)?;
-
-        Ok(result)
+        self.with_conn(|conn| {
+            let result = users_dsl::users
+                .filter(users_dsl::github_id.eq(id))
+                .first::<User>(conn)
+                .optional()
+                .map_err(DBError::from)?;
+
+            Ok(result)
+        })
     }
     fn by_username(&self, username: &str) -> Result<Option<User>, DBError> {
-        let conn = &mut self.get_db_conn();
-        let mut query = users_dsl::users.into_boxed();
-        query = query.filter(users_dsl::username.eq(username));
-        query = query.limit(1);
-        let result: Vec<User> = query.load::<User>(conn)?;
-        if result.is_empty() {
-            Ok(None)
-        } else {
-            Ok(Some(User {
-                id: result[0].id,
-                username: result[0].username.clone(),
-                avatar: result[0].avatar.clone(),
-                created_at: result[0].created_at,
-                updated_at: result[0].updated_at,
-                github_id: result[0].github_id,
-                email: result[0].email.clone(),
-                email_notifications_enabled: result[0].email_notifications_enabled,
-            }))
-        }
+        self.with_conn(|conn| {
+            let mut query = users_dsl::users.into_boxed();
+            query = query.filter(users_dsl::username.eq(username));
+            query = query.limit(1);
+            let result: Vec<User> = query.load::<User>(conn)?;
+            if result.is_empty() {
+                Ok(None)
+            } else {
+                Ok(Some(User {
+                    id: result[0].id,
+                    username: result[0].username.clone(),
+                    avatar: result[0].avatar.clone(),
+                    created_at: result[0].created_at,
+                    updated_at: result[0].updated_at,
+                    github_id: result[0].github_id,
+                    email: result[0].email.clone(),
+                    email_notifications_enabled: result[0].email_notifications_enabled,
+                    email_verified_at: result[0].email_verified_at,
+                    pending_email: result[0].pending_email.clone(),
+                }))
+            }
+        })
     }
 
     fn all(&self, params: QueryParams, pagination: PaginationParams) -> Result<Vec<User>, DBError> {
-        let conn = &mut self.get_db_conn();
-
-        let user_ids: Option<Vec<i32>> = if let Some(certified) = params.certified.as_ref() {
-            let ids: Vec<Option<i32>> = issues_dsl::issues
-                .inner_join(
-                    repositories_dsl::repositories
-                        .on(issues_dsl::repository_id.eq(repositories_dsl::id)),
-                )
-                .select(issues_dsl::assignee_id)
-                .filter(issues_dsl::certified.eq(certified))
-                .distinct()
-                .load::<Option<i32>>(conn)
-                .optional()?
-                .unwrap_or_default();
-
-            let user_ids: Vec<i32> = ids.into_iter().flatten().collect();
-            if user_ids.is_empty() {
-                None
+        self.with_conn(|conn| {
+            let user_ids: Option<Vec<i32>> = if let Some(certified) = params.certified.as_ref() {
+                let ids: Vec<Option<i32>> = issues_dsl::issues
+                    .inner_join(
+                        repositories_dsl::repositories
+                            .on(issues_dsl::repository_id.eq(repositories_dsl::id)),
+                    )
+                    .select(issues_dsl::assignee_id)
+                    .filter(issues_dsl::certified.eq(certified))
+                    .distinct()
+                    .load::<Option<i32>>(conn)
+                    .optional()?
+                    .unwrap_or_default();
+
+                let user_ids: Vec<i32> = ids.into_iter().flatten().collect();
+                if user_ids.is_empty() {
+                    None
+                } else {
+                    Some(user_ids)
+                }
             } else {
-                Some(user_ids)
+                None
+            };
+            let mut query = users_dsl::users.into_boxed();
+
+            if let Some(ids) = user_ids {
+                query = query.filter(users_dsl::id.eq_any(ids));
+            } else if params.labels.is_some() {
+                return Ok(vec![]);
             }
-        } else {
-            None
-        };
-        let mut query = users_dsl::users.into_boxed();
-
-        if let Some(ids) = user_ids {
-            query = query.filter(users_dsl::id.eq_any(ids));
-        } else if params.labels.is_some() {
-            return Ok(vec![]);
-        }
-        query = query.offset(pagination.offset).limit(pagination.limit);
-
-        let result = query.load::<User>(conn)?;
-        Ok(result)
+            query = query.offset(pagination.offset).limit(pagination.limit);
+
+            let result = query.load::<User>(conn)?;
+            Ok(result)
+        })
     }
 
     fn create(&self, user: &NewUser) -> Result<User, DBError> {
-        let conn = &mut self.get_db_conn();
-
-        let user = diesel::insert_into(users_dsl::users)
-            .values(user)
-            .get_result(conn)
-            .map_err(DBError::from)?;
-
-        Ok(user)
+        self.with_conn(|conn| {
+            let user = diesel::insert_into(users_dsl::users)
+                .values(user)
+                .get_result(conn)
+                .map_err(DBError::from)?;
+
+            Ok(user)
+        })
     }
 
     fn update(&self, id: i32, form: &UpdateUser) -> Result<User, DBError> {
-        let conn = &mut self.get_db_conn();
+        self.with_conn(|conn| {
+            let user = diesel::update(users_dsl::users.filter(users_dsl::id.eq(id)))
+                .set((form, users_dsl::updated_at.eq(now)))
+                .get_result::<User>(conn)
+                .map_err(DBError::from)?;
+
+            Ok(user)
+        })
+    }
 
-        let user = diesel::update(users_dsl::users.filter(users_dsl::id.eq(id)))
-            .set((form, users_dsl::updated_at.eq(now)))
-            .get_result::<User>(conn)
-            .map_err(DBError::from)?;
+    fn delete(&self, id: i32) -> Result<(), DBError> {
+        self.with_conn(|conn| {
+            diesel::delete(users_dsl::users.filter(users_dsl::id.eq(id)))
+                .execute(conn)
+                .map_err(DBError::from)?;
 
-        Ok(user)
+            Ok(())
+        })
     }
 
-    fn delete(&self, id: i32) -> Result<(), DBError> {
-        let conn = &mut self.get_db_conn();
-        diesel::delete(users_dsl::users.filter(users_dsl::id.eq(id)))
-            .execute(conn)
+    fn set_pending_email(&self, id: i32, email: &str) -> Result<User, DBError> {
+        self.with_conn(|conn| {
+            let user = diesel::update(users_dsl::users.filter(users_dsl::id.eq(id)))
+                .set((
+                    users_dsl::pending_email.eq(email),
+                    users_dsl::updated_at.eq(now),
+                ))
+                .get_result::<User>(conn)
+                .map_err(DBError::from)?;
+
+            Ok(user)
+        })
+    }
+
+    fn confirm_email(&self, id: i32, email: &str) -> Result<Option<User>, DBError> {
+        self.with_conn(|conn| {
+            // Only the address that is still pending can be confirmed, so an old link
+            // cannot override a newer request.
+            let user = diesel::update(
+                users_dsl::users
+                    .filter(users_dsl::id.eq(id))
+                    .filter(users_dsl::pending_email.eq(email)),
+            )
+            .set((
+                users_dsl::email.eq(email),
+                users_dsl::email_verified_at.eq(now),
+                users_dsl::pending_email.eq(None::<String>),
+                users_dsl::updated_at.eq(now),
+            ))
+            .get_result::<User>(conn)
+            .optional()
             .map_err(DBError::from)?;
 
-        Ok(())
+            Ok(user)
+        })
     }
 }
@@ -19,6 +19,10 @@ pub enum UserError {
     CannotCreate(String),
     CannotUpdate(i32, String),
     InvalidPayload(String),
+    InvalidEmail(String),
+    InvalidVerificationToken,
+    EmailVerificationUnavailable,
+    CannotSendVerification(String),
 }
 
 impl fmt::Display for UserError {
@@ -33,6 +37,16 @@ impl fmt::Display for UserError {
                 write!(f, "User #{id} cannot be updated: {error}")
             }
             UserError::InvalidPayload(error) => write!(f, "Cannot create the user: {error}"),
+            UserError::InvalidEmail(email) => write!(f, "Invalid email address: {email}"),
+            UserError::InvalidVerificationToken => {
+                write!(f, "Verification link is invalid or has expired")
+            }
+            UserError::EmailVerificationUnavailable => {
+                write!(f, "Email verification is not configured")
+            }
+            UserError::CannotSendVerification(error) => {
+                write!(f, "Verification email cannot be sent: {error}")
+            }
         }
     }
 }
@@ -49,10 +63,14 @@ impl Reply for UserError {
             UserError::CannotCreate(_) => StatusCode::UNPROCESSABLE_ENTITY,
             UserError::CannotUpdate(_, _) => StatusCode::UNPROCESSABLE_ENTITY,
             UserError::InvalidPayload(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            UserError::InvalidEmail(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            UserError::InvalidVerificationToken => StatusCode::BAD_REQUEST,
+            UserError::EmailVerificationUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            UserError::CannotSendVerification(_) => StatusCode::INTERNAL_SERVER_ERROR,
         };
         let message = self.to_string();
 
-        let json = warp::reply::json(&ErrorResponse { message });
+        let json = warp::reply::json(&ErrorResponse::new(message));
 
         warp::reply::with_status(json, code).into_response()
     }
@@ -6,13 +6,13 @@ use warp::{
     reply::{json, with_status, Reply},
 };
 
-use crate::{api::{roles::{db::DBRole, models::KudosRole, utils::user_has_at_least_one_role}, users::models::UpdateEmailNotificationsUser}, middlewares::github::model::GitHubUser, types::PaginationParams};
+use crate::{api::{roles::{db::DBRole, models::KudosRole, utils::user_has_at_least_one_role}, users::models::UpdateEmailNotificationsUser}, email::verification::EmailVerifier, middlewares::github::model::GitHubUser, types::PaginationParams};
 use log::{error, info, warn};
 
 use super::{
     db::DBUser,
     errors::UserError,
-    models::{NewUser, QueryParams, UpdateUser},
+    models::{CurrentUser, EmailVerificationParams, NewUser, QueryParams, UpdateEmail, UpdateUser},
 };
 
 pub async fn by_id(id: i32, db_access: impl DBUser) -> Result<impl Reply, Rejection> {
@@ -24,7 +24,10 @@ pub async fn by_id(id: i32, db_access: impl DBUser) -> Result<impl Reply, Reject
 
 pub async fn by_github(user: GitHubUser, db_access: impl DBUser) -> Result<impl Reply, Rejection> {
     info!("get github user {:?}", user);
-    by_username(user.username, db_access).await
+    match db_access.by_username(&user.username)? {
+        None => Err(warp::reject::custom(UserError::NotFoundByName(user.username)))?,
+        Some(db_user) => Ok(json(&CurrentUser::from(db_user))),
+    }
 }
 pub async fn create_by_github(user: GitHubUser, db_access: impl DBUser) -> Result<impl Reply, Rejection> {
     info!("create github user {:?}", user);
@@ -130,18 +133,73 @@ pub async fn update_user_github(user: GitHubUser, buf: impl Buf, db_access: impl
     })?;
     match db_access.by_github_id(user.id)? {
         Some(db_user) => Ok(with_status(
-            json(&db_access.update(db_user.id, 
+            json(&CurrentUser::from(db_access.update(db_user.id, 
                 &UpdateUser{ 
                     username: Some(db_user.username), 
                     avatar: db_user.avatar, 
                     github_id: Some(user.id), 
                     email_notifications_enabled: new_values.email_notifications_enabled 
-                })?),
+                })?)),
             StatusCode::OK,
         )),
         None => Err(warp::reject::custom(UserError::GithubNotFound(user.id))),
     }
 }
+pub async fn request_email_verification(
+    user: GitHubUser,
+    buf: impl Buf,
+    db_access: impl DBUser,
+    verifier: EmailVerifier,
+) -> Result<impl Reply, Rejection> {
+    if !verifier.is_enabled() {
+        return Err(warp::reject::custom(UserError::EmailVerificationUnavailable));
+    }
+    let des = &mut serde_json::Deserializer::from_reader(buf.reader());
+    let payload: UpdateEmail = serde_path_to_error::deserialize(des).map_err(|e| {
+        let e = e.to_string();
+        warn!("invalid email update '{e}'",);
+        reject::custom(UserError::InvalidPayload(e))
+    })?;
+    let email = payload.email.trim().to_lowercase();
+    if email.parse::<lettre::Address>().is_err() {
+        return Err(warp::reject::custom(UserError::InvalidEmail(email)));
+    }
+
+    let db_user = db_access
+        .by_github_id(user.id)?
+        .ok_or_else(|| warp::reject::custom(UserError::GithubNotFound(user.id)))?;
+    let db_user = db_access.set_pending_email(db_user.id, &email)?;
+
+    verifier.send(user.id, &email).await.map_err(|e| {
+        error!("error sending the verification email to user '{}': {}", user.id, e);
+        warp::reject::custom(UserError::CannotSendVerification(
+            "error sending the verification email".to_string(),
+        ))
+    })?;
+    info!("verification email requested by user '{}'", user.id);
+    Ok(with_status(json(&CurrentUser::from(db_user)), StatusCode::ACCEPTED))
+}
+
+pub async fn verify_email(
+    params: EmailVerificationParams,
+    db_access: impl DBUser,
+    verifier: EmailVerifier,
+) -> Result<impl Reply, Rejection> {
+    let claims = verifier
+        .verify(&params.token)
+        .ok_or_else(|| warp::reject::custom(UserError::InvalidVerificationToken))?;
+    let db_user = db_access
+        .by_github_id(claims.github_id)?
+        .ok_or_else(|| warp::reject::custom(UserError::GithubNotFound(claims.github_id)))?;
+    match db_access.confirm_email(db_user.id, &claims.email)? {
+        Some(user) => {
+            info!("email of user '{}' verified", user.id);
+            Ok(json(&user))
+        }
+        None => Err(warp::reject::custom(UserError::InvalidVerificationToken)),
+    }
+}
+
 pub async fn delete_handler(
     id: i32, 
     user: GitHubUser,
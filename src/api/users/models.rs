@@ -26,6 +26,28 @@ pub struct User {
     pub github_id: Option<i64>,
     pub email_notifications_enabled: bool,
     pub email: Option<String>,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    /// Not verified yet, only shown to the user through [`CurrentUser`].
+    #[serde(skip_serializing)]
+    pub pending_email: Option<String>,
+}
+
+/// The signed in user as served by `/users/me`, with the address waiting
+/// for verification.
+#[derive(Serialize, Debug)]
+pub struct CurrentUser {
+    #[serde(flatten)]
+    pub user: User,
+    pub pending_email: Option<String>,
+}
+
+impl From<User> for CurrentUser {
+    fn from(user: User) -> Self {
+        Self {
+            pending_email: user.pending_email.clone(),
+            user,
+        }
+    }
 }
 
 #[derive(Insertable, Serialize, Deserialize, Debug)]
@@ -53,6 +75,16 @@ pub struct UpdateEmailNotificationsUser {
     pub email_notifications_enabled: Option<bool>,
 
 }
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UpdateEmail {
+    pub email: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct EmailVerificationParams {
+    pub token: String,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct QueryParams {
     pub labels: Option<String>,
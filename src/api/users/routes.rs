@@ -4,12 +4,13 @@ use warp::filters::BoxedFilter;
 use warp::{Filter, Reply};
 
 use crate::api::roles::db::DBRole;
+use crate::email::verification::EmailVerifier;
 use crate::middlewares::github::auth::with_github_auth;
 use crate::types::PaginationParams;
 
 use super::db::DBUser;
 use super::handlers;
-use super::models::QueryParams;
+use super::models::{EmailVerificationParams, QueryParams};
 
 fn with_db(
     db_pool: impl DBUser + DBRole,
@@ -17,9 +18,17 @@ fn with_db(
     warp::any().map(move || db_pool.clone())
 }
 
-pub fn routes(db_access: impl DBUser + DBRole) -> BoxedFilter<(impl Reply,)> {
+fn with_verifier(
+    verifier: EmailVerifier,
+) -> impl Filter<Extract = (EmailVerifier,), Error = Infallible> + Clone {
+    warp::any().map(move || verifier.clone())
+}
+
+pub fn routes(db_access: impl DBUser + DBRole, verifier: EmailVerifier) -> BoxedFilter<(impl Reply,)> {
     let user = warp::path!("users");
     let user_me = warp::path!("users" / "me");
+    let user_me_email = warp::path!("users" / "me" / "email");
+    let user_email_verify = warp::path!("users" / "email" / "verify");
     let user_id = warp::path!("users" / i32);
     let user_username = warp::path!("users" / "username" / String);
 
@@ -73,6 +82,21 @@ pub fn routes(db_access: impl DBUser + DBRole) -> BoxedFilter<(impl Reply,)> {
         .and(with_db(db_access.clone()))
         .and_then(handlers::update_user_github);
 
+    let request_email_verification = user_me_email
+        .and(warp::post())
+        .and(with_github_auth())
+        .and(warp::body::aggregate())
+        .and(with_db(db_access.clone()))
+        .and(with_verifier(verifier.clone()))
+        .and_then(handlers::request_email_verification);
+
+    let verify_email = user_email_verify
+        .and(warp::get())
+        .and(warp::query::<EmailVerificationParams>())
+        .and(with_db(db_access.clone()))
+        .and(with_verifier(verifier))
+        .and_then(handlers::verify_email);
+
     let delete_user = user_id
         .and(with_github_auth())
         .and(warp::delete())
@@ -87,7 +111,9 @@ pub fn routes(db_access: impl DBUser + DBRole) -> BoxedFilter<(impl Reply,)> {
         .or(update_user)
         .or(get_user_github)
         .or(create_user_github)
-        .or(update_user_github);
+        .or(update_user_github)
+        .or(request_email_verification)
+        .or(verify_email);
 
     route.boxed()
 }
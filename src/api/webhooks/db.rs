@@ -0,0 +1,247 @@
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+
+use super::models::{
+    DeliveryAttempt, NewWebhookDelivery, WebhookDelivery, WebhookEvent, WebhookSubscription,
+    WebhookSubscriptionDB, DELIVERY_FAILED, DELIVERY_PENDING, DELIVERY_SUCCEEDED,
+};
+use crate::schema::{
+    repositories::dsl as repositories_dsl, webhook_deliveries::dsl as deliveries_dsl,
+    webhook_subscriptions::dsl as subscriptions_dsl,
+};
+use crate::types::PaginationParams;
+
+use crate::db::{
+    errors::DBError,
+    pool::DBAccess,
+};
+
+pub trait DBWebhook: Send + Sync + Clone + 'static {
+    /// Lists subscriptions, restricted to `project_ids` when provided.
+    fn subscriptions(&self, project_ids: Option<Vec<i32>>) -> Result<Vec<WebhookSubscription>, DBError>;
+    fn subscription_by_id(&self, id: i32) -> Result<Option<WebhookSubscription>, DBError>;
+    fn create_subscription(&self, subscription: &WebhookSubscriptionDB) -> Result<WebhookSubscription, DBError>;
+    fn delete_subscription(&self, id: i32) -> Result<(), DBError>;
+    /// Queues a delivery of `payload` for every active subscription listening
+    /// to `event` on the project (resolved from the repository if needed).
+    fn enqueue(
+        &self,
+        event: WebhookEvent,
+        project_id: Option<i32>,
+        repository_id: Option<i32>,
+        payload: &serde_json::Value,
+    ) -> Result<usize, DBError>;
+    fn deliveries(
+        &self,
+        subscription_id: i32,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<WebhookDelivery>, i64), DBError>;
+    fn delivery_by_id(&self, id: i32) -> Result<Option<WebhookDelivery>, DBError>;
+    fn redeliver(&self, delivery: &WebhookDelivery) -> Result<WebhookDelivery, DBError>;
+    /// Leases up to `limit` due deliveries of active subscriptions until
+    /// `lease_until` so concurrent workers skip them.
+    fn claim_due(
+        &self,
+        limit: i64,
+        lease_until: DateTime<Utc>,
+    ) -> Result<Vec<(WebhookDelivery, WebhookSubscription)>, DBError>;
+    fn record_attempt(
+        &self,
+        id: i32,
+        attempt: &DeliveryAttempt,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<(), DBError>;
+}
+
+impl DBWebhook for DBAccess {
+    fn subscriptions(&self, project_ids: Option<Vec<i32>>) -> Result<Vec<WebhookSubscription>, DBError> {
+        self.with_conn(|conn| {
+            let mut query = subscriptions_dsl::webhook_subscriptions.into_boxed();
+            if let Some(project_ids) = project_ids {
+                query = query.filter(subscriptions_dsl::project_id.eq_any(project_ids));
+            }
+            let result = query
+                .order(subscriptions_dsl::id.asc())
+                .load::<WebhookSubscription>(conn)?;
+            Ok(result)
+        })
+    }
+
+    fn subscription_by_id(&self, id: i32) -> Result<Option<WebhookSubscription>, DBError> {
+        self.with_conn(|conn| {
+            let result = subscriptions_dsl::webhook_subscriptions
+                .find(id)
+                .first::<WebhookSubscription>(conn)
+                .optional()?;
+            Ok(result)
+        })
+    }
+
+    fn create_subscription(&self, subscription: &WebhookSubscriptionDB) -> Result<WebhookSubscription, DBError> {
+        self.with_conn(|conn| {
+            let result = diesel::insert_into(subscriptions_dsl::webhook_subscriptions)
+                .values(subscription)
+                .get_result(conn)?;
+            Ok(result)
+        })
+    }
+
+    fn delete_subscription(&self, id: i32) -> Result<(), DBError> {
+        self.with_conn(|conn| {
+            diesel::delete(subscriptions_dsl::webhook_subscriptions.find(id)).execute(conn)?;
+            Ok(())
+        })
+    }
+
+    fn enqueue(
+        &self,
+        event: WebhookEvent,
+        project_id: Option<i32>,
+        repository_id: Option<i32>,
+        payload: &serde_json::Value,
+    ) -> Result<usize, DBError> {
+        self.with_conn(|conn| {
+            let project_id = match (project_id, repository_id) {
+                (Some(project_id), _) => Some(project_id),
+                (None, Some(repository_id)) => repositories_dsl::repositories
+                    .find(repository_id)
+                    .select(repositories_dsl::project_id)
+                    .first::<i32>(conn)
+                    .optional()?,
+                (None, None) => None,
+            };
+
+            let mut query = subscriptions_dsl::webhook_subscriptions
+                .filter(subscriptions_dsl::active.eq(true))
+                .filter(subscriptions_dsl::events.contains(vec![event.as_str()]))
+                .into_boxed();
+            query = match project_id {
+                Some(project_id) => query.filter(
+                    subscriptions_dsl::project_id
+                        .is_null()
+                        .or(subscriptions_dsl::project_id.eq(project_id)),
+                ),
+                None => query.filter(subscriptions_dsl::project_id.is_null()),
+            };
+            let subscription_ids = query.select(subscriptions_dsl::id).load::<i32>(conn)?;
+
+            let deliveries: Vec<NewWebhookDelivery> = subscription_ids
+                .into_iter()
+                .map(|subscription_id| NewWebhookDelivery {
+                    subscription_id,
+                    event: event.as_str().to_owned(),
+                    payload: payload.clone(),
+                })
+                .collect();
+            let inserted = diesel::insert_into(deliveries_dsl::webhook_deliveries)
+                .values(&deliveries)
+                .execute(conn)?;
+            Ok(inserted)
+        })
+    }
+
+    fn deliveries(
+        &self,
+        subscription_id: i32,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<WebhookDelivery>, i64), DBError> {
+        self.with_conn(|conn| {
+            let query = deliveries_dsl::webhook_deliveries
+                .filter(deliveries_dsl::subscription_id.eq(subscription_id));
+
+            let total_count = query.count().get_result::<i64>(conn)?;
+            let result = query
+                .order(deliveries_dsl::created_at.desc())
+                .offset(pagination.offset)
+                .limit(pagination.limit)
+                .load::<WebhookDelivery>(conn)?;
+
+            Ok((result, total_count))
+        })
+    }
+
+    fn delivery_by_id(&self, id: i32) -> Result<Option<WebhookDelivery>, DBError> {
+        self.with_conn(|conn| {
+            let result = deliveries_dsl::webhook_deliveries
+                .find(id)
+                .first::<WebhookDelivery>(conn)
+                .optional()?;
+            Ok(result)
+        })
+    }
+
+    fn redeliver(&self, delivery: &WebhookDelivery) -> Result<WebhookDelivery, DBError> {
+        self.with_conn(|conn| {
+            let result = diesel::insert_into(deliveries_dsl::webhook_deliveries)
+                .values(NewWebhookDelivery {
+                    subscription_id: delivery.subscription_id,
+                    event: delivery.event.clone(),
+                    payload: delivery.payload.clone(),
+                })
+                .get_result(conn)?;
+            Ok(result)
+        })
+    }
+
+    fn claim_due(
+        &self,
+        limit: i64,
+        lease_until: DateTime<Utc>,
+    ) -> Result<Vec<(WebhookDelivery, WebhookSubscription)>, DBError> {
+        self.with_conn(|conn| {
+            let result = conn.transaction(|conn| {
+                let active = subscriptions_dsl::webhook_subscriptions
+                    .filter(subscriptions_dsl::active.eq(true))
+                    .select(subscriptions_dsl::id);
+                let ids = deliveries_dsl::webhook_deliveries
+                    .filter(deliveries_dsl::status.eq(DELIVERY_PENDING))
+                    .filter(deliveries_dsl::next_attempt_at.le(Utc::now()))
+                    .filter(deliveries_dsl::subscription_id.eq_any(active))
+                    .order(deliveries_dsl::next_attempt_at.asc())
+                    .limit(limit)
+                    .select(deliveries_dsl::id)
+                    .for_update()
+                    .skip_locked()
+                    .load::<i32>(conn)?;
+
+                diesel::update(deliveries_dsl::webhook_deliveries.filter(deliveries_dsl::id.eq_any(&ids)))
+                    .set(deliveries_dsl::next_attempt_at.eq(lease_until))
+                    .execute(conn)?;
+
+                deliveries_dsl::webhook_deliveries
+                    .inner_join(subscriptions_dsl::webhook_subscriptions)
+                    .filter(deliveries_dsl::id.eq_any(&ids))
+                    .select((WebhookDelivery::as_select(), WebhookSubscription::as_select()))
+                    .load::<(WebhookDelivery, WebhookSubscription)>(conn)
+            })?;
+            Ok(result)
+        })
+    }
+
+    fn record_attempt(
+        &self,
+        id: i32,
+        attempt: &DeliveryAttempt,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<(), DBError> {
+        self.with_conn(|conn| {
+            let (status, next_attempt_at, delivered_at) = match (attempt.succeeded(), retry_at) {
+                (true, _) => (DELIVERY_SUCCEEDED, Utc::now(), Some(Utc::now())),
+                (false, Some(retry_at)) => (DELIVERY_PENDING, retry_at, None),
+                (false, None) => (DELIVERY_FAILED, Utc::now(), None),
+            };
+
+            diesel::update(deliveries_dsl::webhook_deliveries.find(id))
+                .set((
+                    deliveries_dsl::status.eq(status),
+                    deliveries_dsl::attempts.eq(deliveries_dsl::attempts + 1),
+                    deliveries_dsl::next_attempt_at.eq(next_attempt_at),
+                    deliveries_dsl::last_status_code.eq(attempt.status_code),
+                    deliveries_dsl::last_error.eq(attempt.error.as_deref()),
+                    deliveries_dsl::delivered_at.eq(delivered_at),
+                ))
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+}
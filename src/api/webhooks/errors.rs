@@ -0,0 +1,51 @@
+use std::fmt;
+
+use serde_derive::Deserialize;
+use thiserror::Error;
+use warp::{
+    http::StatusCode,
+    reject::Reject,
+    reply::{Reply, Response},
+};
+
+use crate::errors::ErrorResponse;
+
+#[derive(Clone, Error, Debug, Deserialize, PartialEq)]
+pub enum WebhookError {
+    NotFound(i32),
+    DeliveryNotFound(i32),
+    CannotCreate(String),
+    InvalidPayload(String),
+    CannotDelete(String),
+}
+
+impl fmt::Display for WebhookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WebhookError::NotFound(id) => write!(f, "Webhook #{id} not found"),
+            WebhookError::DeliveryNotFound(id) => write!(f, "Webhook delivery #{id} not found"),
+            WebhookError::CannotCreate(error) => write!(f, "Webhook cannot be created: {error}"),
+            WebhookError::CannotDelete(error) => write!(f, "Webhook cannot be deleted: {error}"),
+            WebhookError::InvalidPayload(error) => write!(f, "Invalid webhook: {error}"),
+        }
+    }
+}
+
+impl Reject for WebhookError {}
+
+impl Reply for WebhookError {
+    fn into_response(self) -> Response {
+        let code = match self {
+            WebhookError::NotFound(_) => StatusCode::NOT_FOUND,
+            WebhookError::DeliveryNotFound(_) => StatusCode::NOT_FOUND,
+            WebhookError::CannotCreate(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            WebhookError::CannotDelete(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            WebhookError::InvalidPayload(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        };
+        let message = self.to_string();
+
+        let json = warp::reply::json(&ErrorResponse::new(message));
+
+        warp::reply::with_status(json, code).into_response()
+    }
+}
@@ -0,0 +1,196 @@
+use bytes::Buf;
+use log::{error, info, warn};
+use warp::{
+    http::StatusCode,
+    reject,
+    reject::Rejection,
+    reply::{json, with_status, Reply},
+};
+
+use crate::{
+    api::{
+        roles::{db::DBRole, models::KudosRole, utils::user_has_at_least_one_role},
+        users::db::DBUser,
+    },
+    middlewares::github::model::GitHubUser,
+    types::{PaginatedResponse, PaginationParams},
+};
+
+use super::{
+    db::DBWebhook,
+    errors::WebhookError,
+    models::{NewWebhookSubscription, WebhookSubscription, WebhookSubscriptionDB},
+    utils::validate_url,
+};
+
+const MIN_SECRET_LENGTH: usize = 16;
+
+/// Webhooks without a project receive events from every project and are
+/// reserved to admins, project webhooks can also be managed by its maintainers.
+fn authorize_project(roles: Vec<KudosRole>, project_id: Option<i32>) -> Result<(), Rejection> {
+    let mut allowed = vec![KudosRole::Admin];
+    if let Some(project_id) = project_id {
+        allowed.push(KudosRole::MaintainerWithProjects(Some(vec![project_id])));
+    }
+    user_has_at_least_one_role(roles, allowed)
+}
+
+fn authorized_subscription(
+    id: i32,
+    user: &GitHubUser,
+    db_access: &(impl DBWebhook + DBRole),
+) -> Result<WebhookSubscription, Rejection> {
+    let subscription = db_access
+        .subscription_by_id(id)?
+        .ok_or_else(|| reject::custom(WebhookError::NotFound(id)))?;
+    let roles = DBRole::user_roles(db_access, &user.username)?;
+    authorize_project(roles, subscription.project_id)?;
+    Ok(subscription)
+}
+
+pub async fn all_handler(
+    user: GitHubUser,
+    db_access: impl DBWebhook + DBRole + DBUser,
+) -> Result<impl Reply, Rejection> {
+    let roles = DBRole::user_roles(&db_access, &user.username)?;
+    user_has_at_least_one_role(
+        roles.clone(),
+        vec![KudosRole::Admin, KudosRole::MaintainerWithProjects(None)],
+    )?;
+
+    let project_ids = if roles.contains(&KudosRole::Admin) {
+        None
+    } else {
+        Some(
+            roles
+                .into_iter()
+                .filter_map(|role| match role {
+                    KudosRole::MaintainerWithProjects(Some(ids)) => Some(ids),
+                    _ => None,
+                })
+                .flatten()
+                .collect(),
+        )
+    };
+    let subscriptions = db_access.subscriptions(project_ids)?;
+    Ok(json(&subscriptions))
+}
+
+pub async fn create_handler(
+    user: GitHubUser,
+    buf: impl Buf,
+    db_access: impl DBWebhook + DBRole + DBUser,
+) -> Result<impl Reply, Rejection> {
+    let des = &mut serde_json::Deserializer::from_reader(buf.reader());
+    let subscription: NewWebhookSubscription = serde_path_to_error::deserialize(des).map_err(|e| {
+        let e = e.to_string();
+        warn!("invalid webhook '{e}'");
+        reject::custom(WebhookError::InvalidPayload(e))
+    })?;
+
+    let roles = DBRole::user_roles(&db_access, &user.username)?;
+    authorize_project(roles, subscription.project_id)?;
+
+    validate_url(&subscription.url).await.map_err(|e| reject::custom(WebhookError::InvalidPayload(e)))?;
+    if subscription.secret.len() < MIN_SECRET_LENGTH {
+        return Err(reject::custom(WebhookError::InvalidPayload(format!(
+            "secret must be at least {MIN_SECRET_LENGTH} characters long"
+        ))));
+    }
+    if subscription.events.is_empty() {
+        return Err(reject::custom(WebhookError::InvalidPayload(
+            "at least one event must be provided".to_owned(),
+        )));
+    }
+
+    let created_by = DBUser::by_username(&db_access, &user.username)?.map(|u| u.id);
+    let mut events: Vec<Option<String>> = subscription
+        .events
+        .iter()
+        .map(|event| Some(event.as_str().to_owned()))
+        .collect();
+    events.sort();
+    events.dedup();
+
+    let new_subscription = WebhookSubscriptionDB {
+        url: subscription.url,
+        secret: subscription.secret,
+        events,
+        project_id: subscription.project_id,
+        created_by_user_id: created_by,
+    };
+    match db_access.create_subscription(&new_subscription) {
+        Ok(subscription) => {
+            info!("webhook '{}' created for '{}'", subscription.id, subscription.url);
+            Ok(with_status(json(&subscription), StatusCode::CREATED))
+        }
+        Err(error) => {
+            error!("error creating the webhook for '{}': {}", new_subscription.url, error);
+            if error.to_string().contains("webhook_subscriptions_project_id_fkey") {
+                Err(reject::custom(WebhookError::InvalidPayload(
+                    "project not found".to_owned(),
+                )))
+            } else {
+                Err(reject::custom(WebhookError::CannotCreate(
+                    "error creating the webhook".to_owned(),
+                )))
+            }
+        }
+    }
+}
+
+pub async fn delete_handler(
+    id: i32,
+    user: GitHubUser,
+    db_access: impl DBWebhook + DBRole + DBUser,
+) -> Result<impl Reply, Rejection> {
+    authorized_subscription(id, &user, &db_access)?;
+    match db_access.delete_subscription(id) {
+        Ok(_) => {
+            info!("webhook '{id}' deleted");
+            Ok(StatusCode::NO_CONTENT)
+        }
+        Err(error) => {
+            error!("error deleting the webhook '{id}': {error}");
+            Err(reject::custom(WebhookError::CannotDelete(
+                "error deleting the webhook".to_owned(),
+            )))
+        }
+    }
+}
+
+pub async fn deliveries_handler(
+    id: i32,
+    user: GitHubUser,
+    db_access: impl DBWebhook + DBRole + DBUser,
+    pagination: PaginationParams,
+) -> Result<impl Reply, Rejection> {
+    authorized_subscription(id, &user, &db_access)?;
+    let (deliveries, total_count) = db_access.deliveries(id, pagination.clone())?;
+    let has_next_page = pagination.offset + pagination.limit < total_count;
+    let has_previous_page = pagination.offset > 0;
+
+    let response = PaginatedResponse {
+        total_count: Some(total_count),
+        has_next_page,
+        has_previous_page,
+        data: deliveries,
+    };
+
+    Ok(json(&response))
+}
+
+pub async fn redeliver_handler(
+    id: i32,
+    user: GitHubUser,
+    db_access: impl DBWebhook + DBRole + DBUser,
+) -> Result<impl Reply, Rejection> {
+    let delivery = db_access
+        .delivery_by_id(id)?
+        .ok_or_else(|| reject::custom(WebhookError::DeliveryNotFound(id)))?;
+    authorized_subscription(delivery.subscription_id, &user, &db_access)?;
+
+    let redelivery = db_access.redeliver(&delivery)?;
+    info!("webhook delivery '{id}' queued again as '{}'", redelivery.id);
+    Ok(with_status(json(&redelivery), StatusCode::ACCEPTED))
+}
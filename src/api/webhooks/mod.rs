@@ -0,0 +1,7 @@
+pub mod db;
+pub mod errors;
+pub mod handlers;
+pub mod models;
+pub mod routes;
+pub mod utils;
+pub mod worker;
@@ -0,0 +1,104 @@
+use crate::schema::{webhook_deliveries, webhook_subscriptions};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookEvent {
+    #[serde(rename = "task.created")]
+    TaskCreated,
+    #[serde(rename = "task.status_changed")]
+    TaskStatusChanged,
+    #[serde(rename = "issue.assigned")]
+    IssueAssigned,
+    #[serde(rename = "vote.cast")]
+    VoteCast,
+}
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::TaskCreated => "task.created",
+            WebhookEvent::TaskStatusChanged => "task.status_changed",
+            WebhookEvent::IssueAssigned => "issue.assigned",
+            WebhookEvent::VoteCast => "vote.cast",
+        }
+    }
+}
+
+#[derive(Queryable, Identifiable, Selectable, Debug, PartialEq, Serialize, Clone)]
+#[diesel(table_name = webhook_subscriptions)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct WebhookSubscription {
+    pub id: i32,
+    pub url: String,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub events: Vec<Option<String>>,
+    pub project_id: Option<i32>,
+    pub active: bool,
+    pub created_by_user_id: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NewWebhookSubscription {
+    pub url: String,
+    pub secret: String,
+    pub events: Vec<WebhookEvent>,
+    pub project_id: Option<i32>,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = webhook_subscriptions)]
+pub struct WebhookSubscriptionDB {
+    pub url: String,
+    pub secret: String,
+    pub events: Vec<Option<String>>,
+    pub project_id: Option<i32>,
+    pub created_by_user_id: Option<i32>,
+}
+
+#[derive(Queryable, Identifiable, Selectable, Debug, PartialEq, Serialize, Clone)]
+#[diesel(table_name = webhook_deliveries)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct WebhookDelivery {
+    pub id: i32,
+    pub subscription_id: i32,
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = webhook_deliveries)]
+pub struct NewWebhookDelivery {
+    pub subscription_id: i32,
+    pub event: String,
+    pub payload: serde_json::Value,
+}
+
+/// Outcome of a single POST to a subscriber.
+#[derive(Debug, PartialEq)]
+pub struct DeliveryAttempt {
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+}
+
+impl DeliveryAttempt {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+pub const DELIVERY_PENDING: &str = "pending";
+pub const DELIVERY_SUCCEEDED: &str = "succeeded";
+pub const DELIVERY_FAILED: &str = "failed";
@@ -0,0 +1,65 @@
+use std::convert::Infallible;
+
+use warp::filters::BoxedFilter;
+use warp::{Filter, Reply};
+
+use crate::api::roles::db::DBRole;
+use crate::api::users::db::DBUser;
+use crate::middlewares::github::auth::with_github_auth;
+use crate::types::PaginationParams;
+
+use super::db::DBWebhook;
+use super::handlers;
+
+fn with_db(
+    db_pool: impl DBWebhook + DBRole + DBUser,
+) -> impl Filter<Extract = (impl DBWebhook + DBRole + DBUser,), Error = Infallible> + Clone {
+    warp::any().map(move || db_pool.clone())
+}
+
+pub fn routes(db_access: impl DBWebhook + DBRole + DBUser) -> BoxedFilter<(impl Reply,)> {
+    let webhooks = warp::path!("webhooks");
+    let webhook_id = warp::path!("webhooks" / i32);
+    let webhook_deliveries = warp::path!("webhooks" / i32 / "deliveries");
+    let webhook_redeliver = warp::path!("webhooks" / "deliveries" / i32 / "redeliver");
+
+    let get_webhooks = webhooks
+        .and(warp::get())
+        .and(with_github_auth())
+        .and(with_db(db_access.clone()))
+        .and_then(handlers::all_handler);
+
+    let create_webhook = webhooks
+        .and(warp::post())
+        .and(with_github_auth())
+        .and(warp::body::aggregate())
+        .and(with_db(db_access.clone()))
+        .and_then(handlers::create_handler);
+
+    let delete_webhook = webhook_id
+        .and(warp::delete())
+        .and(with_github_auth())
+        .and(with_db(db_access.clone()))
+        .and_then(handlers::delete_handler);
+
+    let get_deliveries = webhook_deliveries
+        .and(warp::get())
+        .and(with_github_auth())
+        .and(with_db(db_access.clone()))
+        .and(warp::query::<PaginationParams>())
+        .and_then(handlers::deliveries_handler);
+
+    let redeliver = webhook_redeliver
+        .and(warp::post())
+        .and(with_github_auth())
+        .and(with_db(db_access.clone()))
+        .and_then(handlers::redeliver_handler);
+
+    let route = get_webhooks
+        .or(create_webhook)
+        .or(delete_webhook)
+        .or(get_deliveries)
+        .or(redeliver);
+
+    route.boxed()
+}
@@ -0,0 +1,111 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use chrono::Utc;
+use log::{error, info};
+use serde::Serialize;
+use serde_json::json;
+use url::Url;
+
+use super::{db::DBWebhook, models::WebhookEvent};
+
+/// Queues `data` for the subscribers of `event`. Failures are logged so they
+/// never fail the request that produced the event.
+pub fn dispatch_event(
+    db_access: &impl DBWebhook,
+    event: WebhookEvent,
+    project_id: Option<i32>,
+    repository_id: Option<i32>,
+    data: &impl Serialize,
+) {
+    let payload = json!({
+        "event": event,
+        "created_at": Utc::now(),
+        "data": data,
+    });
+    match db_access.enqueue(event, project_id, repository_id, &payload) {
+        Ok(0) => {}
+        Ok(count) => info!("queued {count} '{}' webhook deliveries", event.as_str()),
+        Err(e) => error!("error queueing '{}' webhook deliveries: {e}", event.as_str()),
+    }
+}
+
+/// Whether `ip` is routable on the internet, webhooks must not reach the
+/// loopback, link-local or private networks the server sits in.
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                // shared address space (RFC 6598) and "this network"
+                || (a == 100 && (64..128).contains(&b))
+                || a == 0)
+        }
+        IpAddr::V6(ip) => match embedded_ipv4(ip) {
+            Some(ip) => is_public_address(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_unspecified()
+                    || ip.is_loopback()
+                    || ip.is_multicast()
+                    // unique local (fc00::/7) and link-local (fe80::/10)
+                    || (first & 0xfe00) == 0xfc00
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+/// The IPv4 address `ip` forwards to when it is IPv4-mapped, NAT64
+/// (64:ff9b::/96) or 6to4 (2002::/16).
+fn embedded_ipv4(ip: Ipv6Addr) -> Option<Ipv4Addr> {
+    let [a, b, c, d, e, f, g, h] = ip.segments();
+    let from_segments =
+        |high: u16, low: u16| Ipv4Addr::from((u32::from(high) << 16) | u32::from(low));
+    if let Some(ip) = ip.to_ipv4_mapped() {
+        Some(ip)
+    } else if [a, b, c, d, e, f] == [0x64, 0xff9b, 0, 0, 0, 0] {
+        Some(from_segments(g, h))
+    } else if a == 0x2002 {
+        Some(from_segments(b, c))
+    } else {
+        None
+    }
+}
+
+/// Resolves `host`, failing unless every address it resolves to is public.
+pub async fn resolve_public(host: &str, port: u16) -> Result<Vec<SocketAddr>, String> {
+    let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| format!("cannot resolve '{host}': {e}"))?
+        .collect();
+    if addresses.is_empty() {
+        return Err(format!("cannot resolve '{host}'"));
+    }
+    match addresses.iter().find(|address| !is_public_address(address.ip())) {
+        Some(address) => Err(format!("'{host}' resolves to the non public address {}", address.ip())),
+        None => Ok(addresses),
+    }
+}
+
+/// Checks the scheme of a webhook url and that its host is public.
+pub async fn validate_url(url: &str) -> Result<(), String> {
+    let url = Url::parse(url).map_err(|e| format!("invalid url: {e}"))?;
+    match url.scheme() {
+        "http" | "https" => {}
+        scheme => return Err(format!("unsupported url scheme '{scheme}'")),
+    }
+    let host = match url.host() {
+        Some(url::Host::Domain(domain)) => domain.to_owned(),
+        Some(url::Host::Ipv4(ip)) => ip.to_string(),
+        Some(url::Host::Ipv6(ip)) => ip.to_string(),
+        None => return Err("url without host".to_owned()),
+    };
+    let port = url.port_or_known_default().unwrap_or(80);
+    resolve_public(&host, port).await.map(|_| ())
+}
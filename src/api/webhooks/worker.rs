@@ -0,0 +1,182 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use log::{error, info, warn};
+use sha2::Sha256;
+use tokio_util::sync::CancellationToken;
+
+use super::{
+    db::DBWebhook,
+    models::{DeliveryAttempt, WebhookDelivery, WebhookSubscription},
+    utils::{resolve_public, validate_url},
+};
+
+pub const SIGNATURE_HEADER: &str = "X-Kudos-Signature";
+pub const EVENT_HEADER: &str = "X-Kudos-Event";
+pub const DELIVERY_HEADER: &str = "X-Kudos-Delivery";
+
+const MAX_ATTEMPTS: i32 = 8;
+const POLL_INTERVAL_SECONDS: u64 = 5;
+const RESOLVE_TIMEOUT_SECONDS: u64 = 5;
+const REQUEST_TIMEOUT_SECONDS: u64 = 10;
+/// Deliveries are claimed one at a time, so the lease only has to outlast
+/// checking and sending a single one.
+const LEASE_SECONDS: i64 = (RESOLVE_TIMEOUT_SECONDS + REQUEST_TIMEOUT_SECONDS) as i64 + 30;
+const RETRY_BASE_SECONDS: i64 = 30;
+const RETRY_MAX_SECONDS: i64 = 6 * 60 * 60;
+
+/// `sha256=<hex hmac>` of the raw request body, keyed with the subscription secret.
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    let signature: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    format!("sha256={signature}")
+}
+
+/// Exponential backoff after the `attempts`-th failure.
+pub fn retry_delay(attempts: i32) -> chrono::Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 20) as u32;
+    let seconds = RETRY_BASE_SECONDS.saturating_mul(2_i64.pow(exponent));
+    chrono::Duration::seconds(seconds.min(RETRY_MAX_SECONDS))
+}
+
+/// Resolves hosts for the webhook client, refusing the ones resolving to
+/// non public addresses so a subscription can't be repointed at the internal
+/// network once registered.
+struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        Box::pin(async move {
+            let addresses = resolve_public(name.as_str(), 0).await?;
+            Ok(Box::new(addresses.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+/// HTTP client for deliveries: it only connects to public addresses and
+/// doesn't follow redirects, which could lead anywhere.
+pub fn webhook_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECONDS))
+        .redirect(reqwest::redirect::Policy::none())
+        .dns_resolver(Arc::new(PublicResolver))
+        .build()
+        .expect("Failed to create webhook HTTP client")
+}
+
+/// Checks the subscription url again before sending, hosts given as an ip
+/// don't go through the client resolver.
+async fn check_url(url: &str) -> Result<(), String> {
+    match tokio::time::timeout(Duration::from_secs(RESOLVE_TIMEOUT_SECONDS), validate_url(url)).await {
+        Ok(result) => result,
+        Err(_) => Err("timed out resolving the webhook host".to_owned()),
+    }
+}
+
+pub async fn deliver(
+    client: &reqwest::Client,
+    subscription: &WebhookSubscription,
+    delivery: &WebhookDelivery,
+) -> DeliveryAttempt {
+    let body = match serde_json::to_vec(&delivery.payload) {
+        Ok(body) => body,
+        Err(e) => {
+            return DeliveryAttempt {
+                status_code: None,
+                error: Some(e.to_string()),
+            }
+        }
+    };
+
+    let response = client
+        .post(&subscription.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, &delivery.event)
+        .header(DELIVERY_HEADER, delivery.id.to_string())
+        .header(SIGNATURE_HEADER, sign_payload(&subscription.secret, &body))
+        .body(body)
+        .send()
+        .await;
+
+    match response {
+        Ok(response) if response.status().is_success() => DeliveryAttempt {
+            status_code: Some(response.status().as_u16() as i32),
+            error: None,
+        },
+        Ok(response) => DeliveryAttempt {
+            status_code: Some(response.status().as_u16() as i32),
+            error: Some(format!("unexpected status {}", response.status())),
+        },
+        Err(e) => DeliveryAttempt {
+            status_code: None,
+            error: Some(e.to_string()),
+        },
+    }
+}
+
+/// Polls for due deliveries and POSTs them, rescheduling failures with
+/// exponential backoff until `MAX_ATTEMPTS` is reached. Stops after the
+/// current delivery once `shutdown` is cancelled; deliveries it claimed but
+/// didn't send are picked up again when their lease expires.
+pub async fn start_webhook_worker(db_access: impl DBWebhook, shutdown: CancellationToken) {
+    let client = webhook_client();
+    let mut interval = tokio::time::interval(Duration::from_secs(POLL_INTERVAL_SECONDS));
+
+    while !shutdown.is_cancelled() {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            _ = interval.tick() => {}
+        }
+        // send everything due before waiting for the next tick
+        while !shutdown.is_cancelled() {
+            let lease_until = Utc::now() + chrono::Duration::seconds(LEASE_SECONDS);
+            let (delivery, subscription) = match db_access.claim_due(1, lease_until) {
+                Ok(mut due) => match due.pop() {
+                    Some(claimed) => claimed,
+                    None => break,
+                },
+                Err(e) => {
+                    error!("error claiming webhook deliveries: {e}");
+                    break;
+                }
+            };
+
+            let attempt = match check_url(&subscription.url).await {
+                Ok(()) => deliver(&client, &subscription, &delivery).await,
+                Err(e) => DeliveryAttempt {
+                    status_code: None,
+                    error: Some(e),
+                },
+            };
+            let attempts = delivery.attempts + 1;
+            let retry_at = match &attempt.error {
+                None => None,
+                Some(e) if attempts < MAX_ATTEMPTS => {
+                    warn!("webhook delivery '{}' to '{}' failed: {e}", delivery.id, subscription.url);
+                    Some(Utc::now() + retry_delay(attempts))
+                }
+                Some(e) => {
+                    error!(
+                        "webhook delivery '{}' to '{}' failed after {attempts} attempts: {e}",
+                        delivery.id, subscription.url
+                    );
+                    None
+                }
+            };
+            if attempt.succeeded() {
+                info!("webhook delivery '{}' sent to '{}'", delivery.id, subscription.url);
+            }
+            if let Err(e) = db_access.record_attempt(delivery.id, &attempt, retry_at) {
+                error!("error recording webhook delivery '{}': {e}", delivery.id);
+            }
+        }
+    }
+    info!("webhook worker stopped");
+}
@@ -0,0 +1,175 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use clap::{Parser, Subcommand};
+use kudos_api::{
+    admin::{
+        commands::{apply_manifest, grant_role, read_manifest, revoke_role},
+        db::DBAdmin,
+        errors::AdminError,
+    },
+    api::{tasks::db::DBTask, users::db::DBUser},
+    config::{Cli as ServerCli, Config},
+    db::pool::DBAccess,
+    email::model::{EmailNotifier, SMTPConfig},
+    utils,
+};
+use tokio_util::sync::CancellationToken;
+
+/// Operational tasks against the Kudos database, using the same
+/// configuration as the API server.
+#[derive(Parser, Debug)]
+#[command(name = "kudos-admin", about = "Kudos administration commands")]
+struct Cli {
+    /// Path to a TOML config file (env: KUDOS_CONFIG)
+    #[arg(long, short, global = true)]
+    config: Option<PathBuf>,
+    /// Postgres connection URL (env: DATABASE_URL)
+    #[arg(long, global = true)]
+    database_url: Option<String>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Give a role to a user, globally or on one project
+    GrantRole {
+        username: String,
+        /// Role name, e.g. Admin or Maintainer
+        role: String,
+        /// Slug of the project the role applies to
+        #[arg(long)]
+        project: Option<String>,
+    },
+    /// Take a role away from a user, globally or on one project
+    RevokeRole {
+        username: String,
+        role: String,
+        #[arg(long)]
+        project: Option<String>,
+    },
+    /// Create the projects and repositories of a YAML manifest that don't exist yet
+    ApplyManifest {
+        path: PathBuf,
+        /// Only print what would be created
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Send the notification digest to one user
+    Digest {
+        username: String,
+        /// Log the email instead of sending it
+        #[arg(long)]
+        dry_run: bool,
+        /// Include notifications from this many days (default: notifications.days)
+        #[arg(long)]
+        days: Option<i64>,
+    },
+    /// Recompute task vote counters from the votes
+    RebuildVotes,
+    /// Print row counts and the database size
+    Stats,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let server_cli = ServerCli {
+        config: cli.config.clone(),
+        database_url: cli.database_url.clone(),
+        ..Default::default()
+    };
+    dotenv::dotenv().ok();
+    let config = match Config::from_sources(&server_cli, |key| std::env::var(key).ok()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    utils::init_logging(&config.logging);
+
+    let db = utils::setup_db(&config.database).await;
+    match run(cli.command, &config, db).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(command: Command, config: &Config, db: DBAccess) -> Result<(), AdminError> {
+    match command {
+        Command::GrantRole { username, role, project } => {
+            match grant_role(&db, &username, &role, project.as_deref())? {
+                Some(_) => println!("granted {role} to {username}{}", on_project(&project)),
+                None => println!("{username} already has {role}{}", on_project(&project)),
+            }
+        }
+        Command::RevokeRole { username, role, project } => {
+            if revoke_role(&db, &username, &role, project.as_deref())? {
+                println!("revoked {role} from {username}{}", on_project(&project));
+            } else {
+                println!("{username} does not have {role}{}", on_project(&project));
+            }
+        }
+        Command::ApplyManifest { path, dry_run } => {
+            let manifest = read_manifest(&path)?;
+            let summary = apply_manifest(&db, &manifest, dry_run)?;
+            let verb = if dry_run { "would create" } else { "created" };
+            println!("{verb} projects: {}", list(&summary.created_projects));
+            println!("existing projects: {}", list(&summary.existing_projects));
+            println!("{verb} repositories: {}", list(&summary.created_repositories));
+            println!("existing repositories: {}", list(&summary.existing_repositories));
+        }
+        Command::Digest { username, dry_run, days } => {
+            let user = db
+                .by_username(&username)?
+                .ok_or_else(|| AdminError::UserNotFound(username.clone()))?;
+            let Some(github_id) = user.github_id else {
+                return Err(AdminError::Digest(format!("user '{username}' has no GitHub id")));
+            };
+            let notifications = &config.notifications;
+            let notifier = EmailNotifier::new(
+                SMTPConfig {
+                    smtp_host: notifications.smtp_host.clone(),
+                    smtp_port: notifications.smtp_port,
+                    smtp_username: notifications.smtp_username.clone(),
+                    smtp_password: notifications.smtp_password.clone(),
+                    from_email: notifications.from_email.clone(),
+                },
+                db.clone(),
+            );
+            notifier
+                .send_notifications(
+                    dry_run || notifications.dry_run,
+                    days.unwrap_or(notifications.days),
+                    notifications.subject.clone(),
+                    Some(github_id),
+                    &CancellationToken::new(),
+                )
+                .await
+                .map_err(|e| AdminError::Digest(e.to_string()))?;
+            println!("digest processed for {username}");
+        }
+        Command::RebuildVotes => {
+            let fixed = db.rebuild_vote_counts()?;
+            println!("fixed vote counters of {fixed} tasks");
+        }
+        Command::Stats => println!("{}", db.stats()?),
+    }
+    Ok(())
+}
+
+fn on_project(project: &Option<String>) -> String {
+    project.as_ref().map(|slug| format!(" on {slug}")).unwrap_or_default()
+}
+
+fn list(slugs: &[String]) -> String {
+    if slugs.is_empty() {
+        "none".to_owned()
+    } else {
+        slugs.join(", ")
+    }
+}
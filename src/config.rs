@@ -0,0 +1,562 @@
+use std::{fmt::Display, path::PathBuf, str::FromStr};
+
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::api::jobs::schedule::Schedule;
+use crate::middlewares::cors::OriginPattern;
+
+const REDACTED: &str = "[redacted]";
+
+/// Command line flags. They take precedence over environment variables,
+/// which take precedence over the config file.
+#[derive(Parser, Debug, Default)]
+#[command(name = "kudos_api", about = "Kudos issues API")]
+pub struct Cli {
+    /// Path to a TOML config file (env: KUDOS_CONFIG)
+    #[arg(long, short)]
+    pub config: Option<PathBuf>,
+    /// development or production (env: ENVIRONMENT)
+    #[arg(long)]
+    pub environment: Option<Environment>,
+    /// HTTP server host name (env: HOST)
+    #[arg(long)]
+    pub host: Option<String>,
+    /// HTTP server port (env: PORT)
+    #[arg(long)]
+    pub port: Option<u16>,
+    /// Postgres connection URL (env: DATABASE_URL)
+    #[arg(long)]
+    pub database_url: Option<String>,
+}
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("error reading config file '{path}': {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("error parsing config file '{path}': {source}")]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    #[error("invalid configuration:\n  - {}", .0.join("\n  - "))]
+    Invalid(Vec<String>),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Environment {
+    #[default]
+    Development,
+    Production,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!("unknown log format '{s}', expected text or json")),
+        }
+    }
+}
+
+impl FromStr for Environment {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "development" | "dev" => Ok(Environment::Development),
+            "production" | "prod" => Ok(Environment::Production),
+            _ => Err(format!("unknown environment '{s}', expected development or production")),
+        }
+    }
+}
+
+/// Configuration used by this API.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub environment: Environment,
+    pub logging: LoggingConfig,
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub cors: CorsConfig,
+    pub github: GitHubConfig,
+    pub rate_limits: RateLimitConfig,
+    pub metrics: MetricsConfig,
+    pub notifications: NotificationsConfig,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    pub format: LogFormat,
+    /// `tracing` filter directives, e.g. `info,kudos_api=debug`.
+    pub filter: String,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            format: LogFormat::Text,
+            filter: "info".to_owned(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// HTTP server host name (default: 127.0.0.1).
+    pub host: String,
+    /// HTTP server port (default: 8000).
+    pub port: u16,
+    /// How long in-flight requests and background jobs get to finish after
+    /// SIGTERM or SIGINT before the process exits anyway (default: 30).
+    pub shutdown_timeout_seconds: u64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_owned(),
+            port: 8000,
+            shutdown_timeout_seconds: 30,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub pool_max_open: u32,
+    pub pool_min_idle: u32,
+    /// How long a request waits for a pooled connection before failing.
+    pub pool_timeout_seconds: u64,
+    /// Server side `statement_timeout` applied to every connection.
+    pub statement_timeout_seconds: u64,
+    /// Apply pending embedded migrations before serving requests.
+    pub migrate_on_startup: bool,
+    /// Days deleted projects, repositories, issues and tasks can be restored
+    /// before they are purged, 0 keeps them forever.
+    pub deleted_retention_days: i64,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            pool_max_open: 32,
+            pool_min_idle: 8,
+            pool_timeout_seconds: 15,
+            statement_timeout_seconds: 30,
+            migrate_on_startup: false,
+            deleted_retention_days: 30,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    /// Defaults to the frontend origins of the environment when empty.
+    pub allowed_origins: Vec<String>,
+    pub max_age_seconds: u64,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: Vec::new(),
+            max_age_seconds: 3600,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct GitHubConfig {
+    pub api_base_url: String,
+}
+
+impl Default for GitHubConfig {
+    fn default() -> Self {
+        Self {
+            api_base_url: "https://api.github.com".to_owned(),
+        }
+    }
+}
+
+/// Requests allowed per minute and per client for each route group.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    pub read_per_minute: u32,
+    pub write_per_minute: u32,
+    pub auth_per_minute: u32,
+    /// Identify clients by the last `X-Forwarded-For` address, the one the
+    /// proxy in front of the server added. Only enable behind such a proxy.
+    pub trust_forwarded_for: bool,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            read_per_minute: 300,
+            write_per_minute: 60,
+            auth_per_minute: 20,
+            trust_forwarded_for: false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsConfig {
+    /// Serve `GET /metrics`.
+    pub enabled: bool,
+    /// When set, scrapes must send it as a bearer token.
+    pub bearer_token: String,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            bearer_token: String::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationsConfig {
+    pub days: i64,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_username: String,
+    pub smtp_password: String,
+    pub from_email: String,
+    pub subject: String,
+    pub enabled: bool,
+    pub dry_run: bool,
+    pub verification_secret: String,
+    pub verification_url: String,
+    pub verification_ttl_hours: i64,
+    pub retention_days: i64,
+    /// Digest schedule, defaults to every `days` days.
+    pub schedule: Option<String>,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            days: 30,
+            smtp_host: String::new(),
+            smtp_port: 0,
+            smtp_username: String::new(),
+            smtp_password: String::new(),
+            from_email: String::new(),
+            subject: "Kudos Notifications Summary".to_owned(),
+            enabled: false,
+            dry_run: true,
+            verification_secret: String::new(),
+            verification_url: "http://localhost:8000/users/email/verify".to_owned(),
+            verification_ttl_hours: 24,
+            retention_days: 90,
+            schedule: None,
+        }
+    }
+}
+
+impl NotificationsConfig {
+    pub fn digest_schedule(&self) -> String {
+        self.schedule
+            .clone()
+            .unwrap_or_else(|| format!("@every {}d", self.days))
+    }
+
+    fn validation_errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.days <= 0 {
+            errors.push("notifications.days (NOTIFICATIONS_DAYS) must be positive".to_owned());
+        }
+        if self.verification_ttl_hours <= 0 {
+            errors.push(
+                "notifications.verification_ttl_hours (NOTIFICATIONS_VERIFICATION_TTL_HOURS) must be positive"
+                    .to_owned(),
+            );
+        }
+        if self.retention_days < 0 {
+            errors.push(
+                "notifications.retention_days (NOTIFICATIONS_RETENTION_DAYS) must not be negative".to_owned(),
+            );
+        }
+        if let Err(e) = self.digest_schedule().parse::<Schedule>() {
+            errors.push(format!("notifications.schedule (NOTIFICATIONS_SCHEDULE) is invalid: {e}"));
+        }
+        if !self.enabled || self.dry_run {
+            return errors;
+        }
+        let required = [
+            ("smtp_host", "NOTIFICATIONS_SMTP_HOST", self.smtp_host.is_empty()),
+            ("smtp_port", "NOTIFICATIONS_SMTP_PORT", self.smtp_port == 0),
+            ("smtp_username", "NOTIFICATIONS_SMTP_USERNAME", self.smtp_username.is_empty()),
+            ("smtp_password", "NOTIFICATIONS_SMTP_PASSWORD", self.smtp_password.is_empty()),
+            ("from_email", "NOTIFICATIONS_FROM_EMAIL", self.from_email.is_empty()),
+        ];
+        for (key, env_key, missing) in required {
+            if missing {
+                errors.push(format!(
+                    "notifications.{key} ({env_key}) must be set when notifications are enabled"
+                ));
+            }
+        }
+        errors
+    }
+}
+
+/// Parses `value` into `target`, recording a readable error instead of
+/// panicking on malformed input.
+fn override_with<T>(errors: &mut Vec<String>, key: &str, value: Option<String>, target: &mut T)
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    if let Some(value) = value {
+        match value.trim().parse() {
+            Ok(parsed) => *target = parsed,
+            Err(e) => errors.push(format!("{key}: invalid value '{value}': {e}")),
+        }
+    }
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+fn validate_http_url(key: &str, value: &str, errors: &mut Vec<String>) {
+    match url::Url::parse(value) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+        _ => errors.push(format!("{key} must be an http(s) URL, got '{value}'")),
+    }
+}
+
+impl Config {
+    /// Loads the configuration from the optional config file, the
+    /// environment and the command line, then validates it.
+    pub fn load() -> Result<Self, ConfigError> {
+        dotenv::dotenv().ok();
+        Self::from_sources(&Cli::parse(), |key| std::env::var(key).ok())
+    }
+
+    pub fn from_sources(cli: &Cli, env: impl Fn(&str) -> Option<String>) -> Result<Self, ConfigError> {
+        let path = cli
+            .config
+            .clone()
+            .or_else(|| env("KUDOS_CONFIG").map(PathBuf::from));
+        let mut config = match path {
+            Some(path) => {
+                let contents = std::fs::read_to_string(&path).map_err(|source| ConfigError::Read {
+                    path: path.clone(),
+                    source,
+                })?;
+                toml::from_str(&contents).map_err(|source| ConfigError::Parse { path, source })?
+            }
+            None => Config::default(),
+        };
+
+        let mut errors = config.apply_env(&env);
+        config.apply_cli(cli);
+        errors.extend(config.validation_errors());
+
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError::Invalid(errors))
+        }
+    }
+
+    fn apply_env(&mut self, env: &impl Fn(&str) -> Option<String>) -> Vec<String> {
+        let mut errors = Vec::new();
+        let e = &mut errors;
+
+        override_with(e, "ENVIRONMENT", env("ENVIRONMENT"), &mut self.environment);
+
+        override_with(e, "LOG_FORMAT", env("LOG_FORMAT"), &mut self.logging.format);
+        override_with(e, "RUST_LOG", env("RUST_LOG"), &mut self.logging.filter);
+
+        override_with(e, "HOST", env("HOST"), &mut self.server.host);
+        override_with(e, "PORT", env("PORT"), &mut self.server.port);
+        override_with(e, "SHUTDOWN_TIMEOUT_SECONDS", env("SHUTDOWN_TIMEOUT_SECONDS"), &mut self.server.shutdown_timeout_seconds);
+
+        let database = &mut self.database;
+        override_with(e, "DATABASE_URL", env("DATABASE_URL"), &mut database.url);
+        override_with(e, "DB_POOL_MAX_OPEN", env("DB_POOL_MAX_OPEN"), &mut database.pool_max_open);
+        override_with(e, "DB_POOL_MIN_IDLE", env("DB_POOL_MIN_IDLE"), &mut database.pool_min_idle);
+        override_with(e, "DB_POOL_TIMEOUT_SECONDS", env("DB_POOL_TIMEOUT_SECONDS"), &mut database.pool_timeout_seconds);
+        override_with(e, "DB_STATEMENT_TIMEOUT_SECONDS", env("DB_STATEMENT_TIMEOUT_SECONDS"), &mut database.statement_timeout_seconds);
+        override_with(e, "DB_MIGRATE_ON_STARTUP", env("DB_MIGRATE_ON_STARTUP"), &mut database.migrate_on_startup);
+        override_with(e, "DB_DELETED_RETENTION_DAYS", env("DB_DELETED_RETENTION_DAYS"), &mut database.deleted_retention_days);
+
+        if let Some(origins) = env("CORS_ALLOWED_ORIGINS") {
+            self.cors.allowed_origins = split_list(&origins);
+        }
+        override_with(e, "CORS_MAX_AGE_SECONDS", env("CORS_MAX_AGE_SECONDS"), &mut self.cors.max_age_seconds);
+
+        override_with(e, "GITHUB_API_BASE_URL", env("GITHUB_API_BASE_URL"), &mut self.github.api_base_url);
+
+        let rate_limits = &mut self.rate_limits;
+        override_with(e, "RATE_LIMIT_ENABLED", env("RATE_LIMIT_ENABLED"), &mut rate_limits.enabled);
+        override_with(e, "RATE_LIMIT_READ_PER_MINUTE", env("RATE_LIMIT_READ_PER_MINUTE"), &mut rate_limits.read_per_minute);
+        override_with(e, "RATE_LIMIT_WRITE_PER_MINUTE", env("RATE_LIMIT_WRITE_PER_MINUTE"), &mut rate_limits.write_per_minute);
+        override_with(e, "RATE_LIMIT_AUTH_PER_MINUTE", env("RATE_LIMIT_AUTH_PER_MINUTE"), &mut rate_limits.auth_per_minute);
+        override_with(e, "RATE_LIMIT_TRUST_FORWARDED_FOR", env("RATE_LIMIT_TRUST_FORWARDED_FOR"), &mut rate_limits.trust_forwarded_for);
+
+        override_with(e, "METRICS_ENABLED", env("METRICS_ENABLED"), &mut self.metrics.enabled);
+        override_with(e, "METRICS_BEARER_TOKEN", env("METRICS_BEARER_TOKEN"), &mut self.metrics.bearer_token);
+
+        let notifications = &mut self.notifications;
+        override_with(e, "NOTIFICATIONS_DAYS", env("NOTIFICATIONS_DAYS"), &mut notifications.days);
+        override_with(e, "NOTIFICATIONS_SMTP_HOST", env("NOTIFICATIONS_SMTP_HOST"), &mut notifications.smtp_host);
+        override_with(e, "NOTIFICATIONS_SMTP_PORT", env("NOTIFICATIONS_SMTP_PORT"), &mut notifications.smtp_port);
+        override_with(e, "NOTIFICATIONS_SMTP_USERNAME", env("NOTIFICATIONS_SMTP_USERNAME"), &mut notifications.smtp_username);
+        override_with(e, "NOTIFICATIONS_SMTP_PASSWORD", env("NOTIFICATIONS_SMTP_PASSWORD"), &mut notifications.smtp_password);
+        override_with(e, "NOTIFICATIONS_FROM_EMAIL", env("NOTIFICATIONS_FROM_EMAIL"), &mut notifications.from_email);
+        override_with(e, "NOTIFICATIONS_SUBJECT", env("NOTIFICATIONS_SUBJECT"), &mut notifications.subject);
+        override_with(e, "NOTIFICATIONS_ENABLED", env("NOTIFICATIONS_ENABLED"), &mut notifications.enabled);
+        override_with(e, "NOTIFICATIONS_DRY_RUN", env("NOTIFICATIONS_DRY_RUN"), &mut notifications.dry_run);
+        override_with(e, "NOTIFICATIONS_VERIFICATION_SECRET", env("NOTIFICATIONS_VERIFICATION_SECRET"), &mut notifications.verification_secret);
+        override_with(e, "NOTIFICATIONS_VERIFICATION_URL", env("NOTIFICATIONS_VERIFICATION_URL"), &mut notifications.verification_url);
+        override_with(e, "NOTIFICATIONS_VERIFICATION_TTL_HOURS", env("NOTIFICATIONS_VERIFICATION_TTL_HOURS"), &mut notifications.verification_ttl_hours);
+        override_with(e, "NOTIFICATIONS_RETENTION_DAYS", env("NOTIFICATIONS_RETENTION_DAYS"), &mut notifications.retention_days);
+        if let Some(schedule) = env("NOTIFICATIONS_SCHEDULE") {
+            notifications.schedule = Some(schedule);
+        }
+
+        errors
+    }
+
+    fn apply_cli(&mut self, cli: &Cli) {
+        if let Some(environment) = cli.environment {
+            self.environment = environment;
+        }
+        if let Some(host) = &cli.host {
+            self.server.host = host.clone();
+        }
+        if let Some(port) = cli.port {
+            self.server.port = port;
+        }
+        if let Some(database_url) = &cli.database_url {
+            self.database.url = database_url.clone();
+        }
+    }
+
+    fn validation_errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if let Err(e) = tracing_subscriber::EnvFilter::try_new(&self.logging.filter) {
+            errors.push(format!("logging.filter (RUST_LOG) is invalid: {e}"));
+        }
+
+        if self.server.host.is_empty() {
+            errors.push("server.host (HOST) must not be empty".to_owned());
+        }
+        if self.server.shutdown_timeout_seconds == 0 {
+            errors.push("server.shutdown_timeout_seconds (SHUTDOWN_TIMEOUT_SECONDS) must be positive".to_owned());
+        }
+
+        let database = &self.database;
+        if database.url.is_empty() {
+            errors.push("database.url (DATABASE_URL) must be set".to_owned());
+        }
+        if database.pool_max_open == 0 {
+            errors.push("database.pool_max_open (DB_POOL_MAX_OPEN) must be positive".to_owned());
+        }
+        if database.pool_min_idle > database.pool_max_open {
+            errors.push(
+                "database.pool_min_idle (DB_POOL_MIN_IDLE) must not exceed database.pool_max_open".to_owned(),
+            );
+        }
+        if database.pool_timeout_seconds == 0 {
+            errors.push("database.pool_timeout_seconds (DB_POOL_TIMEOUT_SECONDS) must be positive".to_owned());
+        }
+        if database.statement_timeout_seconds == 0 {
+            errors.push(
+                "database.statement_timeout_seconds (DB_STATEMENT_TIMEOUT_SECONDS) must be positive".to_owned(),
+            );
+        }
+        if database.deleted_retention_days < 0 {
+            errors.push(
+                "database.deleted_retention_days (DB_DELETED_RETENTION_DAYS) must not be negative".to_owned(),
+            );
+        }
+
+        for origin in &self.cors.allowed_origins {
+            match OriginPattern::parse(origin) {
+                Ok(OriginPattern::Any) if self.environment == Environment::Production => errors.push(
+                    "cors.allowed_origins (CORS_ALLOWED_ORIGINS) must list origins explicitly in production, not '*'"
+                        .to_owned(),
+                ),
+                Ok(_) => {}
+                Err(e) => errors.push(format!("cors.allowed_origins (CORS_ALLOWED_ORIGINS): {e}")),
+            }
+        }
+
+        validate_http_url("github.api_base_url (GITHUB_API_BASE_URL)", &self.github.api_base_url, &mut errors);
+
+        let rate_limits = &self.rate_limits;
+        if rate_limits.enabled
+            && [rate_limits.read_per_minute, rate_limits.write_per_minute, rate_limits.auth_per_minute].contains(&0)
+        {
+            errors.push("rate_limits: per minute budgets must be positive when rate limiting is enabled".to_owned());
+        }
+
+        errors.extend(self.notifications.validation_errors());
+        errors
+    }
+
+    /// Copy of the configuration safe to expose to admins: passwords and
+    /// secrets are replaced, including the password of the database URL.
+    pub fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        if let Ok(mut url) = url::Url::parse(&config.database.url) {
+            if url.password().is_some() && url.set_password(Some(REDACTED)).is_ok() {
+                config.database.url = url.to_string();
+            }
+        }
+        for secret in [
+            &mut config.metrics.bearer_token,
+            &mut config.notifications.smtp_password,
+            &mut config.notifications.verification_secret,
+        ] {
+            if !secret.is_empty() {
+                *secret = REDACTED.to_owned();
+            }
+        }
+        config
+    }
+}
@@ -1,18 +1,32 @@
 use diesel::r2d2::PoolError;
-use diesel::result::Error as DieselError;
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
 use thiserror::Error;
-use tokio::time::error::Elapsed;
 
 #[derive(Error, Debug)]
 pub enum DBError {
     #[error("error getting connection from DB pool: {0}")]
     DBPoolConnection(PoolError),
     #[error("error executing DB query: {0}")]
-    DBQuery(#[from] DieselError),
+    DBQuery(DieselError),
     #[error("error reading file: {0}")]
     ReadFile(#[from] std::io::Error),
     #[error("database operation timed out: {0}")]
-    DBTimeout(#[from] Elapsed),
+    DBTimeout(DieselError),
+    #[error("error running DB migrations: {0}")]
+    DBMigration(String),
+}
+
+impl From<DieselError> for DBError {
+    fn from(e: DieselError) -> Self {
+        DBError::DBQuery(e)
+    }
+}
+
+impl DBError {
+    /// Whether a unique constraint or index refused the write.
+    pub fn is_unique_violation(&self) -> bool {
+        matches!(self, DBError::DBQuery(DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _)))
+    }
 }
 
 impl warp::reject::Reject for DBError {}
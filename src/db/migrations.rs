@@ -0,0 +1,80 @@
+use std::{collections::HashSet, time::Instant};
+
+use diesel::{migration::MigrationSource, pg::Pg, sql_query, RunQueryDsl};
+use diesel_migrations::MigrationHarness;
+use log::{info, warn};
+
+use crate::config::DatabaseConfig;
+
+use super::{errors::DBError, pool::DBAccess, types::DBConn, MIGRATIONS};
+
+/// Key of the session advisory lock held while migrations are checked and
+/// applied, so replicas starting together take turns.
+const MIGRATION_LOCK_KEY: i64 = 0x6b75_646f_735f_6d69;
+
+/// Checks the database schema against the embedded migrations and, when
+/// `database.migrate_on_startup` is set, runs the pending ones. Fails when
+/// the database has migrations this binary does not know about, i.e. it was
+/// migrated by a newer release. Returns the names of the migrations that ran.
+pub fn run_migrations(db: &DBAccess, config: &DatabaseConfig) -> Result<Vec<String>, DBError> {
+    db.with_conn(|conn| {
+        // waiting for the lock and migrating may take longer than queries are
+        // allowed to, the pooled connection gets its timeout back afterwards
+        sql_query("SET statement_timeout = 0").execute(conn)?;
+        sql_query(format!("SELECT pg_advisory_lock({MIGRATION_LOCK_KEY})")).execute(conn)?;
+        let result = check_and_apply(conn, config.migrate_on_startup);
+        sql_query(format!("SELECT pg_advisory_unlock({MIGRATION_LOCK_KEY})")).execute(conn)?;
+        sql_query(format!(
+            "SET statement_timeout = {}",
+            config.statement_timeout_seconds * 1000
+        ))
+        .execute(conn)?;
+        result
+    })
+}
+
+fn check_and_apply(conn: &mut DBConn, apply: bool) -> Result<Vec<String>, DBError> {
+    let migration_error = |e: Box<dyn std::error::Error + Send + Sync>| DBError::DBMigration(e.to_string());
+
+    let embedded = MigrationSource::<Pg>::migrations(&MIGRATIONS).map_err(migration_error)?;
+    let known: HashSet<String> = embedded
+        .iter()
+        .map(|migration| migration.name().version().to_string())
+        .collect();
+    let mut unknown: Vec<String> = conn
+        .applied_migrations()
+        .map_err(migration_error)?
+        .iter()
+        .map(ToString::to_string)
+        .filter(|version| !known.contains(version))
+        .collect();
+    if !unknown.is_empty() {
+        unknown.sort();
+        return Err(DBError::DBMigration(format!(
+            "the database schema is newer than this binary, unknown migrations: {}",
+            unknown.join(", ")
+        )));
+    }
+
+    let pending = conn.pending_migrations(MIGRATIONS).map_err(migration_error)?;
+    if pending.is_empty() {
+        info!("database schema is up to date");
+        return Ok(Vec::new());
+    }
+    let names: Vec<String> = pending.iter().map(|migration| migration.name().to_string()).collect();
+    if !apply {
+        warn!(
+            "{} pending migrations not applied, set database.migrate_on_startup to run them: {}",
+            names.len(),
+            names.join(", ")
+        );
+        return Ok(Vec::new());
+    }
+
+    for migration in &pending {
+        let started = Instant::now();
+        conn.run_migration(migration.as_ref()).map_err(migration_error)?;
+        info!("applied migration {} in {}ms", migration.name(), started.elapsed().as_millis());
+    }
+    Ok(names)
+}
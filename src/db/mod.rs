@@ -1,3 +1,9 @@
+use diesel_migrations::{embed_migrations, EmbeddedMigrations};
+
 pub mod errors;
+pub mod migrations;
 pub mod pool;
 pub mod types;
+
+/// The migrations of the `migrations` directory, built into the binary.
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!();
@@ -1,41 +1,119 @@
 use diesel::prelude::*;
-use diesel::r2d2::{self, ConnectionManager, PoolError};
+use diesel::r2d2::{self, ConnectionManager, CustomizeConnection, PoolError};
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use std::panic::Location;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tokio::runtime::{Handle, RuntimeFlavor};
 
+use crate::config::DatabaseConfig;
+use crate::db::errors::DBError;
 use crate::db::types::{DBConn, DBPool};
+use crate::metrics::metrics;
 
-const DB_POOL_MAX_OPEN: u32 = 32; // TODO: move to config
-const DB_POOL_MIN_IDLE: u32 = 8; // TODO: move to config
-const DB_POOL_TIMEOUT_SECONDS: u64 = 15; // TODO: move to config
+/// Sets a server side `statement_timeout` on every new connection so a slow
+/// query is cancelled instead of holding its connection indefinitely.
+#[derive(Debug)]
+struct StatementTimeout(Duration);
 
-pub fn create_db_pool(database_url: &str) -> Result<DBPool, PoolError> {
-    let manager = ConnectionManager::<PgConnection>::new(database_url);
+impl CustomizeConnection<PgConnection, r2d2::Error> for StatementTimeout {
+    fn on_acquire(&self, conn: &mut PgConnection) -> Result<(), r2d2::Error> {
+        diesel::sql_query(format!("SET statement_timeout = {}", self.0.as_millis()))
+            .execute(conn)
+            .map(|_| ())
+            .map_err(r2d2::Error::QueryError)
+    }
+}
+
+pub fn create_db_pool(config: &DatabaseConfig) -> Result<DBPool, PoolError> {
+    let manager = ConnectionManager::<PgConnection>::new(&config.url);
     r2d2::Pool::builder()
-        .max_size(DB_POOL_MAX_OPEN)
-        .min_idle(Some(DB_POOL_MIN_IDLE))
-        .connection_timeout(Duration::from_secs(DB_POOL_TIMEOUT_SECONDS))
+        .max_size(config.pool_max_open)
+        .min_idle(Some(config.pool_min_idle))
+        .connection_timeout(Duration::from_secs(config.pool_timeout_seconds))
+        .connection_customizer(Box::new(StatementTimeout(Duration::from_secs(
+            config.statement_timeout_seconds,
+        ))))
         .build(manager)
 }
 
 pub trait DBAccessor: Send + Sync + Clone + 'static {
     fn new(db_pool: DBPool) -> Self;
-    fn get_db_conn(&self) -> DBConn;
+    fn get_db_conn(&self) -> Result<DBConn, DBError>;
 }
 
 #[derive(Clone)]
 pub struct DBAccess {
     pub db_pool: Arc<DBPool>,
+    /// The `statement_timeout` of the pool connections, if known.
+    pub statement_timeout: Option<Duration>,
 }
 
 impl DBAccessor for DBAccess {
     fn new(db_pool: DBPool) -> Self {
         Self {
             db_pool: Arc::new(db_pool),
+            statement_timeout: None,
         }
     }
 
-    fn get_db_conn(&self) -> DBConn {
-        self.db_pool.get().expect("Failed to get db connection")
+    fn get_db_conn(&self) -> Result<DBConn, DBError> {
+        let started = Instant::now();
+        let conn = self.db_pool.get();
+        metrics().db_pool_wait_duration.observe(started.elapsed().as_secs_f64());
+        if conn.is_err() {
+            metrics().db_pool_timeouts.inc();
+        }
+        conn.map_err(DBError::DBPoolConnection)
+    }
+}
+
+impl DBAccess {
+    /// Connects with the pool `create_db_pool` builds for `config`.
+    pub fn from_config(config: &DatabaseConfig) -> Result<Self, PoolError> {
+        let timeout = Duration::from_secs(config.statement_timeout_seconds);
+        Ok(Self {
+            db_pool: Arc::new(create_db_pool(config)?),
+            statement_timeout: (!timeout.is_zero()).then_some(timeout),
+        })
+    }
+
+    /// Diesel only exposes the SQLSTATE of a few errors, not 57014
+    /// (`query_canceled`), and the message depends on the server language.
+    /// An unclassified error once the `statement_timeout` has elapsed is taken
+    /// as the cancellation instead.
+    fn timed_out(&self, error: DBError, elapsed: Duration) -> DBError {
+        match (error, self.statement_timeout) {
+            (DBError::DBQuery(e @ DieselError::DatabaseError(DatabaseErrorKind::Unknown, _)), Some(timeout))
+                if elapsed >= timeout =>
+            {
+                DBError::DBTimeout(e)
+            }
+            (error, _) => error,
+        }
+    }
+
+    /// Runs `f` with a pooled connection. Waiting for the pool and running the
+    /// query are blocking, so on the multi-threaded runtime the worker thread
+    /// first hands its other tasks off to the rest of the runtime.
+    #[track_caller]
+    pub fn with_conn<T>(
+        &self,
+        f: impl FnOnce(&mut DBConn) -> Result<T, DBError>,
+    ) -> Result<T, DBError> {
+        let span = tracing::debug_span!("db", caller = %Location::caller());
+        let run = || {
+            span.in_scope(|| {
+                let mut conn = self.get_db_conn()?;
+                let started = Instant::now();
+                f(&mut conn).map_err(|e| self.timed_out(e, started.elapsed()))
+            })
+        };
+        match Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(run)
+            }
+            _ => run(),
+        }
     }
 }
@@ -1,2 +1,3 @@
 pub mod model;
-pub mod notifications;
\ No newline at end of file
+pub mod notifications;
+pub mod verification;
@@ -27,7 +27,28 @@ pub struct EmailNotifier {
 pub struct NotificationData {
     pub github_id: Option<i64>,
     pub email: Option<String>,
-    pub title: String,
+    pub task_title: Option<String>,
     pub task_url: Option<String>,
+    pub issue_title: Option<String>,
+    pub issue_number: Option<i32>,
+    pub repository_url: Option<String>,
     pub created_at: DateTime<Utc>,
+}
+
+impl NotificationData {
+    /// Title and link of the task or imported issue the notification is about.
+    pub fn subject(&self) -> (String, Option<String>) {
+        match (&self.task_title, &self.issue_title) {
+            (Some(title), _) => (title.clone(), self.task_url.clone()),
+            (None, Some(title)) => {
+                let url = self
+                    .repository_url
+                    .as_ref()
+                    .zip(self.issue_number)
+                    .map(|(url, number)| format!("{}/issues/{number}", url.trim_end_matches('/')));
+                (title.clone(), url)
+            }
+            (None, None) => (String::new(), None),
+        }
+    }
 }
\ No newline at end of file
@@ -5,10 +5,50 @@ use lettre::{
     transport::smtp::AsyncSmtpTransport,
     AsyncTransport, Tokio1Executor,
 };
-use log::{error, info};
+use log::{error, info, warn};
+use tokio_util::sync::CancellationToken;
 
-use crate::{db::pool::{DBAccess, DBAccessor}, email::model::NotificationData, schema::{notification_schedule, notifications, projects, repositories, tasks, users}};
+use crate::{
+    db::{errors::DBError, pool::DBAccess, types::DBConn},
+    email::model::NotificationData,
+    schema::{issues, notifications, repositories, tasks, users},
+};
 use crate::email::model::{EmailNotifier, SMTPConfig};
+use crate::metrics::metrics;
+
+type DigestEntry = (String, Option<String>, DateTime<Utc>);
+
+/// Unread notifications about tasks and imported issues created since
+/// `since`, for the verified users or only the one with `github_id`.
+pub fn load_digest(
+    conn: &mut DBConn,
+    since: DateTime<Utc>,
+    github_id: Option<i64>,
+) -> Result<Vec<NotificationData>, DBError> {
+    let mut query = notifications::table
+        .inner_join(users::table.on(notifications::github_id.nullable().eq(users::github_id.nullable())))
+        .left_join(tasks::table.on(notifications::task_id.eq(tasks::id.nullable())))
+        .left_join(issues::table.on(notifications::issue_id.eq(issues::id.nullable())))
+        .left_join(repositories::table.on(issues::repository_id.eq(repositories::id)))
+        .filter(notifications::created_at.gt(since))
+        .filter(notifications::seen.eq(false))
+        .filter(users::email_verified_at.is_not_null())
+        .select((
+            users::github_id,
+            users::email,
+            tasks::title.nullable(),
+            tasks::url.nullable(),
+            issues::title.nullable(),
+            issues::number.nullable(),
+            repositories::url.nullable(),
+            notifications::created_at,
+        ))
+        .into_boxed();
+    if let Some(github_id) = github_id {
+        query = query.filter(users::github_id.eq(github_id));
+    }
+    Ok(query.load::<NotificationData>(conn)?)
+}
 
 impl EmailNotifier {
     pub fn new(config: SMTPConfig, db: DBAccess) -> Self {
@@ -24,47 +64,47 @@ impl EmailNotifier {
         Self { config, mailer, db }
     }
 
-    pub async fn send_notifications(&self, dry_run: bool, days: i64, subject: String) -> Result<(), Box<dyn std::error::Error>> {
-        let conn = &mut self.db.get_db_conn();
+    /// Emails every verified user, or only the one with `github_id`, a digest
+    /// of their unread notifications. Once `shutdown` is cancelled no further
+    /// emails are sent and the run fails, so it is retried rather than skipped
+    /// until the next schedule.
+    pub async fn send_notifications(
+        &self,
+        dry_run: bool,
+        days: i64,
+        subject: String,
+        github_id: Option<i64>,
+        shutdown: &CancellationToken,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         let one_week_ago = Utc::now() - Duration::days(days);
         info!("Getting notifications from the last {} days", days);
         // Get all users with their unread notifications from the last week
-        let notifications = notifications::table
-            .inner_join(
-                users::table.on(notifications::github_id.nullable().eq(users::github_id.nullable()))
-            )
-            .inner_join(tasks::table.on(notifications::task_id.eq(tasks::id)))
-            .left_join(repositories::table.on(tasks::repository_id.eq(repositories::id.nullable())))
-            .left_join(projects::table.on(tasks::project_id.eq(projects::id.nullable())))
-            .filter(notifications::created_at.gt(one_week_ago))
-            .filter(notifications::seen.eq(false))
-            .select((
-                users::github_id,
-                users::email,
-                tasks::title,
-                tasks::url,
-                notifications::created_at,
-            ))
-            .load::<NotificationData>(conn)?;
+        let notifications = self.db.with_conn(|conn| load_digest(conn, one_week_ago, github_id))?;
 
         // Group notifications by user
-        let mut user_notifications: std::collections::HashMap<i64, Vec<(String, Option<String>, DateTime<Utc>)>> =
+        let mut user_notifications: std::collections::HashMap<i64, Vec<DigestEntry>> =
             std::collections::HashMap::new();
         let mut user_emails: std::collections::HashMap<i64, String> = std::collections::HashMap::new();
 
         for notification in notifications {
+            let (title, url) = notification.subject();
             if let (Some(github_id), Some(email)) = (notification.github_id, notification.email) {
                 info!("Adding notification for user {} with email {}", github_id, email);
                 user_notifications
                     .entry(github_id)
                     .or_default()
-                    .push((notification.title, notification.task_url, notification.created_at));
+                    .push((title, url, notification.created_at));
                 user_emails.insert(github_id, email);
             }
         }
 
         // Send email to each user
-        for (github_id, notifications) in user_notifications {
+        let recipients = user_notifications.len();
+        for (sent, (github_id, notifications)) in user_notifications.into_iter().enumerate() {
+            if shutdown.is_cancelled() {
+                warn!("digest interrupted by shutdown after {} of {} users", sent, recipients);
+                return Err("interrupted by shutdown".into());
+            }
             if let Some(user_email) = user_emails.get(&github_id) {
                 info!("Sending email to {}", user_email);
                 
@@ -138,8 +178,14 @@ impl EmailNotifier {
                     continue;
                 }
                 match self.mailer.send(email).await {
-                    Ok(_) => info!("Successfully sent weekly notification email to {}", user_email),
-                    Err(e) => error!("Failed to send email to {}: {}", user_email, e),
+                    Ok(_) => {
+                        metrics().notification_emails.with_label_values(&["digest", "sent"]).inc();
+                        info!("Successfully sent weekly notification email to {}", user_email)
+                    }
+                    Err(e) => {
+                        metrics().notification_emails.with_label_values(&["digest", "failed"]).inc();
+                        error!("Failed to send email to {}: {}", user_email, e)
+                    }
                 }
             }
         }
@@ -147,57 +193,3 @@ impl EmailNotifier {
         Ok(())
     }
 }
-
-pub async fn start_notification_job(config: SMTPConfig, db: DBAccess, days: i64, subject: String, dry_run: bool) {
-    let notifier = EmailNotifier::new(config, db.clone());
-    
-    loop {
-        let conn = &mut db.get_db_conn();
-        let now = Utc::now();
-
-        // Get the next run time from the database
-        let schedule = notification_schedule::table
-            .order_by(notification_schedule::id.desc())
-            .first::<(i32, DateTime<Utc>, Option<DateTime<Utc>>, DateTime<Utc>, DateTime<Utc>)>(conn)
-            .expect("Failed to get notification schedule");
-
-        let next_run = schedule.1;
-        let last_run = schedule.2;
-        info!("Next run: {}", next_run);
-        let mut sleep_duration = next_run.signed_duration_since(now);
-        // If next_run is in the past, immediately schedule the next run
-        if now >= next_run && last_run.is_none() {
-            info!("Sending notifications of {:?}", next_run);
-            if let Err(e) = notifier.send_notifications(dry_run, days, subject.clone()).await {
-                error!("Failed to send notifications: {}", e);
-            }
-
-            // Calculate and save next run time
-            let next_run = now + Duration::days(days);
-            info!("Updating notification schedule to run at {}", next_run);
-            sleep_duration = next_run.signed_duration_since(now);
-
-            // Update the existing record instead of inserting a new one
-            diesel::update(notification_schedule::table)
-                .filter(notification_schedule::id.eq(schedule.0))
-                .set((
-                    notification_schedule::updated_at.eq(&now),
-                    notification_schedule::last_run.eq(&now),
-                ))
-                .execute(conn)
-                .expect("Failed to update notification schedule");
-            
-            // Insert a new record for the next run
-            diesel::insert_into(notification_schedule::table)
-                .values((
-                    notification_schedule::next_run.eq(&next_run),
-                    notification_schedule::created_at.eq(&now),
-                ))
-                .execute(conn)
-                .expect("Failed to create notification schedule");
-        }
-
-        info!("Sleeping for {} seconds", sleep_duration.num_seconds());
-        tokio::time::sleep(sleep_duration.to_std().unwrap()).await;
-    }
-} 
\ No newline at end of file
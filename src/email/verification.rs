@@ -0,0 +1,155 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{Duration, Utc};
+use hmac::{Hmac, Mac};
+use lettre::{
+    message::{header::ContentType, Message},
+    transport::smtp::AsyncSmtpTransport,
+    Address, AsyncTransport, Tokio1Executor,
+};
+use log::{error, info};
+use sha2::Sha256;
+
+use crate::email::model::SMTPConfig;
+use crate::metrics::metrics;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Claims carried by an email verification token.
+#[derive(Debug, PartialEq)]
+pub struct VerificationClaims {
+    pub github_id: i64,
+    pub email: String,
+    pub expires_at: i64,
+}
+
+/// Signs `github_id:email:expires_at` with HMAC-SHA256 and returns
+/// `<payload>.<signature>`, both base64url encoded.
+pub fn sign_token(secret: &[u8], claims: &VerificationClaims) -> String {
+    let payload = format!("{}:{}:{}", claims.github_id, claims.email, claims.expires_at);
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(payload.as_bytes());
+    let signature = mac.finalize().into_bytes();
+    format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(payload.as_bytes()),
+        URL_SAFE_NO_PAD.encode(signature)
+    )
+}
+
+/// Checks the signature and expiry of a token produced by [`sign_token`].
+pub fn verify_token(secret: &[u8], token: &str, now: i64) -> Option<VerificationClaims> {
+    let (payload, signature) = token.split_once('.')?;
+    let payload = URL_SAFE_NO_PAD.decode(payload).ok()?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+
+    let mut mac = HmacSha256::new_from_slice(secret).ok()?;
+    mac.update(&payload);
+    mac.verify_slice(&signature).ok()?;
+
+    // The email may itself contain ':' so split from both ends.
+    let payload = String::from_utf8(payload).ok()?;
+    let (github_id, rest) = payload.split_once(':')?;
+    let (email, expires_at) = rest.rsplit_once(':')?;
+    let claims = VerificationClaims {
+        github_id: github_id.parse().ok()?,
+        email: email.to_owned(),
+        expires_at: expires_at.parse().ok()?,
+    };
+    if claims.expires_at < now {
+        return None;
+    }
+    Some(claims)
+}
+
+#[derive(Clone)]
+pub struct EmailVerifier {
+    secret: Vec<u8>,
+    verify_url: String,
+    ttl: Duration,
+    from_email: String,
+    dry_run: bool,
+    mailer: Option<AsyncSmtpTransport<Tokio1Executor>>,
+}
+
+impl EmailVerifier {
+    pub fn new(
+        secret: String,
+        verify_url: String,
+        ttl_hours: i64,
+        config: SMTPConfig,
+        dry_run: bool,
+    ) -> Self {
+        let mailer = if config.smtp_host.is_empty() {
+            None
+        } else {
+            Some(
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host)
+                    .expect("Failed to create SMTP transport")
+                    .port(config.smtp_port)
+                    .credentials(lettre::transport::smtp::authentication::Credentials::new(
+                        config.smtp_username,
+                        config.smtp_password,
+                    ))
+                    .build(),
+            )
+        };
+        Self {
+            secret: secret.into_bytes(),
+            verify_url,
+            ttl: Duration::hours(ttl_hours),
+            from_email: config.from_email,
+            dry_run,
+            mailer,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.secret.is_empty() && (self.dry_run || self.mailer.is_some())
+    }
+
+    pub fn verify(&self, token: &str) -> Option<VerificationClaims> {
+        if self.secret.is_empty() {
+            return None;
+        }
+        verify_token(&self.secret, token, Utc::now().timestamp())
+    }
+
+    pub async fn send(&self, github_id: i64, email: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let claims = VerificationClaims {
+            github_id,
+            email: email.to_owned(),
+            expires_at: (Utc::now() + self.ttl).timestamp(),
+        };
+        let link = format!("{}?token={}", self.verify_url, sign_token(&self.secret, &claims));
+
+        if self.dry_run {
+            info!("Dry run: Would have sent verification link to {}: {}", email, link);
+            return Ok(());
+        }
+        let mailer = self.mailer.as_ref().ok_or("SMTP is not configured")?;
+
+        let message = Message::builder()
+            .from(self.from_email.parse()?)
+            .to(email.parse::<Address>()?.into())
+            .subject("Verify your email address")
+            .header(ContentType::TEXT_PLAIN)
+            .body(format!(
+                "Confirm this address to receive Kudos notifications by opening the link below:\n\n{}\n\nThe link expires in {} hours.\n",
+                link,
+                self.ttl.num_hours()
+            ))?;
+
+        match mailer.send(message).await {
+            Ok(_) => {
+                metrics().notification_emails.with_label_values(&["verification", "sent"]).inc();
+                info!("Sent verification email to {}", email);
+                Ok(())
+            }
+            Err(e) => {
+                metrics().notification_emails.with_label_values(&["verification", "failed"]).inc();
+                error!("Failed to send verification email to {}: {}", email, e);
+                Err(Box::new(e))
+            }
+        }
+    }
+}
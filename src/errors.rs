@@ -1,26 +1,46 @@
 use serde::Serialize;
 use serde_derive::Deserialize;
+use log::{error, warn};
 use std::convert::Infallible;
 use warp::{hyper::StatusCode, Rejection, Reply};
 
 use crate::{
     api::{
+        admin::errors::CatalogError,
+        archival::errors::ArchivalError,
         issues::errors::IssueError, 
         projects::errors::ProjectError, 
         repositories::errors::RepositoryError, 
         roles::errors::RoleError, 
         tasks::errors::TaskError, 
+        taxonomy::errors::TaxonomyError,
         users::errors::UserError,
         subscriptions::errors::UserSubscriptionError,
         notifications::errors::NotificationError,
+        webhooks::errors::WebhookError,
     },
     db::errors::DBError,
-    middlewares::errors::AuthenticationError,
+    middlewares::{
+        context::current_request_id,
+        errors::{AuthenticationError, CorsError, RateLimitError},
+    },
 };
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 pub struct ErrorResponse {
     pub message: String,
+    /// The `X-Request-Id` of the failed request, to correlate with the logs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl ErrorResponse {
+    pub fn new(message: String) -> Self {
+        Self {
+            message,
+            request_id: current_request_id(),
+        }
+    }
 }
 
 pub async fn error_handler(err: Rejection) -> std::result::Result<impl Reply, Infallible> {
@@ -36,26 +56,38 @@ pub async fn error_handler(err: Rejection) -> std::result::Result<impl Reply, In
         return Ok(e.clone().into_response());
     } else if let Some(e) = err.find::<TaskError>() {
         return Ok(e.clone().into_response());
+    } else if let Some(e) = err.find::<TaxonomyError>() {
+        return Ok(e.clone().into_response());
+    } else if let Some(e) = err.find::<ArchivalError>() {
+        return Ok(e.clone().into_response());
     } else if let Some(e) = err.find::<UserSubscriptionError>() {
         return Ok(e.clone().into_response());
     } else if let Some(e) = err.find::<NotificationError>() {
         return Ok(e.clone().into_response());
+    } else if let Some(e) = err.find::<WebhookError>() {
+        return Ok(e.clone().into_response());
+    } else if let Some(e) = err.find::<CatalogError>() {
+        return Ok(e.clone().into_response());
+    } else if let Some(e) = err.find::<CorsError>() {
+        return Ok(e.clone().into_response());
+    } else if let Some(e) = err.find::<RateLimitError>() {
+        return Ok(e.clone().into_response());
     }
     // TODO: add more errors
 
     let (status, message) = if err.is_not_found() {
         (StatusCode::NOT_FOUND, "Resource not found".to_string())
     } else if let Some(e) = err.find::<warp::filters::body::BodyDeserializeError>() {
-        eprintln!("BodyDeserializeError error: {:?}", e);
+        warn!("invalid request body: {e}");
         (StatusCode::BAD_REQUEST, "Invalid request body".to_string())
     } else if let Some(e) = err.find::<warp::reject::InvalidQuery>() {
-        eprintln!("InvalidQuery error: {:?}", e);
+        warn!("invalid query parameters: {e}");
         (
             StatusCode::BAD_REQUEST,
             "Invalid query parameters".to_string(),
         )
     } else if let Some(e) = err.find::<AuthenticationError>() {
-        eprintln!("AuthenticationError: {e}");
+        warn!("authentication failed: {e}");
         (
             StatusCode::UNAUTHORIZED,
             format!("AuthenticationError - {e}"),
@@ -63,7 +95,7 @@ pub async fn error_handler(err: Rejection) -> std::result::Result<impl Reply, In
     } else if let Some(db_error) = err.find::<DBError>() {
         match db_error {
             DBError::DBPoolConnection(_) => (
-                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::SERVICE_UNAVAILABLE,
                 "Database connection error".to_string(),
             ),
             DBError::DBQuery(_) => (StatusCode::BAD_REQUEST, "Database query failed".to_string()),
@@ -75,16 +107,20 @@ pub async fn error_handler(err: Rejection) -> std::result::Result<impl Reply, In
                 StatusCode::REQUEST_TIMEOUT,
                 "Database operation timed out".to_string(),
             ),
+            DBError::DBMigration(_) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database migration error".to_string(),
+            ),
         }
     } else {
-        eprintln!("Unhandled error: {:?}", err); // Ensure all unexpected errors are logged.
+        error!("unhandled rejection: {err:?}");
         (
             StatusCode::INTERNAL_SERVER_ERROR,
             "Internal server error".to_string(),
         )
     };
 
-    let json = warp::reply::json(&ErrorResponse { message });
+    let json = warp::reply::json(&ErrorResponse::new(message));
     let response = warp::reply::with_status(json, status).into_response();
     Ok(response)
 }
@@ -0,0 +1,14 @@
+pub mod admin;
+pub mod api;
+pub mod config;
+pub mod db;
+pub mod email;
+pub mod errors;
+pub mod metrics;
+pub mod middlewares;
+pub mod schema;
+pub mod types;
+pub mod utils;
+
+#[cfg(test)]
+mod tests;
@@ -1,18 +1,8 @@
-mod types;
-use log::{info, error};
+use std::{sync::Arc, time::{Duration, Instant}};
 
-use crate::types::{ApiConfig, NotificationsConfig};
-
-mod api;
-mod middlewares;
-mod db;
-mod errors;
-pub mod schema;
-mod utils;
-mod email;
-
-#[cfg(test)]
-mod tests;
+use kudos_api::{api, config, db, email, middlewares, utils};
+use log::{info, error, warn};
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
 
 #[tokio::main]
 async fn main() {
@@ -20,26 +10,85 @@ async fn main() {
 }
 
 async fn run() {
-    let ApiConfig {
-        http_server_host: host,
-        http_server_port: port,
-        database_url,
-    } = ApiConfig::new();
-
-    let notifications_config = NotificationsConfig::new();
-    if let Err(e) = notifications_config.validate() {
-        error!("Invalid notifications configuration: {}", e);
+    let config = match config::Config::load() {
+        Ok(config) => Arc::new(config),
+        Err(e) => {
+            utils::init_logging(&config::LoggingConfig::default());
+            error!("{e}");
+            std::process::exit(1);
+        }
+    };
+    utils::init_logging(&config.logging);
+    info!("running in {:?} mode", config.environment);
+    middlewares::github::auth::set_api_base_url(&config.github.api_base_url);
+    let notifications_config = config.notifications.clone();
+
+    let db = utils::setup_db(&config.database).await;
+    if let Err(e) = db::migrations::run_migrations(&db, &config.database) {
+        error!("{e}");
         std::process::exit(1);
     }
 
-    env_logger::init();
+    if notifications_config.verification_secret.is_empty() {
+        warn!("NOTIFICATIONS_VERIFICATION_SECRET is not set, email verification is disabled");
+    }
+    let verifier = email::verification::EmailVerifier::new(
+        notifications_config.verification_secret.clone(),
+        notifications_config.verification_url.clone(),
+        notifications_config.verification_ttl_hours,
+        email::model::SMTPConfig {
+            smtp_host: notifications_config.smtp_host.clone(),
+            smtp_port: notifications_config.smtp_port,
+            smtp_username: notifications_config.smtp_username.clone(),
+            smtp_password: notifications_config.smtp_password.clone(),
+            from_email: notifications_config.from_email.clone(),
+        },
+        notifications_config.dry_run,
+    );
+
+    let shutdown = CancellationToken::new();
+    let background = TaskTracker::new();
+
+    let broadcaster = api::notifications::stream::NotificationBroadcaster::new();
+    background.spawn(api::notifications::stream::start_notification_listener(
+        config.database.url.clone(),
+        broadcaster.clone(),
+    ));
+
+    background.spawn(api::webhooks::worker::start_webhook_worker(db.clone(), shutdown.clone()));
+
+    let mut job_runner = api::jobs::runner::JobRunner::new(db.clone());
+
+    if notifications_config.retention_days > 0 {
+        info!(
+            "Purging read notifications after {} days",
+            notifications_config.retention_days
+        );
+        let retention_db = db.clone();
+        let retention_days = notifications_config.retention_days;
+        job_runner = job_runner.register(
+            "notification_retention",
+            "@hourly",
+            Duration::from_secs(10 * 60),
+            move |_| api::notifications::retention::purge_read_notifications(retention_db.clone(), retention_days),
+        );
+    }
 
-    let db = utils::setup_db(&database_url).await;
-    let app_filters = utils::setup_filters(db.clone());
+    let deleted_retention_days = config.database.deleted_retention_days;
+    if deleted_retention_days > 0 {
+        info!("Purging deleted rows after {} days", deleted_retention_days);
+        let retention_db = db.clone();
+        job_runner = job_runner.register(
+            "deleted_retention",
+            "@daily",
+            Duration::from_secs(10 * 60),
+            move |_| api::archival::retention::purge_deleted(retention_db.clone(), deleted_retention_days),
+        );
+    }
 
     if notifications_config.enabled {
-        info!("Starting notification job");
-        // Start the weekly notification job
+        let schedule = notifications_config.digest_schedule();
+        info!("Scheduling notification digest '{}'", schedule);
         let sender_config = email::model::SMTPConfig {
             smtp_host: notifications_config.smtp_host,
             smtp_port: notifications_config.smtp_port,
@@ -47,22 +96,72 @@ async fn run() {
             smtp_password: notifications_config.smtp_password,
             from_email: notifications_config.from_email,
         };
-
-        // Spawn the notification job in a separate task
-        tokio::spawn(email::notifications::start_notification_job(
-            sender_config,
-            db,
-            notifications_config.days,
-            notifications_config.subject,
-            notifications_config.dry_run,
-        ));
+        let notifier = Arc::new(email::model::EmailNotifier::new(sender_config, db.clone()));
+        let days = notifications_config.days;
+        let subject = notifications_config.subject;
+        let dry_run = notifications_config.dry_run;
+        job_runner = job_runner.register(
+            "email_digest",
+            &schedule,
+            Duration::from_secs(30 * 60),
+            move |shutdown| {
+                let notifier = notifier.clone();
+                let subject = subject.clone();
+                async move {
+                    notifier
+                        .send_notifications(dry_run, days, subject, None, &shutdown)
+                        .await
+                        .map_err(|e| e.to_string())
+                }
+            },
+        );
     }
 
-    let addr = format!("{}:{}", host, port)
+    let heartbeat = job_runner.heartbeat();
+    background.spawn(job_runner.start(shutdown.clone()));
+    background.close();
+
+    let app_filters = utils::setup_filters(db.clone(), config.clone(), verifier, broadcaster.clone(), heartbeat);
+
+    let addr = format!("{}:{}", config.server.host, config.server.port)
         .parse::<std::net::SocketAddr>()
         .expect("Invalid server address");
 
     info!("listening on {}", addr);
 
-    warp::serve(app_filters).run(addr).await;
+    let mut server = tokio::spawn(utils::serve(app_filters, addr, shutdown.clone()));
+    tokio::select! {
+        result = &mut server => {
+            match result {
+                Ok(Err(e)) => error!("server error: {e}"),
+                Ok(Ok(())) => error!("server stopped unexpectedly"),
+                Err(e) => error!("server task failed: {e}"),
+            }
+            std::process::exit(1);
+        }
+        _ = utils::shutdown_signal() => {}
+    }
+
+    // stop accepting connections, close the notification streams and let the
+    // workers finish what they are doing, all within the drain timeout
+    let started = Instant::now();
+    let in_flight = middlewares::context::in_flight_requests();
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(config.server.shutdown_timeout_seconds);
+    shutdown.cancel();
+    broadcaster.close();
+
+    let requests_drained = tokio::time::timeout_at(deadline, server).await.is_ok();
+    let jobs_drained = tokio::time::timeout_at(deadline, background.wait()).await.is_ok();
+    let outcome = |drained: bool| if drained { "drained" } else { "cut off" };
+    info!(
+        "shutdown complete in {}ms: {} in-flight requests {}, {} left unfinished; background jobs {}",
+        started.elapsed().as_millis(),
+        in_flight,
+        outcome(requests_drained),
+        middlewares::context::in_flight_requests(),
+        outcome(jobs_drained),
+    );
+    if !(requests_drained && jobs_drained) {
+        std::process::exit(1);
+    }
 }
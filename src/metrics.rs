@@ -0,0 +1,227 @@
+use std::sync::OnceLock;
+
+use diesel::r2d2::State;
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry,
+    TextEncoder,
+};
+use warp::{http::StatusCode, log::Info};
+
+use crate::api::archival::models::ArchivalKind;
+
+/// Paths served by the API, with `:id` standing for integers, `:name` for
+/// free-form values and `:kind` for the tables that are archived. Requests for
+/// anything else are counted under a single `unmatched` route to bound the
+/// label values.
+pub const ROUTES: [&str; 61] = [
+    "/:kind/:id/archive",
+    "/:kind/:id/restore",
+    "/:kind/:id/unarchive",
+    "/:kind/deleted",
+    "/admin/config",
+    "/admin/export",
+    "/admin/import",
+    "/admin/jobs",
+    "/admin/jobs/runs",
+    "/health",
+    "/health/live",
+    "/health/ready",
+    "/issues",
+    "/issues/:id",
+    "/issues/:id/assignee",
+    "/issues/leaderboard",
+    "/languages",
+    "/metrics",
+    "/notifications",
+    "/notifications/:id",
+    "/notifications/:id/archive",
+    "/notifications/:id/read",
+    "/notifications/preferences",
+    "/notifications/read-all",
+    "/notifications/stream",
+    "/notifications/unread-count",
+    "/projects",
+    "/projects/:id",
+    "/projects/:id/stats",
+    "/projects/options",
+    "/projects/slug/:name",
+    "/repositories",
+    "/repositories/:id",
+    "/repositories/slug/:name",
+    "/roles",
+    "/roles/:id",
+    "/roles/assignation",
+    "/roles/assignation/:id",
+    "/subscriptions",
+    "/subscriptions/:id",
+    "/tasks",
+    "/tasks/:id",
+    "/tasks/downvotes",
+    "/tasks/upvotes",
+    "/tasks/votes/:id",
+    "/taxonomy",
+    "/taxonomy/:id",
+    "/teams",
+    "/teams/:id",
+    "/teams/:id/members",
+    "/teams/:id/members/:id",
+    "/users",
+    "/users/:id",
+    "/users/email/verify",
+    "/users/me",
+    "/users/me/email",
+    "/users/username/:name",
+    "/webhooks",
+    "/webhooks/:id",
+    "/webhooks/:id/deliveries",
+    "/webhooks/deliveries/:id/redeliver",
+];
+
+pub struct Metrics {
+    registry: Registry,
+    pub http_requests: IntCounterVec,
+    pub http_request_duration: HistogramVec,
+    pub db_pool_connections: IntGauge,
+    pub db_pool_idle_connections: IntGauge,
+    pub db_pool_max_connections: IntGauge,
+    pub db_pool_wait_duration: Histogram,
+    pub db_pool_timeouts: IntCounter,
+    pub github_auth_duration: Histogram,
+    pub github_auth_failures: IntCounterVec,
+    pub notification_emails: IntCounterVec,
+    pub job_run_duration: HistogramVec,
+}
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::new)
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("kudos".to_owned()), None).expect("valid metrics prefix");
+        let metrics = Self {
+            http_requests: IntCounterVec::new(
+                Opts::new("http_requests_total", "HTTP requests by route and status"),
+                &["method", "route", "status"],
+            )
+            .unwrap(),
+            http_request_duration: HistogramVec::new(
+                HistogramOpts::new("http_request_duration_seconds", "HTTP request latency by route and status"),
+                &["method", "route", "status"],
+            )
+            .unwrap(),
+            db_pool_connections: IntGauge::new("db_pool_connections", "Open database connections").unwrap(),
+            db_pool_idle_connections: IntGauge::new("db_pool_idle_connections", "Idle database connections")
+                .unwrap(),
+            db_pool_max_connections: IntGauge::new("db_pool_max_connections", "Maximum database connections")
+                .unwrap(),
+            db_pool_wait_duration: Histogram::with_opts(HistogramOpts::new(
+                "db_pool_wait_seconds",
+                "Time spent waiting for a database connection",
+            ))
+            .unwrap(),
+            db_pool_timeouts: IntCounter::new(
+                "db_pool_timeouts_total",
+                "Requests that got no database connection in time",
+            )
+            .unwrap(),
+            github_auth_duration: Histogram::with_opts(HistogramOpts::new(
+                "github_auth_duration_seconds",
+                "Latency of GitHub token validations",
+            ))
+            .unwrap(),
+            github_auth_failures: IntCounterVec::new(
+                Opts::new("github_auth_failures_total", "Failed GitHub token validations by reason"),
+                &["reason"],
+            )
+            .unwrap(),
+            notification_emails: IntCounterVec::new(
+                Opts::new("notification_emails_total", "Emails sent to users by kind and outcome"),
+                &["kind", "outcome"],
+            )
+            .unwrap(),
+            job_run_duration: HistogramVec::new(
+                HistogramOpts::new("job_run_duration_seconds", "Background job run durations")
+                    .buckets(vec![0.1, 0.5, 1.0, 5.0, 15.0, 60.0, 300.0, 900.0, 3600.0]),
+                &["job", "outcome"],
+            )
+            .unwrap(),
+            registry,
+        };
+
+        let collectors: Vec<Box<dyn prometheus::core::Collector>> = vec![
+            Box::new(metrics.http_requests.clone()),
+            Box::new(metrics.http_request_duration.clone()),
+            Box::new(metrics.db_pool_connections.clone()),
+            Box::new(metrics.db_pool_idle_connections.clone()),
+            Box::new(metrics.db_pool_max_connections.clone()),
+            Box::new(metrics.db_pool_wait_duration.clone()),
+            Box::new(metrics.db_pool_timeouts.clone()),
+            Box::new(metrics.github_auth_duration.clone()),
+            Box::new(metrics.github_auth_failures.clone()),
+            Box::new(metrics.notification_emails.clone()),
+            Box::new(metrics.job_run_duration.clone()),
+        ];
+        for collector in collectors {
+            metrics.registry.register(collector).expect("metrics are registered once");
+        }
+        metrics
+    }
+
+    pub fn set_pool_state(&self, state: State, max_size: u32) {
+        self.db_pool_connections.set(i64::from(state.connections));
+        self.db_pool_idle_connections.set(i64::from(state.idle_connections));
+        self.db_pool_max_connections.set(i64::from(max_size));
+    }
+
+    /// All metrics in the Prometheus text format.
+    pub fn encode(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("metrics encode to a Vec");
+        String::from_utf8(buffer).expect("metrics are valid UTF-8")
+    }
+}
+
+/// Records a served request, used with `warp::log::custom`.
+pub fn record_request(info: Info) {
+    let method = info.method().as_str();
+    // a path can look like a route and still not be served
+    let route = match info.status() {
+        StatusCode::NOT_FOUND => "unmatched",
+        _ => route_label(info.path()),
+    };
+    let status = info.status().as_u16().to_string();
+    let labels = [method, route, status.as_str()];
+
+    let metrics = metrics();
+    metrics.http_requests.with_label_values(&labels).inc();
+    metrics
+        .http_request_duration
+        .with_label_values(&labels)
+        .observe(info.elapsed().as_secs_f64());
+}
+
+/// The route of `path`, with ids and names replaced by placeholders, e.g.
+/// `/projects/:id/stats` for `/projects/12/stats`, or `unmatched`.
+pub fn route_label(path: &str) -> &'static str {
+    let segments: Vec<&str> = path.split('/').filter(|segment| !segment.is_empty()).collect();
+    ROUTES
+        .iter()
+        .find(|route| matches_route(route, &segments))
+        .map_or("unmatched", |route| route)
+}
+
+fn matches_route(route: &str, segments: &[&str]) -> bool {
+    let parts: Vec<&str> = route.split('/').skip(1).collect();
+    parts.len() == segments.len()
+        && parts.iter().zip(segments).all(|(part, segment)| match *part {
+            ":id" => segment.parse::<i32>().is_ok(),
+            ":name" => true,
+            ":kind" => segment.parse::<ArchivalKind>().is_ok(),
+            part => part == *segment,
+        })
+}
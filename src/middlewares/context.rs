@@ -0,0 +1,123 @@
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use rand::Rng;
+use tracing::{field, info, info_span, Instrument, Span};
+use warp::{
+    http::{HeaderMap, HeaderValue},
+    hyper::{service::Service, Body, Request},
+    reply::Response,
+    Filter,
+};
+
+use crate::metrics::route_label;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+/// Counts a request as in flight for as long as it is alive, including when
+/// its connection is dropped midway.
+struct InFlight;
+
+impl InFlight {
+    fn start() -> Self {
+        IN_FLIGHT.fetch_add(1, Ordering::Relaxed);
+        Self
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        IN_FLIGHT.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Number of requests being handled right now.
+pub fn in_flight_requests() -> usize {
+    IN_FLIGHT.load(Ordering::Relaxed)
+}
+
+/// Address of the client, set by the server for every request.
+#[derive(Debug, Clone, Copy)]
+pub struct RemoteAddr(pub SocketAddr);
+
+/// The id of the request being handled, if any.
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(String::clone).ok()
+}
+
+/// The `X-Request-Id` sent by the client or a proxy, or a new random one
+/// when it is missing or not a short printable token.
+pub fn request_id(headers: &HeaderMap) -> String {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LENGTH)
+        .filter(|id| id.bytes().all(|byte| byte.is_ascii_graphic()))
+        .map_or_else(generate_request_id, str::to_owned)
+}
+
+fn generate_request_id() -> String {
+    let bytes: [u8; 16] = rand::thread_rng().gen();
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// The client address, from the server or the connection in tests.
+pub fn remote_addr() -> impl Filter<Extract = (Option<SocketAddr>,), Error = Infallible> + Clone {
+    warp::ext::get::<RemoteAddr>()
+        .map(|RemoteAddr(addr)| Some(addr))
+        .or(warp::addr::remote())
+        .unify()
+}
+
+/// Records the authenticated user on the request span.
+pub fn record_user(username: &str) {
+    Span::current().record("user", username);
+}
+
+/// Serves `request` with `service` inside a span carrying the request id,
+/// method, route and user, and echoes the request id in the response.
+pub async fn handle<S>(mut service: S, remote: SocketAddr, mut request: Request<Body>) -> Result<Response, Infallible>
+where
+    S: Service<Request<Body>, Response = Response, Error = Infallible>,
+{
+    let id = request_id(request.headers());
+    let header = HeaderValue::from_str(&id).expect("request ids are printable ASCII");
+    request.headers_mut().insert(REQUEST_ID_HEADER, header.clone());
+    request.extensions_mut().insert(RemoteAddr(remote));
+
+    let span = info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        route = %route_label(request.uri().path()),
+        user = field::Empty,
+    );
+    let started = Instant::now();
+    let _in_flight = InFlight::start();
+    let mut response = REQUEST_ID
+        .scope(id, service.call(request))
+        .instrument(span.clone())
+        .await?;
+    response.headers_mut().insert(REQUEST_ID_HEADER, header);
+
+    span.in_scope(|| {
+        info!(
+            status = response.status().as_u16(),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "request completed"
+        )
+    });
+    Ok(response)
+}
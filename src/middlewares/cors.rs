@@ -0,0 +1,262 @@
+use std::{convert::Infallible, sync::Arc};
+
+use warp::{
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    reject,
+    reply::Response,
+    Filter, Rejection, Reply,
+};
+
+use crate::config::{CorsConfig, Environment};
+
+use super::errors::CorsError;
+
+const ALLOWED_METHODS: [Method; 7] = [
+    Method::GET,
+    Method::POST,
+    Method::PUT,
+    Method::PATCH,
+    Method::DELETE,
+    Method::OPTIONS,
+    Method::HEAD,
+];
+
+const ALLOWED_HEADERS: [&str; 9] = [
+    "accept",
+    "authorization",
+    "content-type",
+    "last-event-id",
+    "origin",
+    "x-requested-with",
+    "access-control-allow-headers",
+    "access-control-request-method",
+    "access-control-request-headers",
+];
+
+/// Response headers the frontend may read besides the CORS-safelisted ones.
+const EXPOSED_HEADERS: [&str; 5] = [
+    "retry-after",
+    "x-request-id",
+    "x-ratelimit-limit",
+    "x-ratelimit-remaining",
+    "x-ratelimit-reset",
+];
+
+const DEVELOPMENT_ORIGINS: [&str; 2] = ["http://localhost:3000", "http://127.0.0.1:3000"];
+const PRODUCTION_ORIGINS: [&str; 2] = ["https://morekudos.com", "https://*.morekudos.com"];
+
+/// An allowed origin: `*`, `https://example.com[:port]` or
+/// `https://*.example.com[:port]`, the latter matching any subdomain but not
+/// `example.com` itself.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OriginPattern {
+    Any,
+    Exact(Origin),
+    Subdomains(Origin),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Origin {
+    scheme: String,
+    host: String,
+    port: Option<u16>,
+}
+
+impl Origin {
+    pub fn parse(origin: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid origin '{origin}', expected scheme://host[:port]");
+        let (scheme, authority) = origin.split_once("://").ok_or_else(invalid)?;
+        let authority = authority.strip_suffix('/').unwrap_or(authority);
+        if !matches!(scheme, "http" | "https") || authority.is_empty() || authority.contains(['/', '?', '#', '@']) {
+            return Err(invalid());
+        }
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port.parse::<u16>().map_err(|_| invalid())?)),
+            None => (authority, None),
+        };
+        if host.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            scheme: scheme.to_lowercase(),
+            host: host.to_lowercase(),
+            port,
+        })
+    }
+}
+
+impl OriginPattern {
+    pub fn parse(pattern: &str) -> Result<Self, String> {
+        let pattern = pattern.trim();
+        if pattern == "*" {
+            return Ok(OriginPattern::Any);
+        }
+        match pattern.split_once("://*.") {
+            Some((scheme, rest)) => {
+                let origin = Origin::parse(&format!("{scheme}://{rest}"))?;
+                if origin.host.contains('*') {
+                    return Err(format!("invalid origin '{pattern}', only a leading '*.' wildcard is supported"));
+                }
+                Ok(OriginPattern::Subdomains(origin))
+            }
+            None => {
+                let origin = Origin::parse(pattern)?;
+                if origin.host.contains('*') {
+                    return Err(format!("invalid origin '{pattern}', only a leading '*.' wildcard is supported"));
+                }
+                Ok(OriginPattern::Exact(origin))
+            }
+        }
+    }
+
+    pub fn matches(&self, origin: &Origin) -> bool {
+        match self {
+            OriginPattern::Any => true,
+            OriginPattern::Exact(allowed) => allowed == origin,
+            OriginPattern::Subdomains(parent) => {
+                parent.scheme == origin.scheme
+                    && parent.port == origin.port
+                    && origin
+                        .host
+                        .strip_suffix(&parent.host)
+                        .is_some_and(|subdomain| subdomain.len() > 1 && subdomain.ends_with('.'))
+            }
+        }
+    }
+}
+
+/// Origins the API answers credentialed cross-origin requests for.
+#[derive(Debug, Clone)]
+pub struct CorsPolicy {
+    origins: Arc<Vec<OriginPattern>>,
+    max_age_seconds: u64,
+}
+
+impl CorsPolicy {
+    /// Uses the configured origins, or the defaults of `environment` when
+    /// none are configured. Invalid patterns are rejected at startup by the
+    /// config validation.
+    pub fn new(config: &CorsConfig, environment: Environment) -> Self {
+        let origins = Self::origins(config, environment)
+            .iter()
+            .filter_map(|origin| OriginPattern::parse(origin).ok())
+            .collect();
+        Self {
+            origins: Arc::new(origins),
+            max_age_seconds: config.max_age_seconds,
+        }
+    }
+
+    pub fn origins(config: &CorsConfig, environment: Environment) -> Vec<String> {
+        if !config.allowed_origins.is_empty() {
+            return config.allowed_origins.clone();
+        }
+        let defaults = match environment {
+            Environment::Development => DEVELOPMENT_ORIGINS.as_slice(),
+            Environment::Production => PRODUCTION_ORIGINS.as_slice(),
+        };
+        defaults.iter().map(|origin| origin.to_string()).collect()
+    }
+
+    pub fn allows(&self, origin: &str) -> bool {
+        match Origin::parse(origin) {
+            Ok(origin) => self.origins.iter().any(|pattern| pattern.matches(&origin)),
+            Err(_) => false,
+        }
+    }
+
+    fn preflight(
+        &self,
+        origin: String,
+        method: String,
+        request_headers: Option<String>,
+    ) -> Result<Response, CorsError> {
+        if !self.allows(&origin) {
+            return Err(CorsError::Origin(origin));
+        }
+        if !ALLOWED_METHODS.iter().any(|allowed| allowed.as_str() == method) {
+            return Err(CorsError::Method(method));
+        }
+        let request_headers = request_headers.unwrap_or_default();
+        for requested in request_headers.split(',').map(str::trim).filter(|h| !h.is_empty()) {
+            if !ALLOWED_HEADERS.contains(&requested.to_lowercase().as_str()) {
+                return Err(CorsError::Header(requested.to_owned()));
+            }
+        }
+
+        let mut response = warp::reply::with_status(warp::reply(), StatusCode::NO_CONTENT).into_response();
+        let headers = response.headers_mut();
+        Self::allow_origin(headers, &origin);
+        let methods: Vec<&str> = ALLOWED_METHODS.iter().map(Method::as_str).collect();
+        insert(headers, header::ACCESS_CONTROL_ALLOW_METHODS, &methods.join(", "));
+        insert(headers, header::ACCESS_CONTROL_ALLOW_HEADERS, &ALLOWED_HEADERS.join(", "));
+        insert(headers, header::ACCESS_CONTROL_MAX_AGE, &self.max_age_seconds.to_string());
+        Ok(response)
+    }
+
+    /// Adds the CORS headers to a response for an allowed `origin`.
+    fn decorate(&self, reply: impl Reply, origin: Option<String>) -> Response {
+        let mut response = reply.into_response();
+        let headers = response.headers_mut();
+        if let Some(origin) = origin.filter(|origin| self.allows(origin)) {
+            Self::allow_origin(headers, &origin);
+            insert(headers, header::ACCESS_CONTROL_EXPOSE_HEADERS, &EXPOSED_HEADERS.join(", "));
+        } else {
+            headers.append(header::VARY, HeaderValue::from_static("Origin"));
+        }
+        response
+    }
+
+    fn allow_origin(headers: &mut HeaderMap, origin: &str) {
+        insert(headers, header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+        insert(headers, header::ACCESS_CONTROL_ALLOW_CREDENTIALS, "true");
+        headers.append(header::VARY, HeaderValue::from_static("Origin"));
+    }
+}
+
+fn insert(headers: &mut HeaderMap, name: header::HeaderName, value: &str) {
+    if let Ok(value) = HeaderValue::from_str(value) {
+        headers.insert(name, value);
+    }
+}
+
+/// Answers CORS preflight requests. A refused preflight is answered with a
+/// 403 right away, so it never falls through to the routes.
+pub fn preflight(policy: CorsPolicy) -> impl Filter<Extract = (Response,), Error = Rejection> + Clone {
+    warp::options()
+        .and(warp::header::<String>("origin"))
+        .and(warp::header::<String>("access-control-request-method"))
+        .and(warp::header::optional::<String>("access-control-request-headers"))
+        .map(move |origin, method, request_headers| {
+            policy
+                .preflight(origin, method, request_headers)
+                .unwrap_or_else(Reply::into_response)
+        })
+}
+
+/// Rejects requests from origins outside of the allow-list before they reach
+/// `filter`, and adds the CORS headers to the responses of allowed ones.
+pub fn with_cors<F, R>(
+    policy: CorsPolicy,
+    filter: F,
+) -> impl Filter<Extract = (Response,), Error = Rejection> + Clone
+where
+    F: Filter<Extract = (R,), Error = Infallible> + Clone + Send + Sync + 'static,
+    R: Reply,
+{
+    let check = policy.clone();
+    warp::header::optional::<String>("origin")
+        .and_then(move |origin: Option<String>| {
+            let policy = check.clone();
+            async move {
+                match origin {
+                    Some(origin) if !policy.allows(&origin) => {
+                        Err(reject::custom(CorsError::Origin(origin)))
+                    }
+                    origin => Ok(origin),
+                }
+            }
+        })
+        .and(filter)
+        .map(move |origin: Option<String>, reply: R| policy.decorate(reply, origin))
+}
@@ -41,8 +41,73 @@ impl Reply for AuthenticationError {
         let code = StatusCode::UNAUTHORIZED;
         let message = self.to_string();
 
-        let json = warp::reply::json(&ErrorResponse { message });
+        let json = warp::reply::json(&ErrorResponse::new(message));
 
         warp::reply::with_status(json, code).into_response()
     }
 }
+
+#[derive(Clone, Error, Debug, Deserialize, PartialEq)]
+pub enum CorsError {
+    Origin(String),
+    Method(String),
+    Header(String),
+}
+
+impl fmt::Display for CorsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CorsError::Origin(origin) => write!(f, "Origin '{origin}' is not allowed"),
+            CorsError::Method(method) => write!(f, "Method '{method}' is not allowed"),
+            CorsError::Header(header) => write!(f, "Header '{header}' is not allowed"),
+        }
+    }
+}
+
+impl Reject for CorsError {}
+
+impl Reply for CorsError {
+    fn into_response(self) -> Response {
+        let code = StatusCode::FORBIDDEN;
+        let message = self.to_string();
+
+        let json = warp::reply::json(&ErrorResponse::new(message));
+
+        let mut response = warp::reply::with_status(json, code).into_response();
+        response
+            .headers_mut()
+            .insert(warp::http::header::VARY, warp::http::HeaderValue::from_static("Origin"));
+        response
+    }
+}
+
+#[derive(Clone, Error, Debug, Deserialize, PartialEq)]
+pub struct RateLimitError {
+    pub limit: u32,
+    pub retry_after_seconds: u64,
+}
+
+impl fmt::Display for RateLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Too many requests, retry in {} seconds", self.retry_after_seconds)
+    }
+}
+
+impl Reject for RateLimitError {}
+
+impl Reply for RateLimitError {
+    fn into_response(self) -> Response {
+        let code = StatusCode::TOO_MANY_REQUESTS;
+        let message = self.to_string();
+
+        let json = warp::reply::json(&ErrorResponse::new(message));
+
+        let mut response = warp::reply::with_status(json, code).into_response();
+        let headers = response.headers_mut();
+        headers.insert(warp::http::header::RETRY_AFTER, self.retry_after_seconds.into());
+        headers.insert("x-ratelimit-limit", self.limit.into());
+        headers.insert("x-ratelimit-remaining", 0.into());
+        headers.insert("x-ratelimit-reset", self.retry_after_seconds.into());
+        response
+    }
+}
@@ -1,5 +1,8 @@
+use crate::metrics::metrics;
 use crate::middlewares::{
+    context::record_user,
     errors::AuthenticationError,
+    rate_limit::remember_token,
     utils::{token_from_header, BEARER},
 };
 use surf;
@@ -10,72 +13,99 @@ use warp::{
     reject, Filter, Rejection,
 };
 use log::error;
+use std::{sync::OnceLock, time::Instant};
 
 use super::model::GitHubUser;
 
+const DEFAULT_API_BASE_URL: &str = "https://api.github.com";
+
+static API_BASE_URL: OnceLock<String> = OnceLock::new();
+
+/// Points token validation at `api_base_url` (e.g. a GitHub Enterprise
+/// instance). Only the first call has an effect.
+pub fn set_api_base_url(api_base_url: &str) {
+    let _ = API_BASE_URL.set(api_base_url.trim_end_matches('/').to_owned());
+}
+
+fn api_base_url() -> &'static str {
+    API_BASE_URL.get().map_or(DEFAULT_API_BASE_URL, String::as_str)
+}
 
 pub fn with_github_auth() -> impl Filter<Extract = (GitHubUser,), Error = Rejection> + Clone {
     warp::filters::header::headers_cloned()
-        .and_then(authorize)
+        .and_then(|headers: HeaderMap<HeaderValue>| async move { authorize(&headers).await })
         // .untuple_one()
 }
-async fn authorize(headers: HeaderMap<HeaderValue>) -> Result<GitHubUser, Rejection> {
-    match token_from_header(&headers, BEARER) {
-        Ok(token) => {
-            let mut response = surf::get("https://api.github.com/user")
-                .header(AUTHORIZATION, format!("{BEARER} {token}"))
-                .header(USER_AGENT, "MoreKudos")
-                .await
-                .map_err(|e| {
-                    error!("Error calling GitHub API: {e}");
-                    AuthenticationError::GitHub
-                })?;
-    
-            if response.status().is_success() {
-                let user_data: serde_json::Value = response
-                    .body_json()
-                    .await
-                    .map_err(|_| reject::custom(AuthenticationError::GitHub))?;
 
-                // Extract user ID and username
-                let id = user_data
-                    .get("id")
-                    .and_then(|v| v.as_i64())
-                    .ok_or_else(|| reject::custom(AuthenticationError::GitHub))?;
-                let username = user_data
-                    .get("login")
-                    .and_then(|v| v.as_str())
-                    .ok_or_else(|| reject::custom(AuthenticationError::GitHub))?
-                    .to_string();
-                let email = user_data
-                    .get("email")
-                    .and_then(|v| v.as_str().map(|s| s.to_string()));
-    
-                let avatar_url = user_data
-                    .get("avatar_url")
-                    .and_then(|v| v.as_str())
-                    .ok_or_else(|| reject::custom(AuthenticationError::GitHub))?
-                    .to_string();
-    
-                Ok(GitHubUser { id, username, avatar_url, email })
-            } else {
-                let status = response.status();
-                let body = response
-                    .body_string()
-                    .await
-                    .unwrap_or_else(|_| "Unable to read body".to_string());
-                error!(
-                    "GitHub token validation failed. Status: {:?}, Body: {}",
-                    status, body
-                );
-    
-                if status.is_client_error() {
-                    Err(reject::custom(AuthenticationError::WrongCredentials))
-                } else {
-                    Err(reject::custom(AuthenticationError::GitHub))
-                }
-            }
+async fn authorize(headers: &HeaderMap<HeaderValue>) -> Result<GitHubUser, Rejection> {
+    let token = token_from_header(headers, BEARER).map_err(reject::custom)?;
+    let started = Instant::now();
+    let user = fetch_user(&token).await;
+    metrics().github_auth_duration.observe(started.elapsed().as_secs_f64());
+    let user = user.inspect_err(|rejection| {
+        let reason = match rejection.find::<AuthenticationError>() {
+            Some(AuthenticationError::WrongCredentials) => "rejected",
+            _ => "error",
+        };
+        metrics().github_auth_failures.with_label_values(&[reason]).inc();
+    })?;
+    record_user(&user.username);
+    remember_token(&token, user.id);
+    Ok(user)
+}
+
+async fn fetch_user(token: &str) -> Result<GitHubUser, Rejection> {
+    let mut response = surf::get(format!("{}/user", api_base_url()))
+        .header(AUTHORIZATION, format!("{BEARER} {token}"))
+        .header(USER_AGENT, "MoreKudos")
+        .await
+        .map_err(|e| {
+            error!("Error calling GitHub API: {e}");
+            AuthenticationError::GitHub
+        })?;
+
+    if response.status().is_success() {
+        let user_data: serde_json::Value = response
+            .body_json()
+            .await
+            .map_err(|_| reject::custom(AuthenticationError::GitHub))?;
+
+        // Extract user ID and username
+        let id = user_data
+            .get("id")
+            .and_then(|v| v.as_i64())
+            .ok_or_else(|| reject::custom(AuthenticationError::GitHub))?;
+        let username = user_data
+            .get("login")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| reject::custom(AuthenticationError::GitHub))?
+            .to_string();
+        let email = user_data
+            .get("email")
+            .and_then(|v| v.as_str().map(|s| s.to_string()));
+
+        let avatar_url = user_data
+            .get("avatar_url")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| reject::custom(AuthenticationError::GitHub))?
+            .to_string();
+
+        Ok(GitHubUser { id, username, avatar_url, email })
+    } else {
+        let status = response.status();
+        let body = response
+            .body_string()
+            .await
+            .unwrap_or_else(|_| "Unable to read body".to_string());
+        error!(
+            "GitHub token validation failed. Status: {:?}, Body: {}",
+            status, body
+        );
+
+        if status.is_client_error() {
+            Err(reject::custom(AuthenticationError::WrongCredentials))
+        } else {
+            Err(reject::custom(AuthenticationError::GitHub))
         }
-        Err(e) => Err(reject::custom(e)),
     }
-}    
\ No newline at end of file
+}
@@ -1,4 +1,7 @@
 pub mod basic;
+pub mod context;
+pub mod cors;
 pub mod github;
 pub mod errors;
+pub mod rate_limit;
 pub mod utils;
\ No newline at end of file
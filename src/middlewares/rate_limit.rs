@@ -0,0 +1,281 @@
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use sha2::{Digest, Sha256};
+
+use warp::{
+    http::{HeaderMap, HeaderValue, Method},
+    path::FullPath,
+    reject,
+    reply::Response,
+    Filter, Rejection, Reply,
+};
+
+use crate::{config::RateLimitConfig, errors::error_handler};
+
+use super::{
+    context::remote_addr,
+    errors::RateLimitError,
+    utils::{token_from_header, BEARER},
+};
+
+/// Buckets refill completely within a minute, so idle ones can be dropped
+/// after that without changing any decision.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Validated tokens are forgotten after being unused this long, their next
+/// request then counts against the client address until it is validated again.
+const TOKEN_TTL: Duration = Duration::from_secs(600);
+
+/// Paths that are never limited, so probes and scrapes keep working under load.
+const EXEMPT_PREFIXES: [&str; 2] = ["/health", "/metrics"];
+
+/// Requests sharing a budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteGroup {
+    Read,
+    Write,
+    Auth,
+}
+
+impl RouteGroup {
+    /// Signing up through GitHub and verifying emails are `Auth`, other
+    /// requests are `Read` for safe methods and `Write` otherwise.
+    pub fn of(method: &Method, path: &str) -> Self {
+        let path = path.trim_end_matches('/');
+        let read = matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS);
+        let users_me = path == "/users/me" || path.starts_with("/users/me/");
+        if path.starts_with("/users/email/") || (!read && users_me) {
+            RouteGroup::Auth
+        } else if read {
+            RouteGroup::Read
+        } else {
+            RouteGroup::Write
+        }
+    }
+
+    fn per_minute(self, config: &RateLimitConfig) -> u32 {
+        match self {
+            RouteGroup::Read => config.read_per_minute,
+            RouteGroup::Write => config.write_per_minute,
+            RouteGroup::Auth => config.auth_per_minute,
+        }
+    }
+}
+
+/// Who a budget belongs to: the GitHub user of a token GitHub already
+/// validated, the client address otherwise. Other tokens can't be trusted
+/// before they are validated, and asking GitHub for every request would
+/// defeat the limit.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ClientKey {
+    User(i64),
+    Ip(IpAddr),
+    Unknown,
+}
+
+#[derive(Debug)]
+struct ValidatedTokens {
+    users: HashMap<[u8; 32], (i64, Instant)>,
+    pruned_at: Instant,
+}
+
+/// Digests of the tokens GitHub validated, with their user. Shared by all
+/// limiters since tokens are validated outside of them.
+static VALIDATED_TOKENS: LazyLock<Mutex<ValidatedTokens>> = LazyLock::new(|| {
+    Mutex::new(ValidatedTokens {
+        users: HashMap::new(),
+        pruned_at: Instant::now(),
+    })
+});
+
+fn token_digest(token: &str) -> [u8; 32] {
+    Sha256::digest(token.as_bytes()).into()
+}
+
+/// Remembers that GitHub validated `token` for `user_id`, so that the next
+/// requests carrying it use the budget of the user.
+pub fn remember_token(token: &str, user_id: i64) {
+    let now = Instant::now();
+    let mut tokens = VALIDATED_TOKENS.lock().unwrap();
+    if now.saturating_duration_since(tokens.pruned_at) >= TOKEN_TTL {
+        tokens
+            .users
+            .retain(|_, (_, used_at)| now.saturating_duration_since(*used_at) < TOKEN_TTL);
+        tokens.pruned_at = now;
+    }
+    tokens.users.insert(token_digest(token), (user_id, now));
+}
+
+fn validated_user(token: &str) -> Option<i64> {
+    let now = Instant::now();
+    let mut tokens = VALIDATED_TOKENS.lock().unwrap();
+    let (user_id, used_at) = tokens.users.get_mut(&token_digest(token))?;
+    if now.saturating_duration_since(*used_at) >= TOKEN_TTL {
+        return None;
+    }
+    *used_at = now;
+    Some(*user_id)
+}
+
+/// State of a budget after an allowed request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitStatus {
+    pub limit: u32,
+    pub remaining: u32,
+    pub reset_seconds: u64,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+#[derive(Debug)]
+struct Buckets {
+    buckets: HashMap<(RouteGroup, ClientKey), Bucket>,
+    pruned_at: Instant,
+}
+
+/// In-memory token buckets, one per route group and client.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    config: Arc<RateLimitConfig>,
+    state: Arc<Mutex<Buckets>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        Self {
+            config: Arc::new(config.clone()),
+            state: Arc::new(Mutex::new(Buckets {
+                buckets: HashMap::new(),
+                pruned_at: Instant::now(),
+            })),
+        }
+    }
+
+    pub fn check(&self, group: RouteGroup, key: ClientKey) -> Result<RateLimitStatus, RateLimitError> {
+        self.check_at(group, key, Instant::now())
+    }
+
+    /// Takes a token from the bucket of `key` in `group`. Buckets hold up to
+    /// a minute worth of requests and refill continuously.
+    pub fn check_at(
+        &self,
+        group: RouteGroup,
+        key: ClientKey,
+        now: Instant,
+    ) -> Result<RateLimitStatus, RateLimitError> {
+        let limit = group.per_minute(&self.config);
+        let capacity = f64::from(limit);
+        let per_second = capacity / 60.0;
+
+        let mut state = self.state.lock().unwrap();
+        if now.saturating_duration_since(state.pruned_at) >= PRUNE_INTERVAL {
+            state
+                .buckets
+                .retain(|_, bucket| now.saturating_duration_since(bucket.updated_at) < PRUNE_INTERVAL);
+            state.pruned_at = now;
+        }
+
+        let bucket = state.buckets.entry((group, key)).or_insert(Bucket {
+            tokens: capacity,
+            updated_at: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_second).min(capacity);
+        bucket.updated_at = now;
+
+        if bucket.tokens < 1.0 {
+            return Err(RateLimitError {
+                limit,
+                retry_after_seconds: ((1.0 - bucket.tokens) / per_second).ceil().max(1.0) as u64,
+            });
+        }
+        bucket.tokens -= 1.0;
+        Ok(RateLimitStatus {
+            limit,
+            remaining: bucket.tokens.floor() as u32,
+            reset_seconds: ((capacity - bucket.tokens) / per_second).ceil() as u64,
+        })
+    }
+
+    /// Signing up and verifying emails always count against the address, so
+    /// that a user can't open accounts or send emails past the address budget.
+    fn client_key(&self, group: RouteGroup, headers: &HeaderMap, remote: Option<SocketAddr>) -> ClientKey {
+        if group != RouteGroup::Auth {
+            if let Some(user_id) = token_from_header(headers, BEARER).ok().as_deref().and_then(validated_user) {
+                return ClientKey::User(user_id);
+            }
+        }
+        // the trusted proxy appends the address it got the request from, the
+        // entries before it are whatever the client sent
+        let forwarded = self
+            .config
+            .trust_forwarded_for
+            .then(|| headers.get("x-forwarded-for"))
+            .flatten()
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .and_then(|ip| ip.trim().parse().ok());
+        match forwarded.or(remote.map(|addr| addr.ip())) {
+            Some(ip) => ClientKey::Ip(ip),
+            None => ClientKey::Unknown,
+        }
+    }
+
+    async fn limit(
+        self,
+        method: Method,
+        path: FullPath,
+        remote: Option<SocketAddr>,
+        headers: HeaderMap,
+    ) -> Result<Option<RateLimitStatus>, Rejection> {
+        if !self.config.enabled || EXEMPT_PREFIXES.iter().any(|prefix| path.as_str().starts_with(prefix)) {
+            return Ok(None);
+        }
+        let group = RouteGroup::of(&method, path.as_str());
+        let key = self.client_key(group, &headers, remote);
+        self.check(group, key).map(Some).map_err(reject::custom)
+    }
+}
+
+/// Answers with `429 Too Many Requests` once the client used up the budget
+/// of the route group, and reports the budget in `X-RateLimit-*` headers.
+pub fn with_rate_limit<F, R>(
+    limiter: RateLimiter,
+    filter: F,
+) -> impl Filter<Extract = (impl Reply,), Error = Infallible> + Clone
+where
+    F: Filter<Extract = (R,), Error = Infallible> + Clone + Send + Sync + 'static,
+    R: Reply,
+{
+    warp::method()
+        .and(warp::path::full())
+        .and(remote_addr())
+        .and(warp::header::headers_cloned())
+        .and_then(move |method, path, remote, headers| limiter.clone().limit(method, path, remote, headers))
+        .and(filter)
+        .map(|status: Option<RateLimitStatus>, reply: R| {
+            let mut response = reply.into_response();
+            if let Some(status) = status {
+                add_headers(&mut response, status);
+            }
+            response
+        })
+        .recover(error_handler)
+}
+
+fn add_headers(response: &mut Response, status: RateLimitStatus) {
+    let headers = response.headers_mut();
+    headers.insert("x-ratelimit-limit", HeaderValue::from(status.limit));
+    headers.insert("x-ratelimit-remaining", HeaderValue::from(status.remaining));
+    headers.insert("x-ratelimit-reset", HeaderValue::from(status.reset_seconds));
+}
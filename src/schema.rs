@@ -16,6 +16,36 @@ diesel::table! {
         updated_at -> Nullable<Timestamptz>,
         description -> Nullable<Text>,
         estimation -> Int4,
+        archived_at -> Nullable<Timestamptz>,
+        deleted_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    job_runs (id) {
+        id -> Int4,
+        job_name -> Text,
+        runner -> Text,
+        status -> Text,
+        error -> Nullable<Text>,
+        started_at -> Timestamptz,
+        finished_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    jobs (name) {
+        name -> Text,
+        schedule -> Text,
+        enabled -> Bool,
+        next_run_at -> Timestamptz,
+        last_run_at -> Nullable<Timestamptz>,
+        last_success_at -> Nullable<Timestamptz>,
+        consecutive_failures -> Int4,
+        locked_until -> Nullable<Timestamptz>,
+        locked_by -> Nullable<Text>,
+        created_at -> Timestamptz,
+        updated_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -41,11 +71,10 @@ diesel::table! {
 }
 
 diesel::table! {
-    notification_schedule (id) {
-        id -> Int4,
-        next_run -> Timestamptz,
-        last_run -> Nullable<Timestamptz>,
-        created_at -> Timestamptz,
+    notification_preferences (github_id, kind) {
+        github_id -> Int8,
+        kind -> Text,
+        enabled -> Bool,
         updated_at -> Timestamptz,
     }
 }
@@ -54,9 +83,14 @@ diesel::table! {
     notifications (id) {
         id -> Int4,
         github_id -> Int8,
-        task_id -> Int4,
+        task_id -> Nullable<Int4>,
         seen -> Nullable<Bool>,
         created_at -> Timestamptz,
+        kind -> Text,
+        payload -> Jsonb,
+        read_at -> Nullable<Timestamptz>,
+        archived_at -> Nullable<Timestamptz>,
+        issue_id -> Nullable<Int4>,
     }
 }
 
@@ -74,6 +108,8 @@ diesel::table! {
         created_at -> Timestamptz,
         updated_at -> Nullable<Timestamptz>,
         rewards -> Bool,
+        archived_at -> Nullable<Timestamptz>,
+        deleted_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -87,6 +123,8 @@ diesel::table! {
         project_id -> Int4,
         created_at -> Timestamptz,
         updated_at -> Nullable<Timestamptz>,
+        archived_at -> Nullable<Timestamptz>,
+        deleted_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -99,6 +137,16 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    slug_history (id) {
+        id -> Int4,
+        project_id -> Nullable<Int4>,
+        repository_id -> Nullable<Int4>,
+        slug -> Text,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     tasks (id) {
         id -> Int4,
@@ -131,6 +179,8 @@ diesel::table! {
         issue_closed_at -> Nullable<Timestamptz>,
         created_at -> Timestamptz,
         updated_at -> Nullable<Timestamptz>,
+        archived_at -> Nullable<Timestamptz>,
+        deleted_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -143,6 +193,16 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    taxonomy (id) {
+        id -> Int4,
+        kind -> Text,
+        value -> Text,
+        created_at -> Timestamptz,
+        updated_at -> Nullable<Timestamptz>,
+    }
+}
+
 diesel::table! {
     team_memberships (id) {
         id -> Int4,
@@ -172,6 +232,12 @@ diesel::table! {
         stack_level -> Nullable<Text>,
         technology -> Nullable<Text>,
         created_at -> Timestamptz,
+        project_ids -> Nullable<Array<Nullable<Int4>>>,
+        repository_ids -> Nullable<Array<Nullable<Int4>>>,
+        labels -> Nullable<Array<Nullable<Text>>>,
+        languages -> Nullable<Array<Nullable<Text>>>,
+        task_types -> Nullable<Array<Nullable<Text>>>,
+        min_bounty -> Nullable<Int4>,
     }
 }
 
@@ -185,6 +251,8 @@ diesel::table! {
         github_id -> Nullable<Int8>,
         email_notifications_enabled -> Bool,
         email -> Nullable<Text>,
+        email_verified_at -> Nullable<Timestamptz>,
+        pending_email -> Nullable<Text>,
     }
 }
 
@@ -201,8 +269,42 @@ diesel::table! {
 diesel::joinable!(issues -> repositories (repository_id));
 diesel::joinable!(issues -> users (assignee_id));
 diesel::joinable!(milestones -> projects (project_id));
+diesel::table! {
+    webhook_deliveries (id) {
+        id -> Int4,
+        subscription_id -> Int4,
+        event -> Text,
+        payload -> Jsonb,
+        status -> Text,
+        attempts -> Int4,
+        next_attempt_at -> Timestamptz,
+        last_status_code -> Nullable<Int4>,
+        last_error -> Nullable<Text>,
+        delivered_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    webhook_subscriptions (id) {
+        id -> Int4,
+        url -> Text,
+        secret -> Text,
+        events -> Array<Nullable<Text>>,
+        project_id -> Nullable<Int4>,
+        active -> Bool,
+        created_by_user_id -> Nullable<Int4>,
+        created_at -> Timestamptz,
+        updated_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::joinable!(job_runs -> jobs (job_name));
+diesel::joinable!(notifications -> issues (issue_id));
 diesel::joinable!(notifications -> tasks (task_id));
 diesel::joinable!(repositories -> projects (project_id));
+diesel::joinable!(slug_history -> projects (project_id));
+diesel::joinable!(slug_history -> repositories (repository_id));
 diesel::joinable!(tasks -> projects (project_id));
 diesel::joinable!(tasks -> repositories (repository_id));
 diesel::joinable!(tasks_votes -> tasks (task_id));
@@ -212,21 +314,29 @@ diesel::joinable!(team_memberships -> users (user_id));
 diesel::joinable!(users_projects_roles -> projects (project_id));
 diesel::joinable!(users_projects_roles -> roles (role_id));
 diesel::joinable!(users_projects_roles -> users (user_id));
+diesel::joinable!(webhook_deliveries -> webhook_subscriptions (subscription_id));
+diesel::joinable!(webhook_subscriptions -> projects (project_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     issues,
+    job_runs,
+    jobs,
     languages,
     milestones,
-    notification_schedule,
+    notification_preferences,
     notifications,
     projects,
     repositories,
     roles,
+    slug_history,
     tasks,
     tasks_votes,
+    taxonomy,
     team_memberships,
     teams,
     user_subscriptions,
     users,
     users_projects_roles,
+    webhook_deliveries,
+    webhook_subscriptions,
 );
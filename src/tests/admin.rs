@@ -0,0 +1,116 @@
+#[cfg(test)]
+mod tests {
+    use diesel::RunQueryDsl;
+
+    use crate::{
+        admin::{
+            commands::{apply_manifest, grant_role, revoke_role},
+            db::DBAdmin,
+            errors::AdminError,
+            models::{Manifest, ManifestSummary},
+        },
+        api::{
+            repositories::db::DBRepository,
+            roles::db::DBRole,
+            tasks::db::DBTask,
+            taxonomy::{
+                db::DBTaxonomy,
+                models::{TaxonomyKind, TermDB},
+            },
+        },
+        db::pool::DBAccessor,
+        tests::utils::generate_test_database,
+    };
+
+    const MANIFEST: &str = r#"
+projects:
+  - name: Admin Polkadot
+    slug: admin-polkadot
+    avatar: https://example.com/polkadot.png
+    purposes: [defi]
+    repositories:
+      - name: Admin SDK
+        slug: admin-sdk
+        url: https://github.com/example/admin-sdk
+        language_slug: rust
+  - name: Admin Astar
+    slug: admin-astar
+"#;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest: Manifest = serde_yaml::from_str(MANIFEST).unwrap();
+        assert_eq!(manifest.projects.len(), 2);
+        let project = manifest.projects[0].to_new_project();
+        assert_eq!(project.purposes, Some(vec![Some("defi".to_owned())]));
+        assert_eq!(manifest.projects[0].repositories[0].to_new_repository(7).project_id, 7);
+        assert!(manifest.projects[1].repositories.is_empty());
+
+        assert!(serde_yaml::from_str::<Manifest>("projects:\n  - name: x\n    slug: x\n    owner: y\n").is_err());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_admin_commands() {
+        let db = generate_test_database().await;
+        diesel::sql_query("INSERT INTO users (username, github_id) VALUES ('admin-cli', 9001)")
+            .execute(&mut db.get_db_conn().unwrap())
+            .unwrap();
+
+        let manifest: Manifest = serde_yaml::from_str(MANIFEST).unwrap();
+        // tags must be terms of the taxonomy
+        assert!(matches!(
+            apply_manifest(&db, &manifest, false),
+            Err(AdminError::UnknownTerms(slug, "purposes", terms)) if slug == "admin-polkadot" && terms == "defi"
+        ));
+        assert!(DBRepository::by_slug(&db, "admin-sdk").unwrap().is_none());
+        if db.term_by_value(TaxonomyKind::Purpose, "defi").unwrap().is_none() {
+            db.create_term(&TermDB {
+                kind: TaxonomyKind::Purpose.as_str().to_owned(),
+                value: "defi".to_owned(),
+            })
+            .unwrap();
+        }
+
+        let planned = apply_manifest(&db, &manifest, true).unwrap();
+        assert_eq!(planned.created_projects, vec!["admin-polkadot", "admin-astar"]);
+        assert!(DBRepository::by_slug(&db, "admin-sdk").unwrap().is_none());
+        assert_eq!(apply_manifest(&db, &manifest, false).unwrap(), planned);
+        assert_eq!(
+            apply_manifest(&db, &manifest, false).unwrap(),
+            ManifestSummary {
+                existing_projects: vec!["admin-polkadot".to_owned(), "admin-astar".to_owned()],
+                existing_repositories: vec!["admin-sdk".to_owned()],
+                ..Default::default()
+            }
+        );
+
+        assert!(grant_role(&db, "admin-cli", "Admin", None).unwrap().is_some());
+        assert!(grant_role(&db, "admin-cli", "Admin", None).unwrap().is_none());
+        assert!(grant_role(&db, "admin-cli", "Maintainer", Some("admin-astar")).unwrap().is_some());
+        assert_eq!(db.user_roles("admin-cli").unwrap().len(), 2);
+        assert!(revoke_role(&db, "admin-cli", "Admin", None).unwrap());
+        assert!(!revoke_role(&db, "admin-cli", "Admin", None).unwrap());
+        assert!(matches!(
+            grant_role(&db, "admin-cli", "Overlord", None),
+            Err(AdminError::RoleNotFound(_))
+        ));
+        assert!(matches!(
+            grant_role(&db, "nobody", "Admin", None),
+            Err(AdminError::UserNotFound(_))
+        ));
+
+        diesel::sql_query(
+            "INSERT INTO tasks (title, type, upvotes, downvotes) VALUES ('admin-cli drifted', 'dev', 5, 2)",
+        )
+        .execute(&mut db.get_db_conn().unwrap())
+        .unwrap();
+        assert!(db.rebuild_vote_counts().unwrap() >= 1);
+        assert_eq!(db.rebuild_vote_counts().unwrap(), 0);
+
+        let stats = db.stats().unwrap();
+        assert!(stats.projects >= 2);
+        assert!(stats.users >= 1);
+        assert!(stats.last_applied_migration.is_some());
+    }
+}
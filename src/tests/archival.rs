@@ -0,0 +1,211 @@
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use chrono::{TimeZone, Utc};
+    use diesel::prelude::*;
+
+    use crate::{
+        admin::commands::grant_role,
+        api::{
+            archival::{
+                db::DBArchival,
+                models::{ArchivalKind, PurgeCounts, Stamp},
+            },
+            issues::{
+                db::DBIssue,
+                errors::IssueError,
+                handlers::create_handler,
+                models::NewIssue,
+            },
+            projects::{
+                db::DBProject,
+                models::{NewProject, QueryParams},
+            },
+            repositories::{db::DBRepository, models::NewRepository},
+        },
+        db::pool::DBAccessor,
+        middlewares::github::model::GitHubUser,
+        schema::notifications,
+        tests::utils::generate_test_database,
+        types::PaginationParams,
+    };
+
+    fn params(slug: &str, include_archived: bool) -> QueryParams {
+        QueryParams {
+            slugs: Some(slug.to_owned()),
+            purposes: None,
+            stack_levels: None,
+            technologies: None,
+            rewards: None,
+            certified: None,
+            open: None,
+            labels: None,
+            excluded_labels: None,
+            label_match: None,
+            certified_or_labels: None,
+            types: None,
+            source: None,
+            include_archived: Some(include_archived),
+        }
+    }
+
+    fn pagination() -> PaginationParams {
+        PaginationParams { limit: 1000, offset: 0 }
+    }
+
+    #[test]
+    fn test_archival_kinds() {
+        for kind in [
+            ArchivalKind::Projects,
+            ArchivalKind::Repositories,
+            ArchivalKind::Issues,
+            ArchivalKind::Tasks,
+        ] {
+            assert_eq!(kind.to_string().parse::<ArchivalKind>(), Ok(kind));
+            // the row itself is stamped first
+            assert_eq!(kind.cascade()[0], (kind.as_str(), "id = $1"));
+        }
+        assert!("users".parse::<ArchivalKind>().is_err());
+        assert!(ArchivalKind::Projects.parent_stamped(Stamp::Deleted).is_none());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_archive_delete_restore_and_purge() {
+        let db = generate_test_database().await;
+        let project = DBProject::create(
+            &db,
+            &NewProject {
+                name: "archival".to_owned(),
+                slug: "archival-project".to_owned(),
+                types: None,
+                purposes: None,
+                stack_levels: None,
+                technologies: None,
+                avatar: None,
+                rewards: Some(false),
+            },
+        )
+        .unwrap();
+        let repository = DBRepository::create(
+            &db,
+            &NewRepository {
+                slug: "archival-repo".to_owned(),
+                name: "archival-repo".to_owned(),
+                url: "https://github.com/archival/repo".to_owned(),
+                language_slug: None,
+                project_id: project.id,
+            },
+        )
+        .unwrap();
+        let issue = |number: i32| {
+            DBIssue::create(
+                &db,
+                &NewIssue {
+                    number,
+                    title: format!("archival {number}"),
+                    labels: None,
+                    open: true,
+                    certified: None,
+                    repository_id: repository.id,
+                    assignee_id: None,
+                    issue_created_at: Utc::now(),
+                    description: None,
+                    estimation: None,
+                },
+            )
+            .unwrap()
+        };
+        let kept = issue(1);
+        let dropped = issue(2);
+
+        // archived projects are only listed on request, and still served
+        assert!(db.archive(ArchivalKind::Projects, project.id).unwrap());
+        let (listed, _) = DBProject::all(&db, params("archival-project", false), pagination()).unwrap();
+        assert!(listed.is_empty());
+        let (listed, _) = DBProject::all(&db, params("archival-project", true), pagination()).unwrap();
+        assert!(listed[0].archived_at.is_some());
+        let stored = DBRepository::by_id(&db, repository.id).unwrap().unwrap();
+        assert_eq!(stored.archived_at, listed[0].archived_at);
+        // restoring leaves archived rows archived, unarchiving brings them back
+        db.restore(ArchivalKind::Projects, project.id).unwrap();
+        assert!(DBProject::by_id(&db, project.id).unwrap().unwrap().archived_at.is_some());
+        db.unarchive(ArchivalKind::Projects, project.id).unwrap();
+        assert!(DBProject::by_id(&db, project.id).unwrap().unwrap().archived_at.is_none());
+        assert!(DBRepository::by_id(&db, repository.id).unwrap().unwrap().archived_at.is_none());
+
+        // an issue deleted on its own stays deleted when its project comes back
+        DBIssue::delete(&db, dropped.id).unwrap();
+        DBProject::delete(&db, project.id).unwrap();
+        assert!(DBProject::by_id(&db, project.id).unwrap().is_none());
+        assert!(DBRepository::by_id(&db, repository.id).unwrap().is_none());
+        assert!(DBIssue::by_id(&db, kept.id).unwrap().is_none());
+        assert!(db.parent_stamped(ArchivalKind::Issues, kept.id, Stamp::Deleted).unwrap());
+        assert!(!db.parent_stamped(ArchivalKind::Issues, kept.id, Stamp::Archived).unwrap());
+        let (deleted, _) = db.deleted(ArchivalKind::Projects, pagination()).unwrap();
+        assert!(deleted.iter().any(|item| item.id == project.id && item.label == "archival-project"));
+
+        db.restore(ArchivalKind::Projects, project.id).unwrap();
+        assert!(DBProject::by_id(&db, project.id).unwrap().is_some());
+        assert!(DBIssue::by_id(&db, kept.id).unwrap().is_some());
+        assert!(DBIssue::by_id(&db, dropped.id).unwrap().is_none());
+        assert!(!db.parent_stamped(ArchivalKind::Issues, dropped.id, Stamp::Deleted).unwrap());
+
+        // a deleted issue keeps its number until it is purged
+        diesel::sql_query("INSERT INTO users (username, github_id) VALUES ('archival-admin', 9301)")
+            .execute(&mut db.get_db_conn().unwrap())
+            .unwrap();
+        grant_role(&db, "archival-admin", "Admin", None).unwrap();
+        let user = GitHubUser {
+            id: 9301,
+            username: "archival-admin".to_owned(),
+            avatar_url: String::new(),
+            email: None,
+        };
+        let body = format!(
+            r#"{{"number": 2, "title": "again", "open": true, "repository_id": {}, "issue_created_at": "2026-01-01T00:00:00Z"}}"#,
+            repository.id
+        );
+        let rejection = create_handler(user, Bytes::from(body), db.clone()).await.err().unwrap();
+        assert_eq!(rejection.find::<IssueError>(), Some(&IssueError::Deleted(dropped.id)));
+
+        // a task someone was notified about can be purged too
+        for statement in [
+            format!("INSERT INTO tasks (id, title, type, repository_id) VALUES (9301, 'archival task', 'dev', {})", repository.id),
+            "INSERT INTO notifications (github_id, task_id) VALUES (9301, 9301)".to_owned(),
+        ] {
+            diesel::sql_query(statement).execute(&mut db.get_db_conn().unwrap()).unwrap();
+        }
+        assert!(db.soft_delete(ArchivalKind::Tasks, 9301).unwrap());
+
+        // only rows deleted before the retention period are purged
+        let long_ago = Utc.with_ymd_and_hms(2001, 1, 1, 0, 0, 0).unwrap();
+        diesel::sql_query(format!(
+            "UPDATE issues SET deleted_at = '2000-01-01T00:00:00Z' WHERE id = {}",
+            dropped.id
+        ))
+        .execute(&mut db.get_db_conn().unwrap())
+        .unwrap();
+        diesel::sql_query("UPDATE tasks SET deleted_at = '2000-01-01T00:00:00Z' WHERE id = 9301")
+            .execute(&mut db.get_db_conn().unwrap())
+            .unwrap();
+        let purged = db.purge_deleted(long_ago).unwrap();
+        assert_eq!(
+            purged,
+            PurgeCounts {
+                issues: 1,
+                tasks: 1,
+                ..Default::default()
+            }
+        );
+        assert!(db.stamps(ArchivalKind::Issues, dropped.id).unwrap().is_none());
+        assert!(db.stamps(ArchivalKind::Issues, kept.id).unwrap().is_some());
+        assert!(db.stamps(ArchivalKind::Tasks, 9301).unwrap().is_none());
+        let notifications = notifications::table
+            .filter(notifications::task_id.eq(9301))
+            .count()
+            .get_result::<i64>(&mut db.get_db_conn().unwrap())
+            .unwrap();
+        assert_eq!(notifications, 0);
+    }
+}
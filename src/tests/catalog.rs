@@ -0,0 +1,239 @@
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use diesel::RunQueryDsl;
+
+    use crate::{
+        admin::commands::grant_role,
+        api::{
+            admin::{
+                db::DBCatalog,
+                errors::CatalogError,
+                handlers::{import_handler, parse_catalog},
+                models::{
+                    Catalog, CatalogChanges, CatalogProject, CatalogRepository, CatalogUpdate, ImportParams,
+                    CATALOG_VERSION,
+                },
+            },
+            repositories::db::DBRepository,
+        },
+        db::pool::DBAccessor,
+        middlewares::github::model::GitHubUser,
+        tests::utils::generate_test_database,
+    };
+
+    fn repository(slug: &str) -> CatalogRepository {
+        CatalogRepository {
+            name: slug.to_uppercase(),
+            slug: slug.to_owned(),
+            url: format!("https://github.com/example/{slug}"),
+            language_slug: Some("rust".to_owned()),
+        }
+    }
+
+    fn project(slug: &str, repositories: &[&str]) -> CatalogProject {
+        CatalogProject {
+            name: slug.to_uppercase(),
+            slug: slug.to_owned(),
+            avatar: None,
+            types: vec![],
+            purposes: vec!["defi".to_owned()],
+            stack_levels: vec![],
+            technologies: vec![],
+            rewards: false,
+            repositories: repositories.iter().map(|slug| repository(slug)).collect(),
+        }
+    }
+
+    fn catalog(projects: Vec<CatalogProject>) -> Catalog {
+        Catalog {
+            version: CATALOG_VERSION,
+            projects,
+        }
+    }
+
+    #[test]
+    fn test_catalog_diff() {
+        let current = catalog(vec![project("alpha", &["a1", "a2"]), project("beta", &["b1"])]);
+        assert_eq!(current.diff(&current), Default::default());
+
+        let mut changed = project("alpha", &["a1", "a3"]);
+        changed.technologies = vec!["wasm".to_owned()];
+        changed.repositories[0].url = "https://github.com/example/moved".to_owned();
+        let desired = catalog(vec![changed, project("gamma", &["g1"])]);
+
+        let diff = current.diff(&desired);
+        assert_eq!(diff.projects.created, vec!["gamma"]);
+        assert_eq!(
+            diff.projects.updated,
+            vec![CatalogUpdate {
+                slug: "alpha".to_owned(),
+                fields: vec!["technologies".to_owned()]
+            }]
+        );
+        assert_eq!(diff.projects.deleted, vec!["beta"]);
+        assert_eq!(diff.repositories.created, vec!["alpha/a3", "gamma/g1"]);
+        assert_eq!(
+            diff.repositories.updated,
+            vec![CatalogUpdate {
+                slug: "alpha/a1".to_owned(),
+                fields: vec!["url".to_owned()]
+            }]
+        );
+        assert_eq!(diff.repositories.deleted, vec!["alpha/a2", "beta/b1"]);
+
+        // a repository found under another project is moved, not recreated
+        let desired = catalog(vec![project("alpha", &["a1", "a2", "b1"])]);
+        let diff = current.diff(&desired);
+        assert_eq!(diff.projects.deleted, vec!["beta"]);
+        assert_eq!(
+            diff.repositories,
+            CatalogChanges {
+                updated: vec![CatalogUpdate {
+                    slug: "alpha/b1".to_owned(),
+                    fields: vec!["project".to_owned()]
+                }],
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_catalog_validation() {
+        assert!(catalog(vec![project("alpha", &["a1"]), project("beta", &["b1"])]).validate().is_ok());
+        // repository slugs are unique across projects
+        assert_eq!(
+            catalog(vec![project("alpha", &["a1"]), project("beta", &["a1"])]).validate(),
+            Err(CatalogError::DuplicateSlug("beta/a1".to_owned()))
+        );
+        assert_eq!(
+            catalog(vec![project("alpha", &[]), project("alpha", &[])]).validate(),
+            Err(CatalogError::DuplicateSlug("alpha".to_owned()))
+        );
+        assert_eq!(
+            catalog(vec![project("alpha", &["a1", "a1"])]).validate(),
+            Err(CatalogError::DuplicateSlug("alpha/a1".to_owned()))
+        );
+        assert_eq!(
+            Catalog { version: 2, projects: vec![] }.validate(),
+            Err(CatalogError::UnsupportedVersion(2))
+        );
+    }
+
+    #[test]
+    fn test_parse_catalog_as_json_or_yaml() {
+        let expected = catalog(vec![project("alpha", &["a1"])]);
+        let json = serde_json::to_vec(&expected).unwrap();
+        assert_eq!(parse_catalog(None, &json), Ok(expected.clone()));
+        assert_eq!(parse_catalog(Some("application/json"), &json), Ok(expected.clone()));
+
+        let yaml = serde_yaml::to_string(&expected).unwrap();
+        assert_eq!(parse_catalog(Some("application/yaml"), yaml.as_bytes()), Ok(expected));
+
+        let minimal = "version: 1\nprojects:\n  - name: Alpha\n    slug: alpha\n";
+        let parsed = parse_catalog(Some("text/yaml"), minimal.as_bytes()).unwrap();
+        assert!(parsed.projects[0].repositories.is_empty());
+
+        assert!(matches!(
+            parse_catalog(None, br#"{"version": 1, "projects": [{"slug": "x"}]}"#),
+            Err(CatalogError::InvalidDocument(_))
+        ));
+        assert!(matches!(
+            parse_catalog(None, br#"{"version": 1, "extra": true}"#),
+            Err(CatalogError::InvalidDocument(_))
+        ));
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_catalog_import_round_trip() {
+        let db = generate_test_database().await;
+        let mut desired = db.export_catalog().unwrap();
+        desired.projects.retain(|p| !p.slug.starts_with("catalog-"));
+        desired.projects.push(project("catalog-alpha", &["ca1", "ca2"]));
+        desired.projects.push(project("catalog-beta", &["cb1"]));
+
+        let planned = db.import_catalog(&desired, true).unwrap();
+        assert!(planned.dry_run);
+        assert_eq!(planned.projects.created, vec!["catalog-alpha", "catalog-beta"]);
+        assert!(!db.export_catalog().unwrap().projects.iter().any(|p| p.slug == "catalog-alpha"));
+
+        let applied = db.import_catalog(&desired, false).unwrap();
+        assert_eq!(applied.projects, planned.projects);
+        let exported = db.export_catalog().unwrap();
+        let alpha = exported.projects.iter().find(|p| p.slug == "catalog-alpha").unwrap();
+        assert_eq!(alpha, &project("catalog-alpha", &["ca1", "ca2"]));
+        assert_eq!(db.import_catalog(&exported, false).unwrap().projects, Default::default());
+
+        // dropping a repository and a project deletes them
+        let mut trimmed = exported.clone();
+        trimmed.projects.retain(|p| p.slug != "catalog-beta");
+        let alpha = trimmed.projects.iter_mut().find(|p| p.slug == "catalog-alpha").unwrap();
+        alpha.repositories.retain(|r| r.slug != "ca2");
+        alpha.rewards = true;
+        let diff = db.import_catalog(&trimmed, false).unwrap();
+        assert_eq!(diff.projects.deleted, vec!["catalog-beta"]);
+        assert_eq!(diff.projects.updated[0].fields, vec!["rewards"]);
+        assert_eq!(diff.repositories.deleted, vec!["catalog-alpha/ca2", "catalog-beta/cb1"]);
+        assert_eq!(db.export_catalog().unwrap(), trimmed);
+
+        // a repository listed under another project moves there
+        let stored = DBRepository::by_slug(&db, "ca1").unwrap().unwrap();
+        let mut moved = trimmed.clone();
+        let alpha = moved.projects.iter_mut().find(|p| p.slug == "catalog-alpha").unwrap();
+        alpha.repositories.clear();
+        let mut delta = project("catalog-delta", &["ca1"]);
+        delta.repositories[0].name = "moved".to_owned();
+        moved.projects.push(delta);
+        let planned = db.import_catalog(&moved, true).unwrap();
+        assert_eq!(planned.projects.created, vec!["catalog-delta"]);
+        assert_eq!(
+            planned.repositories,
+            CatalogChanges {
+                updated: vec![CatalogUpdate {
+                    slug: "catalog-delta/ca1".to_owned(),
+                    fields: vec!["project".to_owned(), "name".to_owned()]
+                }],
+                ..Default::default()
+            }
+        );
+        let applied = db.import_catalog(&moved, false).unwrap();
+        assert_eq!(applied.repositories, planned.repositories);
+        let exported = db.export_catalog().unwrap();
+        let delta = exported.projects.iter().find(|p| p.slug == "catalog-delta").unwrap();
+        assert_eq!(delta.repositories[0].name, "moved");
+        assert_eq!(DBRepository::by_slug(&db, "ca1").unwrap().unwrap().id, stored.id);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_catalog_import_rejects_unknown_terms() {
+        let db = generate_test_database().await;
+        diesel::sql_query("INSERT INTO users (username, github_id) VALUES ('catalog-admin', 9101)")
+            .execute(&mut db.get_db_conn().unwrap())
+            .unwrap();
+        grant_role(&db, "catalog-admin", "Admin", None).unwrap();
+        let user = GitHubUser {
+            id: 9101,
+            username: "catalog-admin".to_owned(),
+            avatar_url: String::new(),
+            email: None,
+        };
+
+        let mut gamma = project("catalog-gamma", &[]);
+        gamma.purposes = vec!["catalog-unknown".to_owned()];
+        let body = serde_json::to_vec(&catalog(vec![gamma])).unwrap();
+        let rejection = import_handler(user, ImportParams::default(), None, Bytes::from(body), db.clone())
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(
+            rejection.find::<CatalogError>(),
+            Some(&CatalogError::UnknownTerms(
+                "catalog-gamma".to_owned(),
+                "purposes: catalog-unknown".to_owned()
+            ))
+        );
+        assert!(!db.export_catalog().unwrap().projects.iter().any(|p| p.slug == "catalog-gamma"));
+    }
+}
@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use warp::{
+        http::{HeaderMap, HeaderValue, StatusCode},
+        hyper::{body, Body, Request},
+        Filter,
+    };
+
+    use crate::{
+        errors::{error_handler, ErrorResponse},
+        middlewares::context::{current_request_id, handle, remote_addr, request_id, REQUEST_ID_HEADER},
+    };
+
+    fn headers(id: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_str(id).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_request_ids_are_propagated_or_generated() {
+        assert_eq!(request_id(&headers("abc-123")), "abc-123");
+        assert_eq!(request_id(&headers(" abc-123 ")), "abc-123");
+
+        let generated = request_id(&HeaderMap::new());
+        assert_eq!(generated.len(), 32);
+        assert!(generated.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(generated, request_id(&HeaderMap::new()));
+
+        for invalid in ["", "has space", &"x".repeat(129)] {
+            let id = request_id(&headers(invalid));
+            assert_ne!(id, invalid);
+            assert_eq!(id.len(), 32);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_id_is_echoed_in_errors() {
+        let api = warp::path!("ping")
+            .map(|| current_request_id().unwrap_or_default())
+            .recover(error_handler);
+        let service = warp::service(api);
+        let remote: SocketAddr = ([127, 0, 0, 1], 5000).into();
+
+        let request = Request::get("/ping")
+            .header(REQUEST_ID_HEADER, "req-1")
+            .body(Body::empty())
+            .unwrap();
+        let resp = handle(service, remote, request).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()[REQUEST_ID_HEADER], "req-1");
+        assert_eq!(body::to_bytes(resp.into_body()).await.unwrap(), "req-1");
+
+        let request = Request::get("/missing").body(Body::empty()).unwrap();
+        let resp = handle(service, remote, request).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        let id = resp.headers()[REQUEST_ID_HEADER].to_str().unwrap().to_owned();
+        let error: ErrorResponse = serde_json::from_slice(&body::to_bytes(resp.into_body()).await.unwrap()).unwrap();
+        assert_eq!(error.request_id, Some(id));
+    }
+
+    #[tokio::test]
+    async fn test_remote_addr_comes_from_the_server() {
+        let api = remote_addr().map(|addr: Option<SocketAddr>| addr.map(|a| a.to_string()).unwrap_or_default());
+        let service = warp::service(api);
+        let remote: SocketAddr = ([10, 1, 2, 3], 4321).into();
+
+        let request = Request::get("/").body(Body::empty()).unwrap();
+        let resp = handle(service, remote, request).await.unwrap();
+        assert_eq!(body::to_bytes(resp.into_body()).await.unwrap(), "10.1.2.3:4321");
+    }
+}
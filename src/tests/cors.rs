@@ -0,0 +1,141 @@
+#[cfg(test)]
+mod tests {
+    use warp::{http::StatusCode, test::request, Filter, Reply};
+
+    use crate::{
+        config::{CorsConfig, Environment},
+        errors::error_handler,
+        middlewares::cors::{preflight, with_cors, CorsPolicy, Origin, OriginPattern},
+    };
+
+    fn policy(origins: &[&str]) -> CorsPolicy {
+        CorsPolicy::new(
+            &CorsConfig {
+                allowed_origins: origins.iter().map(|origin| origin.to_string()).collect(),
+                max_age_seconds: 600,
+            },
+            Environment::Production,
+        )
+    }
+
+    fn api(policy: CorsPolicy) -> impl Filter<Extract = (impl Reply,), Error = std::convert::Infallible> + Clone {
+        let ping = warp::path!("ping").map(|| "pong").recover(error_handler);
+        preflight(policy.clone())
+            .or(with_cors(policy, ping))
+            .unify()
+            .recover(error_handler)
+    }
+
+    #[test]
+    fn test_origin_patterns() {
+        let matches = |pattern: &str, origin: &str| {
+            OriginPattern::parse(pattern)
+                .unwrap()
+                .matches(&Origin::parse(origin).unwrap())
+        };
+
+        assert!(matches("https://morekudos.com", "https://morekudos.com"));
+        assert!(matches("https://morekudos.com", "https://MoreKudos.com"));
+        assert!(!matches("https://morekudos.com", "http://morekudos.com"));
+        assert!(!matches("https://morekudos.com", "https://morekudos.com:8443"));
+        assert!(matches("http://localhost:3000", "http://localhost:3000"));
+        assert!(!matches("http://localhost:3000", "http://localhost:3001"));
+
+        assert!(matches("https://*.morekudos.com", "https://app.morekudos.com"));
+        assert!(matches("https://*.morekudos.com", "https://a.b.morekudos.com"));
+        assert!(!matches("https://*.morekudos.com", "https://morekudos.com"));
+        assert!(!matches("https://*.morekudos.com", "https://evilmorekudos.com"));
+        assert!(!matches("https://*.morekudos.com", "https://morekudos.com.evil.com"));
+
+        assert!(matches("*", "https://anything.example"));
+
+        for invalid in ["morekudos.com", "ftp://morekudos.com", "https://", "https://a.*.com", "https://morekudos.com/app"] {
+            assert!(OriginPattern::parse(invalid).is_err(), "'{invalid}' should be rejected");
+        }
+    }
+
+    #[test]
+    fn test_environment_defaults() {
+        let empty = CorsConfig::default();
+        assert!(CorsPolicy::origins(&empty, Environment::Development).contains(&"http://localhost:3000".to_owned()));
+        assert!(!CorsPolicy::origins(&empty, Environment::Production).contains(&"http://localhost:3000".to_owned()));
+
+        let production = CorsPolicy::new(&empty, Environment::Production);
+        assert!(production.allows("https://app.morekudos.com"));
+        assert!(!production.allows("http://localhost:3000"));
+    }
+
+    #[tokio::test]
+    async fn test_allowed_origin() {
+        let r = api(policy(&["https://*.morekudos.com"]));
+        let resp = request()
+            .path("/ping")
+            .header("origin", "https://app.morekudos.com")
+            .reply(&r)
+            .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["access-control-allow-origin"], "https://app.morekudos.com");
+        assert_eq!(resp.headers()["access-control-allow-credentials"], "true");
+        assert_eq!(resp.headers()["vary"], "Origin");
+    }
+
+    #[tokio::test]
+    async fn test_rejected_origin() {
+        let r = api(policy(&["https://*.morekudos.com"]));
+        let resp = request()
+            .path("/ping")
+            .header("origin", "https://evil.example")
+            .reply(&r)
+            .await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        assert!(resp.headers().get("access-control-allow-origin").is_none());
+        assert_eq!(resp.headers()["vary"], "Origin");
+    }
+
+    #[tokio::test]
+    async fn test_request_without_origin() {
+        let r = api(policy(&["https://morekudos.com"]));
+        let resp = request().path("/ping").reply(&r).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers().get("access-control-allow-origin").is_none());
+        assert_eq!(resp.headers()["vary"], "Origin");
+    }
+
+    #[tokio::test]
+    async fn test_preflight() {
+        let r = api(policy(&["https://morekudos.com"]));
+        let resp = request()
+            .method("OPTIONS")
+            .path("/ping")
+            .header("origin", "https://morekudos.com")
+            .header("access-control-request-method", "DELETE")
+            .header("access-control-request-headers", "Authorization, Content-Type")
+            .reply(&r)
+            .await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        assert_eq!(resp.headers()["access-control-allow-origin"], "https://morekudos.com");
+        assert_eq!(resp.headers()["access-control-max-age"], "600");
+        assert!(resp.headers()["access-control-allow-methods"].to_str().unwrap().contains("DELETE"));
+        assert_eq!(resp.headers()["vary"], "Origin");
+
+        let resp = request()
+            .method("OPTIONS")
+            .path("/ping")
+            .header("origin", "https://evil.example")
+            .header("access-control-request-method", "GET")
+            .reply(&r)
+            .await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        assert!(resp.headers().get("access-control-allow-origin").is_none());
+
+        let resp = request()
+            .method("OPTIONS")
+            .path("/ping")
+            .header("origin", "https://morekudos.com")
+            .header("access-control-request-method", "GET")
+            .header("access-control-request-headers", "X-Unknown")
+            .reply(&r)
+            .await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    }
+}
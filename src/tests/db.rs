@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use diesel::{
+        r2d2::{ConnectionManager, Pool},
+        PgConnection, RunQueryDsl,
+    };
+    use warp::{http::StatusCode, test::request, Filter};
+
+    use crate::{
+        api::{health::routes::routes, jobs::runner::Heartbeat},
+        config::DatabaseConfig,
+        db::{
+            errors::DBError,
+            pool::{DBAccess, DBAccessor},
+        },
+        errors::error_handler,
+        tests::utils::generate_test_database,
+    };
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore]
+    async fn test_exhausted_pool_is_unavailable() {
+        let database_url = std::env::var("DATABASE_URL").expect("missing DATABASE");
+        let pool = Pool::builder()
+            .max_size(1)
+            .connection_timeout(Duration::from_millis(200))
+            .build(ConnectionManager::<PgConnection>::new(database_url))
+            .unwrap();
+        let db = DBAccess::new(pool);
+
+        let _held = db.get_db_conn().unwrap();
+        let r = routes(db.clone(), Heartbeat::default()).recover(error_handler);
+        let resp = request().path("/health").reply(&r).await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore]
+    async fn test_slow_query_times_out() {
+        generate_test_database().await;
+        let config = DatabaseConfig {
+            url: std::env::var("DATABASE_URL").expect("missing DATABASE"),
+            pool_max_open: 1,
+            pool_min_idle: 0,
+            statement_timeout_seconds: 1,
+            ..DatabaseConfig::default()
+        };
+        let db = DBAccess::from_config(&config).unwrap();
+
+        let result = db.with_conn(|conn| {
+            diesel::sql_query("SELECT pg_sleep(3)").execute(conn)?;
+            Ok(())
+        });
+        assert!(matches!(result, Err(DBError::DBTimeout(_))), "{result:?}");
+
+        // failing fast is an error of the query, not a timeout
+        let result = db.with_conn(|conn| {
+            diesel::sql_query("SELECT 1 / 0").execute(conn)?;
+            Ok(())
+        });
+        assert!(matches!(result, Err(DBError::DBQuery(_))), "{result:?}");
+    }
+}
@@ -0,0 +1,228 @@
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use diesel::{debug_query, pg::Pg, prelude::*};
+
+    use crate::{
+        api::{
+            filters::{IssueFilter, ItemFilter, ItemSource, LabelMatch, ProjectFilter},
+            issues::{db::DBIssue, models::NewIssue},
+            projects::{
+                db::DBProject,
+                models::{FacetCount, FacetRow, NewProject, ProjectOptions, QueryParams},
+            },
+            repositories::{db::DBRepository, models::NewRepository},
+        },
+        db::pool::DBAccessor,
+        schema::{issues, tasks},
+        tests::utils::generate_test_database,
+    };
+
+    fn tags(values: &[&str]) -> Option<Vec<Option<String>>> {
+        Some(values.iter().map(|v| Some(v.to_string())).collect())
+    }
+
+    fn row(project_id: i32, purposes: &[&str], language: Option<&str>) -> FacetRow {
+        FacetRow {
+            project_id,
+            types: None,
+            purposes: tags(purposes),
+            technologies: None,
+            stack_levels: None,
+            language_slug: language.map(str::to_owned),
+        }
+    }
+
+    fn facet(value: &str, count: i64) -> FacetCount {
+        FacetCount {
+            value: value.to_owned(),
+            count,
+        }
+    }
+
+    fn params() -> QueryParams {
+        QueryParams {
+            slugs: None,
+            purposes: None,
+            stack_levels: None,
+            technologies: None,
+            rewards: None,
+            certified: None,
+            open: None,
+            labels: None,
+            excluded_labels: None,
+            label_match: None,
+            certified_or_labels: None,
+            types: None,
+            source: None,
+            include_archived: None,
+        }
+    }
+
+    #[test]
+    fn test_options_count_projects_per_value() {
+        // a project appears once per repository, but counts once per value
+        let rows = vec![
+            row(1, &["defi", "gaming"], Some("rust")),
+            row(1, &["defi", "gaming"], Some("rust")),
+            row(2, &["defi"], Some("typescript")),
+            row(2, &["defi"], Some("rust")),
+            row(3, &["tooling"], None),
+        ];
+        let options = ProjectOptions::from_rows(&rows);
+        assert_eq!(options.purposes, vec![facet("defi", 2), facet("gaming", 1), facet("tooling", 1)]);
+        assert_eq!(options.languages, vec![facet("rust", 2), facet("typescript", 1)]);
+        assert!(options.types.is_empty());
+        assert_eq!(ProjectOptions::from_rows(&[]), ProjectOptions::default());
+    }
+
+    #[test]
+    fn test_item_filter_combines_labels_and_certification() {
+        let sql = |filter: &ItemFilter| {
+            let query = issues::table.select(issues::id).filter(filter.issue_predicate().unwrap());
+            debug_query::<Pg, _>(&query).to_string()
+        };
+        let mut filter = ItemFilter::new(&Some("bug,help".to_owned()), &None, None, Some(true), None, Some(true));
+        let both = sql(&filter);
+        assert!(both.contains(r#""issues"."labels" && $1) AND ("issues"."certified" = $2)"#), "{both}");
+        assert!(both.contains(r#""issues"."open" = $3"#), "{both}");
+
+        filter.certified_or_labels = true;
+        let either = sql(&filter);
+        assert!(either.contains(r#""issues"."labels" && $1) OR ("issues"."certified" = $2)"#), "{either}");
+
+        assert!(ItemFilter::default().issue_predicate().is_none());
+        assert!(ItemFilter::default().is_empty());
+    }
+
+    #[test]
+    fn test_item_filter_label_matching_and_negation() {
+        let all = ItemFilter::new(&Some("bug,help".to_owned()), &None, Some(LabelMatch::All), None, None, None);
+        let query = tasks::table.select(tasks::id).filter(all.task_predicate().unwrap());
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(sql.contains(r#""tasks"."labels" @> $1"#), "{sql}");
+        assert!(sql.contains(r#"["bug", "help"]"#), "{sql}");
+
+        let excluded = ItemFilter::new(&None, &Some("wontfix".to_owned()), None, Some(true), None, None);
+        assert!(!excluded.is_empty());
+        let query = tasks::table.select(tasks::id).filter(excluded.task_predicate().unwrap());
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(
+            sql.contains(r#"("tasks"."is_certified" = $1) AND (("tasks"."labels" IS NULL) OR  NOT (("tasks"."labels" && $2)))"#),
+            "{sql}"
+        );
+    }
+
+    #[test]
+    fn test_issue_filter_uses_subqueries_for_related_tables() {
+        let filter = IssueFilter {
+            project: ProjectFilter {
+                slugs: Some(vec!["alpha".to_owned()]),
+                ..Default::default()
+            },
+            language_slugs: Some(vec!["rust".to_owned()]),
+            has_assignee: Some(false),
+            ..Default::default()
+        };
+        let query = issues::table.select(issues::id).filter(filter.predicate().unwrap());
+        let sql = debug_query::<Pg, _>(&query).to_string();
+        assert!(
+            sql.contains(r#""issues"."repository_id" = ANY(SELECT "repositories"."id" FROM "repositories" WHERE ("repositories"."project_id" = ANY(SELECT "projects"."id" FROM "projects" WHERE (("projects"."slug" = ANY($1)) OR ("projects"."id" = ANY(SELECT "slug_history"."project_id" FROM "slug_history" WHERE ("slug_history"."slug" = ANY($2)))))))"#),
+            "{sql}"
+        );
+        assert!(sql.contains(r#""repositories"."language_slug" = ANY($3)"#), "{sql}");
+        assert!(sql.contains(r#""issues"."assignee_id" IS NULL"#), "{sql}");
+        assert!(IssueFilter::default().predicate().is_none());
+        assert!(ProjectFilter::default().ids().is_none());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_options_from_issues_and_tasks() {
+        let db = generate_test_database().await;
+        let project = |slug: &str, purposes: &[&str]| {
+            DBProject::create(
+                &db,
+                &NewProject {
+                    name: slug.to_owned(),
+                    slug: slug.to_owned(),
+                    types: None,
+                    purposes: tags(purposes),
+                    stack_levels: None,
+                    technologies: None,
+                    avatar: None,
+                    rewards: Some(false),
+                },
+            )
+            .unwrap()
+        };
+        let alpha = project("options-alpha", &["defi"]);
+        let beta = project("options-beta", &["defi", "gaming"]);
+        let repository = |project_id: i32, slug: &str, language: &str| {
+            DBRepository::create(
+                &db,
+                &NewRepository {
+                    slug: slug.to_owned(),
+                    name: slug.to_owned(),
+                    url: format!("https://github.com/options/{slug}"),
+                    language_slug: Some(language.to_owned()),
+                    project_id,
+                },
+            )
+            .unwrap()
+        };
+        let alpha_repo = repository(alpha.id, "options-a1", "rust");
+        repository(alpha.id, "options-a2", "go");
+        let beta_repo = repository(beta.id, "options-b1", "rust");
+        let issue = |repository_id: i32, number: i32, labels: &[&str]| {
+            DBIssue::create(
+                &db,
+                &NewIssue {
+                    number,
+                    title: "options".to_owned(),
+                    labels: Some(labels.iter().map(|l| l.to_string()).collect()),
+                    open: true,
+                    certified: Some(false),
+                    repository_id,
+                    assignee_id: None,
+                    issue_created_at: Utc::now(),
+                    description: None,
+                    estimation: None,
+                },
+            )
+            .unwrap()
+        };
+        issue(alpha_repo.id, 1, &["bug"]);
+        issue(beta_repo.id, 1, &["feature"]);
+        diesel::sql_query(format!(
+            "INSERT INTO tasks (title, type, labels, project_id) VALUES ('options task', 'dev', '{{bug}}', {})",
+            beta.id
+        ))
+        .execute(&mut db.get_db_conn().unwrap())
+        .unwrap();
+
+        let mut query = params();
+        query.slugs = Some("options-alpha,options-beta".to_owned());
+        let options = db.options(query).unwrap();
+        assert_eq!(options.purposes, vec![facet("defi", 2), facet("gaming", 1)]);
+        assert_eq!(options.languages, vec![facet("rust", 2), facet("go", 1)]);
+
+        let mut query = params();
+        query.slugs = Some("options-alpha,options-beta".to_owned());
+        query.labels = Some("bug".to_owned());
+        assert_eq!(db.options(query).unwrap().purposes, vec![facet("defi", 1)]);
+
+        let mut query = params();
+        query.slugs = Some("options-alpha,options-beta".to_owned());
+        query.excluded_labels = Some("bug".to_owned());
+        assert_eq!(db.options(query).unwrap().purposes, vec![facet("defi", 1), facet("gaming", 1)]);
+
+        let mut query = params();
+        query.slugs = Some("options-alpha,options-beta".to_owned());
+        query.labels = Some("bug".to_owned());
+        query.source = Some(ItemSource::Tasks);
+        let options = db.options(query).unwrap();
+        assert_eq!(options.purposes, vec![facet("defi", 1), facet("gaming", 1)]);
+        assert_eq!(options.languages, vec![facet("rust", 1)]);
+    }
+}
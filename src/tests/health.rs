@@ -1,36 +1,152 @@
 #[cfg(test)]
 mod tests {
     use crate::{
-        api::health::{db::DBHealth, routes::routes},
-        db::errors::DBError,
+        api::{
+            health::{
+                db::DBHealth,
+                models::{CheckStatus, HealthReport, PoolState},
+                routes::routes,
+            },
+            jobs::runner::{Heartbeat, JobRunner},
+        },
+        db::{
+            errors::DBError,
+            pool::{DBAccess, DBAccessor},
+        },
         tests::utils::generate_test_database,
     };
+    use diesel::{
+        r2d2::{ConnectionManager, Pool},
+        PgConnection,
+    };
+    use tokio_util::sync::CancellationToken;
     use warp::test::request;
 
     #[derive(Clone)]
-    pub struct DBMock {}
+    pub struct DBMock {
+        pending: Vec<String>,
+        pool: PoolState,
+    }
+
+    impl Default for DBMock {
+        fn default() -> Self {
+            Self {
+                pending: Vec::new(),
+                pool: PoolState {
+                    connections: 4,
+                    idle_connections: 3,
+                    max_connections: 4,
+                },
+            }
+        }
+    }
 
     impl DBHealth for DBMock {
         fn health(&self) -> Result<(), DBError> {
             Ok(())
         }
+
+        fn pending_migrations(&self) -> Result<Vec<String>, DBError> {
+            Ok(self.pending.clone())
+        }
+
+        fn pool_state(&self) -> PoolState {
+            self.pool
+        }
+    }
+
+    fn beating() -> Heartbeat {
+        let heartbeat = Heartbeat::default();
+        heartbeat.beat();
+        heartbeat
     }
 
     #[tokio::test]
     async fn test_health_mock_db() {
-        let r = routes(DBMock {});
+        let r = routes(DBMock::default(), Heartbeat::default());
         let resp = request().path("/health").reply(&r).await;
         assert_eq!(resp.status(), 200);
         assert!(resp.body().is_empty());
+
+        let resp = request().method("POST").path("/health").reply(&r).await;
+        assert_eq!(resp.status(), 405);
+    }
+
+    #[tokio::test]
+    async fn test_live() {
+        let r = routes(DBMock::default(), Heartbeat::default());
+        let resp = request().path("/health/live").reply(&r).await;
+        assert_eq!(resp.status(), 200);
+        let report: HealthReport = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(report.status, CheckStatus::Ok);
+    }
+
+    #[tokio::test]
+    async fn test_ready() {
+        let r = routes(DBMock::default(), beating());
+        let resp = request().path("/health/ready").reply(&r).await;
+        assert_eq!(resp.status(), 200);
+        let report: HealthReport = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(report.status, CheckStatus::Ok);
+        let checks: Vec<&String> = report.checks.keys().collect();
+        assert_eq!(checks, ["database", "jobs", "migrations", "pool"]);
+        assert_eq!(report.checks["pool"].detail.as_deref(), Some("1 of 4 connections in use"));
+    }
+
+    #[tokio::test]
+    async fn test_ready_without_jobs() {
+        // a runner without jobs returns right away, before any query
+        let manager = ConnectionManager::<PgConnection>::new("postgres://localhost:1/none");
+        let db = DBAccess::new(Pool::builder().min_idle(Some(0)).build_unchecked(manager));
+        let runner = JobRunner::new(db);
+        let heartbeat = runner.heartbeat();
+        runner.start(CancellationToken::new()).await;
+
+        let r = routes(DBMock::default(), heartbeat);
+        let resp = request().path("/health/ready").reply(&r).await;
+        assert_eq!(resp.status(), 200);
+        let report: HealthReport = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(report.checks["jobs"].status, CheckStatus::Ok);
+        assert_eq!(report.checks["jobs"].detail.as_deref(), Some("no jobs registered"));
+    }
+
+    #[tokio::test]
+    async fn test_not_ready() {
+        let db = DBMock {
+            pending: vec!["2026-10-19-170000_next".to_owned()],
+            pool: PoolState {
+                connections: 4,
+                idle_connections: 0,
+                max_connections: 4,
+            },
+        };
+        let r = routes(db, Heartbeat::default());
+        let resp = request().path("/health/ready").reply(&r).await;
+        assert_eq!(resp.status(), 503);
+        let report: HealthReport = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(report.status, CheckStatus::Fail);
+        assert_eq!(report.checks["database"].status, CheckStatus::Ok);
+        for check in ["jobs", "migrations", "pool"] {
+            assert_eq!(report.checks[check].status, CheckStatus::Fail, "{check}");
+        }
+        assert_eq!(
+            report.checks["migrations"].detail.as_deref(),
+            Some("pending: 2026-10-19-170000_next")
+        );
     }
 
     #[tokio::test]
     #[ignore]
     async fn test_health_db() {
         let db = generate_test_database().await;
-        let r = routes(db);
+        let r = routes(db, beating());
         let resp = request().path("/health").reply(&r).await;
         assert_eq!(resp.status(), 200);
         assert!(resp.body().is_empty());
+
+        let resp = request().path("/health/ready").reply(&r).await;
+        let report: HealthReport = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(report.checks["database"].status, CheckStatus::Ok);
+        assert_eq!(report.checks["migrations"].status, CheckStatus::Ok);
     }
 }
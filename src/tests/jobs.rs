@@ -0,0 +1,125 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+
+    use crate::{
+        api::jobs::{
+            db::DBJob,
+            models::{JobOutcome, JobRunsParams, NewJob},
+            runner::{failure_backoff, next_run_at},
+            schedule::Schedule,
+        },
+        tests::utils::generate_test_database,
+        types::PaginationParams,
+    };
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0).unwrap()
+    }
+
+    fn next(expression: &str, after: DateTime<Utc>) -> DateTime<Utc> {
+        expression.parse::<Schedule>().unwrap().next_after(after).unwrap()
+    }
+
+    #[test]
+    fn test_parse_schedules() {
+        assert_eq!("@every 30d".parse::<Schedule>(), Ok(Schedule::Every(Duration::days(30))));
+        assert_eq!("@every 15m".parse::<Schedule>(), Ok(Schedule::Every(Duration::minutes(15))));
+        assert!("@hourly".parse::<Schedule>().is_ok());
+        assert!("*/5 1-3,22 * * mon".parse::<Schedule>().is_err());
+        assert!("*/5 1-3,22 * * 1-5".parse::<Schedule>().is_ok());
+
+        for invalid in ["", "@every", "@every 0d", "@every 10w", "@every 1000d", "* * * *", "60 * * * *", "* * 0 * *", "*/0 * * * *", "5-1 * * * *", "0 0 30 2 *"] {
+            assert!(invalid.parse::<Schedule>().is_err(), "'{invalid}' should be rejected");
+        }
+    }
+
+    #[test]
+    fn test_next_after() {
+        let now = at(2026, 10, 19, 10, 7);
+        assert_eq!(next("@every 6h", now), now + Duration::hours(6));
+        assert_eq!(next("@hourly", now), at(2026, 10, 19, 11, 0));
+        assert_eq!(next("@daily", now), at(2026, 10, 20, 0, 0));
+        // 2026-10-19 is a Monday
+        assert_eq!(next("@weekly", now), at(2026, 10, 25, 0, 0));
+        assert_eq!(next("0 9 * * 7", now), at(2026, 10, 25, 9, 0));
+        assert_eq!(next("@monthly", now), at(2026, 11, 1, 0, 0));
+        assert_eq!(next("*/15 * * * *", now), at(2026, 10, 19, 10, 15));
+        assert_eq!(next("5/20 10 * * *", now), at(2026, 10, 19, 10, 25));
+        assert_eq!(next("30 8 * * 1-5", now), at(2026, 10, 20, 8, 30));
+        assert_eq!(next("0 0 1 1 *", now), at(2027, 1, 1, 0, 0));
+        assert_eq!(next("0 0 29 2 *", now), at(2028, 2, 29, 0, 0));
+        // day of month and day of week are OR-ed when both are restricted
+        assert_eq!(next("0 12 1 * 6", now), at(2026, 10, 24, 12, 0));
+        // always strictly after
+        assert_eq!(next("7 10 * * *", now), at(2026, 10, 20, 10, 7));
+    }
+
+    #[test]
+    fn test_failure_backoff() {
+        assert_eq!(failure_backoff(1), Duration::minutes(1));
+        assert_eq!(failure_backoff(3), Duration::minutes(4));
+        assert_eq!(failure_backoff(30), Duration::hours(1));
+
+        let daily: Schedule = "@daily".parse().unwrap();
+        let now = at(2026, 10, 19, 10, 7);
+        assert_eq!(next_run_at(&daily, now, 0), at(2026, 10, 20, 0, 0));
+        assert_eq!(next_run_at(&daily, now, 2), now + Duration::minutes(2));
+        // retries never push the job past its next scheduled run
+        let late = at(2026, 10, 19, 23, 30);
+        assert_eq!(next_run_at(&daily, late, 10), at(2026, 10, 20, 0, 0));
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_due_job_is_claimed_once() {
+        let db = generate_test_database().await;
+        let names = vec!["test_job".to_owned()];
+        let lease_until = Utc::now() + Duration::minutes(5);
+
+        db.register_job(&NewJob {
+            name: "test_job".to_owned(),
+            schedule: "@every 1h".to_owned(),
+            next_run_at: Utc::now() + Duration::hours(1),
+        })
+        .unwrap();
+        assert!(db.claim_due_job(&names, "runner-a", lease_until).unwrap().is_none());
+
+        // a new schedule reschedules the job
+        db.register_job(&NewJob {
+            name: "test_job".to_owned(),
+            schedule: "@every 1m".to_owned(),
+            next_run_at: Utc::now() - Duration::seconds(1),
+        })
+        .unwrap();
+        let (job, run) = db.claim_due_job(&names, "runner-a", lease_until).unwrap().unwrap();
+        assert_eq!(job.locked_by.as_deref(), Some("runner-a"));
+        assert_eq!(run.status, "running");
+        assert!(db.claim_due_job(&names, "runner-b", lease_until).unwrap().is_none());
+
+        db.finish_run(
+            &job,
+            run.id,
+            &JobOutcome {
+                error: Some("boom".to_owned()),
+                next_run_at: Utc::now() - Duration::seconds(1),
+            },
+        )
+        .unwrap();
+        let (job, _) = db.claim_due_job(&names, "runner-b", lease_until).unwrap().unwrap();
+        assert_eq!(job.consecutive_failures, 1);
+
+        let (runs, total_count) = db
+            .job_runs(
+                JobRunsParams {
+                    job_name: Some("test_job".to_owned()),
+                    status: Some("failed".to_owned()),
+                },
+                PaginationParams { limit: 10, offset: 0 },
+            )
+            .unwrap();
+        assert_eq!(total_count, 1);
+        assert_eq!(runs[0].error.as_deref(), Some("boom"));
+        assert_eq!(runs[0].runner, "runner-a");
+    }
+}
@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, fs, path::Path};
+
+    use diesel::{
+        r2d2::{ConnectionManager, Pool},
+        PgConnection,
+    };
+    use warp::{http::StatusCode, test::request, Filter};
+
+    use crate::{
+        api::metrics::routes::routes,
+        config::MetricsConfig,
+        db::pool::{DBAccess, DBAccessor},
+        errors::error_handler,
+        metrics::{metrics, record_request, route_label, ROUTES},
+    };
+
+    /// Paths the `routes.rs` files register, written as in `ROUTES`.
+    fn registered_routes() -> BTreeSet<String> {
+        let api = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/api");
+        let mut registered = BTreeSet::new();
+        for module in fs::read_dir(api).unwrap() {
+            let Ok(routes) = fs::read_to_string(module.unwrap().path().join("routes.rs")) else {
+                continue;
+            };
+            for (start, prefix) in routes.match_indices("warp::path!(") {
+                let rest = &routes[start + prefix.len()..];
+                let route: String = rest[..rest.find(')').unwrap()]
+                    .split('/')
+                    .map(|segment| match segment.trim() {
+                        "i32" => "/:id".to_owned(),
+                        "String" => "/:name".to_owned(),
+                        "ArchivalKind" => "/:kind".to_owned(),
+                        literal => format!("/{}", literal.trim_matches('"')),
+                    })
+                    .collect();
+                registered.insert(route);
+            }
+        }
+        registered
+    }
+
+    /// A pool that never connects, enough to report its state.
+    fn unconnected_db() -> DBAccess {
+        let manager = ConnectionManager::<PgConnection>::new("postgres://localhost:1/none");
+        DBAccess::new(Pool::builder().max_size(3).min_idle(Some(0)).build_unchecked(manager))
+    }
+
+    #[test]
+    fn test_routes_are_the_registered_ones() {
+        let routes: BTreeSet<String> = ROUTES.iter().map(|route| route.to_string()).collect();
+        assert_eq!(routes, registered_routes());
+    }
+
+    #[test]
+    fn test_route_labels() {
+        assert_eq!(route_label("/projects"), "/projects");
+        assert_eq!(route_label("/projects/12/stats/"), "/projects/:id/stats");
+        assert_eq!(route_label("/users/username/octocat"), "/users/username/:name");
+        assert_eq!(route_label("/tasks/votes/3"), "/tasks/votes/:id");
+        assert_eq!(route_label("/repositories/4/archive"), "/:kind/:id/archive");
+        assert_eq!(route_label("/"), "unmatched");
+        assert_eq!(route_label("/wp-admin/setup.php"), "unmatched");
+        // unknown paths under a known root don't make new labels either
+        assert_eq!(route_label("/projects/abc"), "unmatched");
+        assert_eq!(route_label("/issues/x7f3/x"), "unmatched");
+        assert_eq!(route_label("/users/4/archive"), "unmatched");
+    }
+
+    #[tokio::test]
+    async fn test_not_found_requests_are_unmatched() {
+        let api = warp::path!("projects" / i32)
+            .and_then(|_| async { Err::<&str, _>(warp::reject::not_found()) })
+            .recover(error_handler)
+            .with(warp::log::custom(record_request));
+        let resp = request().path("/projects/404404").reply(&api).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+        let exposed = metrics().encode();
+        assert!(exposed.contains(r#"kudos_http_requests_total{method="GET",route="unmatched",status="404"}"#));
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_recorded() {
+        let api = warp::path!("projects" / i32)
+            .map(|_| "project")
+            .with(warp::log::custom(record_request));
+        let resp = request().path("/projects/42").reply(&api).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let exposed = metrics().encode();
+        assert!(exposed.contains(r#"kudos_http_requests_total{method="GET",route="/projects/:id",status="200"}"#));
+        assert!(exposed.contains(r#"kudos_http_request_duration_seconds_bucket{method="GET",route="/projects/:id",status="200""#));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint() {
+        let r = routes(unconnected_db(), MetricsConfig::default()).recover(error_handler);
+        let resp = request().path("/metrics").reply(&r).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers()["content-type"].to_str().unwrap().starts_with("text/plain"));
+        let body = std::str::from_utf8(resp.body()).unwrap();
+        assert!(body.contains("kudos_db_pool_max_connections 3"));
+        assert!(body.contains("kudos_db_pool_idle_connections 0"));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_requires_the_token() {
+        let config = MetricsConfig {
+            bearer_token: "scrape-secret".to_owned(),
+            ..MetricsConfig::default()
+        };
+        let r = routes(unconnected_db(), config).recover(error_handler);
+
+        let resp = request().path("/metrics").reply(&r).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let resp = request()
+            .path("/metrics")
+            .header("authorization", "Bearer wrong")
+            .reply(&r)
+            .await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let resp = request()
+            .path("/metrics")
+            .header("authorization", "Bearer scrape-secret")
+            .reply(&r)
+            .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let disabled = MetricsConfig {
+            enabled: false,
+            ..MetricsConfig::default()
+        };
+        let r = routes(unconnected_db(), disabled).recover(error_handler);
+        let resp = request().path("/metrics").reply(&r).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+}
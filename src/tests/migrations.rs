@@ -0,0 +1,79 @@
+#[cfg(test)]
+mod tests {
+    use diesel::{sql_query, RunQueryDsl};
+    use diesel_migrations::MigrationHarness;
+
+    use crate::{
+        config::DatabaseConfig,
+        db::{
+            errors::DBError,
+            migrations::run_migrations,
+            pool::DBAccessor,
+            MIGRATIONS,
+        },
+        tests::utils::generate_random_database_name,
+        utils::setup_db,
+    };
+
+    /// A pool on a new, empty database next to the one of `DATABASE_URL`.
+    async fn empty_database() -> DatabaseConfig {
+        let database_url = std::env::var("DATABASE_URL").expect("missing DATABASE");
+        let admin = setup_db(&DatabaseConfig {
+            url: database_url.clone(),
+            pool_min_idle: 0,
+            ..Default::default()
+        })
+        .await;
+        let name = generate_random_database_name().to_lowercase();
+        sql_query(format!("CREATE DATABASE {name}"))
+            .execute(&mut admin.get_db_conn().unwrap())
+            .unwrap();
+
+        let mut url = url::Url::parse(&database_url).unwrap();
+        url.set_path(&name);
+        DatabaseConfig {
+            url: url.to_string(),
+            pool_max_open: 2,
+            pool_min_idle: 0,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore]
+    async fn test_migrations_run_on_startup_only_when_enabled() {
+        let mut config = empty_database().await;
+        let db = setup_db(&config).await;
+
+        assert_eq!(run_migrations(&db, &config).unwrap(), Vec::<String>::new());
+        let pending = db.get_db_conn().unwrap().pending_migrations(MIGRATIONS).unwrap();
+        assert!(!pending.is_empty());
+
+        config.migrate_on_startup = true;
+        let applied = run_migrations(&db, &config).unwrap();
+        assert_eq!(applied.len(), pending.len());
+        assert_eq!(applied[0], pending[0].name().to_string());
+        assert!(db.get_db_conn().unwrap().pending_migrations(MIGRATIONS).unwrap().is_empty());
+
+        // nothing left to do, and the lock was released
+        assert_eq!(run_migrations(&db, &config).unwrap(), Vec::<String>::new());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore]
+    async fn test_newer_schema_is_refused() {
+        let mut config = empty_database().await;
+        config.migrate_on_startup = true;
+        let db = setup_db(&config).await;
+        run_migrations(&db, &config).unwrap();
+
+        sql_query("INSERT INTO __diesel_schema_migrations (version) VALUES ('29991231000000')")
+            .execute(&mut db.get_db_conn().unwrap())
+            .unwrap();
+        let result = run_migrations(&db, &config);
+        match result {
+            Err(DBError::DBMigration(message)) => assert!(message.contains("29991231000000"), "{message}"),
+            other => panic!("expected a migration error, got {other:?}"),
+        }
+    }
+}
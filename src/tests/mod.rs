@@ -1,2 +1,23 @@
+pub mod admin;
+pub mod archival;
+pub mod catalog;
+pub mod config;
+pub mod context;
+pub mod cors;
+pub mod db;
+pub mod filters;
 pub mod health;
+pub mod jobs;
+pub mod metrics;
+pub mod migrations;
+pub mod notifications;
+pub mod project_detail;
+pub mod project_stats;
+pub mod rate_limit;
+pub mod shutdown;
+pub mod slug_history;
+pub mod subscriptions;
+pub mod taxonomy;
 pub mod utils;
+pub mod verification;
+pub mod webhooks;
@@ -0,0 +1,255 @@
+#[cfg(test)]
+mod tests {
+    use std::{env, time::Duration};
+
+    use diesel::prelude::*;
+    use warp::{
+        hyper::{body::HttpBody, Body},
+        Reply,
+    };
+
+    use crate::{
+        api::notifications::{
+            db::DBNotification,
+            handlers::stream_handler,
+            models::{DeleteNotification, NotificationEvent, NotificationKind, NotificationPreference, QueryParams},
+            stream::{start_notification_listener, CertificateCheck, ListenerTls, NotificationBroadcaster},
+        },
+        api::tasks::models::UpdateTask,
+        db::pool::{DBAccess, DBAccessor},
+        middlewares::github::model::GitHubUser,
+        schema::notifications,
+        tests::utils::generate_test_database,
+        types::PaginationParams,
+    };
+
+    fn execute(db: &DBAccess, statement: &str) {
+        diesel::sql_query(statement).execute(&mut db.get_db_conn().unwrap()).unwrap();
+    }
+
+    fn update_task(db: &DBAccess, id: i32, task: &UpdateTask, actor_id: Option<i32>) {
+        // imported here, its `all` and `delete` clash with the notification ones
+        use crate::api::tasks::db::DBTask;
+        DBTask::update(db, id, task, actor_id).unwrap();
+    }
+
+    /// Kinds of the user's notifications, sorted.
+    fn kinds(db: &DBAccess, github_id: i64) -> Vec<String> {
+        let (notifications, _) = db
+            .all(github_id, QueryParams::default(), PaginationParams { limit: 100, offset: 0 })
+            .unwrap();
+        let mut kinds: Vec<String> = notifications.into_iter().map(|n| n.kind).collect();
+        kinds.sort();
+        kinds
+    }
+
+    /// The next event sent on the stream.
+    async fn next_event(body: &mut Body) -> String {
+        let chunk = tokio::time::timeout(Duration::from_secs(5), body.data())
+            .await
+            .expect("no event sent")
+            .unwrap()
+            .unwrap();
+        String::from_utf8(chunk.to_vec()).unwrap()
+    }
+
+    fn event_id(event: &str) -> i32 {
+        let line = event.lines().find(|line| line.starts_with("id:")).expect("event without id");
+        line["id:".len()..].trim().parse().unwrap()
+    }
+
+    #[test]
+    fn test_listener_tls_follows_sslmode() {
+        let tls = ListenerTls::from_database_url("postgres://kudos@db:5432/kudos");
+        assert_eq!(tls.url, "postgres://kudos@db:5432/kudos");
+        assert_eq!(tls.check, CertificateCheck::None);
+
+        let tls = ListenerTls::from_database_url(
+            "postgres://kudos@db/kudos?sslmode=verify-full&sslrootcert=/etc/ca.pem&application_name=api",
+        );
+        assert_eq!(tls.url, "postgres://kudos@db/kudos?sslmode=require&application_name=api");
+        assert_eq!(tls.check, CertificateCheck::Full);
+        assert_eq!(tls.root_certificate.as_deref(), Some("/etc/ca.pem"));
+
+        let tls = ListenerTls::from_database_url("postgres://kudos@db/kudos?sslmode=verify-ca");
+        assert_eq!(tls.check, CertificateCheck::Authority);
+        let tls = ListenerTls::from_database_url("postgres://kudos@db/kudos?sslmode=require");
+        assert_eq!((tls.url.as_str(), tls.check), ("postgres://kudos@db/kudos?sslmode=require", CertificateCheck::None));
+        let tls = ListenerTls::from_database_url("host=db user=kudos sslmode=require");
+        assert_eq!(tls.url, "host=db user=kudos sslmode=require");
+    }
+
+    #[test]
+    fn test_notification_kind_matches_stored_value() {
+        for kind in NotificationKind::ALL {
+            let serialized = serde_json::to_value(kind).unwrap();
+            assert_eq!(serialized, kind.as_str());
+        }
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_listener_forwards_pg_notifications() {
+        let db = generate_test_database().await;
+        let database_url = env::var("DATABASE_URL").expect("missing DATABASE");
+        let broadcaster = NotificationBroadcaster::new();
+        let mut receiver = broadcaster.subscribe();
+        tokio::spawn(start_notification_listener(database_url, broadcaster));
+
+        // NOTIFY is only delivered to sessions already listening, so keep
+        // sending until the listener has connected.
+        let event = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                diesel::sql_query(r#"NOTIFY notifications, '{"id": 7, "github_id": 42}'"#)
+                    .execute(&mut db.get_db_conn().unwrap())
+                    .unwrap();
+                if let Ok(Ok(event)) =
+                    tokio::time::timeout(Duration::from_millis(200), receiver.recv()).await
+                {
+                    return event;
+                }
+            }
+        })
+        .await
+        .expect("no notification received");
+
+        assert_eq!(event, NotificationEvent { id: 7, github_id: 42 });
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_read_archive_and_unread_count() {
+        let db = generate_test_database().await;
+        for statement in [
+            "INSERT INTO users (id, username, github_id) VALUES (7001, 'reader', 7001)",
+            "INSERT INTO tasks (id, title, type, assignee_user_id) VALUES (7001, 'a', 'dev', 7001), (7002, 'b', 'dev', 7001)",
+        ] {
+            diesel::sql_query(statement).execute(&mut db.get_db_conn().unwrap()).unwrap();
+        }
+        assert_eq!(db.unread_count(7001).unwrap(), 2);
+
+        let (notifications, total) = db
+            .all(7001, QueryParams::default(), PaginationParams { limit: 1, offset: 0 })
+            .unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].kind, NotificationKind::TaskAssigned.as_str());
+
+        let first = DeleteNotification { id: notifications[0].id, github_id: Some(7001) };
+        assert!(db.mark_read(&first).unwrap());
+        assert_eq!(db.unread_count(7001).unwrap(), 1);
+
+        // reading it again succeeds without restamping read_at
+        let read_at = || {
+            notifications::table
+                .find(first.id)
+                .select(notifications::read_at)
+                .get_result::<Option<chrono::DateTime<chrono::Utc>>>(&mut db.get_db_conn().unwrap())
+                .unwrap()
+        };
+        let first_read = read_at();
+        assert!(first_read.is_some());
+        assert!(db.mark_read(&first).unwrap());
+        assert_eq!(read_at(), first_read);
+
+        assert!(db.archive(&first).unwrap());
+        let (active, _) = db
+            .all(7001, QueryParams::default(), PaginationParams { limit: 10, offset: 0 })
+            .unwrap();
+        assert_eq!(active.len(), 1);
+
+        assert_eq!(db.mark_all_read(7001).unwrap(), 1);
+        assert_eq!(db.unread_count(7001).unwrap(), 0);
+
+        assert_eq!(db.purge_seen(chrono::Utc::now() + chrono::Duration::days(1)).unwrap(), 2);
+        assert!(!db.delete(&first).unwrap());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_stream_replays_only_after_last_event_id() {
+        let db = generate_test_database().await;
+        execute(&db, "INSERT INTO users (id, username, github_id) VALUES (7101, 'streamer', 7101)");
+        execute(
+            &db,
+            "INSERT INTO tasks (id, title, type, assignee_user_id) VALUES (7101, 'a', 'dev', 7101), (7102, 'b', 'dev', 7101)",
+        );
+        let older = db.unseen_after(7101, 0).unwrap();
+        assert_eq!(older.len(), 2);
+        let user = GitHubUser {
+            id: 7101,
+            username: "streamer".to_owned(),
+            avatar_url: String::new(),
+            email: None,
+        };
+        let broadcaster = NotificationBroadcaster::new();
+
+        // a fresh stream only gets what is created once it is open
+        let fresh = stream_handler(user.clone(), None, db.clone(), broadcaster.clone()).await.unwrap();
+        let mut fresh = fresh.into_response().into_body();
+        // and a reconnecting client what it missed since its last event
+        let resumed = stream_handler(user, Some(older[0].id.to_string()), db.clone(), broadcaster.clone())
+            .await
+            .unwrap();
+        let mut resumed = resumed.into_response().into_body();
+
+        execute(&db, "INSERT INTO tasks (id, title, type, assignee_user_id) VALUES (7103, 'c', 'dev', 7101)");
+        let latest = db.latest_id(7101).unwrap().unwrap();
+        broadcaster.publish(NotificationEvent { id: latest, github_id: 7101 });
+
+        assert_eq!(event_id(&next_event(&mut fresh).await), latest);
+        assert_eq!(event_id(&next_event(&mut resumed).await), older[1].id);
+        assert_eq!(event_id(&next_event(&mut resumed).await), latest);
+        broadcaster.close();
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_task_events_notify_the_right_users() {
+        let db = generate_test_database().await;
+        for statement in [
+            "INSERT INTO users (id, username, github_id) VALUES (7301, 'creator', 7301), (7302, 'assignee', 7302), (7303, 'teammate', 7303)",
+            "INSERT INTO teams (id, name, created_by_user_id) VALUES (7301, 'events', 7301)",
+            "INSERT INTO team_memberships (team_id, user_id, role) VALUES (7301, 7303, 'member')",
+            "INSERT INTO tasks (id, title, type, created_by_user_id, assignee_user_id, assignee_team_id) VALUES (7301, 'events', 'dev', 7301, 7302, 7301)",
+        ] {
+            execute(&db, statement);
+        }
+        assert!(kinds(&db, 7301).is_empty());
+        assert_eq!(kinds(&db, 7302), ["task_assigned"]);
+        assert_eq!(kinds(&db, 7303), ["team_task_assigned"]);
+
+        // the user changing the status isn't told about it
+        let status = |status: &str| UpdateTask {
+            status: Some(status.to_owned()),
+            ..Default::default()
+        };
+        update_task(&db, 7301, &status("in-progress"), Some(7302));
+        assert_eq!(kinds(&db, 7301), ["task_status_changed"]);
+        assert_eq!(kinds(&db, 7302), ["task_assigned"]);
+
+        // nor are the users who opted out of the kind
+        db.set_preferences(
+            7301,
+            &[NotificationPreference {
+                kind: NotificationKind::TaskStatusChanged,
+                enabled: false,
+            }],
+        )
+        .unwrap();
+        update_task(&db, 7301, &status("completed"), None);
+        assert_eq!(kinds(&db, 7301), ["task_status_changed"]);
+        assert_eq!(kinds(&db, 7302), ["task_assigned", "task_status_changed"]);
+
+        // approvals go to the creator, vote milestones to the creator and the assignee
+        let approval = UpdateTask {
+            approved_by: Some(vec![Some(7303)]),
+            ..Default::default()
+        };
+        update_task(&db, 7301, &approval, Some(7303));
+        execute(&db, "UPDATE tasks SET upvotes = 5 WHERE id = 7301");
+        assert_eq!(kinds(&db, 7301), ["task_approved", "task_status_changed", "vote_milestone"]);
+        assert_eq!(kinds(&db, 7302), ["task_assigned", "task_status_changed", "vote_milestone"]);
+        assert_eq!(kinds(&db, 7303), ["team_task_assigned"]);
+    }
+}
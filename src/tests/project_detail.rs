@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use crate::{
+        api::{
+            issues::{db::DBIssue, models::NewIssue},
+            projects::{
+                db::DBProject,
+                models::{NewProject, OpenCounts, ProjectIncludes},
+            },
+            repositories::{db::DBRepository, models::NewRepository},
+            roles::{db::DBRole, models::{NewUserProjectRole, MAINTAINER_ROLE}},
+            users::{db::DBUser, models::NewUser},
+        },
+        tests::utils::generate_test_database,
+    };
+
+    #[test]
+    fn test_parse_includes() {
+        assert_eq!(ProjectIncludes::parse(None), Ok(ProjectIncludes::default()));
+        let includes = ProjectIncludes::parse(Some("counts, maintainers")).unwrap();
+        assert!(includes.counts && includes.maintainers);
+        assert!(!includes.repositories && !includes.languages);
+        assert!(!ProjectIncludes::parse(Some("")).unwrap().repositories);
+        assert!(ProjectIncludes::parse(Some("repositories,issues")).is_err());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_project_detail() {
+        let db = generate_test_database().await;
+        let project = DBProject::create(
+            &db,
+            &NewProject {
+                name: "detail".to_owned(),
+                slug: "detail-project".to_owned(),
+                types: None,
+                purposes: None,
+                stack_levels: None,
+                technologies: None,
+                avatar: None,
+                rewards: Some(false),
+            },
+        )
+        .unwrap();
+        let repository = |slug: &str, language: Option<&str>| {
+            DBRepository::create(
+                &db,
+                &NewRepository {
+                    slug: slug.to_owned(),
+                    name: slug.to_owned(),
+                    url: format!("https://github.com/detail/{slug}"),
+                    language_slug: language.map(str::to_owned),
+                    project_id: project.id,
+                },
+            )
+            .unwrap()
+        };
+        let repo = repository("detail-b", Some("rust"));
+        repository("detail-a", Some("go"));
+        repository("detail-c", Some("rust"));
+        DBIssue::create(
+            &db,
+            &NewIssue {
+                number: 1,
+                title: "detail".to_owned(),
+                labels: None,
+                open: true,
+                certified: None,
+                repository_id: repo.id,
+                assignee_id: None,
+                issue_created_at: Utc::now(),
+                description: None,
+                estimation: None,
+            },
+        )
+        .unwrap();
+        let user = DBUser::create(
+            &db,
+            &NewUser {
+                username: "detail-maintainer".to_owned(),
+                avatar: None,
+                email: None,
+                github_id: None,
+            },
+        )
+        .unwrap();
+        let maintainer = DBRole::by_name(&db, MAINTAINER_ROLE).unwrap().unwrap();
+        db.create_role_to_user_and_project(&NewUserProjectRole {
+            user_id: user.id,
+            project_id: Some(project.id),
+            role_id: maintainer.id,
+        })
+        .unwrap();
+
+        let found = DBProject::by_slug(&db, "detail-project").unwrap().unwrap();
+        let detail = db.detail(found, ProjectIncludes::default()).unwrap();
+        let slugs: Vec<&str> = detail.repositories.as_ref().unwrap().iter().map(|r| r.slug.as_str()).collect();
+        assert_eq!(slugs, vec!["detail-a", "detail-b", "detail-c"]);
+        assert_eq!(detail.languages, Some(vec!["go".to_owned(), "rust".to_owned()]));
+        let maintainers = detail.maintainers.unwrap();
+        assert_eq!(maintainers.len(), 1);
+        assert_eq!(maintainers[0].username, "detail-maintainer");
+        assert_eq!(
+            detail.counts,
+            Some(OpenCounts {
+                open_issues: 1,
+                open_tasks: 0
+            })
+        );
+
+        let found = DBProject::by_slug(&db, "detail-project").unwrap().unwrap();
+        let detail = db.detail(found, ProjectIncludes::parse(Some("languages")).unwrap()).unwrap();
+        assert!(detail.repositories.is_none() && detail.maintainers.is_none() && detail.counts.is_none());
+        let json = serde_json::to_value(&detail).unwrap();
+        assert_eq!(json["slug"], "detail-project");
+        assert_eq!(json["languages"], serde_json::json!(["go", "rust"]));
+        assert!(json.get("repositories").is_none());
+    }
+}
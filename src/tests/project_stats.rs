@@ -0,0 +1,166 @@
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+    use diesel::RunQueryDsl;
+
+    use crate::{
+        api::{
+            issues::{
+                db::DBIssue,
+                models::{NewIssue, UpdateIssue},
+            },
+            projects::{
+                db::DBProject,
+                models::{NewProject, OpenClosed, StatsParams, DEFAULT_STATS_WEEKS, GOOD_FIRST_ISSUE_LABEL},
+            },
+            repositories::{db::DBRepository, models::NewRepository},
+        },
+        db::pool::DBAccessor,
+        tests::utils::generate_test_database,
+    };
+
+    fn at(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, day, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_stats_range() {
+        let now = at(20);
+        let range = StatsParams::default().range(now).unwrap();
+        assert_eq!(range.from, None);
+        assert_eq!(range.to, now);
+        assert_eq!(range.series_from, now - Duration::weeks(DEFAULT_STATS_WEEKS));
+
+        let range = StatsParams {
+            from: Some(at(2)),
+            to: Some(at(16)),
+        }
+        .range(now)
+        .unwrap();
+        assert_eq!((range.from, range.to, range.series_from), (Some(at(2)), at(16), at(2)));
+
+        assert!(StatsParams {
+            from: Some(at(16)),
+            to: Some(at(2)),
+        }
+        .range(now)
+        .is_err());
+        assert!(StatsParams {
+            from: Some(now - Duration::weeks(600)),
+            to: None,
+        }
+        .range(now)
+        .is_err());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_project_stats() {
+        let db = generate_test_database().await;
+        let project = DBProject::create(
+            &db,
+            &NewProject {
+                name: "stats".to_owned(),
+                slug: "stats-project".to_owned(),
+                types: None,
+                purposes: None,
+                stack_levels: None,
+                technologies: None,
+                avatar: None,
+                rewards: Some(false),
+            },
+        )
+        .unwrap();
+        let repository = DBRepository::create(
+            &db,
+            &NewRepository {
+                slug: "stats-repo".to_owned(),
+                name: "stats-repo".to_owned(),
+                url: "https://github.com/stats/repo".to_owned(),
+                language_slug: None,
+                project_id: project.id,
+            },
+        )
+        .unwrap();
+        let issue = |number: i32, created: DateTime<Utc>, labels: &[&str]| {
+            DBIssue::create(
+                &db,
+                &NewIssue {
+                    number,
+                    title: "stats".to_owned(),
+                    labels: Some(labels.iter().map(|l| l.to_string()).collect()),
+                    open: true,
+                    certified: Some(false),
+                    repository_id: repository.id,
+                    assignee_id: None,
+                    issue_created_at: created,
+                    description: None,
+                    estimation: None,
+                },
+            )
+            .unwrap()
+        };
+        let close = |id: i32, closed: DateTime<Utc>| {
+            DBIssue::update(
+                &db,
+                id,
+                &UpdateIssue {
+                    open: Some(false),
+                    issue_closed_at: Some(closed),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        };
+        let first = issue(1, at(2), &[]);
+        close(first.id, at(2) + Duration::hours(10));
+        let second = issue(2, at(3), &[]);
+        close(second.id, at(3) + Duration::hours(30));
+        issue(3, at(10), &[GOOD_FIRST_ISSUE_LABEL]);
+        let conn = &mut db.get_db_conn().unwrap();
+        diesel::sql_query(format!(
+            "INSERT INTO tasks (title, type, project_id, bounty, created_at) VALUES
+                ('stats open', 'dev', {id}, 100, '2026-03-04T00:00:00Z'),
+                ('stats done', 'dev', {id}, 50, '2026-03-04T00:00:00Z')",
+            id = project.id
+        ))
+        .execute(conn)
+        .unwrap();
+        diesel::sql_query(format!(
+            "UPDATE tasks SET open = false, status = 'completed' WHERE project_id = {} AND title = 'stats done'",
+            project.id
+        ))
+        .execute(conn)
+        .unwrap();
+
+        let range = StatsParams {
+            from: Some(at(1)),
+            to: Some(at(15)),
+        }
+        .range(Utc::now())
+        .unwrap();
+        let stats = db.stats(project.id, &range).unwrap();
+        assert_eq!(stats.issues, OpenClosed { open: 1, closed: 2 });
+        assert_eq!(stats.tasks, OpenClosed { open: 1, closed: 1 });
+        assert_eq!(stats.median_hours_to_close, Some(20.0));
+        assert_eq!(stats.unassigned_good_first_issues, 1);
+        assert_eq!(stats.outstanding_bounty, 100);
+        assert_eq!(stats.contributors, 0);
+        let opened: i64 = stats.weekly.iter().map(|week| week.issues_opened).sum();
+        let closed: i64 = stats.weekly.iter().map(|week| week.issues_closed).sum();
+        assert_eq!((opened, closed), (3, 2));
+        assert_eq!(stats.weekly.iter().map(|week| week.tasks_created).sum::<i64>(), 2);
+
+        // only the issue of the second week is in range
+        let range = StatsParams {
+            from: Some(at(9)),
+            to: Some(at(15)),
+        }
+        .range(Utc::now())
+        .unwrap();
+        let stats = db.stats(project.id, &range).unwrap();
+        assert_eq!(stats.issues, OpenClosed { open: 1, closed: 0 });
+        assert_eq!(stats.median_hours_to_close, None);
+        assert_eq!(stats.tasks, OpenClosed { open: 0, closed: 0 });
+    }
+}
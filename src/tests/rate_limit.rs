@@ -0,0 +1,203 @@
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{IpAddr, SocketAddr},
+        time::{Duration, Instant},
+    };
+
+    use warp::{
+        http::{Method, StatusCode},
+        test::request,
+        Filter,
+    };
+
+    use crate::{
+        config::RateLimitConfig,
+        errors::error_handler,
+        middlewares::rate_limit::{remember_token, with_rate_limit, ClientKey, RateLimiter, RouteGroup},
+    };
+
+    fn config(read_per_minute: u32) -> RateLimitConfig {
+        RateLimitConfig {
+            read_per_minute,
+            write_per_minute: 2,
+            auth_per_minute: 1,
+            ..RateLimitConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_route_groups() {
+        assert_eq!(RouteGroup::of(&Method::GET, "/issues"), RouteGroup::Read);
+        assert_eq!(RouteGroup::of(&Method::GET, "/users/me"), RouteGroup::Read);
+        assert_eq!(RouteGroup::of(&Method::POST, "/tasks/1/upvotes"), RouteGroup::Write);
+        assert_eq!(RouteGroup::of(&Method::DELETE, "/projects/1"), RouteGroup::Write);
+        assert_eq!(RouteGroup::of(&Method::POST, "/users/me"), RouteGroup::Auth);
+        assert_eq!(RouteGroup::of(&Method::POST, "/users/me/email"), RouteGroup::Auth);
+        assert_eq!(RouteGroup::of(&Method::GET, "/users/email/verify"), RouteGroup::Auth);
+    }
+
+    #[test]
+    fn test_buckets_drain_and_refill() {
+        let limiter = RateLimiter::new(&config(60));
+        let start = Instant::now();
+        let alice = || ClientKey::Ip(IpAddr::from([10, 0, 0, 1]));
+
+        for remaining in (0..60).rev() {
+            let status = limiter.check_at(RouteGroup::Read, alice(), start).unwrap();
+            assert_eq!(status.limit, 60);
+            assert_eq!(status.remaining, remaining);
+        }
+        let error = limiter.check_at(RouteGroup::Read, alice(), start).unwrap_err();
+        assert_eq!(error.limit, 60);
+        assert_eq!(error.retry_after_seconds, 1);
+
+        // budgets are separate per group and per client
+        assert!(limiter.check_at(RouteGroup::Write, alice(), start).is_ok());
+        assert!(limiter.check_at(RouteGroup::Read, ClientKey::Ip(IpAddr::from([10, 0, 0, 2])), start).is_ok());
+
+        // one token per second comes back
+        let later = start + Duration::from_secs(1);
+        assert!(limiter.check_at(RouteGroup::Read, alice(), later).is_ok());
+        assert!(limiter.check_at(RouteGroup::Read, alice(), later).is_err());
+
+        let status = limiter
+            .check_at(RouteGroup::Read, alice(), start + Duration::from_secs(120))
+            .unwrap();
+        assert_eq!(status.remaining, 59);
+        assert_eq!(status.reset_seconds, 1);
+    }
+
+    #[test]
+    fn test_retry_after_follows_the_refill_rate() {
+        let limiter = RateLimiter::new(&config(60));
+        let start = Instant::now();
+        assert!(limiter.check_at(RouteGroup::Auth, ClientKey::Unknown, start).is_ok());
+        let error = limiter.check_at(RouteGroup::Auth, ClientKey::Unknown, start).unwrap_err();
+        assert_eq!(error.retry_after_seconds, 60);
+    }
+
+    #[tokio::test]
+    async fn test_filter_limits_by_ip() {
+        let ping = warp::path!("ping").map(|| "pong").recover(error_handler);
+        let api = with_rate_limit(RateLimiter::new(&config(2)), ping);
+        let client: SocketAddr = ([10, 0, 0, 1], 4000).into();
+
+        for remaining in ["1", "0"] {
+            let resp = request().path("/ping").remote_addr(client).reply(&api).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+            assert_eq!(resp.headers()["x-ratelimit-limit"], "2");
+            assert_eq!(resp.headers()["x-ratelimit-remaining"], remaining);
+        }
+
+        let resp = request().path("/ping").remote_addr(client).reply(&api).await;
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(resp.headers()["retry-after"], "30");
+        assert_eq!(resp.headers()["x-ratelimit-remaining"], "0");
+
+        let other: SocketAddr = ([10, 0, 0, 2], 4000).into();
+        let resp = request().path("/ping").remote_addr(other).reply(&api).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_filter_limits_tokens_by_ip() {
+        let ping = warp::path!("ping").map(|| "pong").recover(error_handler);
+        let api = with_rate_limit(RateLimiter::new(&config(1)), ping);
+        let client: SocketAddr = ([10, 0, 0, 1], 4000).into();
+
+        // a new token doesn't get a new budget
+        for (token, status) in [("first", StatusCode::OK), ("second", StatusCode::TOO_MANY_REQUESTS)] {
+            let resp = request()
+                .path("/ping")
+                .remote_addr(client)
+                .header("authorization", format!("Bearer {token}"))
+                .reply(&api)
+                .await;
+            assert_eq!(resp.status(), status);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_filter_limits_validated_users_separately() {
+        let ping = warp::path!("ping").map(|| "pong").recover(error_handler);
+        let api = with_rate_limit(RateLimiter::new(&config(1)), ping);
+        let office: SocketAddr = ([10, 0, 1, 1], 4000).into();
+        remember_token("rate-limit-alice", 36001);
+        remember_token("rate-limit-bob", 36002);
+
+        // users behind one address each get their budget, and keep it from
+        // another address
+        for (token, client, status) in [
+            ("rate-limit-alice", office, StatusCode::OK),
+            ("rate-limit-bob", office, StatusCode::OK),
+            ("rate-limit-alice", office, StatusCode::TOO_MANY_REQUESTS),
+            ("rate-limit-alice", ([10, 0, 1, 2], 4000).into(), StatusCode::TOO_MANY_REQUESTS),
+            ("rate-limit-unknown", office, StatusCode::OK),
+            ("rate-limit-unknown", office, StatusCode::TOO_MANY_REQUESTS),
+        ] {
+            let resp = request()
+                .path("/ping")
+                .remote_addr(client)
+                .header("authorization", format!("Bearer {token}"))
+                .reply(&api)
+                .await;
+            assert_eq!(resp.status(), status, "{token}");
+        }
+
+        // signing up stays limited per address
+        let api = with_rate_limit(RateLimiter::new(&config(1)), warp::any().map(|| "ok"));
+        for (token, status) in [("rate-limit-alice", StatusCode::OK), ("rate-limit-bob", StatusCode::TOO_MANY_REQUESTS)] {
+            let resp = request()
+                .method("POST")
+                .path("/users/me")
+                .remote_addr(office)
+                .header("authorization", format!("Bearer {token}"))
+                .reply(&api)
+                .await;
+            assert_eq!(resp.status(), status, "{token}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_filter_uses_the_address_added_by_the_proxy() {
+        let ping = warp::path!("ping").map(|| "pong").recover(error_handler);
+        let behind_proxy = RateLimitConfig {
+            trust_forwarded_for: true,
+            ..config(1)
+        };
+        let api = with_rate_limit(RateLimiter::new(&behind_proxy), ping);
+        let proxy: SocketAddr = ([10, 0, 0, 100], 4000).into();
+
+        // clients can prepend anything, only the last entry is the proxy's
+        for (forwarded, status) in [
+            ("203.0.113.1, 198.51.100.7", StatusCode::OK),
+            ("203.0.113.2, 198.51.100.7", StatusCode::TOO_MANY_REQUESTS),
+            ("198.51.100.8", StatusCode::OK),
+        ] {
+            let resp = request()
+                .path("/ping")
+                .remote_addr(proxy)
+                .header("x-forwarded-for", forwarded)
+                .reply(&api)
+                .await;
+            assert_eq!(resp.status(), status, "{forwarded}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_filter_can_be_disabled() {
+        let ping = warp::path!("ping").map(|| "pong").recover(error_handler);
+        let disabled = RateLimitConfig {
+            enabled: false,
+            ..config(1)
+        };
+        let api = with_rate_limit(RateLimiter::new(&disabled), ping);
+
+        for _ in 0..3 {
+            let resp = request().path("/ping").reply(&api).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+            assert!(resp.headers().get("x-ratelimit-limit").is_none());
+        }
+    }
+}
@@ -0,0 +1,94 @@
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{SocketAddr, TcpListener},
+        sync::Arc,
+        time::Duration,
+    };
+
+    use tokio::sync::Notify;
+
+    use tokio_util::sync::CancellationToken;
+    use warp::Filter;
+
+    use crate::{
+        api::{jobs::runner::JobRunner, notifications::stream::{start_notification_listener, NotificationBroadcaster}},
+        tests::utils::generate_test_database,
+        utils::serve,
+    };
+
+    fn free_addr() -> SocketAddr {
+        TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_serve_drains_in_flight_requests() {
+        let received = Arc::new(Notify::new());
+        let api = warp::path!("slow")
+            .then({
+                let received = received.clone();
+                move || {
+                    let received = received.clone();
+                    async move {
+                        received.notify_one();
+                        tokio::time::sleep(Duration::from_millis(300)).await;
+                        "done"
+                    }
+                }
+            })
+            .boxed();
+        let addr = free_addr();
+        let shutdown = CancellationToken::new();
+        let server = tokio::spawn(serve(api, addr, shutdown.clone()));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let request = tokio::spawn(reqwest::get(format!("http://{addr}/slow")));
+        // shut down only once the request is being handled
+        tokio::time::timeout(Duration::from_secs(5), received.notified())
+            .await
+            .expect("request not received");
+        shutdown.cancel();
+
+        let resp = request.await.unwrap().unwrap();
+        assert_eq!(resp.text().await.unwrap(), "done");
+        tokio::time::timeout(Duration::from_secs(2), server)
+            .await
+            .expect("server did not stop")
+            .unwrap()
+            .unwrap();
+        assert!(reqwest::get(format!("http://{addr}/slow")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_listener_stops_when_closed() {
+        let broadcaster = NotificationBroadcaster::new();
+        // nothing listens there, so the listener is waiting to reconnect
+        let listener = tokio::spawn(start_notification_listener(
+            "postgres://postgres@127.0.0.1:1/none".to_owned(),
+            broadcaster.clone(),
+        ));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        broadcaster.close();
+        tokio::time::timeout(Duration::from_secs(1), listener)
+            .await
+            .expect("listener did not stop")
+            .unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore]
+    async fn test_job_runner_stops_when_cancelled() {
+        let db = generate_test_database().await;
+        let runner = JobRunner::new(db).register("idle", "@hourly", Duration::from_secs(60), |_| async { Ok(()) });
+        let shutdown = CancellationToken::new();
+        let started = tokio::spawn(runner.start(shutdown.clone()));
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        shutdown.cancel();
+        tokio::time::timeout(Duration::from_secs(1), started)
+            .await
+            .expect("job runner did not stop")
+            .unwrap();
+    }
+}
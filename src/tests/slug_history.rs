@@ -0,0 +1,219 @@
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use diesel::RunQueryDsl;
+    use warp::{http::StatusCode, test::request};
+
+    use crate::{
+        admin::commands::grant_role,
+        api::{
+            projects::{
+                self,
+                db::DBProject,
+                errors::ProjectError,
+                models::{NewProject, QueryParams, UpdateProject},
+                routes,
+            },
+            repositories::{
+                self,
+                db::DBRepository,
+                errors::RepositoryError,
+                models::{NewRepository, UpdateRepository},
+            },
+        },
+        db::pool::DBAccessor,
+        middlewares::github::model::GitHubUser,
+        tests::utils::generate_test_database,
+        types::PaginationParams,
+    };
+
+    fn rename(slug: &str) -> UpdateProject {
+        UpdateProject {
+            name: None,
+            slug: Some(slug.to_owned()),
+            types: None,
+            purposes: None,
+            stack_levels: None,
+            technologies: None,
+            avatar: None,
+            rewards: None,
+        }
+    }
+
+    fn params(slug: &str) -> QueryParams {
+        QueryParams {
+            slugs: Some(slug.to_owned()),
+            purposes: None,
+            stack_levels: None,
+            technologies: None,
+            rewards: None,
+            certified: None,
+            open: None,
+            labels: None,
+            excluded_labels: None,
+            label_match: None,
+            certified_or_labels: None,
+            types: None,
+            source: None,
+            include_archived: None,
+        }
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_renamed_project_keeps_its_former_slugs() {
+        let db = generate_test_database().await;
+        let project = DBProject::create(
+            &db,
+            &NewProject {
+                name: "renamed".to_owned(),
+                slug: "renamed-old".to_owned(),
+                types: None,
+                purposes: None,
+                stack_levels: None,
+                technologies: None,
+                avatar: None,
+                rewards: Some(false),
+            },
+        )
+        .unwrap();
+        DBProject::update(&db, project.id, &rename("renamed-new")).unwrap();
+
+        // the former slug is taken and still finds the project
+        assert_eq!(DBProject::slug_owner(&db, "renamed-old").unwrap(), Some(project.id));
+        assert!(DBProject::by_slug(&db, "renamed-old").unwrap().is_none());
+        let found = DBProject::by_former_slug(&db, "renamed-old").unwrap().unwrap();
+        assert_eq!(found.slug, "renamed-new");
+        let (listed, _) =
+            DBProject::all(&db, params("renamed-old"), PaginationParams { limit: 10, offset: 0 }).unwrap();
+        assert_eq!(listed.len(), 1);
+
+        let routes = routes::routes(db.clone());
+        let response = request()
+            .method("GET")
+            .path("/projects/slug/renamed-old?include=counts")
+            .reply(&routes)
+            .await;
+        assert_eq!(response.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(response.headers()["location"], "/projects/slug/renamed-new?include=counts");
+        let response = request().method("GET").path("/projects/slug/renamed-new").reply(&routes).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // going back to a former slug takes it out of the history
+        DBProject::update(&db, project.id, &rename("renamed-old")).unwrap();
+        assert!(DBProject::by_former_slug(&db, "renamed-old").unwrap().is_none());
+        assert_eq!(DBProject::by_former_slug(&db, "renamed-new").unwrap().unwrap().id, project.id);
+        assert_eq!(DBProject::slug_owner(&db, "renamed-new").unwrap(), Some(project.id));
+        assert_eq!(DBProject::slug_owner(&db, "renamed-unknown").unwrap(), None);
+
+        let repository = DBRepository::create(
+            &db,
+            &NewRepository {
+                slug: "renamed-repo-old".to_owned(),
+                name: "renamed-repo".to_owned(),
+                url: "https://github.com/renamed/repo".to_owned(),
+                language_slug: None,
+                project_id: project.id,
+            },
+        )
+        .unwrap();
+        DBRepository::update(
+            &db,
+            repository.id,
+            &UpdateRepository {
+                slug: Some("renamed-repo-new".to_owned()),
+                name: None,
+                url: None,
+                language_slug: None,
+                project_id: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(DBRepository::slug_owner(&db, "renamed-repo-old").unwrap(), Some(repository.id));
+        let found = DBRepository::by_former_slug(&db, "renamed-repo-old").unwrap().unwrap();
+        assert_eq!((found.id, found.slug.as_str()), (repository.id, "renamed-repo-new"));
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_database_refuses_taken_slugs() {
+        let db = generate_test_database().await;
+        diesel::sql_query("INSERT INTO users (username, github_id) VALUES ('slug-admin', 9201)")
+            .execute(&mut db.get_db_conn().unwrap())
+            .unwrap();
+        grant_role(&db, "slug-admin", "Admin", None).unwrap();
+        let user = GitHubUser {
+            id: 9201,
+            username: "slug-admin".to_owned(),
+            avatar_url: String::new(),
+            email: None,
+        };
+        let new_project = |slug: &str| NewProject {
+            name: slug.to_owned(),
+            slug: slug.to_owned(),
+            types: None,
+            purposes: None,
+            stack_levels: None,
+            technologies: None,
+            avatar: None,
+            rewards: Some(false),
+        };
+        let project = DBProject::create(&db, &new_project("taken-old")).unwrap();
+        DBProject::update(&db, project.id, &rename("taken-new")).unwrap();
+        let other = DBProject::create(&db, &new_project("taken-other")).unwrap();
+
+        // the check holds for any writer, not only the handlers
+        let error = DBProject::create(&db, &new_project("taken-old")).err().unwrap();
+        assert!(error.is_unique_violation());
+        let error = DBProject::update(&db, other.id, &rename("taken-old")).err().unwrap();
+        assert!(error.is_unique_violation());
+
+        let body = r#"{"name": "taken", "slug": "taken-old"}"#;
+        let rejection = projects::handlers::create_handler(user.clone(), Bytes::from(body), db.clone())
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(rejection.find::<ProjectError>(), Some(&ProjectError::AlreadyExists(project.id)));
+        let rejection = projects::handlers::update_handler(other.id, user.clone(), rename("taken-new"), db.clone())
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(rejection.find::<ProjectError>(), Some(&ProjectError::AlreadyExists(project.id)));
+
+        let repository = DBRepository::create(
+            &db,
+            &NewRepository {
+                slug: "taken-repo-old".to_owned(),
+                name: "taken-repo".to_owned(),
+                url: "https://github.com/taken/repo".to_owned(),
+                language_slug: None,
+                project_id: project.id,
+            },
+        )
+        .unwrap();
+        DBRepository::update(
+            &db,
+            repository.id,
+            &UpdateRepository {
+                slug: Some("taken-repo-new".to_owned()),
+                name: None,
+                url: None,
+                language_slug: None,
+                project_id: None,
+            },
+        )
+        .unwrap();
+        let body = format!(
+            r#"{{"slug": "taken-repo-old", "name": "taken-repo", "url": "https://github.com/taken/other", "project_id": {}}}"#,
+            other.id
+        );
+        let rejection = repositories::handlers::create_handler(user, Bytes::from(body), db.clone())
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(
+            rejection.find::<RepositoryError>(),
+            Some(&RepositoryError::AlreadyExists(repository.id))
+        );
+    }
+}
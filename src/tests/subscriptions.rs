@@ -0,0 +1,207 @@
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use chrono::{Duration, Utc};
+    use diesel::prelude::*;
+
+    use crate::{
+        api::subscriptions::{
+            db::DBUserSubscription,
+            errors::UserSubscriptionError,
+            handlers::create_handler,
+            models::{DeleteUserSubscription, NewUserSubscription},
+        },
+        db::pool::{DBAccess, DBAccessor},
+        email::notifications::load_digest,
+        middlewares::github::model::GitHubUser,
+        schema::notifications,
+        tests::utils::generate_test_database,
+    };
+
+    fn execute(db: &DBAccess, statement: &str) {
+        diesel::sql_query(statement).execute(&mut db.get_db_conn().unwrap()).unwrap();
+    }
+
+    fn subscription(github_id: i64) -> NewUserSubscription {
+        NewUserSubscription {
+            github_id: Some(github_id),
+            purpose: None,
+            stack_level: None,
+            technology: None,
+            project_ids: None,
+            repository_ids: None,
+            labels: None,
+            languages: None,
+            task_types: None,
+            min_bounty: None,
+        }
+    }
+
+    fn values(values: &[&str]) -> Option<Vec<Option<String>>> {
+        Some(values.iter().map(|value| Some(value.to_string())).collect())
+    }
+
+    /// The (task, issue) pairs the user was notified about, sorted.
+    fn matches(db: &DBAccess, github_id: i64) -> Vec<(Option<i32>, Option<i32>)> {
+        notifications::table
+            .filter(notifications::github_id.eq(github_id))
+            .filter(notifications::kind.eq_any(["task_matched_subscription", "issue_matched_subscription"]))
+            .select((notifications::task_id, notifications::issue_id))
+            .order((notifications::task_id.asc(), notifications::issue_id.asc()))
+            .load(&mut db.get_db_conn().unwrap())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_subscriptions_match_tasks_and_issues() {
+        let db = generate_test_database().await;
+        execute(
+            &db,
+            "INSERT INTO users (id, username, github_id) VALUES
+                (7401, 'labels', 7401), (7402, 'both', 7402), (7403, 'bounty', 7403),
+                (7404, 'types', 7404), (7405, 'languages', 7405)",
+        );
+        execute(
+            &db,
+            "INSERT INTO projects (id, name, slug, purposes, stack_levels, technologies)
+                VALUES (7401, 'Matching', 'matching-7401', '{defi}', '{protocol}', '{rust}')",
+        );
+        execute(
+            &db,
+            "INSERT INTO repositories (id, slug, name, url, language_slug, project_id)
+                VALUES (7401, 'matching-7401', 'matching', 'https://github.com/kudos/matching', 'rust', 7401)",
+        );
+
+        let mut labels = subscription(7401);
+        labels.purpose = Some("defi".to_owned());
+        labels.labels = values(&["C-good-first-issue"]);
+        // each criterion matches on its own, but not together
+        let mut both = subscription(7402);
+        both.purpose = Some("defi".to_owned());
+        both.stack_level = Some("tooling".to_owned());
+        let mut bounty = subscription(7403);
+        bounty.min_bounty = Some(100);
+        let mut types = subscription(7404);
+        types.task_types = values(&["dev"]);
+        let mut languages = subscription(7405);
+        languages.languages = values(&["rust"]);
+        languages.labels = values(&["bug", "docs"]);
+        for subscription in [labels, both, bounty, types, languages] {
+            db.create(&subscription).unwrap();
+        }
+
+        execute(
+            &db,
+            "INSERT INTO tasks (id, title, type, repository_id, labels, bounty) VALUES
+                (7401, 'labelled', 'dev', 7401, '{bug,C-good-first-issue}', 150),
+                (7402, 'plain', 'non-dev', 7401, NULL, 50)",
+        );
+        execute(
+            &db,
+            "INSERT INTO issues (id, number, title, labels, open, repository_id, issue_created_at)
+                VALUES (7401, 1, 'an issue', '{docs}', TRUE, 7401, now())",
+        );
+
+        assert_eq!(matches(&db, 7401), vec![(Some(7401), None)]);
+        assert_eq!(matches(&db, 7402), vec![]);
+        // issues have neither a bounty nor a type
+        assert_eq!(matches(&db, 7403), vec![(Some(7401), None)]);
+        assert_eq!(matches(&db, 7404), vec![(Some(7401), None)]);
+        assert_eq!(matches(&db, 7405), vec![(Some(7401), None), (None, Some(7401))]);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_digest_includes_issue_notifications() {
+        let db = generate_test_database().await;
+        execute(
+            &db,
+            "INSERT INTO users (id, username, github_id, email, email_verified_at)
+                VALUES (7411, 'digest', 7411, 'digest@kudos.test', now())",
+        );
+        execute(
+            &db,
+            "INSERT INTO projects (id, name, slug) VALUES (7411, 'Digest', 'digest-7411')",
+        );
+        execute(
+            &db,
+            "INSERT INTO repositories (id, slug, name, url, project_id)
+                VALUES (7411, 'digest-7411', 'digest', 'https://github.com/kudos/digest', 7411)",
+        );
+        let mut repository = subscription(7411);
+        repository.repository_ids = Some(vec![Some(7411)]);
+        db.create(&repository).unwrap();
+        execute(
+            &db,
+            "INSERT INTO tasks (id, title, type, repository_id, url)
+                VALUES (7411, 'a task', 'dev', 7411, 'https://github.com/kudos/digest/issues/1')",
+        );
+        execute(
+            &db,
+            "INSERT INTO issues (id, number, title, open, repository_id, issue_created_at)
+                VALUES (7411, 2, 'an issue', TRUE, 7411, now())",
+        );
+
+        let since = Utc::now() - Duration::hours(1);
+        let digest = load_digest(&mut db.get_db_conn().unwrap(), since, Some(7411)).unwrap();
+        let mut subjects: Vec<_> = digest.iter().map(|notification| notification.subject()).collect();
+        subjects.sort();
+        assert_eq!(
+            subjects,
+            vec![
+                ("a task".to_owned(), Some("https://github.com/kudos/digest/issues/1".to_owned())),
+                ("an issue".to_owned(), Some("https://github.com/kudos/digest/issues/2".to_owned())),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_legacy_delete_keeps_combined_subscriptions() {
+        let db = generate_test_database().await;
+        execute(&db, "INSERT INTO users (id, username, github_id) VALUES (7421, 'legacy', 7421)");
+        let mut single = subscription(7421);
+        single.purpose = Some("defi".to_owned());
+        let mut combined = subscription(7421);
+        combined.purpose = Some("defi".to_owned());
+        combined.labels = values(&["bug"]);
+        db.create(&single).unwrap();
+        let combined = db.create(&combined).unwrap();
+
+        let delete = DeleteUserSubscription {
+            github_id: Some(7421),
+            purpose: Some("defi".to_owned()),
+            stack_level: None,
+            technology: None,
+        };
+        db.delete(&delete).unwrap();
+
+        let remaining: Vec<i32> = db.by_github_id(7421).unwrap().into_iter().map(|s| s.id).collect();
+        assert_eq!(remaining, vec![combined.id]);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_create_rejects_duplicate_subscription() {
+        let db = generate_test_database().await;
+        execute(&db, "INSERT INTO users (id, username, github_id) VALUES (7431, 'duplicate', 7431)");
+        let user = GitHubUser {
+            id: 7431,
+            username: "duplicate".to_owned(),
+            avatar_url: String::new(),
+            email: None,
+        };
+        let body = r#"{"purpose": "defi", "labels": ["bug", "docs"]}"#;
+        create_handler(user.clone(), Bytes::from(body), db.clone()).await.unwrap();
+        let existing = db.by_github_id(7431).unwrap()[0].id;
+
+        // the same criteria listed in another order
+        let body = r#"{"purpose": "defi", "labels": ["docs", "bug", "docs"]}"#;
+        let rejection = create_handler(user, Bytes::from(body), db.clone()).await.err().unwrap();
+        assert_eq!(
+            rejection.find::<UserSubscriptionError>(),
+            Some(&UserSubscriptionError::AlreadyExists(existing))
+        );
+    }
+}
@@ -0,0 +1,159 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        api::{
+            projects::{db::DBProject, models::NewProject},
+            subscriptions::{db::DBUserSubscription, models::NewUserSubscription},
+            taxonomy::{
+                db::DBTaxonomy,
+                models::{normalize_term, TaxonomyKind, TermDB},
+            },
+            users::{db::DBUser, models::NewUser},
+        },
+        tests::utils::generate_test_database,
+    };
+
+    fn tags(values: &[&str]) -> Option<Vec<Option<String>>> {
+        Some(values.iter().map(|v| Some(v.to_string())).collect())
+    }
+
+    #[test]
+    fn test_taxonomy_kinds() {
+        for kind in TaxonomyKind::ALL {
+            assert_eq!(TaxonomyKind::parse(kind.as_str()), Some(kind));
+        }
+        assert_eq!(TaxonomyKind::parse("language"), None);
+        assert_eq!(TaxonomyKind::StackLevel.project_column(), "stack_levels");
+        assert_eq!(TaxonomyKind::Type.subscription_column(), None);
+        let kind: TaxonomyKind = serde_json::from_str(r#""stack_level""#).unwrap();
+        assert_eq!(kind, TaxonomyKind::StackLevel);
+        assert_eq!(normalize_term("  defi "), Ok("defi".to_owned()));
+        assert!(normalize_term(" ").is_err());
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_rename_cascades() {
+        let db = generate_test_database().await;
+        let term = |kind: TaxonomyKind, value: &str| {
+            db.create_term(&TermDB {
+                kind: kind.as_str().to_owned(),
+                value: value.to_owned(),
+            })
+            .unwrap()
+        };
+        let purpose = term(TaxonomyKind::Purpose, "taxonomy-payments");
+        let kind = term(TaxonomyKind::Type, "taxonomy-dapp");
+        term(TaxonomyKind::Purpose, "taxonomy-gaming");
+
+        assert_eq!(
+            db.unknown_terms(
+                TaxonomyKind::Purpose,
+                &["taxonomy-gaming".to_owned(), "taxonomy-dapp".to_owned()]
+            )
+            .unwrap(),
+            vec!["taxonomy-dapp".to_owned()]
+        );
+        assert!(!db.term_in_use(TaxonomyKind::Purpose, "taxonomy-payments").unwrap());
+
+        let project = DBProject::create(
+            &db,
+            &NewProject {
+                name: "taxonomy".to_owned(),
+                slug: "taxonomy-project".to_owned(),
+                types: tags(&["taxonomy-dapp"]),
+                purposes: tags(&["taxonomy-gaming", "taxonomy-payments"]),
+                stack_levels: None,
+                technologies: None,
+                avatar: None,
+                rewards: Some(false),
+            },
+        )
+        .unwrap();
+        DBUser::create(
+            &db,
+            &NewUser {
+                username: "taxonomy-subscriber".to_owned(),
+                avatar: None,
+                email: None,
+                github_id: Some(4242),
+            },
+        )
+        .unwrap();
+        let subscription = DBUserSubscription::create(
+            &db,
+            &NewUserSubscription {
+                github_id: Some(4242),
+                purpose: Some("taxonomy-payments".to_owned()),
+                stack_level: None,
+                technology: None,
+                project_ids: None,
+                repository_ids: None,
+                labels: None,
+                languages: None,
+                task_types: None,
+                min_bounty: None,
+            },
+        )
+        .unwrap();
+        assert!(db.term_in_use(TaxonomyKind::Purpose, "taxonomy-payments").unwrap());
+        assert!(db.term_in_use(TaxonomyKind::Type, "taxonomy-dapp").unwrap());
+
+        let renamed = db.rename_term(&purpose, TaxonomyKind::Purpose, "taxonomy-finance").unwrap();
+        assert_eq!(renamed.value, "taxonomy-finance");
+        db.rename_term(&kind, TaxonomyKind::Type, "taxonomy-app").unwrap();
+        let project = DBProject::by_id(&db, project.id).unwrap().unwrap();
+        assert_eq!(project.purposes, tags(&["taxonomy-gaming", "taxonomy-finance"]));
+        assert_eq!(project.types, tags(&["taxonomy-app"]));
+        let subscriptions = DBUserSubscription::by_github_id(&db, 4242).unwrap();
+        let subscription = subscriptions.iter().find(|s| s.id == subscription.id).unwrap();
+        assert_eq!(subscription.purpose.as_deref(), Some("taxonomy-finance"));
+        assert!(db.term_by_value(TaxonomyKind::Purpose, "taxonomy-payments").unwrap().is_none());
+
+        // tags stored before they had to be terms can clash with the new value
+        let legacy = DBProject::create(
+            &db,
+            &NewProject {
+                name: "taxonomy legacy".to_owned(),
+                slug: "taxonomy-legacy-project".to_owned(),
+                types: None,
+                purposes: tags(&["taxonomy-legacy", "taxonomy-gaming"]),
+                stack_levels: None,
+                technologies: None,
+                avatar: None,
+                rewards: Some(false),
+            },
+        )
+        .unwrap();
+        for purpose in ["taxonomy-gaming", "taxonomy-legacy"] {
+            DBUserSubscription::create(
+                &db,
+                &NewUserSubscription {
+                    github_id: Some(4242),
+                    purpose: Some(purpose.to_owned()),
+                    stack_level: None,
+                    technology: None,
+                    project_ids: None,
+                    repository_ids: None,
+                    labels: None,
+                    languages: None,
+                    task_types: None,
+                    min_bounty: None,
+                },
+            )
+            .unwrap();
+        }
+        let gaming = db.term_by_value(TaxonomyKind::Purpose, "taxonomy-gaming").unwrap().unwrap();
+        db.rename_term(&gaming, TaxonomyKind::Purpose, "taxonomy-legacy").unwrap();
+        let legacy = DBProject::by_id(&db, legacy.id).unwrap().unwrap();
+        assert_eq!(legacy.purposes, tags(&["taxonomy-legacy"]));
+        let project = DBProject::by_id(&db, project.id).unwrap().unwrap();
+        assert_eq!(project.purposes, tags(&["taxonomy-legacy", "taxonomy-finance"]));
+        let purposes: Vec<Option<String>> = DBUserSubscription::by_github_id(&db, 4242)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.purpose)
+            .collect();
+        assert_eq!(purposes.iter().filter(|p| p.as_deref() == Some("taxonomy-legacy")).count(), 1);
+    }
+}
@@ -1,12 +1,15 @@
 use std::env;
 
 use crate::{
-    db::pool::{DBAccess, DBAccessor},
+    config::DatabaseConfig,
+    db::{
+        pool::{DBAccess, DBAccessor},
+        MIGRATIONS,
+    },
     utils::setup_db,
 };
 use diesel::RunQueryDsl;
 use diesel_migrations::MigrationHarness;
-use diesel_migrations::{embed_migrations, EmbeddedMigrations};
 use rand::{distributions::Alphanumeric, thread_rng, Rng};
 
 pub fn generate_random_database_name() -> String {
@@ -20,15 +23,18 @@ pub fn generate_random_database_name() -> String {
 }
 
 pub async fn generate_test_database() -> DBAccess {
-    const MIGRATIONS: EmbeddedMigrations = embed_migrations!();
     let database_url = env::var("DATABASE_URL").expect("missing DATABASE");
     let database_name = generate_random_database_name();
-    let db = setup_db(&database_url).await;
-    let conn = &mut db.get_db_conn();
+    let db = setup_db(&DatabaseConfig {
+        url: database_url,
+        ..Default::default()
+    })
+    .await;
+    let conn = &mut db.get_db_conn().expect("Failed to get db connection");
     diesel::sql_query(format!("CREATE DATABASE {}", database_name))
         .execute(conn)
         .expect("Failed to create database");
-    db.get_db_conn()
+    db.get_db_conn().expect("Failed to get db connection")
         .run_pending_migrations(MIGRATIONS)
         .expect("Could not run migrations");
     db
@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use crate::{
+        api::users::models::{CurrentUser, User},
+        email::verification::{sign_token, verify_token, VerificationClaims},
+    };
+
+    const SECRET: &[u8] = b"secret";
+
+    fn claims() -> VerificationClaims {
+        VerificationClaims {
+            github_id: 42,
+            email: "dev@example.com".to_string(),
+            expires_at: 1_000,
+        }
+    }
+
+    #[test]
+    fn test_verify_token_roundtrip() {
+        let token = sign_token(SECRET, &claims());
+        assert_eq!(verify_token(SECRET, &token, 999), Some(claims()));
+    }
+
+    #[test]
+    fn test_verify_token_expired() {
+        let token = sign_token(SECRET, &claims());
+        assert_eq!(verify_token(SECRET, &token, 1_001), None);
+    }
+
+    #[test]
+    fn test_verify_token_wrong_secret() {
+        let token = sign_token(SECRET, &claims());
+        assert_eq!(verify_token(b"other", &token, 999), None);
+    }
+
+    #[test]
+    fn test_verify_token_tampered_payload() {
+        let token = sign_token(SECRET, &claims());
+        let (_, signature) = token.split_once('.').unwrap();
+        let forged = sign_token(
+            SECRET,
+            &VerificationClaims {
+                email: "attacker@example.com".to_string(),
+                ..claims()
+            },
+        );
+        let (payload, _) = forged.split_once('.').unwrap();
+        assert_eq!(verify_token(SECRET, &format!("{payload}.{signature}"), 999), None);
+    }
+
+    #[test]
+    fn test_pending_email_is_only_shown_to_its_user() {
+        let user = User {
+            id: 1,
+            username: "dev".to_string(),
+            avatar: None,
+            created_at: Utc::now(),
+            updated_at: None,
+            github_id: Some(42),
+            email_notifications_enabled: true,
+            email: Some("dev@example.com".to_string()),
+            email_verified_at: Some(Utc::now()),
+            pending_email: Some("new@example.com".to_string()),
+        };
+        let public = serde_json::to_value(&user).unwrap();
+        assert!(public.get("pending_email").is_none());
+        assert_eq!(public["email"], "dev@example.com");
+        let me = serde_json::to_value(CurrentUser::from(user)).unwrap();
+        assert_eq!(me["pending_email"], "new@example.com");
+        assert_eq!(me["username"], "dev");
+    }
+}
@@ -0,0 +1,203 @@
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use chrono::Utc;
+    use diesel::RunQueryDsl;
+    use tokio::sync::mpsc;
+    use warp::{http::HeaderMap, http::StatusCode, Filter};
+
+    use crate::{
+        api::webhooks::{
+            db::DBWebhook,
+            models::{WebhookDelivery, WebhookEvent, WebhookSubscription, WebhookSubscriptionDB},
+            utils::validate_url,
+            worker::{
+                deliver, retry_delay, sign_payload, webhook_client, DELIVERY_HEADER, EVENT_HEADER,
+                SIGNATURE_HEADER,
+            },
+        },
+        db::pool::DBAccessor,
+        tests::utils::generate_test_database,
+        types::PaginationParams,
+    };
+
+    const SECRET: &str = "a-very-secret-value";
+
+    /// Starts a local receiver answering `status` and forwarding what it got.
+    fn start_receiver(status: StatusCode) -> (SocketAddr, mpsc::UnboundedReceiver<(HeaderMap, bytes::Bytes)>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let route = warp::post()
+            .and(warp::header::headers_cloned())
+            .and(warp::body::bytes())
+            .map(move |headers: HeaderMap, body: bytes::Bytes| {
+                tx.send((headers, body)).unwrap();
+                warp::reply::with_status("", status)
+            });
+        let (addr, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        (addr, rx)
+    }
+
+    fn subscription(addr: SocketAddr) -> WebhookSubscription {
+        WebhookSubscription {
+            id: 1,
+            url: format!("http://{addr}/hook"),
+            secret: SECRET.to_owned(),
+            events: vec![Some("task.created".to_owned())],
+            project_id: None,
+            active: true,
+            created_by_user_id: None,
+            created_at: Utc::now(),
+            updated_at: None,
+        }
+    }
+
+    fn delivery() -> WebhookDelivery {
+        WebhookDelivery {
+            id: 42,
+            subscription_id: 1,
+            event: "task.created".to_owned(),
+            payload: serde_json::json!({ "event": "task.created", "data": { "id": 7 } }),
+            status: "pending".to_owned(),
+            attempts: 0,
+            next_attempt_at: Utc::now(),
+            last_status_code: None,
+            last_error: None,
+            delivered_at: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delivery_is_signed() {
+        let (addr, mut received) = start_receiver(StatusCode::OK);
+        let attempt = deliver(&reqwest::Client::new(), &subscription(addr), &delivery()).await;
+        assert!(attempt.succeeded());
+        assert_eq!(attempt.status_code, Some(200));
+
+        let (headers, body) = received.recv().await.unwrap();
+        assert_eq!(headers[EVENT_HEADER], "task.created");
+        assert_eq!(headers[DELIVERY_HEADER], "42");
+        assert_eq!(headers[SIGNATURE_HEADER], sign_payload(SECRET, &body).as_str());
+        let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload, delivery().payload);
+    }
+
+    #[tokio::test]
+    async fn test_delivery_fails_on_error_status() {
+        let (addr, _received) = start_receiver(StatusCode::INTERNAL_SERVER_ERROR);
+        let attempt = deliver(&reqwest::Client::new(), &subscription(addr), &delivery()).await;
+        assert!(!attempt.succeeded());
+        assert_eq!(attempt.status_code, Some(500));
+    }
+
+    #[tokio::test]
+    async fn test_validate_url_rejects_internal_hosts() {
+        for url in [
+            "ftp://example.com/hook",
+            "http://127.0.0.1/hook",
+            "http://localhost:8080/hook",
+            "http://10.1.2.3/hook",
+            "http://172.16.0.1/hook",
+            "https://192.168.1.1/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://100.64.0.1/hook",
+            "http://0.0.0.0/hook",
+            "http://[::1]/hook",
+            "http://[fd00::1]/hook",
+            "http://[fe80::1]/hook",
+            "http://[::ffff:127.0.0.1]/hook",
+            "http://[64:ff9b::7f00:1]/hook",
+            "http://[64:ff9b::a9fe:a9fe]/hook",
+            "http://[2002:7f00:1::]/hook",
+            "http://[2002:c0a8:101::1]/hook",
+        ] {
+            assert!(validate_url(url).await.is_err(), "{url} accepted");
+        }
+        assert_eq!(validate_url("https://93.184.216.34/hook").await, Ok(()));
+        assert_eq!(validate_url("https://[2606:4700::1111]/hook").await, Ok(()));
+        assert_eq!(validate_url("https://[64:ff9b::5db8:d822]/hook").await, Ok(()));
+        assert_eq!(validate_url("https://[2002:5db8:d822::]/hook").await, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_webhook_client_refuses_internal_hosts() {
+        let (addr, mut received) = start_receiver(StatusCode::OK);
+        let mut subscription = subscription(addr);
+        subscription.url = format!("http://localhost:{}/hook", addr.port());
+
+        let attempt = deliver(&webhook_client(), &subscription, &delivery()).await;
+        assert!(!attempt.succeeded());
+        assert_eq!(attempt.status_code, None);
+        assert!(received.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off_and_caps() {
+        assert_eq!(retry_delay(1).num_seconds(), 30);
+        assert_eq!(retry_delay(2).num_seconds(), 60);
+        assert_eq!(retry_delay(4).num_seconds(), 240);
+        assert_eq!(retry_delay(30).num_seconds(), 6 * 60 * 60);
+    }
+
+    #[tokio::test]
+    #[ignore]
+    async fn test_enqueue_claim_and_redeliver() {
+        let db = generate_test_database().await;
+        let (addr, mut received) = start_receiver(StatusCode::OK);
+        diesel::sql_query("DELETE FROM webhook_subscriptions")
+            .execute(&mut db.get_db_conn().unwrap())
+            .unwrap();
+        let subscription = db
+            .create_subscription(&WebhookSubscriptionDB {
+                url: format!("http://{addr}/hook"),
+                secret: SECRET.to_owned(),
+                events: vec![Some("task.created".to_owned())],
+                project_id: None,
+                created_by_user_id: None,
+            })
+            .unwrap();
+
+        let payload = serde_json::json!({ "id": 1 });
+        assert_eq!(db.enqueue(WebhookEvent::TaskCreated, None, None, &payload).unwrap(), 1);
+        assert_eq!(db.enqueue(WebhookEvent::VoteCast, None, None, &payload).unwrap(), 0);
+
+        let lease_until = Utc::now() + chrono::Duration::seconds(60);
+        let due = db.claim_due(10, lease_until).unwrap();
+        assert_eq!(due.len(), 1);
+        assert!(db.claim_due(10, lease_until).unwrap().is_empty());
+
+        let (delivery, claimed) = &due[0];
+        let attempt = deliver(&reqwest::Client::new(), claimed, delivery).await;
+        db.record_attempt(delivery.id, &attempt, None).unwrap();
+        received.recv().await.unwrap();
+
+        let (history, total) = db
+            .deliveries(subscription.id, PaginationParams { limit: 10, offset: 0 })
+            .unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(history[0].status, "succeeded");
+        assert_eq!(history[0].attempts, 1);
+
+        let redelivery = db.redeliver(&history[0]).unwrap();
+        assert_eq!(redelivery.status, "pending");
+        assert_eq!(redelivery.payload, history[0].payload);
+
+        // deliveries of inactive subscriptions wait until they're active again
+        let set_active = |active: bool| {
+            diesel::sql_query(format!(
+                "UPDATE webhook_subscriptions SET active = {active} WHERE id = {}",
+                subscription.id
+            ))
+            .execute(&mut db.get_db_conn().unwrap())
+            .unwrap();
+        };
+        set_active(false);
+        assert!(db.claim_due(10, lease_until).unwrap().is_empty());
+        set_active(true);
+        let due = db.claim_due(10, lease_until).unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].0.id, redelivery.id);
+    }
+}
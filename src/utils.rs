@@ -1,43 +1,115 @@
 use crate::{
-    api::{health, issues, projects, repositories, users, roles, tasks, teams, subscriptions, notifications},
-    db::{
-        self,
-        errors::DBError,
-        pool::{DBAccess, DBAccessor},
-    },
+    api::{admin, archival, health, issues, projects, repositories, users, roles, tasks, taxonomy, teams, subscriptions, notifications, webhooks, jobs, metrics},
+    db::{errors::DBError, pool::DBAccess},
+    api::{jobs::runner::Heartbeat, notifications::stream::NotificationBroadcaster},
+    config::{Config, DatabaseConfig, LogFormat, LoggingConfig},
+    email::verification::EmailVerifier,
     errors::error_handler,
+    middlewares::{
+        context,
+        cors::{self, CorsPolicy},
+        rate_limit::{with_rate_limit, RateLimiter},
+    },
 };
+use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+
 use ::warp::Reply;
-use warp::{filters::BoxedFilter, http::Method, Filter};
+use log::info;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio_util::sync::CancellationToken;
+use tracing_subscriber::EnvFilter;
+use warp::{
+    filters::BoxedFilter,
+    hyper::{
+        self,
+        server::conn::AddrStream,
+        service::{make_service_fn, service_fn},
+        Server,
+    },
+    Filter,
+};
 
-pub async fn setup_db(url: &str) -> DBAccess {
-    let db_pool = db::pool::create_db_pool(url)
+/// Sends `log` and `tracing` events to stderr, as text or one JSON object
+/// per line. Falls back to `info` when the filter is invalid.
+pub fn init_logging(config: &LoggingConfig) {
+    let filter = EnvFilter::try_new(&config.filter).unwrap_or_else(|_| EnvFilter::new("info"));
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr);
+    match config.format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder.json().flatten_event(true).with_current_span(true).with_span_list(true).init(),
+    }
+}
+
+/// Completes on the first SIGTERM or SIGINT.
+pub async fn shutdown_signal() {
+    let mut terminate = signal(SignalKind::terminate()).expect("Failed to listen for SIGTERM");
+    let mut interrupt = signal(SignalKind::interrupt()).expect("Failed to listen for SIGINT");
+    let name = tokio::select! {
+        _ = terminate.recv() => "SIGTERM",
+        _ = interrupt.recv() => "SIGINT",
+    };
+    info!("received {name}, shutting down");
+}
+
+/// Serves `filter` on `addr`, giving every request an id and a tracing span.
+/// Once `shutdown` is cancelled the listener is closed and the future
+/// completes when the open connections are done.
+pub async fn serve<R: Reply + 'static>(
+    filter: BoxedFilter<(R,)>,
+    addr: SocketAddr,
+    shutdown: CancellationToken,
+) -> Result<(), hyper::Error> {
+    let service = warp::service(filter);
+    let make_service = make_service_fn(move |conn: &AddrStream| {
+        let remote = conn.remote_addr();
+        let service = service.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| context::handle(service.clone(), remote, request)))
+        }
+    });
+    Server::try_bind(&addr)?
+        .serve(make_service)
+        .with_graceful_shutdown(shutdown.cancelled_owned())
+        .await
+}
+
+pub async fn setup_db(config: &DatabaseConfig) -> DBAccess {
+    DBAccess::from_config(config)
         .map_err(DBError::DBPoolConnection)
-        .expect("Failed to create DB pool");
-    DBAccess::new(db_pool)
+        .expect("Failed to create DB pool")
 }
 
-pub fn setup_filters(db: DBAccess) -> BoxedFilter<(impl Reply,)> {
-    let health_route = health::routes::routes(db.clone());
+pub fn setup_filters(
+    db: DBAccess,
+    config: Arc<Config>,
+    verifier: EmailVerifier,
+    broadcaster: NotificationBroadcaster,
+    heartbeat: Heartbeat,
+) -> BoxedFilter<(impl Reply,)> {
+    let health_route = health::routes::routes(db.clone(), heartbeat);
     let projects_route = projects::routes::routes(db.clone());
     let repositories_route = repositories::routes::routes(db.clone());
     let issues_route = issues::routes::routes(db.clone());
-    let users_route = users::routes::routes(db.clone());
+    let users_route = users::routes::routes(db.clone(), verifier);
     let teams_route = teams::routes::routes(db.clone());
     let roles_route = roles::routes::routes(db.clone());
     let tasks_route = tasks::routes::routes(db.clone());
+    let taxonomy_route = taxonomy::routes::routes(db.clone());
+    let archival_route = archival::routes::routes(db.clone());
     let subscriptions_route = subscriptions::routes::routes(db.clone());
-    let notifications_route = notifications::routes::routes(db.clone());
+    let notifications_route = notifications::routes::routes(db.clone(), broadcaster);
+    let webhooks_route = webhooks::routes::routes(db.clone());
+    let jobs_route = jobs::routes::routes(db.clone());
+    let admin_route = admin::routes::routes(db.clone(), config.clone());
+    let metrics_route = metrics::routes::routes(db.clone(), config.metrics.clone());
 
 
-    let cors = warp::cors()
-        .allow_any_origin()
-        .allow_headers(vec!["Access-Control-Allow-Headers", "Access-Control-Request-Method", "Access-Control-Request-Headers", "Origin", "Accept", "X-Requested-With", "Content-Type", "Authorization"])
-        .allow_credentials(true)
-        .allow_methods(&[Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE, Method::OPTIONS, Method::HEAD]);
+    let cors = CorsPolicy::new(&config.cors, config.environment);
+    let limiter = RateLimiter::new(&config.rate_limits);
 
-
-    health_route
+    let api = health_route
         .or(projects_route)
         .or(repositories_route)
         .or(issues_route)
@@ -45,11 +117,21 @@ pub fn setup_filters(db: DBAccess) -> BoxedFilter<(impl Reply,)> {
         .or(teams_route)
         .or(roles_route)
         .or(tasks_route)
+        .or(taxonomy_route)
+        .or(archival_route)
         .or(subscriptions_route)
         .or(notifications_route)
+        .or(webhooks_route)
+        .or(jobs_route)
+        .or(admin_route)
+        .or(metrics_route)
+        .recover(error_handler);
+
+    cors::preflight(cors.clone())
+        .or(cors::with_cors(cors, with_rate_limit(limiter, api)))
+        .unify()
         .recover(error_handler)
-        .with(warp::log("api"))
-        .with(cors)
+        .with(warp::log::custom(crate::metrics::record_request))
         .boxed()
 }
 